@@ -1,109 +1,1281 @@
 use super::syntax::SyntaxKind::*;
-use log::*;
-
-macro_rules! ast_node {
-    ($ast:ident, $kind:ident) => {
-        #[derive(Debug,PartialEq, Eq, Hash)]
-        #[repr(transparent)]
-        pub struct $ast(SyntaxNode);
-        impl $ast {
-            #[allow(unused)]
-            pub(crate) fn position(&self) -> rowan::TextRange {
-                self.0.text_range()
-            }
-            #[allow(dead_code)]
-            pub(crate) fn syntax(&self) -> &SyntaxNode {
-                &self.0
-            }
-            #[allow(unused)]
-            pub fn cast(node: SyntaxNode) -> Option<Self> {
-                if node.kind() == $kind {
-                    Some(Self(node))
-                } else {
-                    trace!("returning none");
-                    None
-                }
-            }
-        }
-    };
-}
+
+mod generated;
+
+pub use self::generated::*;
+
 pub type SyntaxNode = rowan::SyntaxNode<super::syntax::Lang>;
 #[allow(unused)]
 pub type SyntaxToken = rowan::SyntaxToken<super::syntax::Lang>;
 #[allow(unused)]
 pub type SyntaxElement = rowan::NodeOrToken<SyntaxNode, SyntaxToken>;
-ast_node!(RootNode, ROOT);
-ast_node!(ParticipantDeclNode, PARTICIPANT_DECL);
-ast_node!(IdentifierNode, IDENTIFIER);
 
-#[allow(dead_code)]
-pub struct StatementNode(SyntaxNode);
+/// The directives that pull in another file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludeKind {
+    Include,
+    /// `!include_many`, which may include the same file more than once
+    IncludeMany,
+    /// `!include_once`, which complains if the file was already included
+    IncludeOnce,
+    /// `!includesub file!PART`, which includes the `!startsub PART` of a file
+    IncludeSub,
+    IncludeUrl,
+    /// `!import`, which makes the files of an archive available to `!include`
+    Import,
+}
+
+/// How a salt `{ }` is drawn, going by the character after its `{`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaltStyle {
+    Grid,
+    /// `{#`, with all the lines of a table
+    Lines,
+    /// `{!`
+    VerticalLines,
+    /// `{-`
+    HorizontalLines,
+    /// `{+`, with only the lines around it
+    Border,
+    /// `{/`
+    Tabs,
+    /// `{*`
+    Menu,
+    /// `{T`
+    Tree,
+    /// `{S`, `{SI`
+    Scroll,
+    /// `{^"Title"`
+    GroupBox,
+}
+
+/// The widgets of a salt wireframe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaltWidget {
+    /// `[Ok]`
+    Button,
+    /// `[X]` if checked, `[ ]` if not
+    Checkbox(bool),
+    /// `(X)` if chosen, `( )` if not
+    Radio(bool),
+    /// `"text"`
+    TextField,
+    /// `^Choice^`
+    Droplist,
+}
+
+/// The flavours of classifier a CLASS_DECL can declare
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassKind {
+    Class,
+    AbstractClass,
+    Interface,
+    Enum,
+    Annotation,
+}
+
+/// What a state is, going by its stereotype, e.g. `state c <<choice>>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateKind {
+    State,
+    Choice,
+    Fork,
+    Join,
+    End,
+    EntryPoint,
+    ExitPoint,
+    InputPin,
+    OutputPin,
+    ExpansionInput,
+    ExpansionOutput,
+    History,
+    DeepHistory,
+}
+
+/// What a COMPONENT_DECL declares, a component or a `() Name` interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementKind {
+    Component,
+    Interface,
+}
+
+/// The keyword a CONTAINER_DECL was declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    Node,
+    Folder,
+    Frame,
+    Cloud,
+    Database,
+    Rectangle,
+}
+
+/// `port`, `portin` or `portout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortKind {
+    Port,
+    In,
+    Out,
+}
+
+/// The verb of a gantt CONSTRAINT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    Starts,
+    Ends,
+    Happens,
+}
+
+/// The keyword a timing diagram's SIGNAL_DECL was declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalKind {
+    Robust,
+    Concise,
+    Clock,
+    Binary,
+}
 
-#[allow(dead_code)]
-pub enum StatementNodeKind {
-    ParticipantDeclNodeKind(ParticipantDeclNode),
+/// A point in time as written in a timing diagram
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeReference {
+    /// `100`
+    Absolute(f64),
+    /// `+50`, relative to the time before
+    Relative(f64),
+    /// `:label+50`, relative to the time labelled `label`
+    Label(String, f64),
 }
 
 impl RootNode {
-    #[allow(dead_code)]
-    pub fn statements(&self) -> impl Iterator<Item = StatementNode> + '_ {
-        self.0.children().filter_map(StatementNode::cast)
-    }
     #[allow(dead_code)]
     pub fn participant_decls(&self) -> impl Iterator<Item = ParticipantDeclNode> + '_ {
         self.0.children().filter_map(ParticipantDeclNode::cast)
     }
+    #[allow(dead_code)]
+    pub fn package_decls(&self) -> impl Iterator<Item = PackageDeclNode> + '_ {
+        self.0.children().filter_map(PackageDeclNode::cast)
+    }
+    /// The bodies of the `@startjson` and `@startyaml` diagrams
+    #[allow(dead_code)]
+    pub fn data_nodes(&self) -> impl Iterator<Item = DataNode> + '_ {
+        self.0.children().filter_map(DataNode::cast)
+    }
+    #[allow(dead_code)]
+    pub fn data_highlights(&self) -> impl Iterator<Item = DataHighlightNode> + '_ {
+        self.0.children().filter_map(DataHighlightNode::cast)
+    }
+    /// The outermost `{ }` of every salt wireframe
+    #[allow(dead_code)]
+    pub fn salt_groups(&self) -> impl Iterator<Item = SaltGroupNode> + '_ {
+        self.0
+            .descendants()
+            .filter(|it| it.parent().map(|parent| parent.kind()) != Some(SALT_GROUP))
+            .filter_map(SaltGroupNode::cast)
+    }
 }
 impl IdentifierNode {
     #[allow(dead_code)]
     pub fn identifier(&self) -> rowan::SyntaxText {
         self.0.text().clone()
     }
+    /// The name without its quotes or brackets, e.g. `Long Name` for
+    /// `"Long Name"`, `[Long Name]`, `(Long Name)` or `:Long Name:`
+    pub fn value(&self) -> String {
+        let range = self.value_range() - self.0.text_range().start();
+        self.0.text().to_string()[range].to_string()
+    }
+    /// Where the `value` is, inside any quotes or brackets
+    pub fn value_range(&self) -> rowan::TextRange {
+        let text = self.0.text().to_string();
+        let delimited = text.len() >= 2
+            && matches!(
+                (text.chars().next(), text.chars().last()),
+                (Some('"'), Some('"'))
+                    | (Some('['), Some(']'))
+                    | (Some('('), Some(')'))
+                    | (Some(':'), Some(':'))
+            );
+        let (start, end) = if delimited {
+            (1, text.len() - 1)
+        } else {
+            (0, text.len())
+        };
+        let inner = &text[start..end];
+        let trimmed = inner.trim();
+        let start = if trimmed.is_empty() {
+            start
+        } else {
+            start + inner.find(trimmed).unwrap_or(0)
+        };
+        let offset = self.0.text_range().start();
+        rowan::TextRange::at(
+            offset + rowan::TextSize::from(start as u32),
+            rowan::TextSize::from(trimmed.len() as u32),
+        )
+    }
+    /// The last part of a qualified name, e.g. `Bar` for `net.foo.Bar` and
+    /// `key` for `Map::key`, or the `value` of any other name
+    pub fn last_segment(&self) -> String {
+        let range = self.last_segment_range() - self.0.text_range().start();
+        self.0.text().to_string()[range].to_string()
+    }
+    /// Where the `last_segment` is
+    pub fn last_segment_range(&self) -> rowan::TextRange {
+        let range = self.value_range();
+        if self.0.text().to_string().starts_with(['"', '[', '(', ':']) {
+            return range;
+        }
+        let value = self.value();
+        match value.rfind(['.', ':']) {
+            Some(index) => rowan::TextRange::new(
+                range.start() + rowan::TextSize::from(index as u32 + 1),
+                range.end(),
+            ),
+            None => range,
+        }
+    }
+}
+
+impl LabelNode {
+    #[allow(dead_code)]
+    pub fn text(&self) -> rowan::SyntaxText {
+        self.0.text()
+    }
+    /// The text as it's shown, without its Creole markup and with its
+    /// escapes, such as `\n`, replaced by what they stand for
+    pub fn plain_text(&self) -> String {
+        self.0
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter_map(|it| match it.kind() {
+                CREOLE_DELIMITER | CREOLE_TAG_START | CREOLE_TAG_END | CREOLE_ICON
+                | CREOLE_IMAGE => None,
+                CREOLE_ESCAPE => Some(match it.text().as_str() {
+                    "\\n" | "\\l" | "\\r" => "\n".to_string(),
+                    "\\t" => "\t".to_string(),
+                    escape => escape[1..].to_string(),
+                }),
+                _ => Some(it.text().to_string()),
+            })
+            .collect()
+    }
+    /// The OpenIconic icons, such as `<&heart>`
+    pub fn icons(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.0
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == CREOLE_ICON)
+    }
+    /// The tags, such as `<color:red>`, and the text they apply to
+    pub fn tags(&self) -> impl Iterator<Item = CreoleTagNode> + '_ {
+        self.0.descendants().filter_map(CreoleTagNode::cast)
+    }
+}
+
+impl CreoleTagNode {
+    fn start(&self) -> Option<SyntaxToken> {
+        self.0
+            .first_token()
+            .filter(|it| it.kind() == CREOLE_TAG_START)
+    }
+    /// The name of the tag, in lowercase, e.g. `color` for `<Color:red>`
+    pub fn name(&self) -> String {
+        let start = match self.start() {
+            Some(start) => start,
+            None => return String::new(),
+        };
+        start
+            .text()
+            .trim_start_matches('<')
+            .split(|it: char| !it.is_ascii_alphabetic())
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase()
+    }
+    /// What comes after the `:`, e.g. `red` for `<color:red>`, and where it is
+    pub fn value(&self) -> Option<(String, rowan::TextRange)> {
+        let start = self.start()?;
+        let text = start.text().as_str();
+        let colon = text.find(':')?;
+        let value = text[colon + 1..text.len() - 1].trim();
+        let offset = colon + 1 + text[colon + 1..].find(value)?;
+        let range = rowan::TextRange::at(
+            start.text_range().start() + rowan::TextSize::from(offset as u32),
+            rowan::TextSize::from(value.len() as u32),
+        );
+        Some((value.to_string(), range))
+    }
+    /// Whether the tag has a closing tag, rather than running to the end of
+    /// the line
+    #[allow(dead_code)]
+    pub fn is_closed(&self) -> bool {
+        self.0
+            .last_token()
+            .is_some_and(|it| it.kind() == CREOLE_TAG_END)
+    }
 }
 
-impl ParticipantDeclNode {
-    pub fn participant_name(&self) -> Option<IdentifierNode> {
-        self.syntax().children().find_map(IdentifierNode::cast)
-    }
-
-    // #[allow(dead_code)]
-    // pub fn text(&self) -> &rowan::SmolStr {
-    //     trace!("child count is {:?}", self.0.children().count());
-    //     self.0.children().for_each(|c| {
-    //         trace!("child => {}", c.text())
-    //     });
-    //     trace!("green child count is {:?}", self.0.green().children().count());
-    //     self.0.green().children().for_each(|c| {
-    //         match c {
-    //             rowan::NodeOrToken::Token(p) => trace!("green token => {}", p.text()),
-    //             rowan::NodeOrToken::Node(p) => trace!("green node => {:?}", p),
-    //             _ => unreachable!(),
-    //         }
-    //     });
-    //     match &self.0.green().children().next() {
-    //         Some(rowan::NodeOrToken::Token(token)) => token.text(),
-    //         _ => unreachable!(),
-    //     }
-    // }
-}
-
-// statement node is a wrapper around 'n' different types of statements.
-// as such, its implementation is 'special'
-impl StatementNode {
-    #[allow(dead_code)]
-    fn cast(node: SyntaxNode) -> Option<Self> {
-        if ParticipantDeclNode::cast(node.clone()).is_some() {
-            Some(StatementNode(node))
+impl StereotypeNode {
+    /// The text between the `<<` and `>>`
+    pub fn name(&self) -> String {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| !matches!(it.kind(), STEREO_OPEN | STEREO_CLOSE))
+            .map(|it| it.text().to_string())
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+}
+
+impl ClassDeclNode {
+    pub fn class_kind(&self) -> ClassKind {
+        let keywords = self
+            .0
+            .children_with_tokens()
+            .map(|it| it.kind())
+            .take_while(|kind| *kind != IDENTIFIER)
+            .collect::<Vec<_>>();
+        if keywords.contains(&ABSTRACT_KW) {
+            ClassKind::AbstractClass
+        } else if keywords.contains(&INTERFACE_KW) {
+            ClassKind::Interface
+        } else if keywords.contains(&ENUM_KW) {
+            ClassKind::Enum
+        } else if keywords.contains(&ANNOTATION_KW) {
+            ClassKind::Annotation
         } else {
-            None
+            ClassKind::Class
         }
     }
+    #[allow(dead_code)]
+    pub fn is_abstract(&self) -> bool {
+        self.class_kind() == ClassKind::AbstractClass
+    }
+    /// The classes named in the `extends` and `implements` clauses
+    #[allow(dead_code)]
+    pub fn supertypes(&self) -> impl Iterator<Item = IdentifierNode> + '_ {
+        let extends = self
+            .extends_clause()
+            .into_iter()
+            .flat_map(|it| it.identifiers());
+        let implements = self
+            .implements_clause()
+            .into_iter()
+            .flat_map(|it| it.identifiers());
+        extends.chain(implements)
+    }
+    pub fn members(&self) -> impl Iterator<Item = MemberNode> + '_ {
+        self.class_body().into_iter().flat_map(|it| it.members())
+    }
+}
 
-    // fn kind(&self) -> StatementNodeKind {
-    //     trace!("StatementNode::kind {:?}", self.0.kind());
-    //     ParticipantDeclNode::cast(self.0.clone())
-    //         .map(StatementNodeKind::ParticipantDeclNodeKind)
-    //         //    //.or_else(|| List::cast(self.0.clone()).map(StatementNodeKind::List))
-    //         .unwrap()
-    // }
+impl MemberNode {
+    pub fn is_method(&self) -> bool {
+        self.0.kind() == METHOD
+    }
+    fn has_modifier(&self, modifier: &str) -> bool {
+        self.modifiers().any(|it| {
+            let text = it.syntax().text().to_string();
+            text.trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace()) == modifier
+        })
+    }
+    #[allow(dead_code)]
+    pub fn is_static(&self) -> bool {
+        self.has_modifier("static") || self.has_modifier("classifier")
+    }
+    #[allow(dead_code)]
+    pub fn is_abstract(&self) -> bool {
+        self.has_modifier("abstract")
+    }
+}
+
+impl VisibilityNode {
+    /// One of `+`, `-`, `#` or `~`
+    #[allow(dead_code)]
+    pub fn symbol(&self) -> Option<char> {
+        self.0.text().char_at(0.into())
+    }
+}
+
+impl ActionNode {
+    /// Whether the action was closed by one of its terminators
+    pub fn is_terminated(&self) -> bool {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != LABEL)
+            .skip(1)
+            .any(|it| it.kind() != EOL && it.kind() != WHITESPACE)
+    }
+}
+
+impl ControlNode {
+    /// Whether the block has the keyword that closes it, e.g. `endif`
+    pub fn is_closed(&self) -> bool {
+        matches!(
+            self.0.last_child_or_token(),
+            Some(rowan::NodeOrToken::Token(_))
+        )
+    }
+    /// The keyword that opens the block, e.g. `if`
+    pub fn keyword(&self) -> Option<SyntaxToken> {
+        self.0.first_token()
+    }
+}
+
+impl StateDeclNode {
+    pub fn state_kind(&self) -> StateKind {
+        let stereotype = match self.stereotype() {
+            Some(stereotype) => stereotype.name(),
+            None => return StateKind::State,
+        };
+        match stereotype.as_str() {
+            "choice" => StateKind::Choice,
+            "fork" => StateKind::Fork,
+            "join" => StateKind::Join,
+            "end" => StateKind::End,
+            "entryPoint" => StateKind::EntryPoint,
+            "exitPoint" => StateKind::ExitPoint,
+            "inputPin" => StateKind::InputPin,
+            "outputPin" => StateKind::OutputPin,
+            "expansionInput" => StateKind::ExpansionInput,
+            "expansionOutput" => StateKind::ExpansionOutput,
+            "history" => StateKind::History,
+            "history*" => StateKind::DeepHistory,
+            _ => StateKind::State,
+        }
+    }
+    pub fn is_composite(&self) -> bool {
+        self.state_body().is_some()
+    }
+    /// The concurrent regions of a composite state, separated by `--` or `||`
+    pub fn regions(&self) -> impl Iterator<Item = RegionNode> + '_ {
+        self.state_body().into_iter().flat_map(|it| it.regions())
+    }
+}
+
+impl ComponentDeclNode {
+    pub fn element_kind(&self) -> ElementKind {
+        match self.0.first_token().map(|it| it.kind()) {
+            Some(L_PAREN) => ElementKind::Interface,
+            _ => ElementKind::Component,
+        }
+    }
+    /// The ports declared directly in the component's body
+    #[allow(dead_code)]
+    pub fn ports(&self) -> impl Iterator<Item = PortDeclNode> + '_ {
+        self.block()
+            .into_iter()
+            .flat_map(|it| it.0.children())
+            .filter_map(PortDeclNode::cast)
+    }
+}
+
+impl ContainerDeclNode {
+    pub fn container_kind(&self) -> ContainerKind {
+        match self.0.first_token().map(|it| it.kind()) {
+            Some(FOLDER_KW) => ContainerKind::Folder,
+            Some(FRAME_KW) => ContainerKind::Frame,
+            Some(CLOUD_KW) => ContainerKind::Cloud,
+            Some(DATABASE_KW) => ContainerKind::Database,
+            Some(RECTANGLE_KW) => ContainerKind::Rectangle,
+            _ => ContainerKind::Node,
+        }
+    }
+    /// The containers nested directly in this one
+    #[allow(dead_code)]
+    pub fn containers(&self) -> impl Iterator<Item = ContainerDeclNode> + '_ {
+        self.block()
+            .into_iter()
+            .flat_map(|it| it.0.children())
+            .filter_map(ContainerDeclNode::cast)
+    }
+}
+
+impl PortDeclNode {
+    pub fn port_kind(&self) -> PortKind {
+        match self.0.first_token().map(|it| it.kind()) {
+            Some(PORTIN_KW) => PortKind::In,
+            Some(PORTOUT_KW) => PortKind::Out,
+            _ => PortKind::Port,
+        }
+    }
+}
+
+impl DirectionNode {
+    /// `left to right direction`, as opposed to `top to bottom direction`
+    #[allow(dead_code)]
+    pub fn is_left_to_right(&self) -> bool {
+        self.0.text().to_string().starts_with("left")
+    }
+}
+
+impl ObjectDeclNode {
+    pub fn is_map(&self) -> bool {
+        self.0.kind() == MAP_DECL
+    }
+    /// The fields of an object or the entries of a map
+    pub fn entries(&self) -> impl Iterator<Item = EntryNode> + '_ {
+        self.object_body().into_iter().flat_map(|it| it.entries())
+    }
+}
+
+/// The text of the words, keywords included, directly in `node`, lower cased
+fn words(node: &SyntaxNode) -> Vec<String> {
+    node.children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() == IDENTIFIER || it.kind().is_keyword())
+        .map(|it| it.text().to_lowercase())
+        .collect()
+}
+
+/// The text of the DATE tokens directly in `node`
+fn dates(node: &SyntaxNode) -> Vec<String> {
+    node.children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() == DATE)
+        .map(|it| it.text().to_string())
+        .collect()
+}
+
+impl TaskNode {
+    /// Whether the statement starts with `then`, chaining it to the task of
+    /// the previous statement
+    pub fn follows_previous(&self) -> bool {
+        self.0.first_token().map(|it| it.kind()) == Some(THEN_KW)
+    }
+    /// How many working days the task lasts, given how many of them make up
+    /// a week
+    pub fn duration_days(&self, week: u32) -> Option<u32> {
+        let duration = self.duration()?;
+        let count = duration
+            .syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == NUMBER)?
+            .text()
+            .parse::<u32>()
+            .ok()?;
+        if words(duration.syntax())
+            .iter()
+            .any(|it| it.starts_with("week"))
+        {
+            Some(count.saturating_mul(week))
+        } else {
+            Some(count)
+        }
+    }
+    /// A milestone `happens` rather than starting and ending, or lasts no time
+    pub fn is_milestone(&self) -> bool {
+        self.constraints()
+            .any(|it| it.constraint_kind() == ConstraintKind::Happens)
+            || self.duration_days(7) == Some(0)
+    }
+}
+
+impl ConstraintNode {
+    pub fn constraint_kind(&self) -> ConstraintKind {
+        match words(&self.0).first().map(|it| it.as_str()) {
+            Some("ends") => ConstraintKind::Ends,
+            Some("happens") => ConstraintKind::Happens,
+            _ => ConstraintKind::Starts,
+        }
+    }
+    /// The date in `starts 2020-07-01`
+    pub fn date(&self) -> Option<String> {
+        dates(&self.0).into_iter().next()
+    }
+    /// Whether the constraint refers to the end, rather than the start, of
+    /// its task
+    pub fn at_task_end(&self) -> bool {
+        words(&self.0).last().map(|it| it.as_str()) == Some("end")
+    }
+    /// The days in `starts 2 days after [A]'s end`, negative for `before`
+    pub fn offset_days(&self) -> i64 {
+        let count = self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == NUMBER)
+            .and_then(|it| it.text().parse::<i64>().ok())
+            .unwrap_or(0);
+        let words = words(&self.0);
+        if words.iter().any(|it| it == "before") {
+            -count
+        } else if words.iter().any(|it| it == "after") {
+            count
+        } else {
+            0
+        }
+    }
+}
+
+impl ResourceNode {
+    /// How much of their time the resource spends on the task, `100` unless
+    /// given as in `{Alice:50%}`
+    pub fn percent(&self) -> u32 {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == NUMBER)
+            .and_then(|it| it.text().parse().ok())
+            .unwrap_or(100)
+    }
+}
+
+impl ProjectStartNode {
+    pub fn date(&self) -> Option<String> {
+        dates(&self.0).into_iter().next()
+    }
+}
+
+impl ClosedDaysNode {
+    /// Whether the days are opened, as in `2020-07-14 is open`
+    pub fn is_open(&self) -> bool {
+        words(&self.0).iter().any(|it| it == "open")
+    }
+    /// The day of the week in `saturday are closed`, with monday as 0
+    pub fn weekday(&self) -> Option<usize> {
+        let words = words(&self.0);
+        crate::parsing::gantt::WEEKDAYS
+            .iter()
+            .position(|day| words.first().map(|it| it.as_str()) == Some(day))
+    }
+    /// The first and last day in `2020-07-13 to 2020-07-15 is closed`
+    pub fn dates(&self) -> Vec<String> {
+        dates(&self.0)
+    }
+}
+
+impl MindmapNode {
+    /// The tokens before the label: the depth markers, the color, the `_` of a
+    /// boxless node and the `<` or `>` of a WBS node
+    fn markers(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.0
+            .children_with_tokens()
+            .take_while(|it| it.kind() != LABEL && it.kind() != EOL)
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() != WHITESPACE)
+    }
+    /// How deep the node is, `1` for the root of the diagram
+    #[allow(dead_code)]
+    pub fn depth(&self) -> usize {
+        self.markers()
+            .take_while(|it| matches!(it.kind(), STAR | PLUS | MINUS | ARROW))
+            .map(|it| it.text().len())
+            .sum()
+    }
+    /// Whether the node is drawn without a box, as in `**_ text`
+    #[allow(dead_code)]
+    pub fn is_boxless(&self) -> bool {
+        self.markers().any(|it| it.text() == "_")
+    }
+    /// Whether the node is drawn left of the root: `-` nodes, `<` nodes of a
+    /// WBS, and `*` nodes following `left side` or a parent on the left
+    #[allow(dead_code)]
+    pub fn is_left(&self) -> bool {
+        for marker in self.markers() {
+            match marker.kind() {
+                MINUS | ARROW | L_ANGLE => return true,
+                PLUS | R_ANGLE => return false,
+                _ => {}
+            }
+        }
+        let side = self
+            .0
+            .siblings(rowan::Direction::Prev)
+            .find_map(SideNode::cast);
+        if let Some(side) = side {
+            return side.is_left();
+        }
+        self.0
+            .parent()
+            .and_then(MindmapNode::cast)
+            .map(|it| it.is_left())
+            .unwrap_or(false)
+    }
+    /// The text of the node, without the `:` and `;` of a multi-line node
+    pub fn text(&self) -> String {
+        let text = self
+            .label()
+            .map(|it| it.plain_text())
+            .unwrap_or_default();
+        let text = text.trim();
+        match text.strip_prefix(':') {
+            Some(text) => text.strip_suffix(';').unwrap_or(text).trim().to_string(),
+            None => text.to_string(),
+        }
+    }
+    /// The nodes one level deeper
+    #[allow(dead_code)]
+    pub fn children(&self) -> impl Iterator<Item = MindmapNode> + '_ {
+        self.0.children().filter_map(MindmapNode::cast)
+    }
+}
+
+impl SideNode {
+    #[allow(dead_code)]
+    pub fn is_left(&self) -> bool {
+        self.0
+            .first_token()
+            .map(|it| it.text().eq_ignore_ascii_case("left"))
+            .unwrap_or(false)
+    }
+}
+
+impl SignalDeclNode {
+    pub fn signal_kind(&self) -> SignalKind {
+        match self.0.first_token().map(|it| it.text().to_lowercase()).as_deref() {
+            Some("concise") => SignalKind::Concise,
+            Some("clock") => SignalKind::Clock,
+            Some("binary") => SignalKind::Binary,
+            _ => SignalKind::Robust,
+        }
+    }
+}
+
+impl TimeNode {
+    pub fn reference(&self) -> Option<TimeReference> {
+        let mut label = None;
+        let mut sign = 1.0;
+        let mut value = None;
+        for token in self.0.children_with_tokens().filter_map(|it| it.into_token()) {
+            match token.kind() {
+                IDENTIFIER => label = Some(token.text().to_string()),
+                MINUS => sign = -1.0,
+                NUMBER => value = token.text().parse::<f64>().ok(),
+                _ => {}
+            }
+        }
+        let relative = self.0.first_token().map(|it| it.kind()) == Some(PLUS)
+            || self.0.first_token().map(|it| it.kind()) == Some(MINUS);
+        let offset = sign * value.unwrap_or(0.0);
+        match label {
+            Some(label) => Some(TimeReference::Label(label, offset)),
+            None if relative => Some(TimeReference::Relative(offset)),
+            None => Some(TimeReference::Absolute(value?)),
+        }
+    }
+}
+
+impl TimeAnchorNode {
+    /// The label in `@100 as :start`
+    pub fn label(&self) -> Option<String> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == IDENTIFIER)
+            .map(|it| it.text().to_string())
+    }
+}
+
+impl TimingLinkNode {
+    /// Whether the link is a `<->` constraint rather than a message
+    #[allow(dead_code)]
+    pub fn is_constraint(&self) -> bool {
+        self.0.kind() == TIMING_CONSTRAINT
+    }
+}
+
+impl DataNode {
+    pub fn is_yaml(&self) -> bool {
+        self.0.kind() == YAML_DATA
+    }
+}
+
+impl DataHighlightNode {
+    /// The keys in `#highlight "address" / "city"`, without their quotes
+    pub fn path(&self) -> Vec<(String, rowan::TextRange)> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == STRING)
+            .map(|it| (it.text().trim_matches('"').to_string(), it.text_range()))
+            .collect()
+    }
+    /// The data of the diagram the highlight is in
+    pub fn data(&self) -> Option<DataNode> {
+        self.0
+            .siblings(rowan::Direction::Next)
+            .take_while(|it| matches!(it.kind(), DATA_HIGHLIGHT | JSON_DATA | YAML_DATA))
+            .find_map(DataNode::cast)
+    }
+}
+
+impl SaltGroupNode {
+    fn tokens(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.0.children_with_tokens().filter_map(|it| it.into_token())
+    }
+    pub fn style(&self) -> SaltStyle {
+        let marker = match self.tokens().nth(1) {
+            Some(marker) => marker,
+            None => return SaltStyle::Grid,
+        };
+        match (marker.kind(), marker.text().as_str()) {
+            (HASH, _) => SaltStyle::Lines,
+            (MINUS, _) => SaltStyle::HorizontalLines,
+            (PLUS, _) => SaltStyle::Border,
+            (SLASH, _) => SaltStyle::Tabs,
+            (STAR, _) => SaltStyle::Menu,
+            (IDENTIFIER, "T") => SaltStyle::Tree,
+            (IDENTIFIER, "S") | (IDENTIFIER, "SI") => SaltStyle::Scroll,
+            (DIRECTIVE, "!") => SaltStyle::VerticalLines,
+            (ERROR, "^") => SaltStyle::GroupBox,
+            _ => SaltStyle::Grid,
+        }
+    }
+    /// The title of a group box, as in `{^"Title"`
+    #[allow(dead_code)]
+    pub fn title(&self) -> Option<String> {
+        if self.style() != SaltStyle::GroupBox {
+            return None;
+        }
+        self.tokens()
+            .skip(2)
+            .find(|it| it.kind() != WHITESPACE)
+            .filter(|it| it.kind() == STRING)
+            .map(|it| it.text().trim_matches('"').to_string())
+    }
+    /// Whether the group has its closing `}`
+    #[allow(dead_code)]
+    pub fn is_closed(&self) -> bool {
+        self.0.last_child_or_token().map(|it| it.kind()) == Some(R_BRACE)
+    }
+}
+
+impl SaltWidgetNode {
+    /// The text between the delimiters of the widget
+    fn inner(&self) -> String {
+        let text = self
+            .0
+            .children_with_tokens()
+            .take_while(|it| it.kind() != LABEL)
+            .filter_map(|it| it.into_token())
+            .map(|it| it.text().to_string())
+            .collect::<String>();
+        let text = text.trim();
+        let mut chars = text.chars();
+        chars.next();
+        if matches!(text.chars().last(), Some(']') | Some(')') | Some('"') | Some('^'))
+            && text.len() > 1
+        {
+            chars.next_back();
+        }
+        chars.as_str().trim().to_string()
+    }
+    pub fn widget_kind(&self) -> SaltWidget {
+        let checked = || self.inner().eq_ignore_ascii_case("x");
+        match self.0.first_token().map(|it| it.kind()) {
+            Some(STRING) => SaltWidget::TextField,
+            Some(L_PAREN) => SaltWidget::Radio(checked()),
+            Some(L_BRACK) if self.inner().is_empty() || checked() => {
+                SaltWidget::Checkbox(checked())
+            }
+            Some(L_BRACK) => SaltWidget::Button,
+            _ => SaltWidget::Droplist,
+        }
+    }
+    /// What the widget says: the caption of a button, the text of a field or
+    /// the label of a checkbox
+    pub fn text(&self) -> String {
+        match self.widget_kind() {
+            SaltWidget::Checkbox(_) | SaltWidget::Radio(_) => self
+                .label()
+                .map(|it| it.text().to_string().trim().to_string())
+                .unwrap_or_default(),
+            _ => self.inner(),
+        }
+    }
+}
+
+impl IncludeNode {
+    pub fn include_kind(&self) -> IncludeKind {
+        let directive = self.0.first_token().map(|it| it.text().to_string());
+        match directive.as_deref() {
+            Some("!include_many") => IncludeKind::IncludeMany,
+            Some("!include_once") => IncludeKind::IncludeOnce,
+            Some("!includesub") => IncludeKind::IncludeSub,
+            Some("!includeurl") => IncludeKind::IncludeUrl,
+            Some("!import") => IncludeKind::Import,
+            _ => IncludeKind::Include,
+        }
+    }
+    /// The file that's included, without the `!PART` or `!1` that picks part
+    /// of it, and where it's written
+    pub fn target(&self) -> Option<(String, rowan::TextRange)> {
+        let node = self.include_path()?;
+        let text = node.syntax().text().to_string();
+        let start = node.position().start();
+        // the '!' of 'https://...' can't be told apart, so only look past the
+        // last '/'
+        let name = text.rfind('/').map(|it| it + 1).unwrap_or(0);
+        let end = text[name..]
+            .find('!')
+            .map(|it| it + name)
+            .unwrap_or_else(|| text.len());
+        let range = rowan::TextRange::at(start, rowan::TextSize::from(end as u32));
+        Some((text[..end].to_string(), range))
+    }
+    /// The part of the file `!includesub` includes
+    pub fn sub(&self) -> Option<String> {
+        if self.include_kind() != IncludeKind::IncludeSub {
+            return None;
+        }
+        let text = self.include_path()?.syntax().text().to_string();
+        let (path, _) = self.target()?;
+        text.get(path.len() + 1..).map(|it| it.to_string())
+    }
+    /// Whether the file comes from the standard library, as in `<C4/C4>`
+    #[allow(dead_code)]
+    pub fn is_stdlib(&self) -> bool {
+        self.target()
+            .map(|(path, _)| path.starts_with('<'))
+            .unwrap_or(false)
+    }
+}
+
+impl PreprocessorNode {
+    /// The directive, e.g. `!startsub`
+    pub fn directive(&self) -> String {
+        self.0
+            .first_token()
+            .map(|it| it.text().to_string())
+            .unwrap_or_default()
+    }
+    /// The rest of the line
+    pub fn argument(&self) -> String {
+        let text = self.0.text().to_string();
+        text.get(self.directive().len()..)
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    }
+}
+
+impl VariableDefNode {
+    /// The variable, with its `$`
+    pub fn name(&self) -> Option<String> {
+        self.variable().map(|it| it.text().to_string())
+    }
+    /// Whether it's written `?=`, which leaves a variable that has a value
+    /// alone
+    pub fn is_default(&self) -> bool {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .any(|it| it.text() == "?")
+    }
+    /// Whether it's `!local`, only visible in the procedure it's in
+    #[allow(dead_code)]
+    pub fn is_local(&self) -> bool {
+        self.0.first_token().map(|it| it.text() == "!local") == Some(true)
+    }
+}
+
+impl DefineNode {
+    /// The name of the macro
+    pub fn name(&self) -> Option<String> {
+        self.name_token().map(|it| it.text().to_string())
+    }
+    /// The parameters of `!define NAME(a, b="x")`, with their defaults, or
+    /// `None` if it doesn't take any
+    pub fn params(&self) -> Option<Vec<(String, Option<String>)>> {
+        let mut tokens = self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .skip_while(|it| it.kind() != IDENTIFIER)
+            .skip(1);
+        if tokens.next()?.kind() != L_PAREN {
+            return None;
+        }
+        let mut text = String::new();
+        for token in tokens.take_while(|it| it.kind() != R_PAREN) {
+            text.push_str(token.text());
+        }
+        if text.trim().is_empty() {
+            return Some(Vec::new());
+        }
+        let params = text
+            .split(',')
+            .map(|param| match param.split_once('=') {
+                Some((name, default)) => (name.trim().into(), Some(default.trim().into())),
+                None => (param.trim().into(), None),
+            })
+            .collect();
+        Some(params)
+    }
+    /// Whether it's a `!definelong`
+    #[allow(dead_code)]
+    pub fn is_long(&self) -> bool {
+        self.0.first_token().map(|it| it.text() == "!definelong") == Some(true)
+    }
+    /// What the macro expands to: the rest of the line, or the lines up to
+    /// `!enddefinelong`
+    pub fn value(&self) -> String {
+        if let Some(label) = self.label() {
+            return label.syntax().text().to_string().trim_end().to_string();
+        }
+        if !self.is_long() {
+            return String::new();
+        }
+        // the body starts on the line after the name
+        let mut tokens = self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .skip_while(|it| it.kind() != EOL)
+            .skip(1)
+            .map(|it| it.text().to_string())
+            .collect::<Vec<_>>();
+        if let Some(end) = tokens.iter().position(|it| it == "!enddefinelong") {
+            tokens.truncate(end);
+        }
+        tokens.concat()
+    }
+}
+
+impl UndefNode {
+    /// The name of the macro that's forgotten
+    pub fn name(&self) -> Option<String> {
+        self.name_token().map(|it| it.text().to_string())
+    }
+}
+
+impl PpIfNode {
+    /// Whether the conditional has its `!endif`
+    pub fn is_closed(&self) -> bool {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .any(|it| it.text() == "!endif")
+    }
+}
+
+impl PpBranchNode {
+    /// `!if`, `!ifdef`, `!ifndef`, `!elseif` or `!else`
+    pub fn directive(&self) -> String {
+        self.0
+            .first_token()
+            .map(|it| it.text().to_string())
+            .unwrap_or_default()
+    }
+    /// Where the statements of the branch are, from the line after its
+    /// directive up to the next branch or the `!endif`
+    pub fn body_range(&self) -> rowan::TextRange {
+        let range = self.0.text_range();
+        let start = self
+            .0
+            .children_with_tokens()
+            .find(|it| it.kind() == EOL)
+            .map(|it| it.text_range().end())
+            .unwrap_or_else(|| range.end());
+        rowan::TextRange::new(start, range.end())
+    }
+}
+
+/// The first token of the node that's a name: a `$variable` or an
+/// identifier
+fn name_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|it| matches!(it.kind(), VARIABLE | IDENTIFIER))
+}
+
+impl ProcedureNode {
+    /// The name, with its `$` if it has one
+    #[allow(dead_code)]
+    pub fn name(&self) -> Option<String> {
+        self.name_token().map(|it| it.text().to_string())
+    }
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        // skip the 'procedure' of '!unquoted procedure'
+        let mut tokens = self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| matches!(it.kind(), VARIABLE | IDENTIFIER));
+        let first = tokens.next()?;
+        if self.0.first_token()?.text() == "!unquoted" {
+            tokens.next()
+        } else {
+            Some(first)
+        }
+    }
+    /// Whether it's a `!function`, which returns a value
+    pub fn is_function(&self) -> bool {
+        let mut tokens = self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() != WHITESPACE);
+        let keyword = match tokens.next() {
+            Some(it) if it.text() == "!unquoted" => tokens.next(),
+            it => it,
+        };
+        keyword.map(|it| it.text().ends_with("function")) == Some(true)
+    }
+    /// Whether it has its `!endprocedure` or `!endfunction`, or is a
+    /// function written on a single line
+    pub fn is_closed(&self) -> bool {
+        let tokens = self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .collect::<Vec<_>>();
+        tokens.iter().all(|it| it.kind() != EOL)
+            || tokens
+                .iter()
+                .any(|it| matches!(it.text().as_str(), "!endprocedure" | "!endfunction"))
+    }
+    pub fn params(&self) -> impl Iterator<Item = ParamNode> {
+        self.param_list().into_iter().flat_map(|it| it.params())
+    }
+}
+
+impl ParamNode {
+    pub fn name(&self) -> Option<String> {
+        name_token(&self.0).map(|it| it.text().to_string())
+    }
+    /// The value the parameter takes when the call leaves it out
+    pub fn default(&self) -> Option<String> {
+        let text = self.0.text().to_string();
+        let (_, value) = text.split_once('=')?;
+        Some(value.trim().to_string())
+    }
+}
+
+impl CallNode {
+    /// The name of what's called
+    pub fn name(&self) -> Option<String> {
+        self.name_token().map(|it| it.text().to_string())
+    }
+    pub fn arguments(&self) -> impl Iterator<Item = ArgumentNode> {
+        self.arg_list().into_iter().flat_map(|it| it.arguments())
+    }
+}
+
+impl SkinparamNode {
+    /// The `class` of `skinparam class { ... }`
+    pub fn prefix(&self) -> Option<String> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == IDENTIFIER)
+            .nth(1)
+            .map(|it| it.text().to_string())
+    }
+}
+
+impl SkinparamEntryNode {
+    /// The whole key, including the prefix of the block it's in, e.g.
+    /// `classBackgroundColor`
+    pub fn key(&self) -> Option<String> {
+        let key = self.key_token()?.text().to_string();
+        let prefix = self
+            .0
+            .parent()
+            .and_then(SkinparamNode::cast)
+            .and_then(|it| it.prefix())
+            .unwrap_or_default();
+        Some(prefix + &key)
+    }
+    /// The value, and where it's written
+    pub fn value(&self) -> Option<(String, rowan::TextRange)> {
+        self.param_value().map(|it| it.value())
+    }
+}
+
+impl ParamValueNode {
+    /// The value as written, and where it's written
+    pub fn value(&self) -> (String, rowan::TextRange) {
+        (self.0.text().to_string(), self.position())
+    }
+}
+
+impl StyleNode {
+    #[allow(dead_code)]
+    /// Whether the style sheet ends with `</style>`
+    pub fn is_closed(&self) -> bool {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .any(|it| it.kind() == SLASH)
+    }
+}
+
+impl StyleRuleNode {
+    #[allow(dead_code)]
+    /// The selectors before the `{`, e.g. `class` and `.entity` for
+    /// `class, .entity {`
+    pub fn selectors(&self) -> Vec<String> {
+        let mut selectors = Vec::new();
+        let mut current = String::new();
+        for token in self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .take_while(|it| it.kind() != L_BRACE)
+        {
+            match token.kind() {
+                COMMA | WHITESPACE => {
+                    if !current.is_empty() {
+                        selectors.push(std::mem::take(&mut current));
+                    }
+                }
+                _ => current.push_str(token.text()),
+            }
+        }
+        if !current.is_empty() {
+            selectors.push(current);
+        }
+        selectors
+    }
+}
+
+impl StylePropertyNode {
+    #[allow(dead_code)]
+    pub fn name(&self) -> Option<String> {
+        self.name_token().map(|it| it.text().to_string())
+    }
+    /// The value, and where it's written
+    pub fn value(&self) -> Option<(String, rowan::TextRange)> {
+        self.param_value().map(|it| it.value())
+    }
+}
+
+impl ThemeNode {
+    /// The name of the theme, and where it's written
+    pub fn name(&self) -> Option<(String, rowan::TextRange)> {
+        let node = self.theme_name()?;
+        Some((node.syntax().text().to_string(), node.position()))
+    }
+    /// Where the theme comes from, for `!theme name from path`
+    pub fn source(&self) -> Option<String> {
+        Some(self.theme_source()?.syntax().text().to_string())
+    }
+}
+
+impl ArgumentNode {
+    #[allow(dead_code)]
+    pub fn text(&self) -> String {
+        self.0.text().to_string().trim().to_string()
+    }
+    /// The parameter a keyword argument such as `$greeting="Hi"` is for
+    #[allow(dead_code)]
+    pub fn keyword(&self) -> Option<String> {
+        let mut tokens = self
+            .0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() != WHITESPACE);
+        let first = tokens.next()?;
+        if first.kind() == VARIABLE && tokens.next()?.kind() == EQ {
+            Some(first.text().to_string())
+        } else {
+            None
+        }
+    }
 }
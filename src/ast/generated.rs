@@ -0,0 +1,2496 @@
+//! Generated from `src/puml.ungram` by the `codegen` test, don't edit by hand
+#![allow(dead_code)]
+
+use super::{SyntaxNode, SyntaxToken};
+use crate::syntax::SyntaxKind::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct RootNode(pub(super) SyntaxNode);
+
+impl RootNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            ROOT => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        self.0.children().filter_map(Statement::cast)
+    }
+    /// All classes, including the ones nested in packages
+    pub fn class_decls(&self) -> impl Iterator<Item = ClassDeclNode> {
+        self.0.descendants().filter_map(ClassDeclNode::cast)
+    }
+    /// All relations, including the ones nested in packages
+    pub fn relations(&self) -> impl Iterator<Item = RelationNode> {
+        self.0.descendants().filter_map(RelationNode::cast)
+    }
+    /// All activity actions, including the ones nested in blocks
+    pub fn actions(&self) -> impl Iterator<Item = ActionNode> {
+        self.0.descendants().filter_map(ActionNode::cast)
+    }
+    pub fn swimlanes(&self) -> impl Iterator<Item = SwimlaneNode> {
+        self.0.descendants().filter_map(SwimlaneNode::cast)
+    }
+    /// All states, including the ones nested in composite states
+    pub fn state_decls(&self) -> impl Iterator<Item = StateDeclNode> {
+        self.0.descendants().filter_map(StateDeclNode::cast)
+    }
+    /// All components, including the ones nested in containers
+    pub fn component_decls(&self) -> impl Iterator<Item = ComponentDeclNode> {
+        self.0.descendants().filter_map(ComponentDeclNode::cast)
+    }
+    /// All containers, including the ones nested in other containers
+    pub fn container_decls(&self) -> impl Iterator<Item = ContainerDeclNode> {
+        self.0.descendants().filter_map(ContainerDeclNode::cast)
+    }
+    pub fn actor_decls(&self) -> impl Iterator<Item = ActorDeclNode> {
+        self.0.descendants().filter_map(ActorDeclNode::cast)
+    }
+    pub fn usecase_decls(&self) -> impl Iterator<Item = UsecaseDeclNode> {
+        self.0.descendants().filter_map(UsecaseDeclNode::cast)
+    }
+    /// All objects and maps
+    pub fn object_decls(&self) -> impl Iterator<Item = ObjectDeclNode> {
+        self.0.descendants().filter_map(ObjectDeclNode::cast)
+    }
+    /// All gantt task statements, a task may be the subject of several
+    pub fn tasks(&self) -> impl Iterator<Item = TaskNode> {
+        self.0.descendants().filter_map(TaskNode::cast)
+    }
+    pub fn dependencies(&self) -> impl Iterator<Item = DependencyNode> {
+        self.0.descendants().filter_map(DependencyNode::cast)
+    }
+    pub fn project_start(&self) -> Option<ProjectStartNode> {
+        self.0.descendants().find_map(ProjectStartNode::cast)
+    }
+    pub fn closed_days(&self) -> impl Iterator<Item = ClosedDaysNode> {
+        self.0.descendants().filter_map(ClosedDaysNode::cast)
+    }
+    /// All mind map and WBS nodes, at any depth
+    pub fn mindmap_nodes(&self) -> impl Iterator<Item = MindmapNode> {
+        self.0.descendants().filter_map(MindmapNode::cast)
+    }
+    pub fn signal_decls(&self) -> impl Iterator<Item = SignalDeclNode> {
+        self.0.descendants().filter_map(SignalDeclNode::cast)
+    }
+    pub fn state_changes(&self) -> impl Iterator<Item = StateChangeNode> {
+        self.0.descendants().filter_map(StateChangeNode::cast)
+    }
+    /// Every `!include`, wherever it is
+    pub fn includes(&self) -> impl Iterator<Item = IncludeNode> {
+        self.0.descendants().filter_map(IncludeNode::cast)
+    }
+    /// The preprocessor directives that aren't includes
+    pub fn preprocessor_lines(&self) -> impl Iterator<Item = PreprocessorNode> {
+        self.0.descendants().filter_map(PreprocessorNode::cast)
+    }
+    /// Every `!$name = value`, in the order they're written
+    pub fn variable_defs(&self) -> impl Iterator<Item = VariableDefNode> {
+        self.0.descendants().filter_map(VariableDefNode::cast)
+    }
+    /// Every `!define` and `!definelong`
+    pub fn defines(&self) -> impl Iterator<Item = DefineNode> {
+        self.0.descendants().filter_map(DefineNode::cast)
+    }
+    /// Every `!if`, `!ifdef` and `!ifndef`, including the nested ones
+    pub fn conditionals(&self) -> impl Iterator<Item = PpIfNode> {
+        self.0.descendants().filter_map(PpIfNode::cast)
+    }
+    /// Every `!procedure` and `!function`
+    pub fn procedures(&self) -> impl Iterator<Item = ProcedureNode> {
+        self.0.descendants().filter_map(ProcedureNode::cast)
+    }
+    /// Every call of a procedure, function or macro, including the ones in the
+    /// arguments of other calls
+    pub fn calls(&self) -> impl Iterator<Item = CallNode> {
+        self.0.descendants().filter_map(CallNode::cast)
+    }
+    /// Every `skinparam`, single or block
+    pub fn skinparams(&self) -> impl Iterator<Item = SkinparamNode> {
+        self.0.descendants().filter_map(SkinparamNode::cast)
+    }
+    /// Every `<style>` sheet
+    pub fn styles(&self) -> impl Iterator<Item = StyleNode> {
+        self.0.descendants().filter_map(StyleNode::cast)
+    }
+    /// Every label, such as the text of an action or of a link
+    pub fn labels(&self) -> impl Iterator<Item = LabelNode> {
+        self.0.descendants().filter_map(LabelNode::cast)
+    }
+    /// Every `!theme`
+    pub fn themes(&self) -> impl Iterator<Item = ThemeNode> {
+        self.0.descendants().filter_map(ThemeNode::cast)
+    }
+    pub fn descriptions(&self) -> impl Iterator<Item = DescriptionNode> {
+        self.0.descendants().filter_map(DescriptionNode::cast)
+    }
+}
+
+/// A name, as written: `Name`, `"Long Name"`, `[Component]`, `(Use case)`
+/// or `:Actor:`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct IdentifierNode(pub(super) SyntaxNode);
+
+impl IdentifierNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            IDENTIFIER => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct BlockNode(pub(super) SyntaxNode);
+
+impl BlockNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            BLOCK => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        self.0.children().filter_map(Statement::cast)
+    }
+}
+
+/// The text of an action, a link or a note, with its Creole markup
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct LabelNode(pub(super) SyntaxNode);
+
+impl LabelNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            LABEL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn creole_emphases(&self) -> impl Iterator<Item = CreoleEmphasisNode> {
+        self.0.children().filter_map(CreoleEmphasisNode::cast)
+    }
+    pub fn creole_tags(&self) -> impl Iterator<Item = CreoleTagNode> {
+        self.0.children().filter_map(CreoleTagNode::cast)
+    }
+    pub fn calls(&self) -> impl Iterator<Item = CallNode> {
+        self.0.children().filter_map(CallNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct StereotypeNode(pub(super) SyntaxNode);
+
+impl StereotypeNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            STEREOTYPE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PackageDeclNode(pub(super) SyntaxNode);
+
+impl PackageDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PACKAGE_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn block(&self) -> Option<BlockNode> {
+        self.0.children().find_map(BlockNode::cast)
+    }
+    pub fn class_decls(&self) -> impl Iterator<Item = ClassDeclNode> {
+        self.0.descendants().filter_map(ClassDeclNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ClassDeclNode(pub(super) SyntaxNode);
+
+impl ClassDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CLASS_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn generic_params(&self) -> Option<GenericParamsNode> {
+        self.0.children().find_map(GenericParamsNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn extends_clause(&self) -> Option<ExtendsClauseNode> {
+        self.0.children().find_map(ExtendsClauseNode::cast)
+    }
+    pub fn implements_clause(&self) -> Option<ImplementsClauseNode> {
+        self.0.children().find_map(ImplementsClauseNode::cast)
+    }
+    pub fn class_body(&self) -> Option<ClassBodyNode> {
+        self.0.children().find_map(ClassBodyNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct GenericParamsNode(pub(super) SyntaxNode);
+
+impl GenericParamsNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            GENERIC_PARAMS => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ExtendsClauseNode(pub(super) SyntaxNode);
+
+impl ExtendsClauseNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            EXTENDS_CLAUSE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn identifiers(&self) -> impl Iterator<Item = IdentifierNode> {
+        self.0.children().filter_map(IdentifierNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ImplementsClauseNode(pub(super) SyntaxNode);
+
+impl ImplementsClauseNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            IMPLEMENTS_CLAUSE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn identifiers(&self) -> impl Iterator<Item = IdentifierNode> {
+        self.0.children().filter_map(IdentifierNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ClassBodyNode(pub(super) SyntaxNode);
+
+impl ClassBodyNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CLASS_BODY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn members(&self) -> impl Iterator<Item = MemberNode> {
+        self.0.children().filter_map(MemberNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct MemberNode(pub(super) SyntaxNode);
+
+impl MemberNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            FIELD | METHOD => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn visibility(&self) -> Option<VisibilityNode> {
+        self.0.children().find_map(VisibilityNode::cast)
+    }
+    pub fn modifiers(&self) -> impl Iterator<Item = ModifierNode> {
+        self.0.children().filter_map(ModifierNode::cast)
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+}
+
+/// `+`, `-`, `#` or `~`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct VisibilityNode(pub(super) SyntaxNode);
+
+impl VisibilityNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            VISIBILITY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+/// `{static}`, `{classifier}` or `{abstract}`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ModifierNode(pub(super) SyntaxNode);
+
+impl ModifierNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            MODIFIER => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct RelationNode(pub(super) SyntaxNode);
+
+impl RelationNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            RELATION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn left(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .take_while(|it| it.kind() != ARROW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn right(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != ARROW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn cardinalities(&self) -> impl Iterator<Item = CardinalityNode> {
+        self.0.children().filter_map(CardinalityNode::cast)
+    }
+    pub fn arrow(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == ARROW)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct CardinalityNode(pub(super) SyntaxNode);
+
+impl CardinalityNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CARDINALITY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ParticipantDeclNode(pub(super) SyntaxNode);
+
+impl ParticipantDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PARTICIPANT_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct TerminalNode(pub(super) SyntaxNode);
+
+impl TerminalNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            START | STOP => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ActionNode(pub(super) SyntaxNode);
+
+impl ActionNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            ACTION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ActivityArrowNode(pub(super) SyntaxNode);
+
+impl ActivityArrowNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            ACTIVITY_ARROW => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+/// `if`, `while`, `repeat`, `fork` or `split`, with the statements of each
+/// of their branches
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ControlNode(pub(super) SyntaxNode);
+
+impl ControlNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            IF_BLOCK | WHILE_BLOCK | REPEAT_BLOCK | FORK_BLOCK | SPLIT_BLOCK => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn branches(&self) -> impl Iterator<Item = BranchNode> {
+        self.0.children().filter_map(BranchNode::cast)
+    }
+}
+
+/// The `(a)` of `if (a) then (yes)`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ConditionNode(pub(super) SyntaxNode);
+
+impl ConditionNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CONDITION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct BranchNode(pub(super) SyntaxNode);
+
+impl BranchNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            BRANCH => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        self.0.children().filter_map(Statement::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SwimlaneNode(pub(super) SyntaxNode);
+
+impl SwimlaneNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SWIMLANE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PartitionNode(pub(super) SyntaxNode);
+
+impl PartitionNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PARTITION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn block(&self) -> Option<BlockNode> {
+        self.0.children().find_map(BlockNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct StateDeclNode(pub(super) SyntaxNode);
+
+impl StateDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            STATE_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+    pub fn state_body(&self) -> Option<StateBodyNode> {
+        self.0.children().find_map(StateBodyNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct StateBodyNode(pub(super) SyntaxNode);
+
+impl StateBodyNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            STATE_BODY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn regions(&self) -> impl Iterator<Item = RegionNode> {
+        self.0.children().filter_map(RegionNode::cast)
+    }
+}
+
+/// One of the concurrent parts of a composite state, separated by `--` or
+/// `||`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct RegionNode(pub(super) SyntaxNode);
+
+impl RegionNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            REGION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        self.0.children().filter_map(Statement::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DescriptionNode(pub(super) SyntaxNode);
+
+impl DescriptionNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            DESCRIPTION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ComponentDeclNode(pub(super) SyntaxNode);
+
+impl ComponentDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            COMPONENT_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn block(&self) -> Option<BlockNode> {
+        self.0.children().find_map(BlockNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ContainerDeclNode(pub(super) SyntaxNode);
+
+impl ContainerDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CONTAINER_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn keyword(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| {
+                matches!(
+                    it.kind(),
+                    NODE_KW | FOLDER_KW | FRAME_KW | CLOUD_KW | DATABASE_KW | RECTANGLE_KW
+                )
+            })
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .take_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn block(&self) -> Option<BlockNode> {
+        self.0.children().find_map(BlockNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PortDeclNode(pub(super) SyntaxNode);
+
+impl PortDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PORT_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ActorDeclNode(pub(super) SyntaxNode);
+
+impl ActorDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            ACTOR_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn block(&self) -> Option<BlockNode> {
+        self.0.children().find_map(BlockNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct UsecaseDeclNode(pub(super) SyntaxNode);
+
+impl UsecaseDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            USECASE_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn block(&self) -> Option<BlockNode> {
+        self.0.children().find_map(BlockNode::cast)
+    }
+}
+
+/// `left to right direction` or `top to bottom direction`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DirectionNode(pub(super) SyntaxNode);
+
+impl DirectionNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            DIRECTION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ObjectDeclNode(pub(super) SyntaxNode);
+
+impl ObjectDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            OBJECT_DECL | MAP_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn object_body(&self) -> Option<ObjectBodyNode> {
+        self.0.children().find_map(ObjectBodyNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ObjectBodyNode(pub(super) SyntaxNode);
+
+impl ObjectBodyNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            OBJECT_BODY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn entries(&self) -> impl Iterator<Item = EntryNode> {
+        self.0.children().filter_map(EntryNode::cast)
+    }
+}
+
+/// `field = value`, `key => value` or `key *-> Other`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct EntryNode(pub(super) SyntaxNode);
+
+impl EntryNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            ENTRY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn key(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn target(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != ARROW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn value(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct TaskNode(pub(super) SyntaxNode);
+
+impl TaskNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            TASK_STATEMENT => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn duration(&self) -> Option<DurationNode> {
+        self.0.children().find_map(DurationNode::cast)
+    }
+    pub fn constraints(&self) -> impl Iterator<Item = ConstraintNode> {
+        self.0.children().filter_map(ConstraintNode::cast)
+    }
+    pub fn resources(&self) -> impl Iterator<Item = ResourceNode> {
+        self.0.children().filter_map(ResourceNode::cast)
+    }
+}
+
+/// `lasts 10 days` or `requires 2 weeks`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DurationNode(pub(super) SyntaxNode);
+
+impl DurationNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            DURATION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+/// `starts 2020-07-01` or `ends 2 days after [A]'s end`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ConstraintNode(pub(super) SyntaxNode);
+
+impl ConstraintNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CONSTRAINT => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn task(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+}
+
+/// `{Alice}` or `{Bob:50%}`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ResourceNode(pub(super) SyntaxNode);
+
+impl ResourceNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            RESOURCE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DependencyNode(pub(super) SyntaxNode);
+
+impl DependencyNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            DEPENDENCY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn tasks(&self) -> impl Iterator<Item = IdentifierNode> {
+        self.0.children().filter_map(IdentifierNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ProjectStartNode(pub(super) SyntaxNode);
+
+impl ProjectStartNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PROJECT_START => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ClosedDaysNode(pub(super) SyntaxNode);
+
+impl ClosedDaysNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CLOSED_DAYS => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+/// A node of a mind map, followed by the nodes that are deeper than it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct MindmapNode(pub(super) SyntaxNode);
+
+impl MindmapNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            MINDMAP_NODE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        self.0.children().filter_map(Statement::cast)
+    }
+}
+
+/// `left side` or `right side`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SideNode(pub(super) SyntaxNode);
+
+impl SideNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SIDE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SignalDeclNode(pub(super) SyntaxNode);
+
+impl SignalDeclNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SIGNAL_DECL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn alias(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+}
+
+/// `WB has Idle,Processing,Waiting`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SignalStatesNode(pub(super) SyntaxNode);
+
+impl SignalStatesNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SIGNAL_STATES => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn signal(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+    pub fn states(&self) -> impl Iterator<Item = IdentifierNode> {
+        self.0.children().filter_map(IdentifierNode::cast).skip(1)
+    }
+}
+
+/// `100`, `+50`, `:label` or `:label+50`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct TimeNode(pub(super) SyntaxNode);
+
+impl TimeNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            TIME => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct TimeAnchorNode(pub(super) SyntaxNode);
+
+impl TimeAnchorNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            TIME_ANCHOR => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn time(&self) -> Option<TimeNode> {
+        self.0.children().find_map(TimeNode::cast)
+    }
+    pub fn signal(&self) -> Option<IdentifierNode> {
+        self.0.children().find_map(IdentifierNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct StateChangeNode(pub(super) SyntaxNode);
+
+impl StateChangeNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            STATE_CHANGE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn time(&self) -> Option<TimeNode> {
+        self.0.children().find_map(TimeNode::cast)
+    }
+    pub fn signal(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .take_while(|it| it.kind() != IS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn state(&self) -> Option<IdentifierNode> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != IS_KW)
+            .filter_map(|it| it.into_node())
+            .find_map(IdentifierNode::cast)
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+/// `highlight 200 to 450 #Gold : caption`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct HighlightNode(pub(super) SyntaxNode);
+
+impl HighlightNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            HIGHLIGHT => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn times(&self) -> impl Iterator<Item = TimeNode> {
+        self.0.children().filter_map(TimeNode::cast)
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct TimingLinkNode(pub(super) SyntaxNode);
+
+impl TimingLinkNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            TIMING_CONSTRAINT | TIMING_MESSAGE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn times(&self) -> impl Iterator<Item = TimeNode> {
+        self.0.children().filter_map(TimeNode::cast)
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+/// The body of a `@startjson` or `@startyaml` diagram
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DataNode(pub(super) SyntaxNode);
+
+impl DataNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            JSON_DATA | YAML_DATA => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+/// `#highlight "address" / "city"`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DataHighlightNode(pub(super) SyntaxNode);
+
+impl DataHighlightNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            DATA_HIGHLIGHT => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SaltDiagramNode(pub(super) SyntaxNode);
+
+impl SaltDiagramNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SALT_DIAGRAM => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn group(&self) -> Option<SaltGroupNode> {
+        self.0.children().find_map(SaltGroupNode::cast)
+    }
+}
+
+/// `{ ... }`, with cells separated by `|` and rows by line breaks
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SaltGroupNode(pub(super) SyntaxNode);
+
+impl SaltGroupNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SALT_GROUP => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn groups(&self) -> impl Iterator<Item = SaltGroupNode> {
+        self.0.children().filter_map(SaltGroupNode::cast)
+    }
+    pub fn widgets(&self) -> impl Iterator<Item = SaltWidgetNode> {
+        self.0.children().filter_map(SaltWidgetNode::cast)
+    }
+}
+
+/// `[Ok]`, `[X] checked`, `(X) chosen`, `"text field"` or `^droplist^`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SaltWidgetNode(pub(super) SyntaxNode);
+
+impl SaltWidgetNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SALT_WIDGET => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct IncludeNode(pub(super) SyntaxNode);
+
+impl IncludeNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            INCLUDE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn include_path(&self) -> Option<IncludePathNode> {
+        self.0.children().find_map(IncludePathNode::cast)
+    }
+}
+
+/// The file `!include` includes, and the part of it after any `!`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct IncludePathNode(pub(super) SyntaxNode);
+
+impl IncludePathNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            INCLUDE_PATH => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+/// A directive other than the ones with a node of their own, such as
+/// `!startsub`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PreprocessorNode(pub(super) SyntaxNode);
+
+impl PreprocessorNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PREPROCESSOR => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct VariableDefNode(pub(super) SyntaxNode);
+
+impl VariableDefNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            VARIABLE_DEF => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn variable(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == VARIABLE)
+    }
+    pub fn value(&self) -> Option<ExpressionNode> {
+        self.0.children().find_map(ExpressionNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ExpressionNode(pub(super) SyntaxNode);
+
+impl ExpressionNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            EXPRESSION => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn calls(&self) -> impl Iterator<Item = CallNode> {
+        self.0.children().filter_map(CallNode::cast)
+    }
+}
+
+/// `!define` or `!definelong`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DefineNode(pub(super) SyntaxNode);
+
+impl DefineNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            DEFINE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == IDENTIFIER)
+    }
+    pub fn label(&self) -> Option<LabelNode> {
+        self.0.children().find_map(LabelNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct UndefNode(pub(super) SyntaxNode);
+
+impl UndefNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            UNDEF => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == IDENTIFIER)
+    }
+}
+
+/// An `!if`, `!ifdef` or `!ifndef`, with its `!elseif` and `!else` branches
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PpIfNode(pub(super) SyntaxNode);
+
+impl PpIfNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PP_IF => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn branches(&self) -> impl Iterator<Item = PpBranchNode> {
+        self.0.children().filter_map(PpBranchNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PpBranchNode(pub(super) SyntaxNode);
+
+impl PpBranchNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PP_BRANCH => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn condition(&self) -> Option<ExpressionNode> {
+        self.0.children().find_map(ExpressionNode::cast)
+    }
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        self.0.children().filter_map(Statement::cast)
+    }
+}
+
+/// `!procedure` or `!function`, with its body
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ProcedureNode(pub(super) SyntaxNode);
+
+impl ProcedureNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PROCEDURE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn param_list(&self) -> Option<ParamListNode> {
+        self.0.children().find_map(ParamListNode::cast)
+    }
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        self.0.children().filter_map(Statement::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ParamListNode(pub(super) SyntaxNode);
+
+impl ParamListNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PARAM_LIST => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn params(&self) -> impl Iterator<Item = ParamNode> {
+        self.0.children().filter_map(ParamNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ParamNode(pub(super) SyntaxNode);
+
+impl ParamNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PARAM => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn expression(&self) -> Option<ExpressionNode> {
+        self.0.children().find_map(ExpressionNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ReturnNode(pub(super) SyntaxNode);
+
+impl ReturnNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            RETURN => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn value(&self) -> Option<ExpressionNode> {
+        self.0.children().find_map(ExpressionNode::cast)
+    }
+}
+
+/// A call of a procedure, a function or a macro
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct CallNode(pub(super) SyntaxNode);
+
+impl CallNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CALL => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| matches!(it.kind(), IDENTIFIER | VARIABLE | BUILTIN))
+    }
+    pub fn arg_list(&self) -> Option<ArgListNode> {
+        self.0.children().find_map(ArgListNode::cast)
+    }
+    pub fn block(&self) -> Option<BlockNode> {
+        self.0.children().find_map(BlockNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ArgListNode(pub(super) SyntaxNode);
+
+impl ArgListNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            ARG_LIST => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn arguments(&self) -> impl Iterator<Item = ArgumentNode> {
+        self.0.children().filter_map(ArgumentNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ArgumentNode(pub(super) SyntaxNode);
+
+impl ArgumentNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            ARGUMENT => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn expression(&self) -> Option<ExpressionNode> {
+        self.0.children().find_map(ExpressionNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SkinparamNode(pub(super) SyntaxNode);
+
+impl SkinparamNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SKINPARAM => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn entries(&self) -> impl Iterator<Item = SkinparamEntryNode> {
+        self.0.children().filter_map(SkinparamEntryNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SkinparamEntryNode(pub(super) SyntaxNode);
+
+impl SkinparamEntryNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SKINPARAM_ENTRY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn key_token(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == IDENTIFIER)
+    }
+    pub fn stereotype(&self) -> Option<StereotypeNode> {
+        self.0.children().find_map(StereotypeNode::cast)
+    }
+    pub fn param_value(&self) -> Option<ParamValueNode> {
+        self.0.children().find_map(ParamValueNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ParamValueNode(pub(super) SyntaxNode);
+
+impl ParamValueNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PARAM_VALUE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+/// `<style>` ... `</style>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct StyleNode(pub(super) SyntaxNode);
+
+impl StyleNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            STYLE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn rules(&self) -> impl Iterator<Item = StyleRuleNode> {
+        self.0.children().filter_map(StyleRuleNode::cast)
+    }
+    pub fn properties(&self) -> impl Iterator<Item = StylePropertyNode> {
+        self.0.children().filter_map(StylePropertyNode::cast)
+    }
+}
+
+/// `classDiagram, .entity { ... }`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct StyleRuleNode(pub(super) SyntaxNode);
+
+impl StyleRuleNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            STYLE_RULE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn rules(&self) -> impl Iterator<Item = StyleRuleNode> {
+        self.0.children().filter_map(StyleRuleNode::cast)
+    }
+    pub fn properties(&self) -> impl Iterator<Item = StylePropertyNode> {
+        self.0.children().filter_map(StylePropertyNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct StylePropertyNode(pub(super) SyntaxNode);
+
+impl StylePropertyNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            STYLE_PROPERTY => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == IDENTIFIER)
+    }
+    pub fn param_value(&self) -> Option<ParamValueNode> {
+        self.0.children().find_map(ParamValueNode::cast)
+    }
+}
+
+/// `!theme cerulean`, or `!theme mine from ./themes`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ThemeNode(pub(super) SyntaxNode);
+
+impl ThemeNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            THEME => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn theme_name(&self) -> Option<ThemeNameNode> {
+        self.0.children().find_map(ThemeNameNode::cast)
+    }
+    pub fn theme_source(&self) -> Option<ThemeSourceNode> {
+        self.0.children().find_map(ThemeSourceNode::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ThemeNameNode(pub(super) SyntaxNode);
+
+impl ThemeNameNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            THEME_NAME => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ThemeSourceNode(pub(super) SyntaxNode);
+
+impl ThemeSourceNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            THEME_SOURCE => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+}
+
+/// `**bold**`, `//italic//`, `""monospaced""`, `--stricken--` or
+/// `__underlined__` text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct CreoleEmphasisNode(pub(super) SyntaxNode);
+
+impl CreoleEmphasisNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CREOLE_BOLD | CREOLE_ITALIC | CREOLE_MONOSPACED | CREOLE_STRIKE | CREOLE_UNDERLINE => {
+                Some(Self(node))
+            }
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn creole_emphases(&self) -> impl Iterator<Item = CreoleEmphasisNode> {
+        self.0.children().filter_map(CreoleEmphasisNode::cast)
+    }
+    pub fn creole_tags(&self) -> impl Iterator<Item = CreoleTagNode> {
+        self.0.children().filter_map(CreoleTagNode::cast)
+    }
+    pub fn calls(&self) -> impl Iterator<Item = CallNode> {
+        self.0.children().filter_map(CallNode::cast)
+    }
+}
+
+/// A CREOLE_TAG_START, the text it applies to, and its CREOLE_TAG_END
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct CreoleTagNode(pub(super) SyntaxNode);
+
+impl CreoleTagNode {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            CREOLE_TAG => Some(Self(node)),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.0.text_range()
+    }
+    pub fn creole_emphases(&self) -> impl Iterator<Item = CreoleEmphasisNode> {
+        self.0.children().filter_map(CreoleEmphasisNode::cast)
+    }
+    pub fn creole_tags(&self) -> impl Iterator<Item = CreoleTagNode> {
+        self.0.children().filter_map(CreoleTagNode::cast)
+    }
+    pub fn calls(&self) -> impl Iterator<Item = CallNode> {
+        self.0.children().filter_map(CallNode::cast)
+    }
+}
+
+/// What a line, or a braced block, of a diagram may be
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
+pub enum Statement {
+    ParticipantDecl(ParticipantDeclNode),
+    PackageDecl(PackageDeclNode),
+    ClassDecl(ClassDeclNode),
+    Relation(RelationNode),
+    Terminal(TerminalNode),
+    Action(ActionNode),
+    ActivityArrow(ActivityArrowNode),
+    Control(ControlNode),
+    Swimlane(SwimlaneNode),
+    Partition(PartitionNode),
+    StateDecl(StateDeclNode),
+    Description(DescriptionNode),
+    ComponentDecl(ComponentDeclNode),
+    ContainerDecl(ContainerDeclNode),
+    PortDecl(PortDeclNode),
+    ActorDecl(ActorDeclNode),
+    UsecaseDecl(UsecaseDeclNode),
+    Direction(DirectionNode),
+    ObjectDecl(ObjectDeclNode),
+    Task(TaskNode),
+    Dependency(DependencyNode),
+    ProjectStart(ProjectStartNode),
+    ClosedDays(ClosedDaysNode),
+    Mindmap(MindmapNode),
+    Side(SideNode),
+    SignalDecl(SignalDeclNode),
+    SignalStates(SignalStatesNode),
+    TimeAnchor(TimeAnchorNode),
+    StateChange(StateChangeNode),
+    Highlight(HighlightNode),
+    TimingLink(TimingLinkNode),
+    Data(DataNode),
+    DataHighlight(DataHighlightNode),
+    SaltDiagram(SaltDiagramNode),
+    SaltGroup(SaltGroupNode),
+    Include(IncludeNode),
+    Preprocessor(PreprocessorNode),
+    VariableDef(VariableDefNode),
+    Define(DefineNode),
+    Undef(UndefNode),
+    PpIf(PpIfNode),
+    Procedure(ProcedureNode),
+    Return(ReturnNode),
+    Call(CallNode),
+    Skinparam(SkinparamNode),
+    Style(StyleNode),
+    Theme(ThemeNode),
+}
+
+impl Statement {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            PARTICIPANT_DECL => Some(Self::ParticipantDecl(ParticipantDeclNode(node))),
+            PACKAGE_DECL => Some(Self::PackageDecl(PackageDeclNode(node))),
+            CLASS_DECL => Some(Self::ClassDecl(ClassDeclNode(node))),
+            RELATION => Some(Self::Relation(RelationNode(node))),
+            START | STOP => Some(Self::Terminal(TerminalNode(node))),
+            ACTION => Some(Self::Action(ActionNode(node))),
+            ACTIVITY_ARROW => Some(Self::ActivityArrow(ActivityArrowNode(node))),
+            IF_BLOCK | WHILE_BLOCK | REPEAT_BLOCK | FORK_BLOCK | SPLIT_BLOCK => {
+                Some(Self::Control(ControlNode(node)))
+            }
+            SWIMLANE => Some(Self::Swimlane(SwimlaneNode(node))),
+            PARTITION => Some(Self::Partition(PartitionNode(node))),
+            STATE_DECL => Some(Self::StateDecl(StateDeclNode(node))),
+            DESCRIPTION => Some(Self::Description(DescriptionNode(node))),
+            COMPONENT_DECL => Some(Self::ComponentDecl(ComponentDeclNode(node))),
+            CONTAINER_DECL => Some(Self::ContainerDecl(ContainerDeclNode(node))),
+            PORT_DECL => Some(Self::PortDecl(PortDeclNode(node))),
+            ACTOR_DECL => Some(Self::ActorDecl(ActorDeclNode(node))),
+            USECASE_DECL => Some(Self::UsecaseDecl(UsecaseDeclNode(node))),
+            DIRECTION => Some(Self::Direction(DirectionNode(node))),
+            OBJECT_DECL | MAP_DECL => Some(Self::ObjectDecl(ObjectDeclNode(node))),
+            TASK_STATEMENT => Some(Self::Task(TaskNode(node))),
+            DEPENDENCY => Some(Self::Dependency(DependencyNode(node))),
+            PROJECT_START => Some(Self::ProjectStart(ProjectStartNode(node))),
+            CLOSED_DAYS => Some(Self::ClosedDays(ClosedDaysNode(node))),
+            MINDMAP_NODE => Some(Self::Mindmap(MindmapNode(node))),
+            SIDE => Some(Self::Side(SideNode(node))),
+            SIGNAL_DECL => Some(Self::SignalDecl(SignalDeclNode(node))),
+            SIGNAL_STATES => Some(Self::SignalStates(SignalStatesNode(node))),
+            TIME_ANCHOR => Some(Self::TimeAnchor(TimeAnchorNode(node))),
+            STATE_CHANGE => Some(Self::StateChange(StateChangeNode(node))),
+            HIGHLIGHT => Some(Self::Highlight(HighlightNode(node))),
+            TIMING_CONSTRAINT | TIMING_MESSAGE => Some(Self::TimingLink(TimingLinkNode(node))),
+            JSON_DATA | YAML_DATA => Some(Self::Data(DataNode(node))),
+            DATA_HIGHLIGHT => Some(Self::DataHighlight(DataHighlightNode(node))),
+            SALT_DIAGRAM => Some(Self::SaltDiagram(SaltDiagramNode(node))),
+            SALT_GROUP => Some(Self::SaltGroup(SaltGroupNode(node))),
+            INCLUDE => Some(Self::Include(IncludeNode(node))),
+            PREPROCESSOR => Some(Self::Preprocessor(PreprocessorNode(node))),
+            VARIABLE_DEF => Some(Self::VariableDef(VariableDefNode(node))),
+            DEFINE => Some(Self::Define(DefineNode(node))),
+            UNDEF => Some(Self::Undef(UndefNode(node))),
+            PP_IF => Some(Self::PpIf(PpIfNode(node))),
+            PROCEDURE => Some(Self::Procedure(ProcedureNode(node))),
+            RETURN => Some(Self::Return(ReturnNode(node))),
+            CALL => Some(Self::Call(CallNode(node))),
+            SKINPARAM => Some(Self::Skinparam(SkinparamNode(node))),
+            STYLE => Some(Self::Style(StyleNode(node))),
+            THEME => Some(Self::Theme(ThemeNode(node))),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::ParticipantDecl(it) => &it.0,
+            Self::PackageDecl(it) => &it.0,
+            Self::ClassDecl(it) => &it.0,
+            Self::Relation(it) => &it.0,
+            Self::Terminal(it) => &it.0,
+            Self::Action(it) => &it.0,
+            Self::ActivityArrow(it) => &it.0,
+            Self::Control(it) => &it.0,
+            Self::Swimlane(it) => &it.0,
+            Self::Partition(it) => &it.0,
+            Self::StateDecl(it) => &it.0,
+            Self::Description(it) => &it.0,
+            Self::ComponentDecl(it) => &it.0,
+            Self::ContainerDecl(it) => &it.0,
+            Self::PortDecl(it) => &it.0,
+            Self::ActorDecl(it) => &it.0,
+            Self::UsecaseDecl(it) => &it.0,
+            Self::Direction(it) => &it.0,
+            Self::ObjectDecl(it) => &it.0,
+            Self::Task(it) => &it.0,
+            Self::Dependency(it) => &it.0,
+            Self::ProjectStart(it) => &it.0,
+            Self::ClosedDays(it) => &it.0,
+            Self::Mindmap(it) => &it.0,
+            Self::Side(it) => &it.0,
+            Self::SignalDecl(it) => &it.0,
+            Self::SignalStates(it) => &it.0,
+            Self::TimeAnchor(it) => &it.0,
+            Self::StateChange(it) => &it.0,
+            Self::Highlight(it) => &it.0,
+            Self::TimingLink(it) => &it.0,
+            Self::Data(it) => &it.0,
+            Self::DataHighlight(it) => &it.0,
+            Self::SaltDiagram(it) => &it.0,
+            Self::SaltGroup(it) => &it.0,
+            Self::Include(it) => &it.0,
+            Self::Preprocessor(it) => &it.0,
+            Self::VariableDef(it) => &it.0,
+            Self::Define(it) => &it.0,
+            Self::Undef(it) => &it.0,
+            Self::PpIf(it) => &it.0,
+            Self::Procedure(it) => &it.0,
+            Self::Return(it) => &it.0,
+            Self::Call(it) => &it.0,
+            Self::Skinparam(it) => &it.0,
+            Self::Style(it) => &it.0,
+            Self::Theme(it) => &it.0,
+        }
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.syntax().text_range()
+    }
+}
+
+/// What links two elements of a diagram
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
+pub enum Arrow {
+    Relation(RelationNode),
+    ActivityArrow(ActivityArrowNode),
+    TimingLink(TimingLinkNode),
+}
+
+impl Arrow {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            RELATION => Some(Self::Relation(RelationNode(node))),
+            ACTIVITY_ARROW => Some(Self::ActivityArrow(ActivityArrowNode(node))),
+            TIMING_CONSTRAINT | TIMING_MESSAGE => Some(Self::TimingLink(TimingLinkNode(node))),
+            _ => None,
+        }
+    }
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Relation(it) => &it.0,
+            Self::ActivityArrow(it) => &it.0,
+            Self::TimingLink(it) => &it.0,
+        }
+    }
+    pub(crate) fn position(&self) -> rowan::TextRange {
+        self.syntax().text_range()
+    }
+}
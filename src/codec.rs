@@ -30,7 +30,7 @@ impl Encoder<LspEvent> for LspCodec {
     }
 }
 
-fn parse_header<'a>(input: &'a [u8]) -> nom::IResult<&'a [u8], String> {
+fn parse_header(input: &[u8]) -> nom::IResult<&[u8], String> {
     use nom::error::ErrorKind;
 
     let (input, _) = nom::bytes::complete::tag("Content-Length: ")(input)?;
@@ -45,7 +45,7 @@ fn parse_header<'a>(input: &'a [u8]) -> nom::IResult<&'a [u8], String> {
     parse(input, content_length)
 }
 
-fn parse<'a>(input: &[u8], count: usize) -> nom::IResult<&[u8], String> {
+fn parse(input: &[u8], count: usize) -> nom::IResult<&[u8], String> {
     use nom::error::ErrorKind;
     let (input, json) = nom::bytes::complete::take(count)(input)?;
     std::str::from_utf8(json)
@@ -59,16 +59,16 @@ impl Decoder for LspCodec {
 
     fn decode(&mut self, b: &mut BytesMut) -> std::result::Result<Option<LspEvent>, io::Error> {
         trace!(target: "decode", "bytes len {}", b.len());
-        if b.len() == 0 {
+        if b.is_empty() {
             return Ok(None);
         }
 
         //let z = b.clone();
-        if let Ok((input, payload)) = parse_header(&b) {
+        if let Ok((_input, payload)) = parse_header(b) {
             b.advance(b.len());
             return Ok(Some(LspEvent::Message(payload)));
         }
-        return Ok(None);
+        Ok(None)
         /*Err(std::io::Error::new(
             std::io::ErrorKind::InvalidInput,
             "Can't parse json",
@@ -0,0 +1,918 @@
+//! Generates `SyntaxKind`, with the text of its keywords and punctuation, and
+//! the typed nodes of `ast`, with their accessors, from the grammar in
+//! `src/puml.ungram`, which is written the way ungrammar is. The
+//! `generated_code_is_up_to_date` test regenerates them whenever the grammar
+//! changes.
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const HEADER: &str =
+    "//! Generated from `src/puml.ungram` by the `codegen` test, don't edit by hand";
+
+/// The rules go past this, as the generated code is formatted
+const MAX_WIDTH: usize = 100;
+
+/// The longest method chain rustfmt leaves on one line
+const CHAIN_WIDTH: usize = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Doc(String),
+    Punct(char),
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '/' => {
+                let line =
+                    std::iter::from_fn(|| chars.next_if(|it| *it != '\n')).collect::<String>();
+                match line.strip_prefix("//") {
+                    Some(doc) => tokens.push(Token::Doc(doc.trim().to_string())),
+                    None if line.starts_with('/') => {}
+                    None => panic!("expected a comment, not '/{}'", line),
+                }
+            }
+            '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                        None => panic!("unterminated token '{}", text),
+                    }
+                }
+                tokens.push(Token::Str(text));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let rest = std::iter::from_fn(|| {
+                    chars.next_if(|it| it.is_ascii_alphanumeric() || *it == '_')
+                });
+                tokens.push(Token::Ident(std::iter::once(c).chain(rest).collect()));
+            }
+            '=' | '|' | '?' | '*' | '(' | ')' | ':' | '[' | ']' => tokens.push(Token::Punct(c)),
+            c => panic!("unexpected '{}' in the grammar", c),
+        }
+    }
+    tokens
+}
+
+/// The right hand side of a rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    /// Another rule, by its name
+    Node(String),
+    /// A token, by the name of its kind
+    Token(String),
+    Labeled(String, Box<Rule>),
+    Seq(Vec<Rule>),
+    Alt(Vec<Rule>),
+    Opt(Box<Rule>),
+    Rep(Box<Rule>),
+}
+
+#[derive(Debug)]
+struct TokenDecl {
+    kind: String,
+    text: Option<String>,
+    doc: Vec<String>,
+}
+
+#[derive(Debug)]
+struct NodeDecl {
+    name: String,
+    /// The kinds the node may be, none if it's an enum of other nodes
+    kinds: Vec<String>,
+    doc: Vec<String>,
+    rule: Rule,
+}
+
+/// `query Root class_decls:ClassDecl*`, a method of the owner for the nodes
+/// of a type wherever they are in it
+#[derive(Debug)]
+struct QueryDecl {
+    owner: String,
+    name: String,
+    node: String,
+    many: bool,
+    doc: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Grammar {
+    tokens: Vec<TokenDecl>,
+    nodes: Vec<NodeDecl>,
+    queries: Vec<QueryDecl>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n)
+    }
+
+    fn bump(&mut self) -> Token {
+        self.pos += 1;
+        self.tokens[self.pos - 1].clone()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.nth(0) == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) {
+        assert!(self.eat(c), "expected '{}', not {:?}", c, self.nth(0));
+    }
+
+    fn ident(&mut self) -> String {
+        match self.bump() {
+            Token::Ident(name) => name,
+            token => panic!("expected a name, not {:?}", token),
+        }
+    }
+
+    /// Whether the next tokens start a declaration rather than go on with a
+    /// rule
+    fn at_decl(&self) -> bool {
+        match (self.nth(0), self.nth(1)) {
+            (None, _) | (Some(Token::Doc(_)), _) => true,
+            (Some(Token::Ident(name)), Some(Token::Ident(_))) => name == "token" || name == "query",
+            (Some(Token::Ident(_)), Some(Token::Punct('='))) => true,
+            (Some(Token::Ident(_)), Some(Token::Punct('['))) => true,
+            _ => false,
+        }
+    }
+
+    fn alt(&mut self) -> Rule {
+        let mut alts = vec![self.seq()];
+        while self.eat('|') {
+            alts.push(self.seq());
+        }
+        if alts.len() == 1 {
+            alts.pop().unwrap()
+        } else {
+            Rule::Alt(alts)
+        }
+    }
+
+    fn seq(&mut self) -> Rule {
+        let mut seq = Vec::new();
+        while !self.at_decl()
+            && matches!(
+                self.nth(0),
+                Some(Token::Ident(_)) | Some(Token::Str(_)) | Some(Token::Punct('('))
+            )
+        {
+            seq.push(self.postfix());
+        }
+        assert!(!seq.is_empty(), "expected a rule, not {:?}", self.nth(0));
+        if seq.len() == 1 {
+            seq.pop().unwrap()
+        } else {
+            Rule::Seq(seq)
+        }
+    }
+
+    fn postfix(&mut self) -> Rule {
+        let mut rule = self.atom();
+        loop {
+            if self.eat('?') {
+                rule = Rule::Opt(Box::new(rule));
+            } else if self.eat('*') {
+                rule = Rule::Rep(Box::new(rule));
+            } else {
+                return rule;
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Rule {
+        match self.bump() {
+            Token::Ident(name) if self.eat(':') => Rule::Labeled(name, Box::new(self.postfix())),
+            Token::Ident(name) => Rule::Node(name),
+            Token::Str(text) => Rule::Token(text),
+            Token::Punct('(') => {
+                let rule = self.alt();
+                self.expect(')');
+                rule
+            }
+            token => panic!("expected a rule, not {:?}", token),
+        }
+    }
+}
+
+impl Grammar {
+    fn parse(text: &str) -> Grammar {
+        let mut parser = Parser {
+            tokens: tokenize(text),
+            pos: 0,
+        };
+        let mut grammar = Grammar {
+            tokens: Vec::new(),
+            nodes: Vec::new(),
+            queries: Vec::new(),
+        };
+        while parser.nth(0).is_some() {
+            let mut doc = Vec::new();
+            while let Some(Token::Doc(line)) = parser.nth(0) {
+                doc.push(line.clone());
+                parser.bump();
+            }
+            let name = parser.ident();
+            if name == "token" {
+                let kind = parser.ident();
+                let text = match parser.nth(0) {
+                    Some(Token::Str(text)) => Some(text.clone()),
+                    _ => None,
+                };
+                if text.is_some() {
+                    parser.bump();
+                }
+                grammar.tokens.push(TokenDecl { kind, text, doc });
+                continue;
+            }
+            if name == "query" {
+                let owner = parser.ident();
+                let (name, node, many) = match parser.postfix() {
+                    Rule::Labeled(name, rule) => match *rule {
+                        Rule::Rep(rule) => (name, *rule, true),
+                        Rule::Opt(rule) => (name, *rule, false),
+                        rule => panic!("expected `*` or `?` after {:?}", rule),
+                    },
+                    rule => panic!("expected a label on {:?}", rule),
+                };
+                let node = match node {
+                    Rule::Node(node) => node,
+                    rule => panic!("can only query nodes, not {:?}", rule),
+                };
+                grammar.queries.push(QueryDecl {
+                    owner,
+                    name,
+                    node,
+                    many,
+                    doc,
+                });
+                continue;
+            }
+            let mut kinds = Vec::new();
+            if parser.eat('[') {
+                kinds.push(parser.ident());
+                while parser.eat('|') {
+                    kinds.push(parser.ident());
+                }
+                parser.expect(']');
+            }
+            parser.expect('=');
+            let rule = parser.alt();
+            grammar.nodes.push(NodeDecl {
+                name,
+                kinds,
+                doc,
+                rule,
+            });
+        }
+        grammar.resolve();
+        grammar
+    }
+
+    /// Give the tokens of the rules by the names of their kinds, and work
+    /// out which nodes are enums and which kinds the others are
+    fn resolve(&mut self) {
+        let mut tokens = HashMap::new();
+        for token in &self.tokens {
+            tokens.insert(token.kind.to_lowercase(), token.kind.clone());
+        }
+        for token in &self.tokens {
+            if let Some(text) = &token.text {
+                let previous = tokens.insert(text.clone(), token.kind.clone());
+                assert!(previous.is_none(), "'{}' is ambiguous", text);
+            }
+        }
+        let names = self
+            .nodes
+            .iter()
+            .map(|it| it.name.clone())
+            .collect::<Vec<_>>();
+        for node in &mut self.nodes {
+            resolve(&mut node.rule, &tokens, &names);
+            if node.kinds.is_empty() && !is_enum(&node.rule) {
+                node.kinds.push(screaming_snake(&node.name));
+            }
+        }
+        for query in &self.queries {
+            for name in [&query.owner, &query.node] {
+                assert!(names.contains(name), "there's no rule for {}", name);
+            }
+        }
+    }
+
+    fn node(&self, name: &str) -> &NodeDecl {
+        self.nodes.iter().find(|it| it.name == name).unwrap()
+    }
+
+    /// The type of the node: its own name for an enum, or with `Node` after
+    /// it for a struct
+    fn type_name(&self, name: &str) -> String {
+        if self.node(name).kinds.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}Node", name)
+        }
+    }
+}
+
+fn resolve(rule: &mut Rule, tokens: &HashMap<String, String>, names: &[String]) {
+    match rule {
+        Rule::Node(name) => assert!(names.contains(name), "there's no rule for {}", name),
+        Rule::Token(text) => match tokens.get(text.as_str()) {
+            Some(kind) => *text = kind.clone(),
+            None => panic!("there's no token '{}'", text),
+        },
+        Rule::Labeled(_, rule) | Rule::Opt(rule) | Rule::Rep(rule) => resolve(rule, tokens, names),
+        Rule::Seq(rules) | Rule::Alt(rules) => {
+            rules.iter_mut().for_each(|it| resolve(it, tokens, names))
+        }
+    }
+}
+
+/// Whether the rule is only a choice between other nodes
+fn is_enum(rule: &Rule) -> bool {
+    match rule {
+        Rule::Alt(alts) => alts.iter().all(|it| matches!(it, Rule::Node(_))),
+        _ => false,
+    }
+}
+
+/// `CLASS_DECL` for `ClassDecl`
+fn screaming_snake(name: &str) -> String {
+    snake(name).to_uppercase()
+}
+
+/// `class_decl` for `ClassDecl`
+fn snake(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+fn plural(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("sis") {
+        format!("{}ses", stem)
+    } else if name.ends_with(['s', 'x', 'h']) {
+        format!("{}es", name)
+    } else if let Some(stem) = name.strip_suffix('y') {
+        format!("{}ies", stem)
+    } else {
+        format!("{}s", name)
+    }
+}
+
+/// What a rule has, in order: a node, or a token
+#[derive(Debug)]
+struct Field {
+    label: Option<String>,
+    node: Option<String>,
+    token: Option<String>,
+    many: bool,
+    /// Whether it's always there, rather than optional, repeated or one of
+    /// several alternatives
+    required: bool,
+}
+
+fn fields(rule: &Rule, label: Option<&str>, many: bool, required: bool, fields: &mut Vec<Field>) {
+    match rule {
+        Rule::Node(name) => fields.push(Field {
+            label: label.map(String::from),
+            node: Some(name.clone()),
+            token: None,
+            many,
+            required,
+        }),
+        Rule::Token(kind) => fields.push(Field {
+            label: label.map(String::from),
+            node: None,
+            token: Some(kind.clone()),
+            many,
+            required,
+        }),
+        Rule::Labeled(label, rule) => self::fields(rule, Some(label), many, required, fields),
+        Rule::Seq(rules) => rules
+            .iter()
+            .for_each(|it| self::fields(it, label, many, required, fields)),
+        Rule::Alt(rules) => rules
+            .iter()
+            .for_each(|it| self::fields(it, label, many, false, fields)),
+        Rule::Opt(rule) => self::fields(rule, label, many, false, fields),
+        Rule::Rep(rule) => self::fields(rule, label, true, false, fields),
+    }
+}
+
+/// A method chain on `self.0`, laid out the way rustfmt would
+fn chain(calls: &[String]) -> String {
+    let line = format!("self.0{}", calls.concat());
+    if line.len() <= CHAIN_WIDTH {
+        return format!("        {}\n", line);
+    }
+    let mut out = String::from("        self.0\n");
+    for call in calls {
+        writeln!(out, "            {}", call).unwrap();
+    }
+    out
+}
+
+fn method(name: &str, ty: &str, calls: &[String]) -> String {
+    format!(
+        "    pub fn {}(&self) -> {} {{\n{}    }}\n",
+        name,
+        ty,
+        chain(calls)
+    )
+}
+
+/// The occurrences of a node that follow each other with the same label
+#[derive(Debug)]
+struct Group<'a> {
+    label: Option<&'a str>,
+    first: usize,
+    last: usize,
+    many: bool,
+    /// Whether it's a single node that's always there
+    required: bool,
+}
+
+/// The last token between two fields, if the accessors can look for it to
+/// tell them apart: a token of the same kind as a node can't be told from
+/// it, and one that the rule has earlier on would be found first
+fn boundary(grammar: &Grammar, all: &[Field], after: usize, before: usize) -> Option<String> {
+    let (i, kind) = (after + 1..before)
+        .rev()
+        .find_map(|i| all[i].token.as_ref().map(|it| (i, it)))?;
+    let is_node = grammar.nodes.iter().any(|it| it.kinds.contains(kind));
+    let first = all.iter().position(|it| it.token.as_ref() == Some(kind));
+    if is_node || first != Some(i) {
+        None
+    } else {
+        Some(kind.clone())
+    }
+}
+
+/// The accessors for the nodes of type `name`, one for each label they have.
+/// Those with different labels are told apart by the tokens between them or,
+/// after nodes that are always there, by how many come before them.
+fn node_accessors(
+    grammar: &Grammar,
+    node: &NodeDecl,
+    name: &str,
+    all: &[Field],
+) -> Vec<(String, String)> {
+    let mut groups: Vec<Group> = Vec::new();
+    for (i, field) in all.iter().enumerate() {
+        if field.node.as_deref() != Some(name) {
+            continue;
+        }
+        match groups.last_mut() {
+            Some(group) if group.label == field.label.as_deref() => {
+                group.last = i;
+                group.many = true;
+                group.required = false;
+            }
+            _ => groups.push(Group {
+                label: field.label.as_deref(),
+                first: i,
+                last: i,
+                many: field.many,
+                required: field.required && !field.many,
+            }),
+        }
+    }
+    let ty = grammar.type_name(name);
+    let cast = match groups.len() {
+        1 if groups[0].first == groups[0].last => "children",
+        // nodes without a label are left to be written by hand
+        _ if groups.iter().any(|it| it.label.is_none()) => return Vec::new(),
+        1 => "children",
+        _ => "children_with_tokens",
+    };
+    let mut accessors = Vec::new();
+    for (i, group) in groups.iter().enumerate() {
+        let method = match (group.label, group.many) {
+            (Some(label), _) => label.to_string(),
+            (None, true) => plural(&snake(name)),
+            (None, false) => snake(name),
+        };
+        let mut calls = vec![format!(".{}()", cast)];
+        let mut skip = None;
+        if i > 0 {
+            match boundary(grammar, all, groups[i - 1].last, group.first) {
+                Some(kind) => calls.push(format!(".skip_while(|it| it.kind() != {})", kind)),
+                None if groups[..i].iter().all(|it| it.required) => skip = Some(i),
+                None => panic!("{} can't tell its {} apart", node.name, method),
+            }
+        }
+        if i + 1 < groups.len() && !group.required {
+            match boundary(grammar, all, group.last, groups[i + 1].first) {
+                Some(kind) if skip.is_none() => {
+                    calls.push(format!(".take_while(|it| it.kind() != {})", kind))
+                }
+                _ => panic!("{} can't tell its {} apart", node.name, method),
+            }
+        }
+        if let Some(skip) = skip {
+            calls[0] = ".children()".to_string();
+            calls.push(format!(".filter_map({}::cast)", ty));
+            calls.push(match group.many {
+                true => format!(".skip({})", skip),
+                false => format!(".nth({})", skip),
+            });
+        } else {
+            if calls.len() == 1 {
+                calls[0] = ".children()".to_string();
+            } else {
+                calls.push(".filter_map(|it| it.into_node())".to_string());
+            }
+            calls.push(match group.many {
+                true => format!(".filter_map({}::cast)", ty),
+                false => format!(".find_map({}::cast)", ty),
+            });
+        }
+        let ty = match group.many {
+            true => format!("impl Iterator<Item = {}>", ty),
+            false => format!("Option<{}>", ty),
+        };
+        let code = self::method(&method, &ty, &calls);
+        accessors.push((method, code));
+    }
+    accessors
+}
+
+/// The accessors of a node: for the nodes it has, the tokens it has a label
+/// for, and its queries, as the accessor's name and code
+fn accessors(grammar: &Grammar, node: &NodeDecl) -> Vec<(String, String)> {
+    let mut all = Vec::new();
+    fields(&node.rule, None, false, true, &mut all);
+    let mut accessors: Vec<(String, String)> = Vec::new();
+    let mut done = Vec::new();
+    for field in &all {
+        match (&field.node, &field.label) {
+            (Some(name), _) if !done.contains(&name) => {
+                done.push(name);
+                accessors.extend(node_accessors(grammar, node, name, &all));
+            }
+            (None, Some(label)) if !done.contains(&label) => {
+                done.push(label);
+                let mut kinds = Vec::new();
+                for it in all.iter().filter(|it| it.label == field.label) {
+                    match &it.token {
+                        Some(kind) if !kinds.contains(&kind.as_str()) => kinds.push(kind.as_str()),
+                        Some(_) => {}
+                        None => panic!("{} has nodes and tokens labeled {}", node.name, label),
+                    }
+                }
+                let find = match kinds.as_slice() {
+                    [kind] => format!(".find(|it| it.kind() == {})", kind),
+                    kinds if kinds.join(" | ").len() + 45 <= MAX_WIDTH => {
+                        format!(".find(|it| matches!(it.kind(), {}))", kinds.join(" | "))
+                    }
+                    kinds => format!(
+                        ".find(|it| {{\n                matches!(\n                    it.kind(),\n                    {}\n                )\n            }})",
+                        kinds.join(" | ")
+                    ),
+                };
+                let calls = vec![
+                    ".children_with_tokens()".to_string(),
+                    ".filter_map(|it| it.into_token())".to_string(),
+                    find,
+                ];
+                let code = method(label, "Option<SyntaxToken>", &calls);
+                accessors.push((label.clone(), code));
+            }
+            _ => {}
+        }
+    }
+    for query in grammar.queries.iter().filter(|it| it.owner == node.name) {
+        let ty = grammar.type_name(&query.node);
+        let (ty, call) = match query.many {
+            true => (format!("impl Iterator<Item = {}>", ty), "filter_map"),
+            false => (format!("Option<{}>", ty), "find_map"),
+        };
+        let calls = vec![
+            ".descendants()".to_string(),
+            format!(".{}({}::cast)", call, grammar.type_name(&query.node)),
+        ];
+        let mut code = String::new();
+        doc(&mut code, &query.doc, "    ");
+        code.push_str(&method(&query.name, &ty, &calls));
+        accessors.push((query.name.clone(), code));
+    }
+    for (i, (method, _)) in accessors.iter().enumerate() {
+        assert!(
+            accessors[..i].iter().all(|(it, _)| it != method),
+            "{} has two accessors named {}",
+            node.name,
+            method
+        );
+    }
+    accessors
+}
+
+fn doc(out: &mut String, doc: &[String], indent: &str) {
+    for line in doc {
+        writeln!(out, "{}/// {}", indent, line).unwrap();
+    }
+}
+
+/// A match arm, laid out the way rustfmt would
+fn arm(out: &mut String, pattern: &[String], expr: &str) {
+    let pattern = pattern.join(" | ");
+    let line = format!("            {} => {},", pattern, expr);
+    if line.len() <= MAX_WIDTH {
+        writeln!(out, "{}", line).unwrap();
+    } else {
+        writeln!(out, "            {} => {{", pattern).unwrap();
+        writeln!(out, "                {}", expr).unwrap();
+        writeln!(out, "            }}").unwrap();
+    }
+}
+
+/// `src/syntax/generated.rs`, with the `SyntaxKind` of every token and node
+fn syntax_kinds(grammar: &Grammar) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", HEADER).unwrap();
+    out.push_str("// num_derive expands to impls inside an anonymous const\n");
+    out.push_str("#![allow(non_local_definitions)]\n\n");
+    out.push_str("use num_derive::{FromPrimitive, ToPrimitive};\n\n");
+    out.push_str("use self::SyntaxKind::*;\n\n");
+    out.push_str("#[derive(FromPrimitive, ToPrimitive, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]\n");
+    out.push_str("#[allow(non_camel_case_types)]\n");
+    out.push_str("#[allow(clippy::upper_case_acronyms)]\n");
+    out.push_str("#[repr(u16)]\n");
+    out.push_str("pub enum SyntaxKind {\n");
+    let mut kinds = Vec::new();
+    for (i, token) in grammar.tokens.iter().enumerate() {
+        doc(&mut out, &token.doc, "    ");
+        match i {
+            0 => writeln!(out, "    {} = 0,", token.kind).unwrap(),
+            _ => writeln!(out, "    {},", token.kind).unwrap(),
+        }
+        kinds.push(token.kind.as_str());
+    }
+    out.push_str("\n    // nodes\n");
+    for node in &grammar.nodes {
+        for kind in &node.kinds {
+            if kinds.contains(&kind.as_str()) {
+                continue;
+            }
+            if node.kinds.len() == 1 {
+                doc(&mut out, &node.doc, "    ");
+            }
+            writeln!(out, "    {},", kind).unwrap();
+            kinds.push(kind);
+        }
+    }
+    out.push_str("}\n\n");
+    let keywords = grammar
+        .tokens
+        .iter()
+        .filter(|it| it.kind.ends_with("_KW"))
+        .map(|it| (it.text.as_deref().unwrap(), it.kind.as_str()))
+        .collect::<Vec<_>>();
+    let chars = grammar
+        .tokens
+        .iter()
+        .filter_map(|it| Some((it.text.as_deref()?, it.kind.as_str())))
+        .filter(|(text, _)| text.chars().count() == 1)
+        .collect::<Vec<_>>();
+    out.push_str("impl SyntaxKind {\n");
+    out.push_str("    /// The keyword `text` is, as keywords are only keywords when they're the\n");
+    out.push_str("    /// whole of a name\n");
+    out.push_str("    pub fn from_keyword(text: &str) -> Option<SyntaxKind> {\n");
+    out.push_str("        let kind = match text {\n");
+    for (text, kind) in &keywords {
+        writeln!(out, "            {:?} => {},", text, kind).unwrap();
+    }
+    out.push_str("            _ => return None,\n");
+    out.push_str("        };\n");
+    out.push_str("        Some(kind)\n");
+    out.push_str("    }\n");
+    out.push_str("    /// The token that's a single character `c`\n");
+    out.push_str("    pub fn from_char(c: char) -> Option<SyntaxKind> {\n");
+    out.push_str("        let kind = match c {\n");
+    for (text, kind) in &chars {
+        writeln!(
+            out,
+            "            {:?} => {},",
+            text.chars().next().unwrap(),
+            kind
+        )
+        .unwrap();
+    }
+    out.push_str("            _ => return None,\n");
+    out.push_str("        };\n");
+    out.push_str("        Some(kind)\n");
+    out.push_str("    }\n");
+    out.push_str("    /// The text of a keyword or of punctuation, which is always the same\n");
+    out.push_str("    pub fn text(self) -> Option<&'static str> {\n");
+    out.push_str("        let text = match self {\n");
+    for token in &grammar.tokens {
+        if let Some(text) = &token.text {
+            writeln!(out, "            {} => {:?},", token.kind, text).unwrap();
+        }
+    }
+    out.push_str("            _ => return None,\n");
+    out.push_str("        };\n");
+    out.push_str("        Some(text)\n");
+    out.push_str("    }\n");
+    out.push_str("    pub fn is_keyword(self) -> bool {\n");
+    out.push_str("        matches!(\n");
+    out.push_str("            self,\n");
+    for (i, (_, kind)) in keywords.iter().enumerate() {
+        match i {
+            0 => writeln!(out, "            {}", kind).unwrap(),
+            _ => writeln!(out, "                | {}", kind).unwrap(),
+        }
+    }
+    out.push_str("        )\n");
+    out.push_str("    }\n");
+    out.push_str("}\n");
+    out
+}
+
+/// `src/ast/generated.rs`, with a struct for every node, its accessors, and
+/// the enums
+fn nodes(grammar: &Grammar) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", HEADER).unwrap();
+    out.push_str("#![allow(dead_code)]\n\n");
+    out.push_str("use super::{SyntaxNode, SyntaxToken};\n");
+    out.push_str("use crate::syntax::SyntaxKind::*;\n");
+    for node in grammar.nodes.iter().filter(|it| !it.kinds.is_empty()) {
+        let ty = grammar.type_name(&node.name);
+        out.push('\n');
+        doc(&mut out, &node.doc, "");
+        out.push_str("#[derive(Debug, Clone, PartialEq, Eq, Hash)]\n");
+        out.push_str("#[repr(transparent)]\n");
+        writeln!(out, "pub struct {}(pub(super) SyntaxNode);\n", ty).unwrap();
+        writeln!(out, "impl {} {{", ty).unwrap();
+        out.push_str("    pub fn cast(node: SyntaxNode) -> Option<Self> {\n");
+        out.push_str("        match node.kind() {\n");
+        arm(&mut out, &node.kinds, "Some(Self(node))");
+        out.push_str("            _ => None,\n");
+        out.push_str("        }\n");
+        out.push_str("    }\n");
+        out.push_str("    pub(crate) fn syntax(&self) -> &SyntaxNode {\n        &self.0\n    }\n");
+        out.push_str(
+            "    pub(crate) fn position(&self) -> rowan::TextRange {\n        self.0.text_range()\n    }\n",
+        );
+        for (_, code) in accessors(grammar, node) {
+            out.push_str(&code);
+        }
+        out.push_str("}\n");
+    }
+    for node in grammar.nodes.iter().filter(|it| it.kinds.is_empty()) {
+        let variants = match &node.rule {
+            Rule::Alt(alts) => alts
+                .iter()
+                .map(|it| match it {
+                    Rule::Node(name) => grammar.node(name),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        out.push('\n');
+        doc(&mut out, &node.doc, "");
+        out.push_str("#[derive(Debug, Clone, PartialEq, Eq, Hash)]\n");
+        out.push_str("#[allow(clippy::enum_variant_names)]\n");
+        writeln!(out, "pub enum {} {{", node.name).unwrap();
+        for variant in &variants {
+            assert!(
+                !variant.kinds.is_empty(),
+                "{} is an enum in an enum",
+                variant.name
+            );
+            writeln!(
+                out,
+                "    {}({}),",
+                variant.name,
+                grammar.type_name(&variant.name)
+            )
+            .unwrap();
+        }
+        out.push_str("}\n\n");
+        writeln!(out, "impl {} {{", node.name).unwrap();
+        out.push_str("    pub fn cast(node: SyntaxNode) -> Option<Self> {\n");
+        out.push_str("        match node.kind() {\n");
+        for variant in &variants {
+            let expr = format!(
+                "Some(Self::{}({}(node)))",
+                variant.name,
+                grammar.type_name(&variant.name)
+            );
+            arm(&mut out, &variant.kinds, &expr);
+        }
+        out.push_str("            _ => None,\n");
+        out.push_str("        }\n");
+        out.push_str("    }\n");
+        out.push_str("    pub(crate) fn syntax(&self) -> &SyntaxNode {\n");
+        out.push_str("        match self {\n");
+        for variant in &variants {
+            writeln!(out, "            Self::{}(it) => &it.0,", variant.name).unwrap();
+        }
+        out.push_str("        }\n");
+        out.push_str("    }\n");
+        out.push_str(
+            "    pub(crate) fn position(&self) -> rowan::TextRange {\n        self.syntax().text_range()\n    }\n",
+        );
+        out.push_str("}\n");
+    }
+    out
+}
+
+mod tests {
+    #[test]
+    fn generated_code_is_up_to_date() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let text = std::fs::read_to_string(root.join("src/puml.ungram")).unwrap();
+        let grammar = super::Grammar::parse(&text);
+        let files = vec![
+            ("src/syntax/generated.rs", super::syntax_kinds(&grammar)),
+            ("src/ast/generated.rs", super::nodes(&grammar)),
+        ];
+        let mut stale = Vec::new();
+        for (path, code) in files {
+            let path = root.join(path);
+            if std::fs::read_to_string(&path).ok().as_deref() != Some(code.as_str()) {
+                std::fs::write(&path, code).unwrap();
+                stale.push(path);
+            }
+        }
+        assert!(
+            stale.is_empty(),
+            "{:?} were out of date, and have been regenerated",
+            stale
+        );
+    }
+
+    #[test]
+    fn test_grammar() {
+        use super::Rule::*;
+        let grammar = super::Grammar::parse(
+            "token IDENTIFIER\ntoken AS_KW 'as'\n/// a thing\nThing[A | B] =\n  name:Part ('as' aliases:Part)*\nPart = 'identifier'\nEither = Thing | Part\nquery Thing parts:Part*\n",
+        );
+        assert_eq!(grammar.tokens.len(), 2);
+        let thing = grammar.node("Thing");
+        assert_eq!(thing.doc, vec!["a thing"]);
+        assert_eq!(thing.kinds, vec!["A", "B"]);
+        assert_eq!(
+            thing.rule,
+            Seq(vec![
+                Labeled("name".into(), Box::new(Node("Part".into()))),
+                Rep(Box::new(Seq(vec![
+                    Token("AS_KW".into()),
+                    Labeled("aliases".into(), Box::new(Node("Part".into())))
+                ]))),
+            ])
+        );
+        assert_eq!(grammar.node("Part").kinds, vec!["PART"]);
+        assert!(grammar.node("Either").kinds.is_empty());
+        // the aliases are told from the name by the `as` before them
+        let accessors = super::accessors(&grammar, thing);
+        let names = accessors
+            .iter()
+            .map(|(it, _)| it.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["name", "aliases", "parts"]);
+        assert!(accessors[0]
+            .1
+            .contains(".children().find_map(PartNode::cast)"));
+        assert!(accessors[1]
+            .1
+            .contains(".skip_while(|it| it.kind() != AS_KW)"));
+        assert!(accessors[2]
+            .1
+            .contains(".descendants().filter_map(PartNode::cast)"));
+        assert_eq!(super::plural("entry"), "entries");
+        assert_eq!(super::plural("branch"), "branches");
+        assert_eq!(super::plural("creole_emphasis"), "creole_emphases");
+    }
+}
@@ -5,11 +5,29 @@ use log::*;
 use rowan::GreenNode;
 use rowan::GreenNodeBuilder;
 use rowan::SmolStr;
+use rowan::{TextRange, TextSize};
 
 use crate::ast;
 use crate::syntax;
+use crate::parsing::activity;
+use crate::parsing::class;
+use crate::parsing::component;
+use crate::parsing::creole::{self, Event};
+use crate::parsing::data;
+use crate::parsing::gantt;
+use crate::parsing::mindmap;
+use crate::parsing::object;
+use crate::parsing::preprocessor;
+use crate::parsing::salt;
 use crate::parsing::sequence::participant_decl;
+use crate::parsing::state;
+use crate::parsing::style;
+use crate::parsing::timing;
+use crate::parsing::usecase;
 
+/// How deeply statements, salt groups and calls may nest, as the parser
+/// takes them apart by recursion
+const MAX_NESTING: usize = 200;
 
 #[allow(dead_code)]
 pub(crate) enum Statement {
@@ -21,11 +39,20 @@ pub(crate) enum Statement {
     // UnexpectedEol,
 }
 
+/// A syntax error, and the text it's about. The text of an ERROR node is
+/// covered by the error the parser made it for, an empty ERROR node stands
+/// for a token that's missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SyntaxError {
+    pub(crate) message: String,
+    pub(crate) range: TextRange,
+}
+
 #[allow(dead_code)]
 pub(crate) struct Parsed {
     green_node: GreenNode,
     #[allow(unused)]
-    errors: Vec<String>,
+    errors: Vec<SyntaxError>,
 }
 
 #[allow(dead_code)]
@@ -37,31 +64,80 @@ impl Parsed {
     pub(crate) fn syntax(&self) -> ast::SyntaxNode {
         ast::SyntaxNode::new_root(self.green_node.clone())
     }
+
+    pub(crate) fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+}
+
+/// The kind of diagram being parsed, going by its `@start...` line. Some
+/// diagrams have a grammar of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Diagram {
+    Uml,
+    Gantt,
+    /// `@startmindmap` and `@startwbs`, which share their outline syntax
+    MindMap,
+    Json,
+    Yaml,
+    Salt,
+}
+
+impl Diagram {
+    fn from_start(text: &str) -> Self {
+        match text {
+            "@startgantt" => Diagram::Gantt,
+            "@startmindmap" | "@startwbs" => Diagram::MindMap,
+            "@startjson" => Diagram::Json,
+            "@startyaml" => Diagram::Yaml,
+            "@startsalt" => Diagram::Salt,
+            _ => Diagram::Uml,
+        }
+    }
 }
 
 #[allow(dead_code)]
 pub(crate) struct Parser {
-    /// input tokens, including whitespace, in *reverse* order.
-    tokens: Vec<(syntax::SyntaxKind, SmolStr)>,
+    /// input tokens, including whitespace, in *reverse* order, with where
+    /// they are.
+    tokens: Vec<(syntax::SyntaxKind, SmolStr, TextRange)>,
     /// the in-progress tree.
     builder: GreenNodeBuilder<'static>,
     /// the list of syntax errors we've accumulated so far.
-    errors: Vec<String>,
+    errors: Vec<SyntaxError>,
+    /// the diagram the parser is in.
+    diagram: Diagram,
+    /// where the text ends.
+    end: TextSize,
+    /// how many braced blocks the current statement is nested in.
+    blocks: usize,
+    /// how many statements, salt groups and calls the parser is inside of.
+    nesting: usize,
 }
 
 
 use syntax::SyntaxKind::*;
 impl Parser {
 
-    pub(crate) fn new(tokens: Vec<(syntax::SyntaxKind, SmolStr)>) -> Self {
+    pub(crate) fn new(tokens: Vec<(syntax::SyntaxKind, SmolStr, TextRange)>) -> Self {
+        let end = tokens
+            .first()
+            .map(|(_, _, range)| range.end())
+            .unwrap_or_default();
         Parser {
             tokens,
             builder: rowan::GreenNodeBuilder::new(),
             errors: Vec::new(),
+            diagram: Diagram::Uml,
+            end,
+            blocks: 0,
+            nesting: 0,
         }
     }
 
     pub(crate) fn start_node(&mut self, kind: syntax::SyntaxKind) {
+        // comments before a node belong to its parent
+        self.consume_comments();
         self.builder.start_node(kind.into());
     }
 
@@ -69,6 +145,118 @@ impl Parser {
         self.builder.finish_node();
     }
 
+    pub(crate) fn checkpoint(&self) -> rowan::Checkpoint {
+        self.builder.checkpoint()
+    }
+
+    pub(crate) fn start_node_at(
+        &mut self,
+        checkpoint: rowan::Checkpoint,
+        kind: syntax::SyntaxKind,
+    ) {
+        self.builder.start_node_at(checkpoint, kind.into());
+    }
+
+    /// Report an error about the current token, or about the place it's
+    /// missing from at the end of a line
+    pub(crate) fn error(&mut self, message: &str) {
+        let start = self
+            .remaining()
+            .next()
+            .map(|(_, _, range)| range.start())
+            .unwrap_or(self.end);
+        let len = if self.at_eol() {
+            TextSize::from(0)
+        } else {
+            TextSize::of(self.current_text())
+        };
+        self.report(message, TextRange::at(start, len));
+    }
+
+    fn report(&mut self, message: &str, range: TextRange) {
+        trace!(target: "parser", "Error: {} at {:?}", message, range);
+        self.errors.push(SyntaxError {
+            message: message.to_string(),
+            range,
+        });
+    }
+
+    /// Whether error recovery should stop here: at the end of the line or of
+    /// the diagram, or at a `}` that may close the block the statement is in
+    pub(crate) fn at_sync(&self) -> bool {
+        self.at_eol() || self.at(DIAGRAM_END) || self.blocks > 0 && self.at(R_BRACE)
+    }
+
+    /// Where the next token, comments included, starts
+    fn offset(&self) -> TextSize {
+        self.tokens
+            .last()
+            .map(|(_, _, range)| range.start())
+            .unwrap_or(self.end)
+    }
+
+    /// Report an error, and wrap what's left of the statement in an ERROR
+    /// node, which takes at least the current token unless the line is over
+    pub(crate) fn recover(&mut self, message: &str) {
+        self.start_node(ERROR);
+        let start = self.offset();
+        while !self.at_eol() && (self.offset() == start || !self.at_sync()) {
+            self.consume_token();
+        }
+        self.finish_node();
+        self.report(message, TextRange::new(start, self.offset()));
+    }
+
+    /// Report an error, with an empty ERROR node to mark where something is
+    /// missing
+    fn missing(&mut self, message: &str) {
+        self.start_node(ERROR);
+        self.finish_node();
+        self.report(message, TextRange::empty(self.offset()));
+    }
+
+    /// Whether going a level deeper into statements, salt groups or calls
+    /// would take the parser more than MAX_NESTING levels deep
+    pub(crate) fn too_deep(&self) -> bool {
+        self.nesting >= MAX_NESTING
+    }
+
+    pub(crate) fn nest(&mut self) {
+        self.nesting += 1;
+    }
+
+    pub(crate) fn unnest(&mut self) {
+        self.nesting -= 1;
+    }
+
+    /// Report that what's next is nested too deeply, and wrap it in an
+    /// ERROR node: everything up to the `close` that isn't matched by an
+    /// `open`, or up to the end of the diagram, or of the line if `close`
+    /// has to be on the same line
+    pub(crate) fn skip_nested(
+        &mut self,
+        open: syntax::SyntaxKind,
+        close: syntax::SyntaxKind,
+        same_line: bool,
+    ) {
+        self.start_node(ERROR);
+        let start = self.offset();
+        let mut depth = 0usize;
+        loop {
+            match self.current() {
+                EOF | DIAGRAM_END => break,
+                _ if same_line && self.at_eol() => break,
+                kind if kind == close && depth == 0 => break,
+                kind if kind == close => depth -= 1,
+                kind if kind == open => depth += 1,
+                _ => {}
+            }
+            self.consume_token();
+        }
+        self.finish_node();
+        self.report("too deeply nested", TextRange::new(start, self.offset()));
+    }
+
     pub(crate) fn parse(mut self) -> Parsed {
         // Make sure that the root node covers all source
         self.builder.start_node(ROOT.into());
@@ -86,8 +274,9 @@ impl Parser {
                 Statement::Ok => (),
             }
         }
-        // Don't forget to eat *trailing* whitespace
+        // Don't forget to eat *trailing* whitespace, and comments
         self.skip_ws();
+        self.consume_comments();
         self.builder.finish_node();
         // Turn the builder into a GreenNode
         Parsed {
@@ -96,20 +285,244 @@ impl Parser {
         }
     }
 
+    /// Consume a token of the given kind. If the current token is another
+    /// one, it's wrapped in an ERROR node instead, and if the line is over,
+    /// an empty ERROR node stands for the missing token.
     pub fn consume(&mut self, kind: syntax::SyntaxKind) {
-        assert_eq!(self.current(), kind);
-        self.consume_token()
+        if self.eat(kind) {
+            return;
+        }
+        let message = format!("expected {}", crate::lex::describe(kind));
+        if self.at_sync() {
+            self.missing(&message);
+        } else {
+            self.start_node(ERROR);
+            self.error(&message);
+            self.consume_token();
+            self.finish_node();
+        }
+    }
+
+    /// Add a token to the tree
+    fn push(&mut self, kind: syntax::SyntaxKind, text: SmolStr) {
+        self.builder.token(kind.into(), text)
+    }
+
+    /// Comments are trivia the rest of the parser never sees, they're added
+    /// to the tree as the tokens after them are consumed
+    fn consume_comments(&mut self) {
+        while self.tokens.last().is_some_and(|(kind, _, _)| *kind == COMMENT) {
+            if let Some((kind, text, _)) = self.tokens.pop() {
+                self.push(kind, text);
+            }
+        }
+    }
+
+    /// The tokens still to be parsed, in order, without the comments
+    fn remaining(&self) -> impl Iterator<Item = &(syntax::SyntaxKind, SmolStr, TextRange)> {
+        self.tokens.iter().rev().filter(|(kind, _, _)| *kind != COMMENT)
     }
 
     #[allow(dead_code)]
     pub(crate) fn consume_token(&mut self) {
-        let (kind, text) = self.tokens.pop().unwrap();
-        trace!(target: "parser", "Consuming token '{}' of type {:?}", text, kind);
-        self.builder.token(kind.into(), text)
+        self.consume_comments();
+        if let Some((kind, text, _)) = self.tokens.pop() {
+            trace!(target: "parser", "Consuming token '{}' of type {:?}", text, kind);
+            self.push(kind, text);
+        }
+    }
+
+    /// Consume the current token as a different kind, e.g. a lone `-` that
+    /// the lexer took for a MINUS but which links two components
+    pub(crate) fn consume_as(&mut self, kind: syntax::SyntaxKind) {
+        self.consume_comments();
+        if let Some((_, text, _)) = self.tokens.pop() {
+            trace!(target: "parser", "Consuming token '{}' as {:?}", text, kind);
+            self.push(kind, text);
+        }
     }
 
     pub(crate) fn current(&self) -> syntax::SyntaxKind {
-        self.tokens.last().map(|(kind, _)| *kind).unwrap_or(EOF)
+        self.remaining()
+            .next()
+            .map(|(kind, _, _)| *kind)
+            .unwrap_or(EOF)
+    }
+
+    pub(crate) fn current_text(&self) -> &str {
+        self.remaining()
+            .next()
+            .map(|(_, text, _)| text.as_str())
+            .unwrap_or("")
+    }
+
+    pub(crate) fn diagram(&self) -> Diagram {
+        self.diagram
+    }
+
+    /// Whether the parser is in the body of a JSON or YAML diagram
+    fn in_data(&self) -> bool {
+        matches!(self.diagram, Diagram::Json | Diagram::Yaml)
+            && !matches!(self.current(), EOL | DIAGRAM_END)
+    }
+
+    /// Whether the current token is the IDENTIFIER `word`
+    pub(crate) fn at_word(&self, word: &str) -> bool {
+        self.at(IDENTIFIER) && self.current_text().eq_ignore_ascii_case(word)
+    }
+
+    /// Look past the current token, `n` significant (non whitespace) tokens
+    /// ahead. `nth(0)` is the first significant token from here on.
+    pub(crate) fn nth(&self, n: usize) -> syntax::SyntaxKind {
+        self.remaining()
+            .map(|(kind, _, _)| *kind)
+            .filter(|kind| !kind.is_trivia())
+            .nth(n)
+            .unwrap_or(EOF)
+    }
+
+    /// Whether the next tokens, whitespace included, are `kinds`
+    pub(crate) fn at_seq(&self, kinds: &[syntax::SyntaxKind]) -> bool {
+        let mut remaining = self.remaining();
+        kinds
+            .iter()
+            .all(|expected| remaining.next().is_some_and(|(kind, _, _)| kind == expected))
+    }
+
+    /// The significant tokens remaining on the current line.
+    pub(crate) fn rest_of_line(&self) -> Vec<(syntax::SyntaxKind, &str)> {
+        self.remaining()
+            .take_while(|(kind, _, _)| *kind != EOL)
+            .filter(|(kind, _, _)| !kind.is_trivia())
+            .map(|(kind, text, _)| (*kind, text.as_str()))
+            .collect()
+    }
+
+    /// Consume the current token if it is of the given kind
+    pub(crate) fn eat(&mut self, kind: syntax::SyntaxKind) -> bool {
+        if self.at(kind) {
+            self.consume_token();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn at_eol(&self) -> bool {
+        self.at(EOL) || self.at(EOF)
+    }
+
+    /// Consume everything up to, but not including, the end of the line.
+    pub(crate) fn skip_to_eol(&mut self) {
+        while !self.at_eol() {
+            self.consume_token();
+        }
+    }
+
+    /// Finish off a statement, ignoring anything unexpected before the end of
+    /// the line.
+    pub(crate) fn eol(&mut self) {
+        self.skip_ws();
+        if !self.at_sync() {
+            self.recover("expected end of line");
+        }
+        self.eat(EOL);
+    }
+
+    /// Parse the rest of the line as a LABEL
+    pub(crate) fn label(&mut self) {
+        self.skip_ws();
+        if self.at_eol() {
+            return;
+        }
+        self.start_node(LABEL);
+        self.markup(Parser::at_eol);
+        self.finish_node();
+    }
+
+    /// Parse the text of a label, up to where `end` says it ends, with its
+    /// Creole markup as nodes of their own. Variables, calls, as in
+    /// ': $double(21) times', comments and line ends are kept as they are.
+    pub(crate) fn markup(&mut self, end: impl Fn(&Parser) -> bool) {
+        // take the tokens of the label off to see where its text is, and put
+        // them back to build the markup out of
+        let mut taken = Vec::new();
+        let mut text = String::new();
+        let mut atoms = Vec::new();
+        while !self.at(EOF) && !end(self) {
+            let start = text.len();
+            let kind = self.tokens.last().map(|(kind, _, _)| *kind);
+            let is_text = !matches!(kind, Some(COMMENT) | Some(EOL) | Some(VARIABLE));
+            let call = kind == Some(VARIABLE) && self.at_seq(&[VARIABLE, L_PAREN]);
+            let mut depth = 0;
+            while let Some((kind, token, range)) = self.tokens.pop() {
+                text.push_str(&token);
+                taken.push((kind, token, range));
+                // a call runs to its closing parenthesis, or the end of the line
+                match kind {
+                    L_PAREN => depth += 1,
+                    R_PAREN => depth -= 1,
+                    _ => (),
+                }
+                if !call || depth == 0 && kind == R_PAREN || self.at_eol() {
+                    break;
+                }
+            }
+            if !is_text {
+                atoms.push(start..text.len());
+            }
+        }
+        self.tokens.extend(taken.into_iter().rev());
+        let mut pending = String::new();
+        for event in creole::markup(&text, &atoms) {
+            match event {
+                Event::Start(kind) => self.builder.start_node(kind.into()),
+                Event::Finish => self.builder.finish_node(),
+                Event::Token(kind, len) => {
+                    while pending.len() < len {
+                        match self.tokens.pop() {
+                            Some((_, token, _)) => pending.push_str(&token),
+                            None => break,
+                        }
+                    }
+                    let rest = pending.split_off(len.min(pending.len()));
+                    self.push(kind, pending.into());
+                    pending = rest;
+                }
+                Event::Atom if self.tokens.last().map(|(kind, _, _)| *kind) == Some(COMMENT) => {
+                    if let Some((kind, token, _)) = self.tokens.pop() {
+                        self.push(kind, token);
+                    }
+                }
+                Event::Atom if self.at_seq(&[VARIABLE, L_PAREN]) => preprocessor::call(self),
+                Event::Atom => self.consume_token(),
+            }
+        }
+    }
+
+    /// Parse a braced BLOCK of statements, e.g. the body of a package
+    pub(crate) fn block(&mut self) {
+        self.start_node(BLOCK);
+        self.consume(L_BRACE);
+        self.blocks += 1;
+        loop {
+            self.skip_ws();
+            match self.current() {
+                EOF | DIAGRAM_END => {
+                    self.error("expected '}'");
+                    break;
+                }
+                R_BRACE => {
+                    self.consume(R_BRACE);
+                    break;
+                }
+                _ => {
+                    self.statement();
+                }
+            }
+        }
+        self.blocks -= 1;
+        self.finish_node();
     }
 
     pub(crate) fn skip_ws(&mut self) {
@@ -119,40 +532,117 @@ impl Parser {
     }
 
     #[allow(dead_code)]
-    fn statement<'a>(&'a mut self) -> Statement {
+    pub(crate) fn statement(&mut self) -> Statement {
         // Eat leading whitespace
         self.skip_ws();
         // Either a list, an atom, a closing paren,
         // or an eof.
         trace!("Start Statement");
+        if self.at(EOF) {
+            return Statement::UnexpectedEof;
+        }
+        // what's left of the block the statement is in, unless that's
+        // nothing more than its end
+        if self.too_deep() && !matches!(self.current(), R_BRACE | DIAGRAM_END) {
+            self.skip_nested(L_BRACE, R_BRACE, false);
+            return Statement::Ok;
+        }
+        self.nest();
         let stmt = match self.current() {
-            EOF => Statement::UnexpectedEof,
+            // the body of '@startjson' and '@startyaml' isn't PlantUML at all
+            _ if self.in_data() => data::statement(self),
             ERROR => {
+                let message = format!("unexpected '{}'", self.current_text());
                 self.start_node(ERROR);
+                self.error(&message);
                 self.consume(ERROR);
                 self.finish_node();
                 Statement::Ok
             },
+            EOL => {
+                self.consume(EOL);
+                Statement::Ok
+            }
+            DIAGRAM_START | DIAGRAM_END => {
+                // '@startuml' and friends
+                self.diagram = if self.at(DIAGRAM_START) {
+                    Diagram::from_start(self.current_text())
+                } else {
+                    Diagram::Uml
+                };
+                self.skip_to_eol();
+                self.eat(EOL);
+                Statement::Ok
+            }
+            DIRECTIVE => preprocessor::directive(self),
+            IDENTIFIER if style::at_skinparam(self) => style::skinparam(self),
+            L_ANGLE if style::at_style(self) => style::style(self),
+            _ if self.diagram == Diagram::Gantt => gantt::statement(self),
+            _ if self.diagram == Diagram::MindMap => mindmap::statement(self),
+            _ if self.diagram == Diagram::Salt => salt::statement(self),
+            // a keyword may also name something, as in `node -> A`
+            kind if kind.is_keyword() && class::at_relation(self) => class::relation(self),
             PARTICIPANT_KW => participant_decl(self),
+            CLASS_KW | ABSTRACT_KW | INTERFACE_KW | ENUM_KW | ANNOTATION_KW => {
+                class::class_decl(self)
+            }
+            PACKAGE_KW | NAMESPACE_KW => class::package_decl(self),
+            IDENTIFIER if usecase::at_direction(self) => usecase::direction(self),
+            IDENTIFIER if salt::at_salt(self) => salt::salt(self),
+            VARIABLE | IDENTIFIER if preprocessor::at_call(self) => {
+                preprocessor::call_statement(self)
+            }
+            IDENTIFIER if timing::at_signal_decl(self) => timing::signal_decl(self),
+            IDENTIFIER if timing::at_signal_states(self) => timing::signal_states(self),
+            IDENTIFIER if timing::at_highlight(self) => timing::highlight(self),
+            IDENTIFIER | AT if timing::at_timing_link(self) => timing::timing_link(self),
+            AT => timing::time_anchor(self),
+            IDENTIFIER | STRING | NUMBER | PLUS if timing::at_state_change(self) => {
+                timing::state_change(self)
+            }
+            IDENTIFIER | STRING | PSEUDO_STATE | L_BRACK | L_PAREN | COLON
+                if class::at_relation(self) =>
+            {
+                class::relation(self)
+            }
+            IDENTIFIER | STRING if state::at_description(self) => state::description(self),
+            STATE_KW => state::state_decl(self),
+            COMPONENT_KW | L_BRACK => component::component_decl(self),
+            L_PAREN if self.nth(1) == R_PAREN => component::component_decl(self),
+            NODE_KW | FOLDER_KW | FRAME_KW | CLOUD_KW | DATABASE_KW | RECTANGLE_KW => {
+                component::container_decl(self)
+            }
+            OBJECT_KW | MAP_KW => object::object_decl(self),
+            ACTOR_KW => usecase::actor_decl(self),
+            COLON if usecase::at_actor_shorthand(self) => usecase::actor_decl(self),
+            USECASE_KW | L_PAREN => usecase::usecase_decl(self),
+            PORT_KW | PORTIN_KW | PORTOUT_KW => component::port_decl(self),
+            START_KW => activity::start(self),
+            STOP_KW | KILL_KW | DETACH_KW => activity::stop(self),
+            END_KW if !activity::at_block_end(self) => activity::stop(self),
+            COLON => activity::action(self),
+            ARROW => activity::arrow(self),
+            IF_KW => activity::if_block(self),
+            WHILE_KW => activity::while_block(self),
+            REPEAT_KW if !activity::at_block_end(self) => activity::repeat_block(self),
+            FORK_KW | SPLIT_KW if !activity::at_block_end(self) => activity::fork_block(self),
+            PIPE => activity::swimlane(self),
+            PARTITION_KW => activity::partition(self),
             _ => {
-                self.start_node(ERROR);
-                self.consume_token();
-                self.finish_node();
+                // something we can't handle i guess, skip the rest of the line
+                self.recover("unexpected statement");
+                self.eat(EOL);
                 Statement::Ok
-                // something we can't handle i guess
-                //unreachable!(format!("uh oh {:?}", self.current()))
             }
         };
+        self.unnest();
         trace!("Finish Statement");
         stmt
     }
 
     pub fn at(&self, kind: syntax::SyntaxKind) -> bool {
         trace!("At {:?} ? current is {:?}", kind, self.current());
-        if self.current() == kind {
-            return true;
-        }
-        return false;
+        self.current() == kind
     }
 
 }
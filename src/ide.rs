@@ -0,0 +1,194 @@
+//! Editor features, computed from a parsed `Document`
+pub(crate) mod code_actions;
+pub(crate) mod colors;
+pub(crate) mod completion;
+pub(crate) mod diagnostics;
+pub(crate) mod expansion;
+pub(crate) mod folding;
+pub(crate) mod goto_definition;
+pub(crate) mod hover;
+pub(crate) mod links;
+pub(crate) mod references;
+pub(crate) mod rename;
+pub(crate) mod signature_help;
+pub(crate) mod symbols;
+
+use crate::ast::{IdentifierNode, RootNode, SyntaxNode};
+use crate::semantic::c4::{C4Element, C4Model};
+use crate::semantic::preprocessor::Signature;
+use crate::semantic::stdlib::{Library, Procedure};
+use crate::semantic::{bracket, Definition, Model};
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::{Document, Included};
+use rowan::TextSize;
+
+/// The name under the cursor, if any
+pub(crate) fn identifier_at(root: &SyntaxNode, offset: TextSize) -> Option<IdentifierNode> {
+    root.token_at_offset(offset)
+        .find_map(|token| token.ancestors().find_map(IdentifierNode::cast))
+}
+
+/// Names that are local to their declaration, such as class members and
+/// object fields, and can't be referenced from elsewhere in the diagram
+pub(crate) fn is_local(identifier: &IdentifierNode) -> bool {
+    identifier
+        .syntax()
+        .parent()
+        .map(|parent| matches!(parent.kind(), FIELD | METHOD | ENTRY))
+        .unwrap_or(false)
+}
+
+/// Whether `identifier` is the display label of a declaration that also has
+/// an alias, such as `:Main Admin:` in `actor :Main Admin: as Admin`. The
+/// alias is what the rest of the diagram uses, so the label isn't a name.
+pub(crate) fn is_aliased_label(identifier: &IdentifierNode) -> bool {
+    let parent = match identifier.syntax().parent() {
+        Some(parent) => parent,
+        None => return false,
+    };
+    let declaration = matches!(
+        parent.kind(),
+        CLASS_DECL
+            | STATE_DECL
+            | COMPONENT_DECL
+            | CONTAINER_DECL
+            | ACTOR_DECL
+            | USECASE_DECL
+            | OBJECT_DECL
+            | MAP_DECL
+            | SIGNAL_DECL
+    );
+    declaration
+        && parent
+            .children_with_tokens()
+            .skip_while(|it| it.as_node() != Some(identifier.syntax()))
+            .skip(1)
+            .skip_while(|it| it.kind() != AS_KW)
+            .filter_map(|it| it.into_node())
+            .any(|it| it.kind() == IDENTIFIER)
+}
+
+/// Every occurrence of the entity `identifier` names, including itself.
+/// `[Api]`, `"Api"` and `Api` all name the same entity, and so do `Line` and
+/// `net.shop.Line` when they resolve to the same declaration. Names that are
+/// never declared match the undeclared names spelled the same way, unless
+/// their brackets make them different kinds of entity, as `(Api)` and `[Api]`
+/// do.
+pub(crate) fn references(root: &SyntaxNode, identifier: &IdentifierNode) -> Vec<IdentifierNode> {
+    if is_local(identifier) || is_aliased_label(identifier) {
+        return vec![identifier.clone()];
+    }
+    let model = match RootNode::cast(root.clone()) {
+        Some(root) => Model::new(&root),
+        None => return vec![identifier.clone()],
+    };
+    let target = model.resolve(identifier).map(|it| it.name.clone());
+    let name = match target {
+        Some(ref target) => target.last_segment(),
+        None => identifier.value(),
+    };
+    root.descendants()
+        .filter_map(IdentifierNode::cast)
+        .filter(|it| !is_local(it) && !is_aliased_label(it))
+        .filter(|it| match target {
+            Some(_) => it.last_segment() == name,
+            None => it.value() == name && same_kind(it, identifier),
+        })
+        .filter(|it| model.resolve(it).map(|it| &it.name) == target.as_ref())
+        .collect()
+}
+
+/// Whether neither name's brackets say it's a different kind of entity from
+/// the other
+fn same_kind(a: &IdentifierNode, b: &IdentifierNode) -> bool {
+    match (bracket(a), bracket(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
+
+/// The declaration, in one of the included files, of a name the document
+/// doesn't declare itself
+pub(crate) fn included_definition<'a>(
+    includes: &[Included<'a>],
+    identifier: &IdentifierNode,
+) -> Option<(&'a Document, Definition)> {
+    includes.iter().find_map(|included| {
+        let model = Model::new(&included.document.parsed.root());
+        let definition = model
+            .resolve(identifier)
+            .filter(|it| included.range.contains_range(it.name.position()))?;
+        Some((included.document, definition.clone()))
+    })
+}
+
+/// The procedure, function or macro called `name`, from the document or from
+/// one of the files it includes, along with the document that defines it
+pub(crate) fn signature<'a>(
+    document: &'a Document,
+    includes: &[Included<'a>],
+    name: &str,
+) -> Option<(&'a Document, Signature)> {
+    let local = crate::semantic::preprocessor::signatures(&document.parsed.root())
+        .into_iter()
+        .find(|it| it.name == name);
+    if let Some(signature) = local {
+        return Some((document, signature));
+    }
+    includes.iter().find_map(|included| {
+        let signature = crate::semantic::preprocessor::signatures(&included.document.parsed.root())
+            .into_iter()
+            .find(|it| it.name == name && included.range.contains_range(it.range))?;
+        Some((included.document, signature))
+    })
+}
+
+/// The standard library procedure called `name`, if the document includes the
+/// file that defines it, itself or through the files it includes
+pub(crate) fn stdlib_procedure(
+    document: &Document,
+    includes: &[Included],
+    name: &str,
+) -> Option<(&'static Library, &'static Procedure)> {
+    stdlib_procedures(document, includes)
+        .into_iter()
+        .find(|(_, it)| it.name == name)
+}
+
+/// The standard library procedures the document has included
+pub(crate) fn stdlib_procedures(
+    document: &Document,
+    includes: &[Included],
+) -> Vec<(&'static Library, &'static Procedure)> {
+    let paths = std::iter::once(document)
+        .chain(includes.iter().map(|it| it.document))
+        .flat_map(|it| {
+            it.parsed
+                .root()
+                .includes()
+                .filter(|it| it.is_stdlib())
+                .filter_map(|it| Some(it.target()?.0))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    crate::semantic::stdlib::procedures(paths.iter().map(|it| it.as_str()))
+}
+
+/// The C4 element declared with the alias, in the document or in one of the
+/// files it includes, along with the document that declares it
+pub(crate) fn c4_element<'a>(
+    document: &'a Document,
+    includes: &[Included<'a>],
+    alias: &str,
+) -> Option<(&'a Document, C4Element)> {
+    if let Some(element) = C4Model::new(&document.parsed.root()).element(alias) {
+        return Some((document, element.clone()));
+    }
+    includes.iter().find_map(|included| {
+        let model = C4Model::new(&included.document.parsed.root());
+        let element = model
+            .element(alias)
+            .filter(|it| included.range.contains_range(it.range))?;
+        Some((included.document, element.clone()))
+    })
+}
@@ -0,0 +1,139 @@
+use crate::workspace::Document;
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, TextEdit, WorkspaceEdit,
+};
+use std::collections::HashMap;
+
+/// The number of UTF-16 code units in the text, as positions count them
+fn width(text: &str) -> u64 {
+    text.encode_utf16().count() as u64
+}
+
+fn indent(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// `'` out the lines of the range, or uncomment them if they all already are
+fn toggle_comment(document: &Document, range: Range) -> Option<CodeAction> {
+    // a selection that ends at the start of a line doesn't take that line in
+    let last = if range.end.character == 0 && range.end.line > range.start.line {
+        range.end.line - 1
+    } else {
+        range.end.line
+    };
+    let lines = document
+        .text
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number as u64, line))
+        .skip(range.start.line as usize)
+        .take((last - range.start.line + 1) as usize)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return None;
+    }
+    let commented = lines
+        .iter()
+        .all(|(_, line)| line.trim_start().starts_with('\''));
+    let edits = if commented {
+        lines
+            .iter()
+            .map(|(number, line)| {
+                let start = width(indent(line));
+                let rest = &line.trim_start()[1..];
+                let end = start + 1 + if rest.starts_with(' ') { 1 } else { 0 };
+                TextEdit {
+                    range: Range::new(Position::new(*number, start), Position::new(*number, end)),
+                    new_text: String::new(),
+                }
+            })
+            .collect::<Vec<_>>()
+    } else {
+        // line the quotes up with the least indented line
+        let column = lines
+            .iter()
+            .map(|(_, line)| width(indent(line)))
+            .min()
+            .unwrap_or(0);
+        lines
+            .iter()
+            .map(|(number, _)| TextEdit {
+                range: Range::new(
+                    Position::new(*number, column),
+                    Position::new(*number, column),
+                ),
+                new_text: "' ".to_string(),
+            })
+            .collect()
+    };
+    let mut changes = HashMap::new();
+    changes.insert(document.uri.clone(), edits);
+    Some(CodeAction {
+        title: if commented {
+            "Uncomment lines".to_string()
+        } else {
+            "Comment out lines".to_string()
+        },
+        kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+/// The actions that apply to the range
+pub(crate) fn code_actions(document: &Document, range: Range) -> Vec<CodeActionOrCommand> {
+    toggle_comment(document, range)
+        .into_iter()
+        .map(CodeActionOrCommand::CodeAction)
+        .collect()
+}
+
+mod tests {
+    #[test]
+    fn test_toggle_comment() {
+        use lsp_types::{Position, Range};
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startuml\n  class A\n\n    class B\n  ' class C\n  'class D\n@enduml\n";
+        let doc = crate::workspace::Document::new(uri.clone(), text.into());
+        let edits = |start, end| {
+            let range = Range::new(Position::new(start, 0), Position::new(end, 0));
+            let action = match super::code_actions(&doc, range).pop()? {
+                lsp_types::CodeActionOrCommand::CodeAction(it) => it,
+                _ => return None,
+            };
+            let edits = action.edit?.changes?.remove(&uri)?;
+            let edits = edits
+                .into_iter()
+                .map(|it| {
+                    let range = it.range;
+                    (
+                        range.start.line,
+                        range.start.character,
+                        range.end.character,
+                        it.new_text,
+                    )
+                })
+                .collect::<Vec<_>>();
+            Some((action.title, edits))
+        };
+        assert_eq!(
+            edits(1, 4),
+            Some((
+                "Comment out lines".to_string(),
+                vec![(1, 2, 2, "' ".to_string()), (3, 2, 2, "' ".to_string())]
+            ))
+        );
+        assert_eq!(
+            edits(4, 6),
+            Some((
+                "Uncomment lines".to_string(),
+                vec![(4, 2, 4, String::new()), (5, 2, 3, String::new())]
+            ))
+        );
+        assert_eq!(edits(2, 2), None);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::ast::{SkinparamEntryNode, StylePropertyNode, SyntaxToken};
+use crate::semantic::color::{colors_at, Color};
+use crate::semantic::skinparam::{self, ValueType};
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::Document;
+use lsp_types::{ColorInformation, ColorPresentation, Range, TextEdit};
+use rowan::{TextRange, TextSize};
+
+/// Whether the `#` starts a color, rather than the visibility of a member, a
+/// `#highlight` or a salt table
+fn starts_color(hash: &SyntaxToken) -> bool {
+    !hash
+        .parent()
+        .ancestors()
+        .any(|it| matches!(it.kind(), VISIBILITY | DATA_HIGHLIGHT | SALT_DIAGRAM))
+}
+
+/// The value of a skinparam or style property that takes a color, which may be
+/// written without a `#`
+fn color_values(document: &Document) -> Vec<TextRange> {
+    let root = document.parsed.root();
+    let skinparams = root
+        .skinparams()
+        .flat_map(|it| it.entries().collect::<Vec<_>>())
+        .filter(|it: &SkinparamEntryNode| {
+            it.key()
+                .and_then(|key| skinparam::skinparam(&key))
+                .is_some_and(|param| param.value == ValueType::Color)
+        })
+        .filter_map(|it| it.value());
+    let properties = root
+        .syntax()
+        .descendants()
+        .filter_map(StylePropertyNode::cast)
+        .filter(|it| {
+            it.name()
+                .and_then(|name| skinparam::style_property(&name))
+                .is_some_and(|param| param.value == ValueType::Color)
+        })
+        .filter_map(|it| it.value());
+    skinparams
+        .chain(properties)
+        .map(|(_, range)| range)
+        .collect()
+}
+
+fn lsp_color(color: Color) -> lsp_types::Color {
+    lsp_types::Color {
+        red: color.red as f64 / 255.0,
+        green: color.green as f64 / 255.0,
+        blue: color.blue as f64 / 255.0,
+        alpha: color.alpha as f64 / 255.0,
+    }
+}
+
+/// Every color the document writes, with its RGBA value: the ones after a
+/// `#`, as in `#red`, `[#blue]` and `#lightblue/white`, and the values of
+/// skinparams and style properties that take a color
+pub(crate) fn document_colors(document: &Document) -> Vec<ColorInformation> {
+    let tokens = document
+        .parsed
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .collect::<Vec<_>>();
+    // the `#` tokens, and the ones inside arrows such as `-[#red,dashed]->`
+    let hashes = tokens.iter().flat_map(|token| {
+        let start = token.text_range().start();
+        let offsets = match token.kind() {
+            HASH if starts_color(token) => vec![start],
+            ARROW => token
+                .text()
+                .match_indices('#')
+                .map(|(it, _)| start + TextSize::from(it as u32))
+                .collect(),
+            _ => Vec::new(),
+        };
+        offsets.into_iter().map(|start| {
+            let end = document.text[usize::from(start)..]
+                .find('\n')
+                .map(|it| start + TextSize::from(it as u32))
+                .unwrap_or_else(|| TextSize::from(document.text.len() as u32));
+            TextRange::new(start, end)
+        })
+    });
+    let values = color_values(document);
+    let mut colors = hashes
+        .chain(values)
+        .flat_map(|range| {
+            colors_at(&document.text[range])
+                .into_iter()
+                .map(move |(color, it)| {
+                    let start = range.start() + TextSize::from(it.start as u32);
+                    let end = range.start() + TextSize::from(it.end as u32);
+                    (color, TextRange::new(start, end))
+                })
+        })
+        .collect::<Vec<_>>();
+    colors.sort_by_key(|(_, range)| range.start());
+    colors.dedup_by_key(|(_, range)| range.start());
+    colors
+        .into_iter()
+        .map(|(color, range)| ColorInformation {
+            range: document.range(range),
+            color: lsp_color(color),
+        })
+        .collect()
+}
+
+/// The ways to write the color: in hex, and by name if it has one
+pub(crate) fn color_presentations(color: lsp_types::Color, range: Range) -> Vec<ColorPresentation> {
+    let channel = |it: f64| (it.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let color = Color {
+        red: channel(color.red),
+        green: channel(color.green),
+        blue: channel(color.blue),
+        alpha: channel(color.alpha),
+    };
+    let named = color.name().map(|it| format!("#{}", it));
+    std::iter::once(color.hex())
+        .chain(named)
+        .map(|label| ColorPresentation {
+            text_edit: Some(TextEdit {
+                range,
+                new_text: label.clone(),
+            }),
+            label,
+            additional_text_edits: None,
+        })
+        .collect()
+}
+
+mod tests {
+    #[test]
+    fn test_document_colors() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startuml\nclass A #lightblue/White {\n  #add()\n}\nA -[#FF000080]-> B\nskinparam ArrowColor Navy\nskinparam class {\n  BorderColor #F80\n}\n@enduml\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let colors = super::document_colors(&doc)
+            .into_iter()
+            .map(|it| {
+                let range = it.range;
+                let color = it.color;
+                (
+                    (range.start.line, range.start.character, range.end.character),
+                    (color.red * 255.0).round() as u8,
+                    (color.alpha * 255.0).round() as u8,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            colors,
+            vec![
+                ((1, 8, 18), 0xAD, 255),
+                ((1, 19, 24), 0xFF, 255),
+                ((4, 4, 13), 0xFF, 0x80),
+                ((5, 21, 25), 0x00, 255),
+                ((7, 14, 18), 0xFF, 255),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_color_presentations() {
+        let range = lsp_types::Range::default();
+        let labels = |red, alpha| {
+            let color = lsp_types::Color {
+                red,
+                green: 0.0,
+                blue: 0.0,
+                alpha,
+            };
+            super::color_presentations(color, range)
+                .into_iter()
+                .map(|it| it.label)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(1.0, 1.0), vec!["#FF0000", "#Red"]);
+        assert_eq!(labels(0.5, 1.0), vec!["#800000", "#Maroon"]);
+        assert_eq!(labels(0.2, 0.5), vec!["#33000080"]);
+    }
+}
@@ -0,0 +1,488 @@
+use crate::ast::{
+    ClassKind, DataHighlightNode, ElementKind, IdentifierNode, RootNode, SkinparamNode, StyleNode,
+};
+use crate::parsing::preprocessor::INCLUDES;
+use crate::semantic::color::NAMED;
+use crate::semantic::data;
+use crate::semantic::skinparam::{self, Param, ValueType, STYLE_PROPERTIES, STYLE_SELECTORS};
+use crate::semantic::stdlib::LIBRARIES;
+use crate::semantic::theme::THEMES;
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::{Document, Included};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation, Position, TextEdit,
+};
+use rowan::{TextRange, TextSize};
+
+/// Offer the files of the standard library in `!include <`, the keys a
+/// `#highlight` path can continue with, the built-in themes, skinparams, style
+/// properties and their values, or else the names of everything declared in the document and
+/// the files it includes
+pub(crate) fn completions(
+    document: &Document,
+    includes: &[Included],
+    position: Position,
+) -> Vec<CompletionItem> {
+    let offset = document.offset(position);
+    offset
+        .and_then(|offset| stdlib_completions(document, offset))
+        .or_else(|| offset.and_then(|offset| highlight_completions(document, offset)))
+        .or_else(|| offset.and_then(|offset| theme_completions(document, offset)))
+        .or_else(|| offset.and_then(|offset| skinparam_completions(document, offset)))
+        .unwrap_or_else(|| names(document, includes))
+}
+
+/// The files of the standard library, when the cursor is after the `<` of an
+/// `!include`
+fn stdlib_completions(document: &Document, offset: TextSize) -> Option<Vec<CompletionItem>> {
+    let offset = usize::from(offset);
+    let start = document.text[..offset]
+        .rfind('\n')
+        .map(|it| it + 1)
+        .unwrap_or(0);
+    let end = document.text[offset..]
+        .find('\n')
+        .map(|it| it + offset)
+        .unwrap_or_else(|| document.text.len());
+    let before = &document.text[start..offset];
+    let open = before.find('<')?;
+    if !INCLUDES.contains(&before[..open].trim()) {
+        return None;
+    }
+    // replace what's been typed, and what follows up to the '>'
+    let (replaced, close) = match document.text[offset..end].find('>') {
+        Some(close) => (offset + close, ""),
+        None => (offset, ">"),
+    };
+    let range = TextRange::new(
+        TextSize::from((start + open + 1) as u32),
+        TextSize::from(replaced as u32),
+    );
+    Some(
+        LIBRARIES
+            .iter()
+            .map(|library| CompletionItem {
+                label: library.path.to_string(),
+                kind: Some(CompletionItemKind::File),
+                detail: Some(library.doc.to_string()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: document.range(range),
+                    new_text: format!("{}{}", library.path, close),
+                })),
+                ..Default::default()
+            })
+            .collect(),
+    )
+}
+
+/// The keys, or indices, of the data at the path before the cursor, quoted
+/// unless the cursor is already in a string
+fn highlight_completions(document: &Document, offset: TextSize) -> Option<Vec<CompletionItem>> {
+    let root = document.parsed.root();
+    let highlight = root.data_highlights().find(|it| {
+        let range = it.syntax().text_range();
+        range.start() <= offset
+            && (offset < range.end() || !it.syntax().text().to_string().ends_with('\n'))
+    })?;
+    let value = data::parse(&highlight.data()?).ok()?;
+    let path = highlight
+        .path()
+        .into_iter()
+        .filter(|(_, range)| range.end() <= offset)
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+    let keys = value.lookup(&path).ok()?.keys();
+    Some(
+        keys.into_iter()
+            .map(|key| CompletionItem {
+                kind: Some(CompletionItemKind::Field),
+                insert_text: Some(if in_string(&highlight, offset) {
+                    key.clone()
+                } else {
+                    format!("\"{}\"", key)
+                }),
+                label: key,
+                ..Default::default()
+            })
+            .collect(),
+    )
+}
+
+/// The text of the line up to the offset
+fn line_before(document: &Document, offset: TextSize) -> &str {
+    let offset = usize::from(offset);
+    let start = document.text[..offset]
+        .rfind('\n')
+        .map(|it| it + 1)
+        .unwrap_or(0);
+    &document.text[start..offset]
+}
+
+/// The built-in themes, when the cursor is on the name of a `!theme`
+fn theme_completions(document: &Document, offset: TextSize) -> Option<Vec<CompletionItem>> {
+    let before = line_before(document, offset).trim_start();
+    let name = before.strip_prefix("!theme")?;
+    if !name.starts_with(char::is_whitespace) || name.trim_start().contains(char::is_whitespace) {
+        return None;
+    }
+    Some(
+        THEMES
+            .iter()
+            .map(|theme| CompletionItem {
+                kind: Some(CompletionItemKind::EnumMember),
+                label: theme.name.to_string(),
+                detail: Some(theme.doc.to_string()),
+                ..Default::default()
+            })
+            .collect(),
+    )
+}
+
+fn param_item(param: &Param, label: String) -> CompletionItem {
+    CompletionItem {
+        kind: Some(CompletionItemKind::Property),
+        label,
+        detail: Some(param.value.describe()),
+        documentation: Some(Documentation::String(param.doc.clone())),
+        ..Default::default()
+    }
+}
+
+fn value_items(value: ValueType) -> Vec<CompletionItem> {
+    value
+        .suggestions()
+        .into_iter()
+        .map(|label| {
+            let hex = NAMED
+                .iter()
+                .find(|(name, _)| *name == label)
+                .filter(|_| value == ValueType::Color)
+                .map(|(_, rgb)| format!("#{:06X}", rgb));
+            CompletionItem {
+                kind: Some(match hex {
+                    Some(_) => CompletionItemKind::Color,
+                    None => CompletionItemKind::Value,
+                }),
+                label,
+                // editors draw a swatch for the hex value of a color item
+                documentation: hex.map(Documentation::String),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// The skinparams, or the values of the one before the cursor, in a
+/// `skinparam` line or block, and the properties, selectors or values in a
+/// `<style>`
+fn skinparam_completions(document: &Document, offset: TextSize) -> Option<Vec<CompletionItem>> {
+    let token = document
+        .parsed
+        .syntax()
+        .token_at_offset(offset)
+        .left_biased()?;
+    let before = line_before(document, offset);
+    let words = before.split_whitespace().collect::<Vec<_>>();
+    // still writing the first word, or already past it
+    let after_key = |index: usize| words.len() > index + 1 || before.ends_with(char::is_whitespace);
+    if let Some(skinparam) = token.parent().ancestors().find_map(SkinparamNode::cast) {
+        let (key, prefix) = match words.first() {
+            Some(first) if first.eq_ignore_ascii_case("skinparam") => {
+                if !after_key(0) {
+                    return None;
+                }
+                (words.get(1).filter(|_| after_key(1)), String::new())
+            }
+            _ => (
+                words.first().filter(|_| after_key(0)),
+                skinparam.prefix().unwrap_or_default(),
+            ),
+        };
+        if let Some(key) = key {
+            // without the stereotype of `BackgroundColor<<Entity>>`
+            let key = key.split('<').next().unwrap_or_default();
+            let param = skinparam::skinparam(&format!("{}{}", prefix, key))?;
+            return Some(value_items(param.value));
+        }
+        let params = skinparam::skinparams().into_iter().filter_map(|param| {
+            let name = param.name.get(prefix.len()..)?;
+            if !param.name[..prefix.len()].eq_ignore_ascii_case(&prefix) || name.is_empty() {
+                return None;
+            }
+            Some(param_item(&param, name.to_string()))
+        });
+        return Some(params.collect());
+    }
+    token.parent().ancestors().find_map(StyleNode::cast)?;
+    if let Some(param) = words
+        .first()
+        .filter(|_| after_key(0))
+        .and_then(|it| skinparam::style_property(it.trim_end_matches(':')))
+    {
+        return Some(value_items(param.value));
+    }
+    let properties = STYLE_PROPERTIES.iter().map(|(name, _, _)| {
+        let param = skinparam::style_property(name).unwrap();
+        param_item(&param, name.to_string())
+    });
+    let selectors = STYLE_SELECTORS.iter().map(|name| CompletionItem {
+        kind: Some(CompletionItemKind::Keyword),
+        label: name.to_string(),
+        ..Default::default()
+    });
+    Some(properties.chain(selectors).collect())
+}
+
+fn in_string(highlight: &DataHighlightNode, offset: TextSize) -> bool {
+    highlight
+        .syntax()
+        .token_at_offset(offset)
+        .left_biased()
+        .is_some_and(|token| {
+            let range = token.text_range();
+            match token.kind() {
+                STRING => range.start() < offset && offset < range.end(),
+                // a string that's not been closed yet
+                ERROR => token.text().starts_with('"'),
+                _ => false,
+            }
+        })
+}
+
+/// The names of everything declared in the document and in the files it
+/// includes, and the standard library procedures it has included
+fn names(document: &Document, includes: &[Included]) -> Vec<CompletionItem> {
+    let local = declared_names(&document.parsed.root())
+        .into_iter()
+        .map(|(name, kind)| (name, kind, None));
+    let included = includes.iter().flat_map(|included| {
+        declared_names(&included.document.parsed.root())
+            .into_iter()
+            .filter(move |(name, _)| included.range.contains_range(name.position()))
+            .map(move |(name, kind)| (name, kind, Some(included.document.file_name())))
+    });
+    let mut items: Vec<CompletionItem> = Vec::new();
+    for (name, kind, file) in local.chain(included) {
+        let label = name.identifier().to_string();
+        if items.iter().any(|it| it.label == label) {
+            continue;
+        }
+        items.push(CompletionItem {
+            kind: Some(kind),
+            label,
+            detail: file.map(|it| format!("from {}", it)),
+            ..Default::default()
+        });
+    }
+    for (library, procedure) in crate::ide::stdlib_procedures(document, includes) {
+        if items.iter().any(|it| it.label == procedure.name) {
+            continue;
+        }
+        items.push(CompletionItem {
+            kind: Some(CompletionItemKind::Function),
+            label: procedure.name.to_string(),
+            detail: Some(procedure.signature().label()),
+            documentation: Some(Documentation::String(format!(
+                "{}\n\nfrom <{}>",
+                procedure.doc, library.path
+            ))),
+            ..Default::default()
+        });
+    }
+    items
+}
+
+fn declared_names(root: &RootNode) -> Vec<(IdentifierNode, CompletionItemKind)> {
+    let participants = root
+        .participant_decls()
+        .filter_map(|it| it.name())
+        .map(|it| (it, CompletionItemKind::Struct));
+    let classes = root.class_decls().filter_map(|it| {
+        let kind = match it.class_kind() {
+            ClassKind::Interface | ClassKind::Annotation => CompletionItemKind::Interface,
+            ClassKind::Enum => CompletionItemKind::Enum,
+            _ => CompletionItemKind::Class,
+        };
+        it.alias().or_else(|| it.name()).map(|name| (name, kind))
+    });
+    let states = root.state_decls().filter_map(|it| {
+        it.alias()
+            .or_else(|| it.name())
+            .map(|name| (name, CompletionItemKind::Struct))
+    });
+    let components = root.component_decls().filter_map(|it| {
+        let kind = match it.element_kind() {
+            ElementKind::Component => CompletionItemKind::Module,
+            ElementKind::Interface => CompletionItemKind::Interface,
+        };
+        it.alias().or_else(|| it.name()).map(|name| (name, kind))
+    });
+    let containers = root.container_decls().filter_map(|it| {
+        it.alias()
+            .or_else(|| it.name())
+            .map(|name| (name, CompletionItemKind::Folder))
+    });
+    let actors = root.actor_decls().filter_map(|it| {
+        it.alias()
+            .or_else(|| it.name())
+            .map(|name| (name, CompletionItemKind::Struct))
+    });
+    let usecases = root.usecase_decls().filter_map(|it| {
+        it.alias()
+            .or_else(|| it.name())
+            .map(|name| (name, CompletionItemKind::Event))
+    });
+    let objects = root.object_decls().filter_map(|it| {
+        it.alias()
+            .or_else(|| it.name())
+            .map(|name| (name, CompletionItemKind::Value))
+    });
+
+    participants
+        .chain(classes)
+        .chain(states)
+        .chain(components)
+        .chain(containers)
+        .chain(actors)
+        .chain(usecases)
+        .chain(objects)
+        .collect()
+}
+
+mod tests {
+    #[test]
+    fn test_highlight_completions() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startjson\n#highlight \"address\" / \n#highlight \"\n{\"address\": {\"city\": \"Paris\", \"zip\": 1}, \"list\": []}\n@endjson\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let complete = |line, character| {
+            super::completions(&doc, &[], lsp_types::Position::new(line, character))
+                .into_iter()
+                .map(|it| it.insert_text.unwrap_or(it.label))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(complete(1, 23), vec!["\"city\"", "\"zip\""]);
+        assert_eq!(complete(2, 12), vec!["address", "list"]);
+    }
+
+    #[test]
+    fn test_included_names() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///parts.puml"),
+            "!startsub ACTORS\nactor Alice\n!endsub\nclass Hidden\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "!includesub parts.puml!ACTORS\nparticipant Bob\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let includes = workspace.includes(doc);
+        let items = super::completions(doc, &includes, lsp_types::Position::new(2, 0))
+            .into_iter()
+            .map(|it| (it.label, it.detail))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![
+                ("Bob".to_string(), None),
+                ("Alice".to_string(), Some("from parts.puml".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stdlib_completions() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "!include <C4/C4_Container>\n!include <C4/\nCon\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let complete = |line, character| {
+            super::completions(&doc, &[], lsp_types::Position::new(line, character))
+        };
+
+        let edits = complete(0, 12)
+            .into_iter()
+            .filter_map(|it| match it.text_edit? {
+                lsp_types::CompletionTextEdit::Edit(edit) => Some(edit),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(edits[1].new_text, "C4/C4_Context");
+        assert_eq!(edits[1].range.start.character, 10);
+        assert_eq!(edits[1].range.end.character, 25);
+        let edits = complete(1, 13)
+            .into_iter()
+            .filter_map(|it| match it.text_edit? {
+                lsp_types::CompletionTextEdit::Edit(edit) => Some(edit),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(edits[0].new_text, "C4/C4>");
+        assert_eq!(edits[0].range.end.character, 13);
+
+        let container = complete(2, 3)
+            .into_iter()
+            .find(|it| it.label == "Container")
+            .unwrap();
+        assert_eq!(
+            container.kind,
+            Some(lsp_types::CompletionItemKind::Function)
+        );
+        assert_eq!(
+            container.detail.unwrap(),
+            "Container($alias, $label, $techn=\"\", $descr=\"\", $sprite=\"\", $tags=\"\", $link=\"\")"
+        );
+    }
+
+    #[test]
+    fn test_skinparam_completions() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startuml\nskinparam \nskinparam Shadowing \nskinparam class {\n  Bor\n  FontStyle \n}\n<style>\n  LineStyle 5\n  FontColor \n</style>\n@enduml\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let complete = |line, character| {
+            super::completions(&doc, &[], lsp_types::Position::new(line, character))
+                .into_iter()
+                .map(|it| it.label)
+                .collect::<Vec<_>>()
+        };
+        let keys = complete(1, 10);
+        assert!(keys.contains(&"ArrowColor".to_string()));
+        assert!(keys.contains(&"ClassBackgroundColor".to_string()));
+        assert_eq!(complete(2, 20), vec!["true", "false"]);
+        let keys = complete(4, 5);
+        assert!(keys.contains(&"BorderColor".to_string()));
+        assert!(!keys.contains(&"ArrowColor".to_string()));
+        assert_eq!(
+            complete(5, 12),
+            vec!["plain", "bold", "italic", "underline"]
+        );
+        let properties = complete(8, 2);
+        assert!(properties.contains(&"LineColor".to_string()));
+        assert!(properties.contains(&"classDiagram".to_string()));
+        let colors = super::completions(&doc, &[], lsp_types::Position::new(9, 12));
+        let red = colors.iter().find(|it| it.label == "Red").unwrap();
+        assert_eq!(red.kind, Some(lsp_types::CompletionItemKind::Color));
+        assert_eq!(
+            red.documentation,
+            Some(lsp_types::Documentation::String("#FF0000".into()))
+        );
+    }
+
+    #[test]
+    fn test_theme_completions() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startuml\n!theme cer\n!theme mine \n@enduml\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let complete = |line, character| {
+            super::completions(&doc, &[], lsp_types::Position::new(line, character))
+                .into_iter()
+                .map(|it| it.label)
+                .collect::<Vec<_>>()
+        };
+        let themes = complete(1, 10);
+        assert!(themes.contains(&"cerulean".to_string()));
+        assert!(themes.contains(&"crt-green".to_string()));
+        assert!(!complete(2, 12).contains(&"cerulean".to_string()));
+    }
+}
@@ -0,0 +1,546 @@
+use crate::ast::{ActionNode, ControlNode, PpIfNode, ProcedureNode, SyntaxNode};
+use crate::semantic::c4::C4Model;
+use crate::semantic::gantt::Schedule;
+use crate::semantic::timing::Timeline;
+use crate::semantic::{Problem, Severity};
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::{Document, Included, Workspace};
+use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag};
+use rowan::TextRange;
+
+/// Problems with the structure of the document, such as blocks that are
+/// never closed or closing keywords without a block, with the tasks of a
+/// gantt diagram or the data of a JSON or YAML one, and the branches of `!if`
+/// that aren't taken, which editors grey out
+pub(crate) fn diagnostics(document: &Document) -> Vec<Diagnostic> {
+    let root = document.parsed.syntax();
+    let mut diagnostics = Vec::new();
+    for node in root.descendants() {
+        let message = structural_error(&node).or_else(|| syntax_error(document, &node));
+        if let Some(message) = message {
+            diagnostics.push(error(document, first_line(&node), message));
+        }
+    }
+    let root = document.parsed.root();
+    if root.tasks().next().is_some() {
+        let schedule = Schedule::new(&root);
+        diagnostics.extend(schedule.problems().iter().map(|it| problem(document, it)));
+    }
+    let data = crate::semantic::data::problems(&root);
+    diagnostics.extend(data.iter().map(|it| problem(document, it)));
+    let skinparams = crate::semantic::skinparam::problems(&root);
+    diagnostics.extend(skinparams.iter().map(|it| problem(document, it)));
+    let themes = crate::semantic::theme::problems(&root);
+    diagnostics.extend(themes.iter().map(|it| problem(document, it)));
+    let markup = crate::semantic::creole::problems(&root);
+    diagnostics.extend(markup.iter().map(|it| problem(document, it)));
+    if root.signal_decls().next().is_some() {
+        let timeline = Timeline::new(&root);
+        diagnostics.extend(timeline.problems().iter().map(|it| problem(document, it)));
+    }
+    diagnostics.extend(document.inactive.iter().map(|range| Diagnostic {
+        severity: Some(DiagnosticSeverity::Hint),
+        tags: Some(vec![DiagnosticTag::Unnecessary]),
+        ..error(document, *range, "inactive preprocessor branch".into())
+    }));
+    diagnostics
+}
+
+/// Included files that can't be found, and includes that end up including the
+/// document again
+pub(crate) fn include_diagnostics(workspace: &Workspace, document: &Document) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for include in document.parsed.root().includes() {
+        let (path, range) = match include.target() {
+            Some(target) => target,
+            None => continue,
+        };
+        if include.is_stdlib() {
+            continue;
+        }
+        if workspace.resolve(&document.uri, &path).is_none() {
+            let message = format!("cannot find '{}'", path);
+            diagnostics.push(error(document, range, message));
+        } else if let Some(cycle) = workspace.include_cycle(document, &include) {
+            let names = cycle
+                .iter()
+                .map(|it| {
+                    it.path_segments()
+                        .and_then(|mut it| it.next_back())
+                        .unwrap_or("")
+                })
+                .collect::<Vec<_>>();
+            let message = format!("include cycle: {}", names.join(" -> "));
+            diagnostics.push(error(document, range, message));
+        }
+    }
+    diagnostics
+}
+
+/// Calls of procedures that neither the document nor the files it includes
+/// define, and calls with the wrong number of arguments
+pub(crate) fn procedure_diagnostics(workspace: &Workspace, document: &Document) -> Vec<Diagnostic> {
+    let includes = workspace.includes(document);
+    // procedures may also come from the standard library, or from files that
+    // can't be found
+    let complete = all_found(workspace, document, &includes, false);
+    let mut diagnostics = Vec::new();
+    for call in document.parsed.root().calls() {
+        let (name, range) = match call.name_token() {
+            Some(token) => (token.text().to_string(), token.text_range()),
+            None => continue,
+        };
+        match crate::ide::signature(document, &includes, &name) {
+            Some((_, signature)) => {
+                if let Some(message) = signature.arity_problem(call.arguments().count()) {
+                    diagnostics.push(error(document, call.syntax().text_range(), message));
+                }
+            }
+            None if complete => {
+                let message = format!("'{}' is not defined", name);
+                diagnostics.push(error(document, range, message));
+            }
+            None => {}
+        }
+    }
+    diagnostics
+}
+
+/// Problems in the lines that procedures and macros write, reported at the
+/// line that calls them
+pub(crate) fn expansion_diagnostics(workspace: &Workspace, document: &Document) -> Vec<Diagnostic> {
+    let expansion = crate::semantic::expansion::expand(workspace, document);
+    if !expansion.lines.iter().any(|it| it.generated) {
+        return Vec::new();
+    }
+    let expanded = Document::new(document.uri.clone(), expansion.text.clone());
+    diagnostics(&expanded)
+        .into_iter()
+        .filter(
+            |it| match expansion.lines.get(it.range.start.line as usize) {
+                Some(source) => source.generated,
+                None => false,
+            },
+        )
+        .filter_map(|diagnostic| {
+            Some(Diagnostic {
+                range: expansion.original(document, diagnostic.range)?,
+                message: format!("{} (once expanded)", diagnostic.message),
+                ..diagnostic
+            })
+        })
+        .collect()
+}
+
+/// Relationships between C4 elements with an alias that no element of the
+/// document, or of the files it includes, is declared with
+pub(crate) fn c4_diagnostics(workspace: &Workspace, document: &Document) -> Vec<Diagnostic> {
+    let model = C4Model::new(&document.parsed.root());
+    if model.is_empty() {
+        return Vec::new();
+    }
+    let includes = workspace.includes(document);
+    // elements may also be declared in files that can't be found
+    if !all_found(workspace, document, &includes, true) {
+        return Vec::new();
+    }
+    model
+        .problems(|alias| crate::ide::c4_element(document, &includes, alias).is_some())
+        .iter()
+        .map(|it| problem(document, it))
+        .collect()
+}
+
+/// Whether every file the document includes, itself or through the files it
+/// includes, can be found, counting those of the standard library only if
+/// `stdlib` says so
+fn all_found(
+    workspace: &Workspace,
+    document: &Document,
+    includes: &[Included],
+    stdlib: bool,
+) -> bool {
+    std::iter::once(document)
+        .chain(includes.iter().map(|it| it.document))
+        .all(|it| {
+            it.parsed.root().includes().all(|include| {
+                if include.is_stdlib() {
+                    return stdlib;
+                }
+                include
+                    .target()
+                    .and_then(|(path, _)| workspace.resolve(&it.uri, &path))
+                    .is_some()
+            })
+        })
+}
+
+fn problem(document: &Document, problem: &Problem) -> Diagnostic {
+    let mut diagnostic = error(document, problem.range, problem.message.clone());
+    if problem.severity == Severity::Warning {
+        diagnostic.severity = Some(DiagnosticSeverity::Warning);
+    }
+    diagnostic
+}
+
+fn structural_error(node: &SyntaxNode) -> Option<String> {
+    if let Some(block) = ControlNode::cast(node.clone()) {
+        if block.is_closed() {
+            return None;
+        }
+        let closer = match node.kind() {
+            IF_BLOCK => "endif",
+            WHILE_BLOCK => "endwhile",
+            REPEAT_BLOCK => "repeat while",
+            FORK_BLOCK => "end fork",
+            _ => "end split",
+        };
+        return Some(format!(
+            "'{}' is missing its '{}'",
+            block.keyword()?.text(),
+            closer
+        ));
+    }
+    if let Some(action) = ActionNode::cast(node.clone()) {
+        if !action.is_terminated() {
+            return Some("action is missing its ';'".into());
+        }
+        return None;
+    }
+    match node.kind() {
+        BLOCK | CLASS_BODY | STATE_BODY | OBJECT_BODY | SALT_GROUP
+            if node.last_token()?.kind() != R_BRACE =>
+        {
+            Some("block is missing its '}'".into())
+        }
+        PP_IF if !PpIfNode::cast(node.clone())?.is_closed() => Some(format!(
+            "'{}' is missing its '!endif'",
+            node.first_token()?.text()
+        )),
+        PROCEDURE if !ProcedureNode::cast(node.clone())?.is_closed() => {
+            Some(if ProcedureNode::cast(node.clone())?.is_function() {
+                "'!function' is missing its '!endfunction'".into()
+            } else {
+                "'!procedure' is missing its '!endprocedure'".into()
+            })
+        }
+        ERROR => {
+            let opener = match node.first_token()?.kind() {
+                ELSEIF_KW | ELSE_KW | ENDIF_KW => "if",
+                ENDWHILE_KW => "while",
+                REPEAT_KW => "repeat",
+                FORK_KW => "fork",
+                SPLIT_KW => "split",
+                END_KW => "fork' or 'split",
+                R_BRACE => "{",
+                DIRECTIVE => match node.first_token()?.text().as_str() {
+                    "!elseif" | "!else" | "!endif" => "!if",
+                    "!endprocedure" => "!procedure",
+                    "!endfunction" => "!function",
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            Some(format!(
+                "'{}' without a matching '{}'",
+                node.text().to_string().trim(),
+                opener
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The error the parser made an ERROR node for, when it's about something
+/// the parser expected, such as a missing `)`. The text it doesn't expect is
+/// left alone, as it may well be PlantUML the parser doesn't know about.
+fn syntax_error(document: &Document, node: &SyntaxNode) -> Option<String> {
+    if node.kind() != ERROR {
+        return None;
+    }
+    document
+        .parsed
+        .errors()
+        .iter()
+        .rfind(|it| it.range == node.text_range())
+        .filter(|it| it.message.starts_with("expected"))
+        .map(|it| it.message.clone())
+}
+
+/// The range of the node on its first line, so that a block that runs to the
+/// end of the document doesn't light up all of it
+fn first_line(node: &SyntaxNode) -> TextRange {
+    let start = node.text_range().start();
+    let end = node
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|it| it.kind() == EOL)
+        .map(|it| it.text_range().start())
+        .unwrap_or_else(|| node.text_range().end());
+    TextRange::new(start, end)
+}
+
+fn error(document: &Document, range: TextRange, message: String) -> Diagnostic {
+    Diagnostic {
+        range: document.range(range),
+        severity: Some(DiagnosticSeverity::Error),
+        source: Some("puml".into()),
+        message,
+        ..Default::default()
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_unbalanced_blocks() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "if (a) then\n  fork\n    :b;\n  fork again\n    :c;\nendif\nendwhile\n:d\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let messages = crate::ide::diagnostics::diagnostics(&doc)
+            .into_iter()
+            .map(|it| (it.range.start.line, it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (1, "'fork' is missing its 'end fork'".to_string()),
+                (6, "'endwhile' without a matching 'while'".to_string()),
+                (7, "action is missing its ';'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "!theme cerulean y\n?-> A\nskinparam class {\n  = red\n}\n<style>\n  class {\n    = 1\n  }\n</style>\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let messages = crate::ide::diagnostics::diagnostics(&doc)
+            .into_iter()
+            .map(|it| (it.range.start.line, it.message))
+            .collect::<Vec<_>>();
+        // the '?' isn't reported, as the parser only knows it doesn't expect it
+        assert_eq!(
+            messages,
+            vec![
+                (0, "expected end of line".to_string()),
+                (3, "expected a skinparam".to_string()),
+                (7, "expected a style property".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gantt_problems() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startgantt\n[A] starts at [B]'s end\n[B] starts at [A]'s end\n@endgantt\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let messages = crate::ide::diagnostics::diagnostics(&doc)
+            .into_iter()
+            .map(|it| (it.range.start.line, it.severity.unwrap(), it.message))
+            .collect::<Vec<_>>();
+        use lsp_types::DiagnosticSeverity::*;
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    1,
+                    Warning,
+                    "task 'B' is used before it is defined".to_string()
+                ),
+                (
+                    2,
+                    Error,
+                    "tasks depend on each other: A → B → A".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_salt_braces() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startsalt\n{\n  [Ok] | { [Cancel]\n}\n}\n}\n@endsalt\n@startsalt\n{+\n  Login\n@endsalt\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let messages = crate::ide::diagnostics::diagnostics(&doc)
+            .into_iter()
+            .map(|it| (it.range.start.line, it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (5, "'}' without a matching '{'".to_string()),
+                (8, "block is missing its '}'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preprocessor_problems() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "!$a = 1\n!if $a > 1\nclass A\n!endif\n!else\n!ifdef B\nclass B\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let messages = crate::ide::diagnostics::diagnostics(&doc)
+            .into_iter()
+            .map(|it| (it.range.start.line, it.tags.is_some(), it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (4, false, "'!else' without a matching '!if'".to_string()),
+                (5, false, "'!ifdef' is missing its '!endif'".to_string()),
+                (2, true, "inactive preprocessor branch".to_string()),
+                (6, true, "inactive preprocessor branch".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_procedure_problems() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///lib.puml"),
+            "!procedure $box($name, $color=\"red\")\n  rectangle $name\n!endprocedure\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "!include lib.puml\n$box(\"a\")\n$box()\n$boxes(\"a\")\n!endfunction\n!function $f($x)\n"
+                .into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let mut diagnostics = crate::ide::diagnostics::diagnostics(doc);
+        diagnostics.extend(crate::ide::diagnostics::procedure_diagnostics(
+            &workspace, doc,
+        ));
+        let messages = diagnostics
+            .into_iter()
+            .map(|it| (it.range.start.line, it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    4,
+                    "'!endfunction' without a matching '!function'".to_string()
+                ),
+                (5, "'!function' is missing its '!endfunction'".to_string()),
+                (
+                    2,
+                    "'$box' takes 1 to 2 arguments, but 0 were given".to_string()
+                ),
+                (3, "'$boxes' is not defined".to_string()),
+            ]
+        );
+
+        // the standard library could define anything
+        workspace.update(
+            url("file:///c4.puml"),
+            "!include <C4/C4_Container>\nPerson(user, \"User\")\n".into(),
+        );
+        let doc = workspace.get(&url("file:///c4.puml")).unwrap();
+        assert!(crate::ide::diagnostics::procedure_diagnostics(&workspace, doc).is_empty());
+    }
+
+    #[test]
+    fn test_expansion_problems() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///lib.puml"),
+            "!procedure $open($name)\n  package $name {\n!endprocedure\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "@startuml\n!include lib.puml\nclass A\n$open(\"Api\")\n@enduml\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let messages = crate::ide::diagnostics::expansion_diagnostics(&workspace, doc)
+            .into_iter()
+            .map(|it| {
+                let range = it.range;
+                (
+                    range.start.line,
+                    range.start.character,
+                    range.end.character,
+                    it.message,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![(
+                3,
+                0,
+                12,
+                "block is missing its '}' (once expanded)".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_c4_problems() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///people.puml"),
+            "!include <C4/C4_Context>\nPerson(user, \"User\")\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "!include people.puml\nSystem(shop, \"Shop\")\nRel(user, shop, \"Buys\")\nRel(shop, bank, \"Pays\")\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let messages = crate::ide::diagnostics::c4_diagnostics(&workspace, doc)
+            .into_iter()
+            .map(|it| {
+                (
+                    it.range.start.line,
+                    it.range.start.character,
+                    it.severity,
+                    it.message,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![(
+                3,
+                10,
+                Some(lsp_types::DiagnosticSeverity::Warning),
+                "'bank' is not a declared element".to_string()
+            )]
+        );
+
+        // 'bank' may be in there
+        workspace.update(
+            url("file:///main.puml"),
+            "!include nowhere.puml\nSystem(shop, \"Shop\")\nRel(shop, bank, \"Pays\")\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        assert!(crate::ide::diagnostics::c4_diagnostics(&workspace, doc).is_empty());
+    }
+
+    #[test]
+    fn test_include_problems() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(url("file:///b.puml"), "!include a.puml\n".into());
+        workspace.update(
+            url("file:///a.puml"),
+            "!include b.puml\n!include missing.puml\n!include <C4/C4>\n".into(),
+        );
+        let doc = workspace.get(&url("file:///a.puml")).unwrap();
+        let messages = crate::ide::diagnostics::include_diagnostics(&workspace, doc)
+            .into_iter()
+            .map(|it| (it.range.start.line, it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (0, "include cycle: a.puml -> b.puml -> a.puml".to_string()),
+                (1, "cannot find 'missing.puml'".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::semantic::expansion::expand;
+use crate::workspace::{Document, Workspace};
+use lsp_types::{TextDocumentIdentifier, Url};
+use serde::{Deserialize, Serialize};
+
+/// The parameters of `puml/expandedSource`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExpandedSourceParams {
+    pub(crate) text_document: TextDocumentIdentifier,
+}
+
+/// The document as PlantUML sees it once the preprocessor has run, with where
+/// each of its lines comes from
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExpandedSource {
+    pub(crate) text: String,
+    /// One entry for every line of `text`
+    pub(crate) source_map: Vec<SourceMapping>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SourceMapping {
+    /// The document the line was written in, which is an included one for
+    /// lines that come from an `!include` or from a procedure defined there
+    pub(crate) uri: Url,
+    pub(crate) line: u64,
+    /// The line of the requested document that produced it
+    pub(crate) original_line: u64,
+    /// Whether a procedure or macro wrote the line
+    pub(crate) generated: bool,
+}
+
+/// Expand the includes, conditionals, variables, macros, procedures and
+/// builtins of the document
+pub(crate) fn expanded_source(workspace: &Workspace, document: &Document) -> ExpandedSource {
+    let expansion = expand(workspace, document);
+    let source_map = expansion
+        .lines
+        .into_iter()
+        .map(|it| SourceMapping {
+            uri: it.uri,
+            line: it.line,
+            original_line: it.anchor,
+            generated: it.generated,
+        })
+        .collect();
+    ExpandedSource {
+        text: expansion.text,
+        source_map,
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_expanded_source() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///main.puml"),
+            "@startuml\n!procedure $pair($a)\nclass $a\nclass $a + 1\n!endprocedure\n$pair(\"X\")\n@enduml\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let response = super::expanded_source(&workspace, doc);
+        assert_eq!(response.text, "@startuml\nclass X\nclass X + 1\n@enduml\n");
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json["sourceMap"][2],
+            serde_json::json!({
+                "uri": "file:///main.puml",
+                "line": 3,
+                "originalLine": 5,
+                "generated": true,
+            })
+        );
+    }
+}
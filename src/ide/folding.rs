@@ -0,0 +1,205 @@
+use crate::ast::SyntaxNode;
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::Document;
+use lsp_types::{FoldingRange, FoldingRangeKind};
+use rowan::TextSize;
+
+/// Fold every construct that spans more than one line: class bodies,
+/// packages, containers, objects, activity blocks, composite states, mind map
+/// nodes with children, salt groups, multi-line actions, `!if` and
+/// `!definelong`, the objects and lists of JSON or YAML data, and comments
+pub(crate) fn folding_ranges(document: &Document) -> Vec<FoldingRange> {
+    let root = document.parsed.syntax();
+    let mut ranges = root
+        .descendants()
+        .filter(is_foldable)
+        .filter_map(|node| {
+            let start = document.position(node.text_range().start()).line;
+            let end = document.position(significant_end(&node)?).line;
+            region(start, end)
+        })
+        .collect::<Vec<_>>();
+    for data in document.parsed.root().data_nodes() {
+        if data.is_yaml() {
+            let first = document.position(data.syntax().text_range().start()).line;
+            let text = data.syntax().text().to_string();
+            ranges.extend(
+                indented_blocks(&text)
+                    .into_iter()
+                    .filter_map(|(start, end)| region(first + start, first + end)),
+            );
+        } else {
+            ranges.extend(bracketed_blocks(data.syntax()).into_iter().filter_map(
+                |(open, close)| region(document.position(open).line, document.position(close).line),
+            ));
+        }
+    }
+    ranges.extend(comment_blocks(document));
+    ranges
+}
+
+/// `/' ... '/` comments that span lines, and runs of `'` comments on
+/// consecutive lines
+fn comment_blocks(document: &Document) -> Vec<FoldingRange> {
+    let mut blocks: Vec<(u64, u64)> = Vec::new();
+    let comments = document
+        .parsed
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() == COMMENT);
+    for comment in comments {
+        let range = comment.text_range();
+        let start = document.position(range.start()).line;
+        let end = document.position(range.end()).line;
+        match blocks.last_mut() {
+            Some((_, last)) if !comment.text().starts_with("/'") && *last + 1 == start => {
+                *last = end
+            }
+            _ => blocks.push((start, end)),
+        }
+    }
+    blocks
+        .into_iter()
+        .filter_map(|(start, end)| {
+            Some(FoldingRange {
+                kind: Some(FoldingRangeKind::Comment),
+                ..region(start, end)?
+            })
+        })
+        .collect()
+}
+
+fn region(start: u64, end: u64) -> Option<FoldingRange> {
+    if end <= start {
+        return None;
+    }
+    Some(FoldingRange {
+        start_line: start,
+        start_character: None,
+        end_line: end,
+        end_character: None,
+        kind: Some(FoldingRangeKind::Region),
+    })
+}
+
+/// Where each `{` or `[` of JSON data opens, and where it's closed
+fn bracketed_blocks(data: &SyntaxNode) -> Vec<(TextSize, TextSize)> {
+    let mut open = Vec::new();
+    let mut blocks = Vec::new();
+    for token in data
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+    {
+        match token.kind() {
+            L_BRACE | L_BRACK => open.push(token.text_range().start()),
+            R_BRACE | R_BRACK => {
+                if let Some(start) = open.pop() {
+                    blocks.push((start, token.text_range().start()));
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks.sort();
+    blocks
+}
+
+/// The lines of YAML data followed by more indented ones, and the last of
+/// those
+fn indented_blocks(text: &str) -> Vec<(u64, u64)> {
+    let lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| (number as u64, line.len() - line.trim_start().len()))
+        .collect::<Vec<_>>();
+    let mut blocks = Vec::new();
+    for (index, (start, indent)) in lines.iter().enumerate() {
+        let end = lines[index + 1..]
+            .iter()
+            .take_while(|(_, it)| it > indent)
+            .last()
+            .map(|(line, _)| *line);
+        if let Some(end) = end {
+            blocks.push((*start, end));
+        }
+    }
+    blocks
+}
+
+fn is_foldable(node: &SyntaxNode) -> bool {
+    matches!(
+        node.kind(),
+        CLASS_DECL
+            | PACKAGE_DECL
+            | ACTION
+            | IF_BLOCK
+            | WHILE_BLOCK
+            | REPEAT_BLOCK
+            | FORK_BLOCK
+            | SPLIT_BLOCK
+            | PARTITION
+            | STATE_DECL
+            | COMPONENT_DECL
+            | CONTAINER_DECL
+            | OBJECT_DECL
+            | MAP_DECL
+            | MINDMAP_NODE
+            | SALT_GROUP
+            | PP_IF
+            | DEFINE
+    )
+}
+
+/// Where the node ends, ignoring the line break and whitespace it swallowed
+pub(crate) fn significant_end(node: &SyntaxNode) -> Option<rowan::TextSize> {
+    let mut token = node.last_token()?;
+    while matches!(token.kind(), EOL | WHITESPACE) {
+        let prev = token.prev_token()?;
+        if !node.text_range().contains_range(prev.text_range()) {
+            return None;
+        }
+        token = prev;
+    }
+    Some(token.text_range().end())
+}
+
+mod tests {
+    #[test]
+    fn test_folding() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "class A {\n  x\n}\nif (a) then\n  :b;\nelse\n  :c;\nendif\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let ranges = crate::ide::folding::folding_ranges(&doc)
+            .into_iter()
+            .map(|it| (it.start_line, it.end_line))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(0, 2), (3, 7)]);
+    }
+
+    #[test]
+    fn test_data_folding() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startjson\n{\n  \"a\": [1,\n    2],\n  \"b\": {}\n}\n@endjson\n@startyaml\na:\n  b:\n    - 1\n\n  c: 2\nd: 3\n@endyaml\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let ranges = crate::ide::folding::folding_ranges(&doc)
+            .into_iter()
+            .map(|it| (it.start_line, it.end_line))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(1, 5), (2, 3), (8, 12), (9, 10)]);
+    }
+
+    #[test]
+    fn test_comment_folding() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "' one\n' two\n  ' three\nclass A\n' alone\n/' a\nblock '/\n' after\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let ranges = crate::ide::folding::folding_ranges(&doc)
+            .into_iter()
+            .map(|it| (it.start_line, it.end_line, it.kind))
+            .collect::<Vec<_>>();
+        let comment = Some(lsp_types::FoldingRangeKind::Comment);
+        assert_eq!(ranges, vec![(0, 2, comment.clone()), (5, 7, comment)]);
+    }
+}
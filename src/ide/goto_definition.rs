@@ -0,0 +1,182 @@
+use crate::ast::IdentifierNode;
+use crate::semantic::c4::C4Model;
+use crate::semantic::preprocessor::call_named_at;
+use crate::semantic::Model;
+use crate::workspace::{Document, Included};
+use lsp_types::{Location, Position};
+
+/// Where the name under the cursor is declared, in the document or in the
+/// files it includes. Names that are never declared, such as the classes of
+/// `A --> B`, go to their first mention, calls go to the procedure they call,
+/// and C4 aliases go to the element they name.
+pub(crate) fn goto_definition(
+    document: &Document,
+    includes: &[Included],
+    position: Position,
+) -> Option<Location> {
+    let offset = document.offset(position)?;
+    let root = document.parsed.syntax();
+    if let Some(call) = call_named_at(&root, offset) {
+        let (document, signature) = crate::ide::signature(document, includes, &call.name()?)?;
+        return Some(Location::new(
+            document.uri.clone(),
+            document.range(signature.range),
+        ));
+    }
+    if let Some(alias) = C4Model::new(&document.parsed.root()).alias_at(offset) {
+        let (document, element) = crate::ide::c4_element(document, includes, &alias)?;
+        return Some(Location::new(
+            document.uri.clone(),
+            document.range(element.range),
+        ));
+    }
+    let identifier = crate::ide::identifier_at(&root, offset)?;
+    let model = Model::new(&document.parsed.root());
+    let (document, target) = match model.resolve(&identifier) {
+        Some(definition) => (document, definition.name.clone()),
+        None => match crate::ide::included_definition(includes, &identifier) {
+            Some((included, definition)) => (included, definition.name),
+            None => (document, first_mention(document, &identifier)?),
+        },
+    };
+    Some(Location::new(
+        document.uri.clone(),
+        document.range(target.position()),
+    ))
+}
+
+fn first_mention(document: &Document, identifier: &IdentifierNode) -> Option<IdentifierNode> {
+    crate::ide::references(&document.parsed.syntax(), identifier)
+        .into_iter()
+        .next()
+}
+
+mod tests {
+    #[test]
+    fn test_goto_nested_state() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "state Outer {\n  state Inner\n}\n[*] --> Outer.Inner\nInner --> A\nA --> [*]\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let goto = |line, character| {
+            crate::ide::goto_definition::goto_definition(
+                &doc,
+                &[],
+                lsp_types::Position::new(line, character),
+            )
+            .map(|it| (it.range.start.line, it.range.start.character))
+        };
+        assert_eq!(goto(3, 10), Some((1, 8)));
+        // not in scope, but there is nothing else it could mean
+        assert_eq!(goto(4, 1), Some((1, 8)));
+        assert_eq!(goto(5, 0), Some((4, 10)));
+    }
+
+    #[test]
+    fn test_goto_map_entry() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text =
+            "map CapitalCity {\n  UK => London\n}\nobject London\nCapitalCity::UK --> London\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let goto = |line, character| {
+            crate::ide::goto_definition::goto_definition(
+                &doc,
+                &[],
+                lsp_types::Position::new(line, character),
+            )
+            .map(|it| (it.range.start.line, it.range.start.character))
+        };
+        assert_eq!(goto(4, 14), Some((1, 2)));
+        assert_eq!(goto(4, 22), Some((3, 7)));
+    }
+
+    #[test]
+    fn test_goto_procedure() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///lib.puml"),
+            "!procedure $box($name)\n  rectangle $name\n!endprocedure\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "!include lib.puml\n!function $twice($x) !return $x * 2\n$box(\"a\")\nA -> B : $twice(2)\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let includes = workspace.includes(doc);
+        let goto = |line, character| {
+            crate::ide::goto_definition::goto_definition(
+                doc,
+                &includes,
+                lsp_types::Position::new(line, character),
+            )
+            .map(|it| {
+                (
+                    it.uri.path().to_string(),
+                    it.range.start.line,
+                    it.range.start.character,
+                )
+            })
+        };
+        assert_eq!(goto(2, 2), Some(("/lib.puml".into(), 0, 11)));
+        assert_eq!(goto(3, 11), Some(("/main.puml".into(), 1, 10)));
+    }
+
+    #[test]
+    fn test_goto_included() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///lib/common.puml"),
+            "participant Alice\nparticipant Bob\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "!include lib/common.puml\nAlice -> Bob\nBob -> Carol\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let includes = workspace.includes(doc);
+        let goto = |line, character| {
+            crate::ide::goto_definition::goto_definition(
+                doc,
+                &includes,
+                lsp_types::Position::new(line, character),
+            )
+            .map(|it| (it.uri.path().to_string(), it.range.start.line))
+        };
+        assert_eq!(goto(1, 1), Some(("/lib/common.puml".into(), 0)));
+        assert_eq!(goto(1, 10), Some(("/lib/common.puml".into(), 1)));
+        assert_eq!(goto(2, 8), Some(("/main.puml".into(), 2)));
+    }
+
+    #[test]
+    fn test_goto_c4_alias() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///people.puml"),
+            "!include <C4/C4_Context>\nPerson(user, \"User\")\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "!include people.puml\nSystem(shop, \"Shop\")\nRel(user, shop, \"Buys\")\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let includes = workspace.includes(doc);
+        let goto = |line, character| {
+            crate::ide::goto_definition::goto_definition(
+                doc,
+                &includes,
+                lsp_types::Position::new(line, character),
+            )
+            .map(|it| {
+                (
+                    it.uri.path().to_string(),
+                    it.range.start.line,
+                    it.range.start.character,
+                )
+            })
+        };
+        assert_eq!(goto(2, 12), Some(("/main.puml".into(), 1, 7)));
+        assert_eq!(goto(2, 5), Some(("/people.puml".into(), 1, 7)));
+    }
+}
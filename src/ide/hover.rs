@@ -0,0 +1,377 @@
+use crate::ast::{
+    CallNode, ClassKind, ElementKind, PortKind, SkinparamEntryNode, StylePropertyNode, ThemeNode,
+    TimeNode,
+};
+use crate::semantic::gantt::Schedule;
+use crate::semantic::skinparam;
+use crate::semantic::theme;
+use crate::semantic::timing::Timeline;
+use crate::semantic::{DefinitionKind, Model};
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::{Document, Included};
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+use rowan::{TextRange, TextSize};
+
+/// What is known about what's under the cursor: when a gantt task starts and
+/// ends, the absolute time of a timing diagram's `@+50`, where a name declared
+/// in an included file comes from, what a standard library procedure does,
+/// what a theme looks like, or what a skinparam or style property sets and the
+/// value the theme gives it
+pub(crate) fn hover(
+    document: &Document,
+    includes: &[Included],
+    position: Position,
+) -> Option<Hover> {
+    let offset = document.offset(position)?;
+    time_hover(document, offset)
+        .or_else(|| task_hover(document, offset))
+        .or_else(|| included_hover(document, includes, offset))
+        .or_else(|| stdlib_hover(document, includes, offset))
+        .or_else(|| theme_hover(document, offset))
+        .or_else(|| skinparam_hover(document, offset))
+}
+
+fn markdown(document: &Document, value: String, range: TextRange) -> Hover {
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(document.range(range)),
+    }
+}
+
+fn time_hover(document: &Document, offset: TextSize) -> Option<Hover> {
+    let time = document
+        .parsed
+        .syntax()
+        .token_at_offset(offset)
+        .find_map(|token| token.ancestors().find_map(TimeNode::cast))?;
+    let timeline = Timeline::new(&document.parsed.root());
+    let value = timeline.time(time.position())?;
+    Some(markdown(
+        document,
+        format!("at time **{}**", value),
+        time.position(),
+    ))
+}
+
+fn included_hover(document: &Document, includes: &[Included], offset: TextSize) -> Option<Hover> {
+    let identifier = crate::ide::identifier_at(&document.parsed.syntax(), offset)?;
+    if Model::new(&document.parsed.root())
+        .resolve(&identifier)
+        .is_some()
+    {
+        return None;
+    }
+    let (included, definition) = crate::ide::included_definition(includes, &identifier)?;
+    Some(markdown(
+        document,
+        format!(
+            "{} **{}**\n\nfrom `{}`",
+            describe(definition.kind),
+            definition.name.value(),
+            included.file_name()
+        ),
+        identifier.position(),
+    ))
+}
+
+fn stdlib_hover(document: &Document, includes: &[Included], offset: TextSize) -> Option<Hover> {
+    let token = document
+        .parsed
+        .syntax()
+        .token_at_offset(offset)
+        .find(|it| it.kind() == IDENTIFIER)?;
+    let call = CallNode::cast(token.parent())?;
+    if call.name_token()? != token {
+        return None;
+    }
+    let name = token.text().to_string();
+    if crate::ide::signature(document, includes, &name).is_some() {
+        return None;
+    }
+    let (library, procedure) = crate::ide::stdlib_procedure(document, includes, &name)?;
+    Some(markdown(
+        document,
+        format!(
+            "```\n{}\n```\n\n{}\n\nfrom `<{}>`",
+            procedure.signature().label(),
+            procedure.doc,
+            library.path
+        ),
+        token.text_range(),
+    ))
+}
+
+fn theme_hover(document: &Document, offset: TextSize) -> Option<Hover> {
+    let token = document.parsed.syntax().token_at_offset(offset).next()?;
+    let (name, range) = token
+        .parent()
+        .ancestors()
+        .find_map(ThemeNode::cast)?
+        .name()
+        .filter(|(_, range)| range.contains_inclusive(offset))?;
+    let theme = theme::theme(&name)?;
+    Some(markdown(
+        document,
+        format!("**{}** theme\n\n{}", theme.name, theme.doc),
+        range,
+    ))
+}
+
+fn skinparam_hover(document: &Document, offset: TextSize) -> Option<Hover> {
+    let token = document
+        .parsed
+        .syntax()
+        .token_at_offset(offset)
+        .find(|it| it.kind() == IDENTIFIER)?;
+    let mut themed = None;
+    let param = if let Some(entry) = SkinparamEntryNode::cast(token.parent()) {
+        if entry.key_token()? != token {
+            return None;
+        }
+        let param = skinparam::skinparam(&entry.key()?)?;
+        if let Some(theme) = theme::active(&document.parsed.root()) {
+            themed = theme
+                .skinparam(&param.name)
+                .map(|value| format!("\n\nThe `{}` theme sets it to `{}`", theme.name, value));
+        }
+        param
+    } else {
+        let property = StylePropertyNode::cast(token.parent())?;
+        if property.name_token()? != token {
+            return None;
+        }
+        skinparam::style_property(token.text())?
+    };
+    Some(markdown(
+        document,
+        format!(
+            "**{}**: {}\n\n{}{}",
+            param.name,
+            param.value.describe(),
+            param.doc,
+            themed.unwrap_or_default()
+        ),
+        token.text_range(),
+    ))
+}
+
+/// What a definition declares, the way the diagram would say it
+fn describe(kind: DefinitionKind) -> String {
+    match kind {
+        DefinitionKind::Participant => "participant".into(),
+        DefinitionKind::Package => "package".into(),
+        DefinitionKind::Class(ClassKind::AbstractClass) => "abstract class".into(),
+        DefinitionKind::Class(kind) => format!("{:?}", kind).to_lowercase(),
+        DefinitionKind::State(_) => "state".into(),
+        DefinitionKind::Element(ElementKind::Component) => "component".into(),
+        DefinitionKind::Element(ElementKind::Interface) => "interface".into(),
+        DefinitionKind::Container(kind) => format!("{:?}", kind).to_lowercase(),
+        DefinitionKind::Port(PortKind::Port) => "port".into(),
+        DefinitionKind::Port(PortKind::In) => "portin".into(),
+        DefinitionKind::Port(PortKind::Out) => "portout".into(),
+        DefinitionKind::Actor => "actor".into(),
+        DefinitionKind::Usecase => "usecase".into(),
+        DefinitionKind::Object => "object".into(),
+        DefinitionKind::Map => "map".into(),
+        DefinitionKind::Entry => "entry".into(),
+        DefinitionKind::Signal(kind) => format!("{:?}", kind).to_lowercase(),
+    }
+}
+
+fn task_hover(document: &Document, offset: TextSize) -> Option<Hover> {
+    let root = document.parsed.syntax();
+    let identifier = crate::ide::identifier_at(&root, offset)?;
+    let in_gantt = identifier
+        .syntax()
+        .ancestors()
+        .any(|it| matches!(it.kind(), TASK_STATEMENT | DEPENDENCY));
+    if !in_gantt {
+        return None;
+    }
+    let schedule = Schedule::new(&document.parsed.root());
+    let name = identifier.value();
+    let span = schedule.task(&name)?;
+    let mut value = if schedule.is_milestone(&name) {
+        format!(
+            "**{}**\n\nhappens on {}",
+            name,
+            schedule.format_day(span.start)
+        )
+    } else {
+        format!(
+            "**{}**\n\nstarts {}, ends {}",
+            name,
+            schedule.format_day(span.start),
+            schedule.format_day(span.end)
+        )
+    };
+    let resources = document
+        .parsed
+        .root()
+        .tasks()
+        .filter(|task| {
+            task.name().map(|it| it.value()) == Some(name.clone())
+                || task.alias().map(|it| it.value()) == Some(name.clone())
+        })
+        .flat_map(|task| task.resources().collect::<Vec<_>>())
+        .filter_map(|resource| {
+            let who = resource.name()?.value();
+            Some(match resource.percent() {
+                100 => who,
+                percent => format!("{} ({}%)", who, percent),
+            })
+        })
+        .collect::<Vec<_>>();
+    if !resources.is_empty() {
+        value.push_str(&format!("\n\non {}", resources.join(", ")));
+    }
+    Some(markdown(document, value, identifier.position()))
+}
+
+mod tests {
+    #[test]
+    fn test_hover_task() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startgantt\nProject starts 2020-07-01\n[Design] as [D] on {Alice:50%} {Bob} lasts 3 days\n[Test] lasts 2 days and starts at [D]'s end\n[Done] happens at [Test]'s end\n@endgantt\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let hover = |line, character| {
+            crate::ide::hover::hover(&doc, &[], lsp_types::Position::new(line, character)).map(
+                |it| match it.contents {
+                    lsp_types::HoverContents::Markup(markup) => markup.value,
+                    _ => unreachable!(),
+                },
+            )
+        };
+        assert_eq!(
+            hover(3, 37),
+            Some("**D**\n\nstarts 2020-07-01, ends 2020-07-03\n\non Alice (50%), Bob".into())
+        );
+        assert_eq!(
+            hover(3, 2),
+            Some("**Test**\n\nstarts 2020-07-04, ends 2020-07-05".into())
+        );
+        assert_eq!(
+            hover(4, 2),
+            Some("**Done**\n\nhappens on 2020-07-05".into())
+        );
+        assert_eq!(hover(1, 2), None);
+    }
+
+    #[test]
+    fn test_hover_time() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "concise \"Web User\" as WU\n@100 as :start\nWU is Idle\n@:start+50\nWU is Busy\n@+25\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let hover = |line, character| {
+            crate::ide::hover::hover(&doc, &[], lsp_types::Position::new(line, character)).map(
+                |it| match it.contents {
+                    lsp_types::HoverContents::Markup(markup) => markup.value,
+                    _ => unreachable!(),
+                },
+            )
+        };
+        assert_eq!(hover(3, 3), Some("at time **150**".into()));
+        assert_eq!(hover(5, 2), Some("at time **175**".into()));
+        assert_eq!(hover(2, 1), None);
+    }
+
+    #[test]
+    fn test_hover_included() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(url("file:///common.puml"), "actor Alice\n".into());
+        workspace.update(
+            url("file:///main.puml"),
+            "!include common.puml\nparticipant Bob\nAlice -> Bob\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let includes = workspace.includes(doc);
+        let hover = |line, character| {
+            crate::ide::hover::hover(doc, &includes, lsp_types::Position::new(line, character)).map(
+                |it| match it.contents {
+                    lsp_types::HoverContents::Markup(markup) => markup.value,
+                    _ => unreachable!(),
+                },
+            )
+        };
+        assert_eq!(
+            hover(2, 1),
+            Some("actor **Alice**\n\nfrom `common.puml`".into())
+        );
+        assert_eq!(hover(2, 10), None);
+    }
+
+    #[test]
+    fn test_hover_stdlib() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "!include <C4/C4_Context>\nPerson(user, \"User\")\nRel(user, web, \"Uses\")\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let hover = |line, character| {
+            crate::ide::hover::hover(&doc, &[], lsp_types::Position::new(line, character)).map(
+                |it| match it.contents {
+                    lsp_types::HoverContents::Markup(markup) => markup.value,
+                    _ => unreachable!(),
+                },
+            )
+        };
+        assert_eq!(
+            hover(2, 1),
+            Some("```\nRel($from, $to, $label, $techn=\"\", $descr=\"\", $sprite=\"\", $tags=\"\", $link=\"\")\n```\n\nA relationship between two elements\n\nfrom `<C4/C4>`".into())
+        );
+        assert_eq!(hover(1, 9), None);
+    }
+
+    #[test]
+    fn test_skinparam_hover() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startuml\nskinparam class {\n  BorderThickness 2\n}\n<style>\nLineStyle 5\n</style>\n@enduml\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let hover = |line, character| match super::hover(
+            &doc,
+            &[],
+            lsp_types::Position::new(line, character),
+        )?
+        .contents
+        {
+            lsp_types::HoverContents::Markup(it) => Some(it.value),
+            _ => None,
+        };
+        assert_eq!(
+            hover(2, 4).unwrap(),
+            "**ClassBorderThickness**: a number\n\nThe thickness of the border of classes"
+        );
+        assert_eq!(
+            hover(5, 2).unwrap(),
+            "**LineStyle**: text\n\nThe dashes of lines, e.g. `5` or `5-2`"
+        );
+        assert_eq!(hover(2, 19), None);
+    }
+
+    #[test]
+    fn test_theme_hover() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startuml\n!theme crt-amber\nskinparam ArrowColor red\n@enduml\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let hover = |line, character| match super::hover(
+            &doc,
+            &[],
+            lsp_types::Position::new(line, character),
+        )?
+        .contents
+        {
+            lsp_types::HoverContents::Markup(it) => Some(it.value),
+            _ => None,
+        };
+        assert_eq!(
+            hover(1, 12).unwrap(),
+            "**crt-amber** theme\n\nAmber text on black, like an old terminal"
+        );
+        assert_eq!(
+            hover(2, 12).unwrap(),
+            "**ArrowColor**: a color\n\nThe color of arrows\n\nThe `crt-amber` theme sets it to `#FFB000`"
+        );
+    }
+}
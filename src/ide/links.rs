@@ -0,0 +1,54 @@
+use crate::workspace::{Document, Workspace};
+use lsp_types::DocumentLink;
+
+/// Make the path of every `!include` that can be found clickable
+pub(crate) fn document_links(workspace: &Workspace, document: &Document) -> Vec<DocumentLink> {
+    document
+        .parsed
+        .root()
+        .includes()
+        .filter_map(|include| {
+            let (path, range) = include.target()?;
+            let target = workspace.resolve(&document.uri, &path)?;
+            Some(DocumentLink {
+                range: document.range(range),
+                target: Some(target),
+                tooltip: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+mod tests {
+    #[test]
+    fn test_include_links() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(url("file:///project/lib/common.puml"), "actor A\n".into());
+        workspace.update(
+            url("file:///project/main.puml"),
+            "!include lib/common.puml!1\n!include nowhere.puml\n!include https://example.com/x.puml\n"
+                .into(),
+        );
+        let doc = workspace.get(&url("file:///project/main.puml")).unwrap();
+        let links = super::document_links(&workspace, doc)
+            .into_iter()
+            .map(|it| {
+                (
+                    it.range.start.line,
+                    it.range.start.character,
+                    it.range.end.character,
+                    it.target.unwrap().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            vec![
+                (0, 9, 24, "file:///project/lib/common.puml".to_string()),
+                (2, 9, 35, "https://example.com/x.puml".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use crate::semantic::c4::C4Model;
+use crate::workspace::Document;
+use lsp_types::{Location, Position};
+
+/// Every mention of the entity under the cursor, whether it is written as
+/// `Name`, `"Name"`, `[Name]`, `(Name)` or `:Name:`, or of the C4 element whose
+/// alias is under it
+pub(crate) fn find_references(document: &Document, position: Position) -> Option<Vec<Location>> {
+    let offset = document.offset(position)?;
+    let c4 = C4Model::new(&document.parsed.root());
+    if let Some(alias) = c4.alias_at(offset) {
+        let locations = c4
+            .references(&alias)
+            .into_iter()
+            .map(|it| Location::new(document.uri.clone(), document.range(it)))
+            .collect();
+        return Some(locations);
+    }
+    let root = document.parsed.syntax();
+    let identifier = crate::ide::identifier_at(&root, offset)?;
+    let locations = crate::ide::references(&root, &identifier)
+        .iter()
+        .map(|it| {
+            Location::new(
+                document.uri.clone(),
+                document.range(it.last_segment_range()),
+            )
+        })
+        .collect();
+    Some(locations)
+}
+
+mod tests {
+    #[test]
+    fn test_references_across_forms() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "actor :Main Admin: as Admin\n(Main Admin) --> Admin\nAdmin --> [Main Admin]\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let lines = |line, character| {
+            crate::ide::references::find_references(&doc, lsp_types::Position::new(line, character))
+                .unwrap()
+                .iter()
+                .map(|it| (it.range.start.line, it.range.start.character))
+                .collect::<Vec<_>>()
+        };
+        // 'Admin' is aliased, so its label is display text rather than a name
+        assert_eq!(lines(0, 8), vec![(0, 7)]);
+        assert_eq!(lines(1, 18), vec![(0, 22), (1, 17), (2, 0)]);
+        // a use case and a component of the same name are different entities
+        assert_eq!(lines(1, 1), vec![(1, 1)]);
+        assert_eq!(lines(2, 11), vec![(2, 11)]);
+    }
+
+    #[test]
+    fn test_references_by_kind() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "usecase (Checkout)\ncomponent [Checkout]\n(Checkout) --> [Checkout]\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let lines = |line, character| {
+            crate::ide::references::find_references(&doc, lsp_types::Position::new(line, character))
+                .unwrap()
+                .iter()
+                .map(|it| (it.range.start.line, it.range.start.character))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines(0, 9), vec![(0, 9), (2, 1)]);
+        assert_eq!(lines(1, 11), vec![(1, 11), (2, 16)]);
+    }
+
+    #[test]
+    fn test_qualified_references() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "package net.shop {\n  class Line\n}\nclass Line\nLine --> net.shop.Line\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let lines = |line, character| {
+            crate::ide::references::find_references(&doc, lsp_types::Position::new(line, character))
+                .unwrap()
+                .iter()
+                .map(|it| (it.range.start.line, it.range.start.character))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines(1, 8), vec![(1, 8), (4, 18)]);
+        assert_eq!(lines(3, 7), vec![(3, 6), (4, 0)]);
+    }
+
+    #[test]
+    fn test_c4_references() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "Container(api, \"API\")\nContainerDb(db, \"DB\")\nRel(api, db, \"Reads\")\nRel_Back(db, \"api\", \"Notifies\")\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let lines = |line, character| {
+            crate::ide::references::find_references(&doc, lsp_types::Position::new(line, character))
+                .unwrap()
+                .iter()
+                .map(|it| (it.range.start.line, it.range.start.character))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines(0, 11), vec![(0, 10), (2, 4), (3, 14)]);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::semantic::c4::C4Model;
+use crate::workspace::Document;
+use lsp_types::{Position, TextEdit, WorkspaceEdit};
+use std::collections::HashMap;
+
+/// Rename the entity under the cursor everywhere it is mentioned. Only the
+/// name itself is replaced, so `[Api]`, `(Log In)` and `:User:` keep their
+/// brackets and colons, and `net.shop.Line` keeps its packages. Renaming a
+/// C4 alias renames its element and every relationship to it.
+pub(crate) fn rename(
+    document: &Document,
+    position: Position,
+    new_name: &str,
+) -> Option<WorkspaceEdit> {
+    let offset = document.offset(position)?;
+    let c4 = C4Model::new(&document.parsed.root());
+    let edits = match c4.alias_at(offset) {
+        Some(alias) => c4
+            .references(&alias)
+            .into_iter()
+            .map(|it| TextEdit::new(document.range(it), new_name.to_string()))
+            .collect(),
+        None => {
+            let root = document.parsed.syntax();
+            let identifier = crate::ide::identifier_at(&root, offset)?;
+            crate::ide::references(&root, &identifier)
+                .iter()
+                .map(|it| {
+                    TextEdit::new(
+                        document.range(it.last_segment_range()),
+                        new_name.to_string(),
+                    )
+                })
+                .collect()
+        }
+    };
+    let mut changes = HashMap::new();
+    changes.insert(document.uri.clone(), edits);
+    Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    })
+}
+
+mod tests {
+    #[test]
+    fn test_rename_class() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "class Order {\n  Order parent\n}\nOrder --> Line\n";
+        let doc = crate::workspace::Document::new(uri.clone(), text.into());
+        let edit =
+            crate::ide::rename::rename(&doc, lsp_types::Position::new(3, 1), "Purchase").unwrap();
+        let edits = &edit.changes.unwrap()[&uri];
+        let lines = edits
+            .iter()
+            .map(|it| it.range.start.line)
+            .collect::<Vec<_>>();
+        // the 'parent' field is left alone
+        assert_eq!(lines, vec![0, 3]);
+    }
+
+    #[test]
+    fn test_rename_in_sibling_packages() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "package A {\n  class B\n  B --> B\n}\npackage C {\n  class B\n}\nA.B --> C.B\n";
+        let doc = crate::workspace::Document::new(uri.clone(), text.into());
+        let edit = crate::ide::rename::rename(&doc, lsp_types::Position::new(1, 8), "Z").unwrap();
+        let edits = &edit.changes.unwrap()[&uri];
+        let ranges = edits
+            .iter()
+            .map(|it| {
+                (
+                    it.range.start.line,
+                    it.range.start.character,
+                    it.range.end.character,
+                )
+            })
+            .collect::<Vec<_>>();
+        // 'C.B' is a different class, and 'A.B' keeps its package
+        assert_eq!(ranges, vec![(1, 8, 9), (2, 2, 3), (2, 8, 9), (7, 2, 3)]);
+    }
+
+    #[test]
+    fn test_rename_shorthand() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "actor :Chef: as C\nusecase (Cook)\n:Chef: --> (Cook)\nC -- Cook\n";
+        let doc = crate::workspace::Document::new(uri.clone(), text.into());
+        let edit =
+            crate::ide::rename::rename(&doc, lsp_types::Position::new(3, 6), "Bake").unwrap();
+        let edits = &edit.changes.unwrap()[&uri];
+        let ranges = edits
+            .iter()
+            .map(|it| {
+                (
+                    it.range.start.line,
+                    it.range.start.character,
+                    it.range.end.character,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(1, 9, 13), (2, 12, 16), (3, 5, 9)]);
+    }
+
+    #[test]
+    fn test_rename_c4_alias() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "Person(user, \"User\")\nSystem(shop, \"Shop\")\nRel(user, shop, \"Buys\")\n";
+        let doc = crate::workspace::Document::new(uri.clone(), text.into());
+        let edit =
+            crate::ide::rename::rename(&doc, lsp_types::Position::new(2, 11), "store").unwrap();
+        let edits = &edit.changes.unwrap()[&uri];
+        let ranges = edits
+            .iter()
+            .map(|it| {
+                (
+                    it.range.start.line,
+                    it.range.start.character,
+                    it.range.end.character,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(1, 7, 11), (2, 10, 14)]);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::ast::CallNode;
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::{Document, Included};
+use lsp_types::{
+    ParameterInformation, ParameterLabel, Position, SignatureHelp, SignatureInformation,
+};
+use rowan::TextSize;
+
+/// The parameters of the procedure whose call the cursor is in, with the one
+/// the cursor is at highlighted
+pub(crate) fn signature_help(
+    document: &Document,
+    includes: &[Included],
+    position: Position,
+) -> Option<SignatureHelp> {
+    let offset = document.offset(position)?;
+    let root = document.parsed.syntax();
+    // the innermost call that's of something known
+    let (call, signature, documentation) = root
+        .token_at_offset(offset)
+        .flat_map(|token| token.ancestors())
+        .filter_map(CallNode::cast)
+        .filter(|call| inside_parens(call, offset))
+        .find_map(|call| {
+            let name = call.name()?;
+            if let Some((defined_in, signature)) = crate::ide::signature(document, includes, &name)
+            {
+                let documentation = if defined_in.uri == document.uri {
+                    None
+                } else {
+                    Some(format!("from {}", defined_in.file_name()))
+                };
+                return Some((call, signature, documentation));
+            }
+            let (library, procedure) = crate::ide::stdlib_procedure(document, includes, &name)?;
+            let documentation = format!("{}\n\nfrom <{}>", procedure.doc, library.path);
+            Some((call, procedure.signature(), Some(documentation)))
+        })?;
+    let arg_list = call.arg_list()?.syntax().clone();
+    let active = arg_list
+        .children_with_tokens()
+        .filter(|it| it.kind() == COMMA && it.text_range().end() <= offset)
+        .count();
+    let documentation = documentation.map(lsp_types::Documentation::String);
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: signature.label(),
+            documentation,
+            parameters: Some(
+                signature
+                    .param_labels()
+                    .into_iter()
+                    .map(|label| ParameterInformation {
+                        label: ParameterLabel::Simple(label),
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active as i64),
+    })
+}
+
+/// Whether `offset` is between the parentheses of the call
+fn inside_parens(call: &CallNode, offset: TextSize) -> bool {
+    let arg_list = match call.arg_list() {
+        Some(it) => it.syntax().clone(),
+        None => return false,
+    };
+    let range = arg_list.text_range();
+    let closed = arg_list.last_token().map(|it| it.kind()) == Some(R_PAREN);
+    offset > range.start() && (offset < range.end() || !closed && offset == range.end())
+}
+
+mod tests {
+    #[test]
+    fn test_signature_help() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///lib.puml"),
+            "!procedure $greet($name, $greeting=\"Hello\")\n!endprocedure\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "!include lib.puml\n$greet(\"Bob\", \n$greet($f(1, 2), \"Hi\")\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let includes = workspace.includes(doc);
+        let help = |line, character| {
+            super::signature_help(doc, &includes, lsp_types::Position::new(line, character))
+                .map(|it| (it.signatures[0].label.clone(), it.active_parameter))
+        };
+        let greet = "$greet($name, $greeting=\"Hello\")".to_string();
+        assert_eq!(help(1, 7), Some((greet.clone(), Some(0))));
+        assert_eq!(help(1, 14), Some((greet.clone(), Some(1))));
+        // '$f' isn't defined anywhere, but the call it's in is
+        assert_eq!(help(2, 13), Some((greet.clone(), Some(0))));
+        assert_eq!(help(2, 20), Some((greet, Some(1))));
+        assert_eq!(help(2, 23), None);
+        assert_eq!(help(1, 2), None);
+    }
+
+    #[test]
+    fn test_stdlib_signature_help() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text =
+            "!include <C4/C4_Container>\nContainer(api, \"API\", \"Rust\")\nComponent(c, \"C\")\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let help = |line, character| {
+            super::signature_help(&doc, &[], lsp_types::Position::new(line, character)).map(|it| {
+                let documentation = match it.signatures[0].documentation.clone() {
+                    Some(lsp_types::Documentation::String(it)) => it,
+                    _ => unreachable!(),
+                };
+                (
+                    it.signatures[0].parameters.clone().unwrap().len(),
+                    it.active_parameter,
+                    documentation,
+                )
+            })
+        };
+        assert_eq!(
+            help(1, 25),
+            Some((
+                7,
+                Some(2),
+                "An application or data store of a system\n\nfrom <C4/C4_Container>".into()
+            ))
+        );
+        // components need '<C4/C4_Component>'
+        assert_eq!(help(2, 11), None);
+    }
+}
@@ -0,0 +1,548 @@
+use crate::ast::{
+    ActionNode, CallNode, ClassDeclNode, ClassKind, ContainerDeclNode, ElementKind, IdentifierNode,
+    MindmapNode, ObjectDeclNode, SaltGroupNode, SaltStyle, SaltWidget, SaltWidgetNode,
+    StateDeclNode, Statement,
+};
+use crate::semantic::c4::{C4Element, C4Kind};
+use crate::syntax::SyntaxKind::*;
+use crate::workspace::Document;
+use lsp_types::{DocumentSymbol, SymbolKind};
+
+/// The outline of the document, with members nested in their classes and
+/// classes nested in their packages
+pub(crate) fn document_symbols(document: &Document) -> Vec<DocumentSymbol> {
+    statement_symbols(document, document.parsed.root().statements())
+}
+
+fn statement_symbols(
+    document: &Document,
+    statements: impl Iterator<Item = Statement>,
+) -> Vec<DocumentSymbol> {
+    statements
+        .flat_map(|statement| statement_symbol(document, statement))
+        .collect()
+}
+
+fn statement_symbol(document: &Document, statement: Statement) -> Vec<DocumentSymbol> {
+    let symbol = match statement {
+        Statement::ParticipantDecl(it) => it
+            .name()
+            .map(|name| symbol(document, &name, SymbolKind::Object, it.syntax(), None)),
+        Statement::PackageDecl(it) => it.name().map(|name| {
+            let kind = match it.syntax().first_token().map(|it| it.kind()) {
+                Some(NAMESPACE_KW) => SymbolKind::Namespace,
+                _ => SymbolKind::Package,
+            };
+            let children = it
+                .block()
+                .map(|block| statement_symbols(document, block.statements()));
+            symbol(document, &name, kind, it.syntax(), children)
+        }),
+        Statement::ClassDecl(it) => class_symbol(document, &it),
+        Statement::Partition(it) => it.name().map(|name| {
+            let children = it
+                .block()
+                .map(|block| statement_symbols(document, block.statements()));
+            symbol(
+                document,
+                &name,
+                SymbolKind::Namespace,
+                it.syntax(),
+                children,
+            )
+        }),
+        Statement::Swimlane(it) => it
+            .name()
+            .map(|name| symbol(document, &name, SymbolKind::Module, it.syntax(), None)),
+        Statement::Action(it) => action_symbol(document, &it),
+        Statement::StateDecl(it) => state_symbol(document, &it),
+        Statement::ComponentDecl(it) => it.name().map(|name| {
+            let kind = match it.element_kind() {
+                ElementKind::Component => SymbolKind::Module,
+                ElementKind::Interface => SymbolKind::Interface,
+            };
+            let children = it
+                .block()
+                .map(|block| statement_symbols(document, block.statements()));
+            symbol(document, &name, kind, it.syntax(), children)
+        }),
+        Statement::ContainerDecl(it) => container_symbol(document, &it),
+        Statement::Call(it) => c4_symbol(document, &it),
+        Statement::ActorDecl(it) => it
+            .alias()
+            .or_else(|| it.name())
+            .map(|name| symbol(document, &name, SymbolKind::Object, it.syntax(), None)),
+        Statement::UsecaseDecl(it) => it
+            .alias()
+            .or_else(|| it.name())
+            .map(|name| symbol(document, &name, SymbolKind::Event, it.syntax(), None)),
+        Statement::ObjectDecl(it) => object_symbol(document, &it),
+        Statement::PortDecl(it) => it
+            .name()
+            .map(|name| symbol(document, &name, SymbolKind::Property, it.syntax(), None)),
+        // a task is listed where its duration is given, the statements that
+        // only constrain it would list it again
+        Statement::Task(it) => it
+            .name()
+            .filter(|_| it.duration_days(7).is_some() || it.is_milestone())
+            .map(|name| symbol(document, &name, SymbolKind::Event, it.syntax(), None)),
+        Statement::Mindmap(it) => mindmap_symbol(document, &it),
+        Statement::SaltDiagram(it) => it.group().and_then(|group| salt_symbol(document, &group)),
+        Statement::SaltGroup(it) => salt_symbol(document, &it),
+        Statement::SignalDecl(it) => it
+            .alias()
+            .or_else(|| it.name())
+            .map(|name| symbol(document, &name, SymbolKind::Variable, it.syntax(), None)),
+        Statement::VariableDef(it) => it
+            .variable()
+            .map(|name| token_symbol(document, &name, SymbolKind::Variable, it.syntax())),
+        Statement::Procedure(it) => it
+            .name_token()
+            .map(|name| token_symbol(document, &name, SymbolKind::Function, it.syntax())),
+        Statement::Define(it) => it
+            .name_token()
+            .map(|name| token_symbol(document, &name, SymbolKind::Constant, it.syntax())),
+        // the statements of a '!if' are listed as if it wasn't there, the
+        // inactive branches of a document are blanked out before this
+        Statement::PpIf(it) => {
+            return it
+                .branches()
+                .flat_map(|branch| statement_symbols(document, branch.statements()))
+                .collect();
+        }
+        // the actions inside a block are listed as if the block wasn't there
+        Statement::Control(it) => {
+            return it
+                .branches()
+                .flat_map(|branch| statement_symbols(document, branch.statements()))
+                .collect();
+        }
+        Statement::Relation(_)
+        | Statement::Skinparam(_)
+        | Statement::Style(_)
+        | Statement::Theme(_)
+        | Statement::Terminal(_)
+        | Statement::ActivityArrow(_)
+        | Statement::Description(_)
+        | Statement::Direction(_)
+        | Statement::Dependency(_)
+        | Statement::ProjectStart(_)
+        | Statement::ClosedDays(_)
+        | Statement::Side(_)
+        | Statement::SignalStates(_)
+        | Statement::TimeAnchor(_)
+        | Statement::StateChange(_)
+        | Statement::Highlight(_)
+        | Statement::TimingLink(_)
+        | Statement::Data(_)
+        | Statement::DataHighlight(_)
+        | Statement::Include(_)
+        | Statement::Preprocessor(_)
+        | Statement::Undef(_)
+        | Statement::Return(_) => None,
+    };
+    symbol.into_iter().collect()
+}
+
+#[allow(deprecated)]
+fn action_symbol(document: &Document, action: &ActionNode) -> Option<DocumentSymbol> {
+    let label = action.label()?;
+    let name = label.plain_text();
+    let name = name.lines().next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(DocumentSymbol {
+        name: name.to_string(),
+        detail: None,
+        kind: SymbolKind::Event,
+        deprecated: None,
+        range: document.range(action.position()),
+        selection_range: document.range(label.position()),
+        children: None,
+    })
+}
+
+/// A mind map or WBS node, with the nodes below it as its children
+#[allow(deprecated)]
+fn mindmap_symbol(document: &Document, node: &MindmapNode) -> Option<DocumentSymbol> {
+    let label = node.label()?;
+    let text = node.text();
+    let name = text.lines().next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let children = statement_symbols(document, node.statements());
+    let kind = if children.is_empty() {
+        SymbolKind::Field
+    } else {
+        SymbolKind::Namespace
+    };
+    Some(DocumentSymbol {
+        name: name.to_string(),
+        detail: None,
+        kind,
+        deprecated: None,
+        range: document.range(node.position()),
+        selection_range: document.range(label.position()),
+        children: Some(children).filter(|it| !it.is_empty()),
+    })
+}
+
+/// A salt `{ }`, with its widgets and the groups nested in it as children
+#[allow(deprecated)]
+fn salt_symbol(document: &Document, group: &SaltGroupNode) -> Option<DocumentSymbol> {
+    let name = match group.style() {
+        SaltStyle::Grid | SaltStyle::Border => "grid".to_string(),
+        SaltStyle::Lines | SaltStyle::VerticalLines | SaltStyle::HorizontalLines => {
+            "table".to_string()
+        }
+        SaltStyle::Tabs => "tabs".to_string(),
+        SaltStyle::Menu => "menu".to_string(),
+        SaltStyle::Tree => "tree".to_string(),
+        SaltStyle::Scroll => "scroll area".to_string(),
+        SaltStyle::GroupBox => group.title().unwrap_or_else(|| "group box".to_string()),
+    };
+    let children = group
+        .syntax()
+        .children()
+        .filter_map(|child| match child.kind() {
+            SALT_GROUP => salt_symbol(document, &SaltGroupNode::cast(child)?),
+            SALT_WIDGET => widget_symbol(document, &SaltWidgetNode::cast(child)?),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let opening = group.syntax().first_token()?.text_range();
+    Some(DocumentSymbol {
+        name,
+        detail: None,
+        kind: SymbolKind::Namespace,
+        deprecated: None,
+        range: document.range(group.position()),
+        selection_range: document.range(opening),
+        children: Some(children).filter(|it| !it.is_empty()),
+    })
+}
+
+#[allow(deprecated)]
+fn widget_symbol(document: &Document, widget: &SaltWidgetNode) -> Option<DocumentSymbol> {
+    let (kind, detail) = match widget.widget_kind() {
+        SaltWidget::Button => (SymbolKind::Event, "button"),
+        SaltWidget::Checkbox(_) => (SymbolKind::Boolean, "checkbox"),
+        SaltWidget::Radio(_) => (SymbolKind::EnumMember, "radio button"),
+        SaltWidget::TextField => (SymbolKind::String, "text field"),
+        SaltWidget::Droplist => (SymbolKind::Enum, "droplist"),
+    };
+    // an empty text field or droplist goes by what it looks like
+    let name = Some(widget.text())
+        .filter(|it| !it.is_empty())
+        .unwrap_or_else(|| widget.syntax().text().to_string().trim().to_string());
+    Some(DocumentSymbol {
+        name,
+        detail: Some(detail.to_string()),
+        kind,
+        deprecated: None,
+        range: document.range(widget.position()),
+        selection_range: document.range(widget.position()),
+        children: None,
+    })
+}
+
+fn class_symbol(document: &Document, class: &ClassDeclNode) -> Option<DocumentSymbol> {
+    let name = class.alias().or_else(|| class.name())?;
+    let kind = match class.class_kind() {
+        ClassKind::Interface | ClassKind::Annotation => SymbolKind::Interface,
+        ClassKind::Enum => SymbolKind::Enum,
+        _ => SymbolKind::Class,
+    };
+    let members = class
+        .members()
+        .filter_map(|member| {
+            let name = member.name()?;
+            let kind = if member.is_method() {
+                SymbolKind::Method
+            } else if class.class_kind() == ClassKind::Enum {
+                SymbolKind::EnumMember
+            } else {
+                SymbolKind::Field
+            };
+            Some(symbol(document, &name, kind, member.syntax(), None))
+        })
+        .collect();
+    let mut symbol = symbol(document, &name, kind, class.syntax(), Some(members));
+    symbol.detail = class.stereotype().map(|it| format!("<<{}>>", it.name()));
+    Some(symbol)
+}
+
+/// A node, folder, frame, cloud or database with everything nested in it.
+/// Anonymous containers, such as `cloud { }`, are named after their keyword.
+#[allow(deprecated)]
+fn container_symbol(document: &Document, container: &ContainerDeclNode) -> Option<DocumentSymbol> {
+    let keyword = container.keyword()?;
+    let children = container
+        .block()
+        .map(|block| statement_symbols(document, block.statements()));
+    let name = container.name();
+    let selection = name
+        .as_ref()
+        .map(|it| it.position())
+        .unwrap_or_else(|| keyword.text_range());
+    Some(DocumentSymbol {
+        name: name
+            .map(|it| it.value())
+            .unwrap_or_else(|| keyword.text().to_string()),
+        detail: container
+            .stereotype()
+            .map(|it| format!("<<{}>>", it.name()))
+            .or_else(|| Some(keyword.text().to_string())),
+        kind: SymbolKind::Package,
+        deprecated: None,
+        range: document.range(container.position()),
+        selection_range: document.range(selection),
+        children,
+    })
+}
+
+/// An object with its fields, or a map with its keys
+fn object_symbol(document: &Document, object: &ObjectDeclNode) -> Option<DocumentSymbol> {
+    let name = object.alias().or_else(|| object.name())?;
+    let (kind, entry_kind) = if object.is_map() {
+        (SymbolKind::Struct, SymbolKind::Key)
+    } else {
+        (SymbolKind::Object, SymbolKind::Field)
+    };
+    let entries = object
+        .entries()
+        .filter_map(|entry| {
+            let key = entry.key()?;
+            Some(symbol(document, &key, entry_kind, entry.syntax(), None))
+        })
+        .collect();
+    Some(symbol(
+        document,
+        &name,
+        kind,
+        object.syntax(),
+        Some(entries),
+    ))
+}
+
+/// A state, with the states of all its regions as children
+fn state_symbol(document: &Document, state: &StateDeclNode) -> Option<DocumentSymbol> {
+    let name = state.alias().or_else(|| state.name())?;
+    let children = if state.is_composite() {
+        Some(
+            state
+                .regions()
+                .flat_map(|region| statement_symbols(document, region.statements()))
+                .collect(),
+        )
+    } else {
+        None
+    };
+    let mut symbol = symbol(
+        document,
+        &name,
+        SymbolKind::Struct,
+        state.syntax(),
+        children,
+    );
+    symbol.detail = state.stereotype().map(|it| format!("<<{}>>", it.name()));
+    Some(symbol)
+}
+
+/// A C4 element, named by its alias, with the elements inside a boundary
+/// nested in it
+#[allow(deprecated)]
+fn c4_symbol(document: &Document, call: &CallNode) -> Option<DocumentSymbol> {
+    let element = C4Element::from_call(call)?;
+    let kind = match element.kind {
+        C4Kind::Person => SymbolKind::Object,
+        C4Kind::System => SymbolKind::Package,
+        C4Kind::Container => SymbolKind::Class,
+        C4Kind::Component => SymbolKind::Module,
+        C4Kind::Node => SymbolKind::Struct,
+        C4Kind::Boundary => SymbolKind::Namespace,
+    };
+    let children = call
+        .block()
+        .map(|block| statement_symbols(document, block.statements()));
+    Some(DocumentSymbol {
+        name: element.alias,
+        detail: element.label,
+        kind,
+        deprecated: None,
+        range: document.range(call.syntax().text_range()),
+        selection_range: document.range(element.range),
+        children,
+    })
+}
+
+#[allow(deprecated)]
+fn symbol(
+    document: &Document,
+    name: &IdentifierNode,
+    kind: SymbolKind,
+    node: &crate::ast::SyntaxNode,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.value(),
+        detail: None,
+        kind,
+        deprecated: None,
+        range: document.range(node.text_range()),
+        selection_range: document.range(name.position()),
+        children,
+    }
+}
+
+/// A symbol named by a single token, such as a `$variable`
+#[allow(deprecated)]
+fn token_symbol(
+    document: &Document,
+    name: &crate::ast::SyntaxToken,
+    kind: SymbolKind,
+    node: &crate::ast::SyntaxNode,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.text().to_string(),
+        detail: None,
+        kind,
+        deprecated: None,
+        range: document.range(node.text_range()),
+        selection_range: document.range(name.text_range()),
+        children: None,
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_class_outline() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "package shop {\n  class Order <<Entity>> {\n    + total() : int\n  }\n}\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let symbols = crate::ide::symbols::document_symbols(&doc);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "shop");
+        let class = &symbols[0].children.as_ref().unwrap()[0];
+        assert_eq!(class.name, "Order");
+        assert_eq!(class.detail, Some("<<Entity>>".into()));
+        assert_eq!(class.children.as_ref().unwrap()[0].name, "total");
+        assert_eq!(
+            class.children.as_ref().unwrap()[0].kind,
+            lsp_types::SymbolKind::Method
+        );
+    }
+
+    #[test]
+    fn test_activity_outline() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "|Web|\npartition Checkout {\n  if (ok?) then\n    :pay;\n  endif\n}\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let symbols = crate::ide::symbols::document_symbols(&doc);
+        let names = symbols
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Web", "Checkout"]);
+        assert_eq!(symbols[1].children.as_ref().unwrap()[0].name, "pay");
+    }
+
+    #[test]
+    fn test_component_outline() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "node Server {\n  cloud {\n    [Api]\n  }\n  database Db\n}\n[Api] --> Db\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let symbols = crate::ide::symbols::document_symbols(&doc);
+        assert_eq!(symbols.len(), 1);
+        let server = &symbols[0];
+        assert_eq!(server.name, "Server");
+        let children = server.children.as_ref().unwrap();
+        assert_eq!(
+            children
+                .iter()
+                .map(|it| it.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cloud", "Db"]
+        );
+        assert_eq!(children[0].children.as_ref().unwrap()[0].name, "Api");
+    }
+
+    #[test]
+    fn test_mindmap_outline() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text =
+            "@startmindmap\n* Root\n** First\n*** Leaf\nleft side\n**:Two\nlines;\n@endmindmap\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let symbols = crate::ide::symbols::document_symbols(&doc);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "Root");
+        let children = symbols[0].children.as_ref().unwrap();
+        assert_eq!(
+            children
+                .iter()
+                .map(|it| it.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["First", "Two"]
+        );
+        assert_eq!(children[0].children.as_ref().unwrap()[0].name, "Leaf");
+        assert_eq!(children[1].kind, lsp_types::SymbolKind::Field);
+
+        let ranges = crate::ide::folding::folding_ranges(&doc)
+            .into_iter()
+            .map(|it| (it.start_line, it.end_line))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(1, 6), (2, 3), (5, 6)]);
+    }
+
+    #[test]
+    fn test_salt_outline() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "@startsalt\n{\n  Login | \"   \"\n  {^\"Options\"\n    [X] Remember\n  }\n  [Ok]\n}\n@endsalt\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let symbols = crate::ide::symbols::document_symbols(&doc);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "grid");
+        let children = symbols[0].children.as_ref().unwrap();
+        assert_eq!(
+            children
+                .iter()
+                .map(|it| it.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["\"   \"", "Options", "Ok"]
+        );
+        let option = &children[1].children.as_ref().unwrap()[0];
+        assert_eq!(option.name, "Remember");
+        assert_eq!(option.kind, lsp_types::SymbolKind::Boolean);
+    }
+
+    #[test]
+    fn test_c4_outline() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "!include <C4/C4_Container>\nPerson(user, \"User\")\nSystem_Boundary(shop, \"Shop\") {\n  Container(web, \"Web\", \"Rust\")\n  ContainerDb(db, \"Database\")\n}\nRel(user, web, \"Uses\")\n";
+        let doc = crate::workspace::Document::new(uri, text.into());
+        let symbols = crate::ide::symbols::document_symbols(&doc);
+        let names = symbols
+            .iter()
+            .map(|it| (it.name.as_str(), it.detail.as_deref(), it.kind))
+            .collect::<Vec<_>>();
+        use lsp_types::SymbolKind;
+        assert_eq!(
+            names,
+            vec![
+                ("user", Some("User"), SymbolKind::Object),
+                ("shop", Some("Shop"), SymbolKind::Namespace),
+            ]
+        );
+        let children = symbols[1]
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(children, vec!["web", "db"]);
+        assert_eq!(symbols[1].range.end.line, 5);
+    }
+}
@@ -1,58 +1,558 @@
 use super::syntax::SyntaxKind::{self, *};
 use log::*;
-use m_lexer;
-use num::ToPrimitive;
-use rowan::SmolStr;
+use rowan::{SmolStr, TextRange, TextSize};
 
-/// Let's use the primitive discriminant from the SyntaxKind enum
-/// as the lexer token (which is internally a u16)
-impl From<SyntaxKind> for m_lexer::TokenKind {
-    fn from(kind: SyntaxKind) -> m_lexer::TokenKind {
-        //trace!(
-        //    "SyntaxKind {:?} to m_lexer::TokenKind {:?}",
-        //    kind,
-        //    kind.to_u16()
-        //);
-        m_lexer::TokenKind(kind.to_u16().unwrap())
+/// How error messages refer to the tokens that don't have a text of their
+/// own
+const DESCRIPTIONS: &[(SyntaxKind, &str)] = &[
+    (EOF, "end of file"),
+    (WHITESPACE, "whitespace"),
+    (COMMENT, "a comment"),
+    (IDENTIFIER, "an identifier"),
+    (EOL, "end of line"),
+    (ERROR, "an unknown character"),
+    (NUMBER, "a number"),
+    (STRING, "a string"),
+    (ARROW, "an arrow"),
+    (DIAGRAM_START, "the start of a diagram"),
+    (DIAGRAM_END, "the end of a diagram"),
+    (PSEUDO_STATE, "'[*]' or '[H]'"),
+    (DATE, "a date"),
+    (DIRECTIVE, "a directive"),
+    (VARIABLE, "a variable"),
+    (BUILTIN, "a builtin function"),
+    (CREOLE_TEXT, "text"),
+    (CREOLE_ESCAPE, "an escape"),
+    (CREOLE_DELIMITER, "a Creole delimiter"),
+    (CREOLE_TAG_START, "a Creole tag"),
+    (CREOLE_TAG_END, "the end of a Creole tag"),
+    (CREOLE_ICON, "an icon"),
+    (CREOLE_IMAGE, "an image"),
+];
+
+/// How an error message refers to a kind of token: by its text if it's a
+/// keyword or punctuation, by what it is otherwise, as in "expected ')'" or
+/// "expected an identifier"
+pub(crate) fn describe(kind: SyntaxKind) -> String {
+    if let Some(text) = kind.text() {
+        return format!("'{}'", text);
     }
+    DESCRIPTIONS
+        .iter()
+        .find(|(it, _)| *it == kind)
+        .map(|(_, description)| description.to_string())
+        // only tokens are ever expected
+        .unwrap_or_else(|| "a token".to_string())
+}
+
+/// The length of the run of characters at the start of `text` that `f`
+/// accepts
+fn run(text: &str, f: impl Fn(char) -> bool) -> usize {
+    text.find(|it: char| !f(it)).unwrap_or(text.len())
 }
 
-impl From<SyntaxKind> for (m_lexer::TokenKind, &str) {
-    fn from(kind: SyntaxKind) -> (m_lexer::TokenKind, &'static str) {
-        (kind.into(), kind.into())
+/// The length of the first of `options` that `text` starts with
+fn first_of(text: &str, options: &[&str]) -> Option<usize> {
+    options
+        .iter()
+        .find(|it| text.starts_with(*it))
+        .map(|it| it.len())
+}
+
+/// A name such as `Order`, `_base` or `Größe`, or a keyword
+fn name(text: &str) -> Option<(SyntaxKind, usize)> {
+    let first = text.chars().next()?;
+    if !(first.is_alphabetic() || first == '_') {
+        return None;
+    }
+    let len = first.len_utf8()
+        + run(&text[first.len_utf8()..], |it| {
+            it.is_alphabetic() || it.is_numeric() || it == '_'
+        });
+    let kind = SyntaxKind::from_keyword(&text[..len]).unwrap_or(IDENTIFIER);
+    Some((kind, len))
+}
+
+fn digits(text: &str) -> usize {
+    run(text, |it| it.is_ascii_digit())
+}
+
+/// `12` or `1.5`
+fn number(text: &str) -> Option<usize> {
+    let whole = digits(text);
+    if whole == 0 {
+        return None;
+    }
+    match text[whole..].strip_prefix('.').map(digits) {
+        Some(fraction) if fraction > 0 => Some(whole + 1 + fraction),
+        _ => Some(whole),
+    }
+}
+
+/// `2020-01-31`, or `2020-1-1`
+fn date(text: &str) -> Option<usize> {
+    if digits(text) < 4 {
+        return None;
+    }
+    let mut len = 4;
+    for last in [false, true] {
+        let part = digits(text[len..].strip_prefix('-')?);
+        // the month must end at the `-` before the day, while the day may be
+        // followed by more digits
+        match (part, last) {
+            (0, _) => return None,
+            (1..=2, _) => len += 1 + part,
+            (_, true) => len += 1 + 2,
+            (_, false) => return None,
+        }
+    }
+    Some(len)
+}
+
+/// `"a name"`, in which a backslash escapes the character after it, as in
+/// `"a \"quoted\" name"`. A string ends on the line it starts on.
+fn string(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '"' => return Some(offset + 1),
+            '\n' => return None,
+            '\\' => match chars.next() {
+                Some((_, '\n')) | None => return None,
+                Some(_) => (),
+            },
+            _ => (),
+        }
+    }
+    None
+}
+
+/// `@startuml`, `@endmindmap` and the like
+fn diagram(text: &str) -> Option<(SyntaxKind, usize)> {
+    let (kind, rest) = if let Some(rest) = text.strip_prefix("@start") {
+        (DIAGRAM_START, rest)
+    } else {
+        (DIAGRAM_END, text.strip_prefix("@end")?)
+    };
+    let name = run(rest, |it| it.is_ascii_lowercase());
+    if name == 0 {
+        return None;
+    }
+    Some((kind, text.len() - rest.len() + name))
+}
+
+/// `[*]` for the initial and final states, `[H]` and `[H*]` for history
+fn pseudo_state(text: &str) -> Option<usize> {
+    first_of(text, &["[*]", "[H]", "[H*]"])
+}
+
+/// Preprocessor directives such as `!include`, and the `!` of `!$a = 1`
+fn directive(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('!')?;
+    Some(1 + run(rest, |it| it.is_ascii_lowercase() || it == '_'))
+}
+
+/// `$name`
+fn variable(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('$')?;
+    if !rest.starts_with(|it: char| it.is_ascii_alphabetic() || it == '_') {
+        return None;
+    }
+    Some(1 + run(rest, |it| it.is_ascii_alphanumeric() || it == '_'))
+}
+
+/// Builtin functions such as `%strlen`
+fn builtin(text: &str) -> Option<usize> {
+    let len = run(text.strip_prefix('%')?, |it| {
+        it.is_ascii_lowercase() || it == '_'
+    });
+    if len == 0 {
+        return None;
+    }
+    Some(1 + len)
+}
+
+/// The run of dashes, dots or two or more `=` an arrow is drawn with
+fn line(text: &str) -> Option<usize> {
+    match text.chars().next()? {
+        '-' => Some(run(text, |it| it == '-')),
+        '.' => Some(run(text, |it| it == '.')),
+        '=' => Some(run(text, |it| it == '=')).filter(|it| *it >= 2),
+        _ => None,
+    }
+}
+
+/// The heads an arrow may start with, including the crow's feet `}o` and
+/// `|o` and the sockets `)` and `0)` of component diagrams, in the order
+/// they're tried
+const LEFT_HEADS: &[&str] = &[
+    "<|", "<", "<<", "}o", "}|", "|o", "||", "}", "*", "o", "+", "#", "0)", ")",
+];
+
+/// What else may break the line of an arrow, tried in order after a style
+/// such as `[#red,dashed]`: a direction, or a ball and socket such as `-(0)-`
+const BREAKS: &[&str] = &[
+    "left", "right", "down", "up", "le", "ri", "do", "l", "r", "u", "d", "(0)", "(0", "0)",
+];
+
+/// The heads an arrow may end with, in the order they're tried
+const RIGHT_HEADS: &[&str] = &[
+    "|>", ">>", ">", "o{", "|{", "o|", "||", "*", "o", "+", "#", "{", "x", "(0", "(",
+];
+
+/// `[#red,dashed]`, which doesn't go past the end of the line
+fn style(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('[')?;
+    let inner = run(rest, |it| it != ']' && it != '\n');
+    rest[inner..].starts_with(']').then_some(inner + 2)
+}
+
+/// A link such as `<|--`, `o..>`, `-up->` or `-[#red]->`: an optional head,
+/// a line, which may be broken by a style, a direction or a ball and socket
+/// and carry on, and an optional head. Of the ways to read it, the first
+/// that works is taken, rather than the longest.
+fn arrow(text: &str) -> Option<usize> {
+    let (head, body) = LEFT_HEADS
+        .iter()
+        .filter(|it| text.starts_with(*it))
+        .map(|it| it.len())
+        .chain(std::iter::once(0))
+        .find_map(|head| line(&text[head..]).map(|body| (head, body)))?;
+    let mut len = head + body;
+    let rest = &text[len..];
+    let broken = style(rest)
+        .into_iter()
+        .chain(
+            BREAKS
+                .iter()
+                .filter(|it| rest.starts_with(*it))
+                .map(|it| it.len()),
+        )
+        .find_map(|it| line(&rest[it..]).map(|line| it + line));
+    len += broken.unwrap_or(0);
+    len += first_of(&text[len..], RIGHT_HEADS).unwrap_or(0);
+    Some(len)
+}
+
+/// The longest token at the start of `text`, or the first of the longest
+/// when several are as long, which is why keywords come before names and
+/// punctuation before arrows
+fn token(text: &str) -> Option<(SyntaxKind, usize)> {
+    let c = text.chars().next()?;
+    let mut candidates = Vec::with_capacity(4);
+    candidates.extend(name(text));
+    candidates.extend(number(text).map(|it| (NUMBER, it)));
+    candidates.extend(string(text).map(|it| (STRING, it)));
+    match c {
+        '\n' => candidates.push((EOL, 1)),
+        '\r' if text[1..].starts_with('\n') => candidates.push((EOL, 2)),
+        '\r' => (),
+        c if c.is_whitespace() => {
+            let len = run(text, |it| it.is_whitespace() && it != '\r' && it != '\n');
+            candidates.push((WHITESPACE, len));
+        }
+        _ => (),
+    }
+    if text.starts_with("<<") {
+        candidates.push((STEREO_OPEN, 2));
+    }
+    if text.starts_with(">>") {
+        candidates.push((STEREO_CLOSE, 2));
+    }
+    candidates.extend(SyntaxKind::from_char(c).map(|kind| (kind, 1)));
+    candidates.extend(diagram(text));
+    candidates.extend(pseudo_state(text).map(|it| (PSEUDO_STATE, it)));
+    if text.starts_with("=>") {
+        candidates.push((FAT_ARROW, 2));
+    }
+    candidates.extend(date(text).map(|it| (DATE, it)));
+    candidates.extend(directive(text).map(|it| (DIRECTIVE, it)));
+    candidates.extend(variable(text).map(|it| (VARIABLE, it)));
+    candidates.extend(builtin(text).map(|it| (BUILTIN, it)));
+    candidates.extend(arrow(text).map(|it| (ARROW, it)));
+    // `max_by_key` keeps the last of the longest
+    candidates.into_iter().rev().max_by_key(|(_, len)| *len)
+}
+
+/// A token, and where it is in the text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub(crate) kind: SyntaxKind,
+    pub(crate) text: &'a str,
+    pub(crate) range: TextRange,
+}
+
+/// Splits text into tokens, trivia included, keeping track of what it needs
+/// to know about where it is:
+///
+/// - a `'` with nothing but whitespace before it on its line comments out the
+///   rest of the line, and `/'` comments out everything up to the next `'/`.
+///   A `'` elsewhere, as in the `[Task]'s` of a gantt chart or a label such
+///   as `don't`, is not a comment
+/// - nothing in the data of a JSON or YAML diagram is a comment, as `'`
+///   quotes strings there
+///
+/// What isn't a token is an ERROR token, so lexing never fails.
+pub(crate) struct Lexer<'a> {
+    text: &'a str,
+    offset: usize,
+    /// whether there's only been whitespace and comments since the start of
+    /// the line
+    line_start: bool,
+    /// whether the lexer is in the body of a `@startjson` or `@startyaml`
+    in_data: bool,
+    /// whether there's a `"` on the line that's not been closed, after which
+    /// a `/'` is part of the string being written rather than a comment
+    in_string: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Lexer {
+            text,
+            offset: 0,
+            line_start: true,
+            in_data: false,
+            in_string: false,
+        }
+    }
+
+    /// The length of the comment at the start of `rest`, if there is one
+    fn comment(&self, rest: &str, line_start: bool, in_string: bool) -> Option<usize> {
+        if self.in_data {
+            return None;
+        }
+        if let Some(inner) = rest.strip_prefix("/'").filter(|_| !in_string) {
+            return Some(inner.find("'/").map(|it| 2 + it + 2).unwrap_or(rest.len()));
+        }
+        if line_start && rest.starts_with('\'') {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            // leave the '\r' of a '\r\n' to the EOL
+            return Some(rest[..end].strip_suffix('\r').map(str::len).unwrap_or(end));
+        }
+        None
+    }
+
+    /// The length of the characters at the start of `rest` that start
+    /// neither a token nor a comment
+    fn error(&self, rest: &str) -> usize {
+        let mut len = 0;
+        let mut line_start = self.line_start;
+        let mut in_string = self.in_string;
+        for c in rest.chars() {
+            len += c.len_utf8();
+            line_start &= c.is_whitespace();
+            in_string ^= c == '"';
+            let next = &rest[len..];
+            let comment = self.comment(next, line_start, in_string);
+            if next.is_empty() || token(next).is_some() || comment.is_some() {
+                break;
+            }
+        }
+        len
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.text[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        if self.line_start {
+            if rest.starts_with("@startjson") || rest.starts_with("@startyaml") {
+                self.in_data = true;
+            } else if rest.starts_with("@end") {
+                self.in_data = false;
+            }
+        }
+        let (kind, len) = match self.comment(rest, self.line_start, self.in_string) {
+            Some(len) => (COMMENT, len),
+            None => token(rest).unwrap_or_else(|| (ERROR, self.error(rest))),
+        };
+        let text = &rest[..len];
+        if kind == EOL {
+            self.line_start = true;
+            self.in_string = false;
+        } else if kind != COMMENT {
+            // a lone '\r' isn't a token, but it's still whitespace
+            self.line_start &= text.chars().all(char::is_whitespace);
+        }
+        // a `"` that doesn't start a STRING opens one that's not been closed
+        if kind == ERROR && text.matches('"').count() % 2 == 1 {
+            self.in_string = !self.in_string;
+        }
+        let range = TextRange::at(
+            TextSize::from(self.offset as u32),
+            TextSize::from(len as u32),
+        );
+        self.offset += len;
+        Some(Token { kind, text, range })
     }
 }
 
 /// Split the input string into a flat list of tokens
 /// (such as L_PAREN, WORD, and WHITESPACE)
-pub fn lex<'a>(text: &str) -> Vec<(SyntaxKind, SmolStr)> {
+pub fn lex(text: &str) -> Vec<(SyntaxKind, SmolStr, TextRange)> {
     trace!("lex({})", text);
-
-    let tokens: [(m_lexer::TokenKind, &str); 4] = [
-        PARTICIPANT_KW.into(),
-        IDENTIFIER.into(),
-        EOL.into(),
-        WHITESPACE.into(),
-    ];
-    // trace!("Tokens {:?}", tokens);
-    let lexer = m_lexer::LexerBuilder::new()
-        .error_token(ERROR.into())
-        .tokens(&tokens)
-        .build();
-
-    // convert m_lexer's token kind to our syntax kind
-    fn to_syntax_kind(t: m_lexer::TokenKind) -> SyntaxKind {
-        // trace!("Lexer token convert to syntax kind {}", t.0);
-        return num::FromPrimitive::from_u16(t.0).unwrap();
-    }
-    lexer
-        .tokenize(text)
-        .into_iter()
-        .map(|t| (t.len, to_syntax_kind(t.kind)))
-        .scan(0usize, |start_offset, (len, kind)| {
-            let s: SmolStr = text[*start_offset..*start_offset + len].into();
-            *start_offset += len;
-            Some((kind, s))
-        })
+    Lexer::new(text)
+        .map(|it| (it.kind, SmolStr::from(it.text), it.range))
         .collect()
 }
+
+mod tests {
+    #[test]
+    fn test_describe() {
+        use crate::syntax::SyntaxKind::{self, *};
+        use num::FromPrimitive;
+        assert_eq!(super::describe(R_PAREN), "')'");
+        assert_eq!(super::describe(AS_KW), "'as'");
+        assert_eq!(super::describe(IDENTIFIER), "an identifier");
+        // every token has a description of its own
+        let undescribed = (0..=CREOLE_IMAGE as u16)
+            .filter_map(SyntaxKind::from_u16)
+            .filter(|it| super::describe(*it) == "a token")
+            .collect::<Vec<_>>();
+        assert_eq!(undescribed, vec![]);
+    }
+
+    #[test]
+    fn test_comments() {
+        use crate::syntax::SyntaxKind::*;
+        let text = "' a comment\nA -> B : don't /' inline\n '/ C\n  'indented\r\n[T]'s end\n\"/'\"\n@startyaml\n'a': 1\n@endyaml\n/' open";
+        let tokens = super::lex(text);
+        let joined = tokens
+            .iter()
+            .map(|(_, it, _)| it.as_str())
+            .collect::<String>();
+        assert_eq!(joined, text);
+        let comments = tokens
+            .iter()
+            .filter(|(kind, _, _)| *kind == COMMENT)
+            .map(|(_, it, _)| it.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            comments,
+            vec!["' a comment", "/' inline\n '/", "'indented", "/' open"]
+        );
+    }
+
+    #[test]
+    fn test_names_and_strings() {
+        use crate::syntax::SyntaxKind::*;
+        let text =
+            "class _Base\nclass Größe\nclass 注文\nuser_id2 \"Order \\\"Service\\\"\" \"a\\\\\"";
+        let tokens = super::lex(text)
+            .into_iter()
+            .filter(|(kind, _, _)| !matches!(kind, WHITESPACE | EOL | CLASS_KW))
+            .map(|(kind, text, _)| (kind, text.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                (IDENTIFIER, "_Base".to_string()),
+                (IDENTIFIER, "Größe".into()),
+                (IDENTIFIER, "注文".into()),
+                (IDENTIFIER, "user_id2".into()),
+                (STRING, "\"Order \\\"Service\\\"\"".into()),
+                (STRING, "\"a\\\\\"".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokens() {
+        use crate::syntax::SyntaxKind::*;
+        let text = "A <|-- B\na -[#red,dashed]-> b : x\nC }o--|| D\n[*] --> S1\n[T] starts 2020-1-31\n!include $a %strlen(\"s\") ?";
+        let tokens = super::Lexer::new(text).collect::<Vec<_>>();
+        // the tokens cover the text, one after the other
+        let mut offset = 0.into();
+        for token in &tokens {
+            assert_eq!(token.range.start(), offset);
+            assert_eq!(&text[token.range], token.text);
+            offset = token.range.end();
+        }
+        assert_eq!(usize::from(offset), text.len());
+        let significant = tokens
+            .iter()
+            .filter(|it| !matches!(it.kind, WHITESPACE | EOL))
+            .map(|it| (it.kind, it.text))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            significant,
+            vec![
+                (IDENTIFIER, "A"),
+                (ARROW, "<|--"),
+                (IDENTIFIER, "B"),
+                (IDENTIFIER, "a"),
+                (ARROW, "-[#red,dashed]->"),
+                (IDENTIFIER, "b"),
+                (COLON, ":"),
+                (IDENTIFIER, "x"),
+                (IDENTIFIER, "C"),
+                (ARROW, "}o--||"),
+                (IDENTIFIER, "D"),
+                (PSEUDO_STATE, "[*]"),
+                (ARROW, "-->"),
+                (IDENTIFIER, "S1"),
+                (L_BRACK, "["),
+                (IDENTIFIER, "T"),
+                (R_BRACK, "]"),
+                (IDENTIFIER, "starts"),
+                (DATE, "2020-1-31"),
+                (DIRECTIVE, "!include"),
+                (VARIABLE, "$a"),
+                (BUILTIN, "%strlen"),
+                (L_PAREN, "("),
+                (STRING, "\"s\""),
+                (R_PAREN, ")"),
+                (ERROR, "?"),
+            ]
+        );
+    }
+
+    /// A diagram of every kind, thousands of lines long
+    #[allow(dead_code)]
+    fn long_document() -> String {
+        let block = "class Order<T> extends Base {\n  - id : int\n  + total() : Decimal\n}\nOrder \"1\" *-- \"many\" Line : contains >\n' a comment\nAlice -> Bob : **hello** <&heart>\n/' a\n block '/\nstart\n:step $i;\nif (ok?) then (yes)\n  -[#green]-> done;\nendif\n[Task$i] lasts 5 days\n";
+        let mut text = "@startuml\n".to_string();
+        for _ in 0..1000 {
+            text.push_str(block);
+        }
+        text.push_str("@enduml\n");
+        text
+    }
+
+    #[test]
+    #[ignore = "a benchmark, run with `cargo test --release -- --ignored --nocapture bench`"]
+    fn bench_lex() {
+        let _ = env_logger::try_init();
+        let text = long_document();
+        assert!(text.lines().count() > 10_000);
+        let started = std::time::Instant::now();
+        let tokens = super::lex(&text);
+        let elapsed = started.elapsed();
+        println!("lexed {} lines in {:?}", text.lines().count(), elapsed);
+        assert_eq!(
+            tokens.iter().map(|(_, it, _)| it.as_str()).collect::<String>(),
+            text
+        );
+    }
+
+    #[test]
+    #[ignore = "a benchmark, run with `cargo test --release -- --ignored --nocapture bench`"]
+    fn bench_parse() {
+        let _ = env_logger::try_init();
+        let text = long_document();
+        let started = std::time::Instant::now();
+        let parsed = crate::parse(&text);
+        let elapsed = started.elapsed();
+        println!("parsed {} lines in {:?}", text.lines().count(), elapsed);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+    }
+}
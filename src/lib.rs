@@ -9,7 +9,6 @@ extern crate nom_locate;
 extern crate serde;
 extern crate serde_json;
 extern crate tokio;
-extern crate m_lexer;
 extern crate num_derive;
 
 mod parsing;
@@ -17,7 +16,12 @@ mod grammar;
 mod syntax;
 mod lex;
 mod ast;
+#[cfg(test)]
+mod codegen;
+mod semantic;
 mod codec;
+mod ide;
+mod workspace;
 pub mod server;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -45,8 +49,149 @@ mod tests {
 
     #[test]
     fn always_returns_ok() {
-        env_logger::init();
+        let _ = env_logger::try_init();
         let text = "a";
-        let root = crate::parse(text).root();
+        let _root = crate::parse(text).root();
+    }
+
+    #[test]
+    fn comments_are_trivia() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\n' a class\nclass A /' inline '/ {\n  ' a field\n  x : int\n}\n/'\nclass B\n'/\nA -> C : don't\n' the end\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+        let classes = root
+            .class_decls()
+            .filter_map(|it| it.name())
+            .map(|it| it.identifier().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(classes, vec!["A"]);
+    }
+
+    /// Documents made of random pieces of PlantUML: keywords, arrows,
+    /// brackets, directives, markup and the odd broken token, so that they
+    /// run into the parser's error recovery a lot
+    #[allow(dead_code)]
+    fn random_document(state: &mut u64) -> String {
+        const PIECES: &[&str] = &[
+            "@startuml", "@enduml", "@startgantt", "@startmindmap", "@startjson", "@startyaml",
+            "@startsalt", "@end", "participant", "class", "abstract", "interface", "enum",
+            "package", "namespace", "state", "component", "node", "object", "map", "actor",
+            "usecase", "port", "start", "stop", "end", "if", "then", "else", "elseif", "endif",
+            "while", "endwhile", "repeat", "fork", "split", "again", "partition", "skinparam",
+            "<style>", "</style>", "as", "extends", "is", "on", "at", "A", "B", "Größe", "_x",
+            "42", "2020-01-02", "\"str", "\"a \\\" b\"", "->", "-->", "<|--", "*--", "..>", "-[#red]->",
+            "{", "}", "(", ")", "[", "]", "<", ">", "<<", ">>", ":", ";", ",", ".", "|", "||",
+            "+", "-", "#", "=", "==", "@", "$x", "$f(", "%upper(", "!if", "!else", "!endif",
+            "!procedure", "!endprocedure", "!function", "!return", "!include", "!theme",
+            "!define", "!definelong", "!enddefinelong", "**", "//", "<&heart>", "<color:red>",
+            "</color>", "~", "\\n", "'", "/'", "'/", "\\", "\u{e9}", "\t", " ", " ", "\n", "\n",
+            "\r\n", "\r",
+        ];
+        let mut next = || {
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            *state
+        };
+        let len = next() % 60;
+        (0..len)
+            .map(|_| PIECES[next() as usize % PIECES.len()])
+            .collect()
+    }
+
+    #[test]
+    fn parse_never_panics() {
+        let _ = env_logger::try_init();
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..20_000 {
+            let text = random_document(&mut state);
+            let parsed = crate::parse(&text);
+            assert_eq!(parsed.syntax().text().to_string(), text);
+            for error in parsed.errors() {
+                assert!(text.get(std::ops::Range::from(error.range)).is_some());
+            }
+        }
+        // nested deeper than the stack would take, were it all parsed by
+        // recursion
+        let deep = vec![
+            "package A {\n".repeat(5000),
+            "if (a) then\n".repeat(5000),
+            format!("@startsalt\n{}", "{\n".repeat(5000)),
+            format!("A -> B : $f{}", "(".repeat(20_000)),
+            format!("A -> B : {}", "$f(".repeat(5000)),
+            format!("A -> B : {}", "<b>".repeat(20_000)),
+        ];
+        for text in deep {
+            let parsed = crate::parse(&text);
+            assert_eq!(parsed.syntax().text().to_string(), text);
+            for error in parsed.errors() {
+                assert!(text.get(std::ops::Range::from(error.range)).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn skips_what_is_nested_too_deeply() {
+        let _ = env_logger::try_init();
+        let text = format!(
+            "{}{}class B\n",
+            "package A {\n".repeat(300),
+            "}\n".repeat(300)
+        );
+        let parsed = crate::parse(&text);
+        let errors = parsed
+            .errors()
+            .iter()
+            .map(|it| it.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(errors, vec!["too deeply nested"]);
+        // the packages are still closed by their '}', and 'class B' is back
+        // at the top level
+        let last = parsed.syntax().children().last().unwrap();
+        assert_eq!(last.kind(), crate::syntax::SyntaxKind::CLASS_DECL);
+    }
+
+    #[test]
+    fn recovers_from_errors() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\npackage P { class A extends }\nclass B\n!theme x y\nobject o {\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        let errors = parsed
+            .errors()
+            .iter()
+            .map(|it| (&text[it.range], it.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                ("}", "expected a class name"),
+                ("y", "expected end of line"),
+                ("@enduml", "expected '}'"),
+            ]
+        );
+        // the '}' still closes the package, and '@enduml' the object
+        let top_level = parsed
+            .syntax()
+            .children_with_tokens()
+            .map(|it| it.kind())
+            .collect::<Vec<_>>();
+        use crate::syntax::SyntaxKind::*;
+        assert_eq!(
+            top_level,
+            vec![
+                DIAGRAM_START,
+                EOL,
+                PACKAGE_DECL,
+                CLASS_DECL,
+                THEME,
+                OBJECT_DECL,
+                DIAGRAM_END,
+                EOL
+            ]
+        );
     }
 }
@@ -1,3 +1,17 @@
+pub(crate) mod activity;
+pub(crate) mod class;
+pub(crate) mod creole;
+pub(crate) mod component;
+pub(crate) mod data;
+pub(crate) mod gantt;
+pub(crate) mod mindmap;
+pub(crate) mod object;
+pub(crate) mod preprocessor;
+pub(crate) mod salt;
 pub(crate) mod sequence;
+pub(crate) mod state;
+pub(crate) mod style;
+pub(crate) mod timing;
+pub(crate) mod usecase;
 
 // pub(crate) use self::sequence::*;
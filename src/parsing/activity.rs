@@ -0,0 +1,338 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::syntax::SyntaxKind::{self, *};
+
+/// Characters that may end an `:action`, each drawing a different shape
+const TERMINATORS: &[SyntaxKind] = &[
+    SEMI, PIPE, L_ANGLE, R_ANGLE, SLASH, BACKSLASH, R_BRACK, R_BRACE,
+];
+
+/// Whether the parser is looking at a keyword that closes, or continues, an
+/// enclosing block. Branches stop here and leave it to the owning block.
+pub(crate) fn at_block_end(parser: &Parser) -> bool {
+    match parser.current() {
+        EOF | R_BRACE | DIAGRAM_END | ELSEIF_KW | ELSE_KW | ENDIF_KW | ENDWHILE_KW => true,
+        REPEAT_KW => parser.nth(1) == WHILE_KW,
+        FORK_KW | SPLIT_KW => parser.nth(1) == AGAIN_KW,
+        END_KW => at_end_of(parser, FORK_KW) || at_end_of(parser, SPLIT_KW),
+        _ => false,
+    }
+}
+
+/// `end fork`, `end merge` or `end split`
+fn at_end_of(parser: &Parser, keyword: SyntaxKind) -> bool {
+    if !parser.at(END_KW) {
+        return false;
+    }
+    let line = parser.rest_of_line();
+    match line.get(1) {
+        Some((kind, _)) if *kind == keyword => true,
+        Some((IDENTIFIER, "merge")) => keyword == FORK_KW,
+        _ => false,
+    }
+}
+
+/// The statements of a block, up to whatever keyword closes it
+fn branch(parser: &mut Parser) {
+    parser.start_node(BRANCH);
+    loop {
+        parser.skip_ws();
+        if at_block_end(parser) {
+            break;
+        }
+        parser.statement();
+    }
+    parser.finish_node();
+}
+
+/// The remainder of a block header such as `if (a) then (yes)`, with each
+/// parenthesised part as a CONDITION
+fn header(parser: &mut Parser) {
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOL | EOF => break,
+            L_PAREN => condition(parser),
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eat(EOL);
+}
+
+fn condition(parser: &mut Parser) {
+    parser.start_node(CONDITION);
+    parser.consume(L_PAREN);
+    let mut depth = 1;
+    while !parser.at_eol() {
+        match parser.current() {
+            L_PAREN => depth += 1,
+            R_PAREN => depth -= 1,
+            _ => {}
+        }
+        parser.consume_token();
+        if depth == 0 {
+            break;
+        }
+    }
+    if depth > 0 {
+        parser.error("expected ')'");
+    }
+    parser.finish_node();
+}
+
+/// Finish a block with its closing line, if that's what comes next
+fn close(parser: &mut Parser, at_close: bool, message: &str) {
+    if at_close {
+        header(parser);
+    } else {
+        parser.error(message);
+    }
+}
+
+/// `start`
+pub(crate) fn start(parser: &mut Parser) -> Statement {
+    parser.start_node(START);
+    parser.consume(START_KW);
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `stop`, `end`, `kill` or `detach`
+pub(crate) fn stop(parser: &mut Parser) -> Statement {
+    parser.start_node(STOP);
+    parser.consume_token();
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `:action;`, which may span several lines and ends with one of the
+/// TERMINATORS at the end of a line
+pub(crate) fn action(parser: &mut Parser) -> Statement {
+    trace!("Starting action node");
+    parser.start_node(ACTION);
+    parser.consume(COLON);
+    parser.start_node(LABEL);
+    parser.markup(|parser| {
+        TERMINATORS.contains(&parser.current()) && matches!(parser.nth(1), EOL | EOF)
+    });
+    parser.finish_node();
+    if parser.at(EOF) {
+        parser.error("expected ';'");
+        parser.finish_node();
+        return Statement::Ok;
+    }
+    parser.consume_token(); // the terminator
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `-> label;`
+pub(crate) fn arrow(parser: &mut Parser) -> Statement {
+    parser.start_node(ACTIVITY_ARROW);
+    parser.consume(ARROW);
+    parser.skip_ws();
+    if !parser.at_eol() && !parser.at(SEMI) {
+        parser.start_node(LABEL);
+        parser.markup(|parser| parser.at_eol() || parser.at(SEMI));
+        parser.finish_node();
+    }
+    parser.eat(SEMI);
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `if (a) then (yes)` ... `elseif (b) then (yes)` ... `else (no)` ... `endif`
+pub(crate) fn if_block(parser: &mut Parser) -> Statement {
+    parser.start_node(IF_BLOCK);
+    header(parser);
+    branch(parser);
+    while parser.at(ELSEIF_KW) {
+        header(parser);
+        branch(parser);
+    }
+    if parser.at(ELSE_KW) {
+        header(parser);
+        branch(parser);
+    }
+    close(parser, parser.at(ENDIF_KW), "expected 'endif'");
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `while (a) is (yes)` ... `endwhile (no)`
+pub(crate) fn while_block(parser: &mut Parser) -> Statement {
+    parser.start_node(WHILE_BLOCK);
+    header(parser);
+    branch(parser);
+    close(parser, parser.at(ENDWHILE_KW), "expected 'endwhile'");
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `repeat` ... `repeat while (a) is (yes)`
+pub(crate) fn repeat_block(parser: &mut Parser) -> Statement {
+    parser.start_node(REPEAT_BLOCK);
+    parser.consume(REPEAT_KW);
+    parser.skip_ws();
+    // 'repeat :first action;'
+    if parser.at(COLON) {
+        action(parser);
+    } else {
+        header(parser);
+    }
+    branch(parser);
+    let at_close = parser.at(REPEAT_KW) && parser.nth(1) == WHILE_KW;
+    close(parser, at_close, "expected 'repeat while'");
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `fork` ... `fork again` ... `end fork`, or the same with `split`
+pub(crate) fn fork_block(parser: &mut Parser) -> Statement {
+    let keyword = parser.current();
+    parser.start_node(if keyword == FORK_KW {
+        FORK_BLOCK
+    } else {
+        SPLIT_BLOCK
+    });
+    header(parser);
+    branch(parser);
+    while parser.at(keyword) && parser.nth(1) == AGAIN_KW {
+        header(parser);
+        branch(parser);
+    }
+    let message = if keyword == FORK_KW {
+        "expected 'end fork'"
+    } else {
+        "expected 'end split'"
+    };
+    close(parser, at_end_of(parser, keyword), message);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `|Lane|` or `|#color|Lane|`
+pub(crate) fn swimlane(parser: &mut Parser) -> Statement {
+    parser.start_node(SWIMLANE);
+    parser.consume(PIPE);
+    // with a color the name is in the second pair of pipes
+    let pipes = parser
+        .rest_of_line()
+        .iter()
+        .filter(|(kind, _)| *kind == PIPE)
+        .count();
+    if pipes >= 2 {
+        while !parser.at_eol() && !parser.eat(PIPE) {
+            parser.consume_token();
+        }
+    }
+    parser.skip_ws();
+    parser.start_node(IDENTIFIER);
+    while !parser.at_eol() && !parser.at(PIPE) {
+        parser.consume_token();
+    }
+    parser.finish_node();
+    if !parser.eat(PIPE) {
+        parser.error("expected '|'");
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `partition Name #color { ... }`
+pub(crate) fn partition(parser: &mut Parser) -> Statement {
+    parser.start_node(PARTITION);
+    parser.consume(PARTITION_KW);
+    parser.skip_ws();
+    parser.start_node(IDENTIFIER);
+    while !parser.at_eol() && !parser.at(L_BRACE) && !parser.at(HASH) {
+        if parser.at(WHITESPACE) && matches!(parser.nth(0), L_BRACE | HASH | EOL | EOF) {
+            break;
+        }
+        parser.consume_token();
+    }
+    parser.finish_node();
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            L_BRACE => {
+                parser.block();
+                break;
+            }
+            EOL | EOF => break,
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_activity() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\nstart\n|Shop|\n:Pick item\n on two lines;\nif (in stock?) then (yes)\n  :ship>\nelseif (backorder?) then (yes)\n  :wait|\nelse (no)\n  :refund/\n  kill\nendif\nwhile (more?) is (yes)\n  -> next;\n  :count;\nendwhile (no)\nrepeat :read;\n  :parse;\nrepeat while (valid?)\nfork\n  :a;\nfork again\n  :b;\nend fork\nsplit\n  :c;\nsplit again\n  :d;\nend split\npartition Checkout {\n  :pay;\n}\ndetach\nstop\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let actions = root
+            .actions()
+            .map(|it| it.label().unwrap().text().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                "Pick item\n on two lines",
+                "ship",
+                "wait",
+                "refund",
+                "count",
+                "read",
+                "parse",
+                "a",
+                "b",
+                "c",
+                "d",
+                "pay"
+            ]
+        );
+        let lanes = root
+            .swimlanes()
+            .map(|it| it.name().unwrap().identifier().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lanes, vec!["Shop"]);
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        let _ = env_logger::try_init();
+        let text = "if (a) then\n  :b;\n  while (c)\n    :d;\nendif\nendwhile\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        let errors = parsed
+            .errors()
+            .iter()
+            .map(|it| (&text[it.range], it.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                ("endif", "expected 'endwhile'"),
+                ("endwhile", "unexpected statement")
+            ]
+        );
+    }
+}
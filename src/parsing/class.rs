@@ -0,0 +1,525 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::parsing::component;
+use crate::syntax::SyntaxKind::{self, *};
+
+/// Modifiers that can appear in braces at the start of a member,
+/// e.g. `{static}` or `{abstract}`
+const MODIFIERS: &[&str] = &["static", "abstract", "classifier", "field", "method"];
+
+/// Whether the token can be a word of a name. Keywords are only keywords
+/// where they start a statement, so `class node`, `state end` and
+/// `A -> end` are fine.
+pub(crate) fn is_word(kind: SyntaxKind) -> bool {
+    kind == IDENTIFIER || kind.is_keyword()
+}
+
+/// A word of a name, a keyword being taken as an IDENTIFIER
+fn word(parser: &mut Parser) {
+    if parser.current().is_keyword() {
+        parser.consume_as(IDENTIFIER);
+    } else {
+        parser.consume(IDENTIFIER);
+    }
+}
+
+/// A (possibly qualified) name such as `Foo`, `net.foo.Bar` or `"Long Name"`,
+/// or a map entry such as `Map::key`
+pub(crate) fn name(parser: &mut Parser) {
+    parser.skip_ws();
+    parser.start_node(IDENTIFIER);
+    if !parser.eat(STRING) {
+        word(parser);
+        while parser.at(DOT) && is_word(parser.nth(1)) {
+            parser.consume(DOT);
+            if !is_word(parser.current()) {
+                break;
+            }
+            word(parser);
+        }
+    }
+    if parser.at_seq(&[COLON, COLON, IDENTIFIER]) {
+        parser.consume(COLON);
+        parser.consume(COLON);
+        parser.consume(IDENTIFIER);
+    }
+    parser.finish_node();
+}
+
+pub(crate) fn at_name(parser: &Parser) -> bool {
+    is_word(parser.current()) || parser.at(STRING)
+}
+
+/// `<< Entity >>`
+pub(crate) fn stereotype(parser: &mut Parser) {
+    parser.start_node(STEREOTYPE);
+    parser.consume(STEREO_OPEN);
+    while !parser.at_eol() && !parser.at(STEREO_CLOSE) {
+        parser.consume_token();
+    }
+    if !parser.eat(STEREO_CLOSE) {
+        parser.error("expected '>>'");
+    }
+    parser.finish_node();
+}
+
+/// `<T extends Comparable<T>>`, a closing `>>` counts for two levels
+fn generic_params(parser: &mut Parser) {
+    parser.start_node(GENERIC_PARAMS);
+    parser.consume(L_ANGLE);
+    let mut depth = 1;
+    while depth > 0 && !parser.at_eol() {
+        match parser.current() {
+            L_ANGLE => depth += 1,
+            R_ANGLE => depth -= 1,
+            STEREO_OPEN => depth += 2,
+            STEREO_CLOSE => depth -= 2,
+            _ => {}
+        }
+        parser.consume_token();
+    }
+    if depth > 0 {
+        parser.error("expected '>'");
+    }
+    parser.finish_node();
+}
+
+/// `extends A, B` or `implements C`
+fn inheritance_clause(parser: &mut Parser, keyword: SyntaxKind, kind: SyntaxKind) {
+    parser.start_node(kind);
+    parser.consume(keyword);
+    loop {
+        parser.skip_ws();
+        if !at_name(parser) {
+            parser.error("expected a class name");
+            break;
+        }
+        name(parser);
+        if parser.at(L_ANGLE) {
+            generic_params(parser);
+        }
+        parser.skip_ws();
+        if !parser.eat(COMMA) {
+            break;
+        }
+    }
+    parser.finish_node();
+}
+
+/// `[abstract] class Name<T> <<stereo>> extends Base implements Iface { ... }`
+pub(crate) fn class_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting class decl node");
+    parser.start_node(CLASS_DECL);
+    if parser.eat(ABSTRACT_KW) {
+        parser.skip_ws();
+        parser.eat(CLASS_KW);
+    } else {
+        // 'class', 'interface', 'enum' or 'annotation'
+        parser.consume_token();
+    }
+
+    parser.skip_ws();
+    if at_name(parser) {
+        name(parser);
+    } else {
+        parser.error("expected a class name");
+    }
+    if parser.at(L_ANGLE) {
+        generic_params(parser);
+    }
+
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            AS_KW => {
+                parser.consume(AS_KW);
+                parser.skip_ws();
+                if at_name(parser) {
+                    name(parser);
+                }
+            }
+            L_ANGLE => generic_params(parser),
+            STEREO_OPEN => stereotype(parser),
+            EXTENDS_KW => inheritance_clause(parser, EXTENDS_KW, EXTENDS_CLAUSE),
+            IMPLEMENTS_KW => inheritance_clause(parser, IMPLEMENTS_KW, IMPLEMENTS_CLAUSE),
+            L_BRACE => {
+                class_body(parser);
+                break;
+            }
+            _ if parser.at_sync() => break,
+            _ => {
+                // colors and other decorations, e.g. '#pink ##[dashed]blue'
+                parser.consume_token();
+            }
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+fn class_body(parser: &mut Parser) {
+    parser.start_node(CLASS_BODY);
+    parser.consume(L_BRACE);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOF | DIAGRAM_END => {
+                parser.error("expected '}'");
+                break;
+            }
+            R_BRACE => {
+                parser.consume(R_BRACE);
+                break;
+            }
+            EOL => parser.consume(EOL),
+            // separators such as '--', '..' and '=='
+            ARROW => parser.skip_to_eol(),
+            _ => member(parser),
+        }
+    }
+    parser.finish_node();
+}
+
+/// A field or method inside a class body:
+/// `{static} + name : String`, `- int count` or `# {abstract} run(int x) : void`
+fn member(parser: &mut Parser) {
+    let checkpoint = parser.checkpoint();
+    loop {
+        match parser.current() {
+            L_BRACE if is_modifier(parser) => {
+                // '{static}' or '{ static }'
+                parser.start_node(MODIFIER);
+                parser.consume(L_BRACE);
+                parser.skip_ws();
+                parser.consume_token();
+                parser.skip_ws();
+                parser.consume(R_BRACE);
+                parser.finish_node();
+            }
+            PLUS | MINUS | HASH | TILDE => {
+                parser.start_node(VISIBILITY);
+                parser.consume_token();
+                parser.finish_node();
+            }
+            WHITESPACE => parser.skip_ws(),
+            _ => break,
+        }
+    }
+
+    let line: Vec<SyntaxKind> = parser
+        .rest_of_line()
+        .into_iter()
+        .map(|(kind, _)| kind)
+        .take_while(|kind| *kind != R_BRACE)
+        .collect();
+    let paren = line.iter().position(|kind| *kind == L_PAREN);
+    let colon = line.iter().position(|kind| *kind == COLON);
+    // keywords are fine as member names, e.g. 'state : int'
+    let is_word = |kind: &SyntaxKind| *kind == IDENTIFIER || kind.is_keyword();
+    // 'name : Type' names come first, 'Type name' names come last
+    let name_index = match (paren, colon) {
+        (Some(paren), _) => line[..paren].iter().rposition(is_word),
+        (None, Some(colon)) => line[..colon].iter().position(is_word),
+        (None, None) => line.iter().rposition(is_word),
+    };
+
+    let mut index = 0;
+    while !parser.at_eol() && !parser.at(R_BRACE) {
+        if parser.at(WHITESPACE) {
+            parser.consume_token();
+            continue;
+        }
+        if Some(index) == name_index {
+            parser.start_node(IDENTIFIER);
+            parser.consume_token();
+            parser.finish_node();
+        } else {
+            parser.consume_token();
+        }
+        index += 1;
+    }
+    parser.start_node_at(checkpoint, if paren.is_some() { METHOD } else { FIELD });
+    parser.finish_node();
+}
+
+fn is_modifier(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    match line.as_slice() {
+        [(L_BRACE, _), (_, word), (R_BRACE, _), ..] => MODIFIERS.contains(word),
+        _ => false,
+    }
+}
+
+/// `package net.foo <<Folder>> { ... }` or `namespace foo { ... }`
+pub(crate) fn package_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting package decl node");
+    parser.start_node(PACKAGE_DECL);
+    parser.consume_token(); // 'package' or 'namespace'
+    parser.skip_ws();
+    if at_name(parser) {
+        name(parser);
+    } else {
+        parser.error("expected a package name");
+    }
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            STEREO_OPEN => stereotype(parser),
+            L_BRACE => {
+                parser.block();
+                break;
+            }
+            EOL | EOF => break,
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// How many significant tokens the name at the start of `line` takes up, for
+/// `Foo`, `net.foo.Bar`, `"Long Name"`, `[Component]`, `(Use Case)`, `:Actor:`
+/// and `[*]`
+fn name_length(line: &[SyntaxKind]) -> Option<usize> {
+    let closing = |close: SyntaxKind| {
+        line.iter()
+            .skip(1)
+            .position(|kind| *kind == close)
+            .map(|it| it + 2)
+    };
+    match line.first()? {
+        STRING | PSEUDO_STATE => Some(1),
+        kind if is_word(*kind) => {
+            let mut index = 1;
+            while line.get(index) == Some(&DOT) && line.get(index + 1).copied().is_some_and(is_word)
+            {
+                index += 2;
+            }
+            if line[index..].starts_with(&[COLON, COLON, IDENTIFIER]) {
+                index += 3;
+            }
+            Some(index)
+        }
+        L_BRACK => closing(R_BRACK),
+        L_PAREN => closing(R_PAREN),
+        COLON => closing(COLON),
+        _ => None,
+    }
+}
+
+/// Whether the current line looks like `A "1" *-- "many" B`. A lone `-` also
+/// links two elements, as in `Api - [Server]`.
+pub(crate) fn at_relation(parser: &Parser) -> bool {
+    let line = parser
+        .rest_of_line()
+        .into_iter()
+        .map(|(kind, _)| kind)
+        .collect::<Vec<_>>();
+    let mut index = match name_length(&line) {
+        Some(index) => index,
+        None => return false,
+    };
+    if line.get(index) == Some(&STRING) {
+        index += 1;
+    }
+    matches!(line.get(index), Some(ARROW) | Some(MINUS))
+}
+
+fn relation_end(parser: &mut Parser) {
+    parser.skip_ws();
+    // '[*]' and '[H]' are left as plain tokens, they don't name anything
+    if parser.eat(PSEUDO_STATE) {
+        return;
+    }
+    if component::at_element_name(parser) {
+        component::element_name(parser);
+    } else {
+        parser.error("expected a name");
+    }
+}
+
+fn cardinality(parser: &mut Parser) {
+    parser.skip_ws();
+    if parser.at(STRING) {
+        parser.start_node(CARDINALITY);
+        parser.consume(STRING);
+        parser.finish_node();
+    }
+}
+
+/// `Left "1" *-- "many" Right : label`
+pub(crate) fn relation(parser: &mut Parser) -> Statement {
+    trace!("Starting relation node");
+    parser.start_node(RELATION);
+    relation_end(parser);
+    cardinality(parser);
+    parser.skip_ws();
+    if parser.at(MINUS) {
+        parser.consume_as(ARROW);
+    } else if !parser.eat(ARROW) {
+        parser.error("expected an arrow");
+    }
+    cardinality(parser);
+    relation_end(parser);
+    parser.skip_ws();
+    if parser.eat(COLON) {
+        // '<<include>>' and '<<extend>>' in use case diagrams
+        parser.skip_ws();
+        if parser.at(STEREO_OPEN) {
+            stereotype(parser);
+        }
+        parser.label();
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_spaced_modifiers() {
+        let _ = env_logger::try_init();
+        let text = "class Counter {\n  { static } x : int\n  {  abstract  } run() : void\n  y : int\n}\n";
+        let parse = crate::parse(text);
+        assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+        let members = parse
+            .root()
+            .class_decls()
+            .flat_map(|it| it.members().collect::<Vec<_>>())
+            .map(|it| {
+                (
+                    it.name().map(|n| n.identifier().to_string()),
+                    it.is_static(),
+                    it.is_abstract(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            members,
+            vec![
+                (Some("x".into()), true, false),
+                (Some("run".into()), false, true),
+                (Some("y".into()), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_class_decl() {
+        let _ = env_logger::try_init();
+        let text = "abstract class List<T> {\n  {static} + count : int\n  - {abstract} add(T item) : void\n  # String name\n}\ninterface Runnable\nenum Color {\n  RED\n}\n";
+        let root = crate::parse(text).root();
+        let classes = root.class_decls().collect::<Vec<_>>();
+        let names = classes
+            .iter()
+            .filter_map(|it| it.name())
+            .map(|it| it.identifier().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["List", "Runnable", "Color"]);
+        assert!(classes[0].is_abstract());
+
+        let members = classes[0]
+            .members()
+            .map(|it| {
+                (
+                    it.name().map(|n| n.identifier().to_string()),
+                    it.is_method(),
+                    it.visibility().and_then(|it| it.symbol()),
+                    it.is_static(),
+                )
+            })
+            .collect::<Vec<_>>();
+        trace!("Members - {:?}", members);
+        assert_eq!(
+            members,
+            vec![
+                (Some("count".into()), false, Some('+'), true),
+                (Some("add".into()), true, Some('-'), false),
+                (Some("name".into()), false, Some('#'), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relations() {
+        let _ = env_logger::try_init();
+        let text = "package net.shop {\n  class Order\n}\nOrder \"1\" *-- \"many\" Line : contains >\nBase <|-- Order\nOrder ..> Invoice\nCar o-- Wheel\n";
+        let root = crate::parse(text).root();
+        let res = root
+            .relations()
+            .map(|it| {
+                (
+                    it.left().map(|n| n.identifier().to_string()),
+                    it.arrow().map(|t| t.text().to_string()),
+                    it.right().map(|n| n.identifier().to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            res,
+            vec![
+                (
+                    Some("Order".into()),
+                    Some("*--".into()),
+                    Some("Line".into())
+                ),
+                (
+                    Some("Base".into()),
+                    Some("<|--".into()),
+                    Some("Order".into())
+                ),
+                (
+                    Some("Order".into()),
+                    Some("..>".into()),
+                    Some("Invoice".into())
+                ),
+                (Some("Car".into()), Some("o--".into()), Some("Wheel".into())),
+            ]
+        );
+        let first = root.relations().next().unwrap();
+        assert_eq!(
+            first
+                .cardinalities()
+                .map(|t| t.syntax().text().to_string())
+                .collect::<Vec<_>>(),
+            vec!["\"1\"", "\"many\""]
+        );
+        assert_eq!(first.label().unwrap().text().to_string(), "contains >");
+
+        let packages = root.package_decls().collect::<Vec<_>>();
+        assert_eq!(
+            packages[0].name().unwrap().identifier().to_string(),
+            "net.shop"
+        );
+        assert_eq!(packages[0].class_decls().count(), 1);
+    }
+
+    #[test]
+    fn test_keywords_as_names() {
+        let _ = env_logger::try_init();
+        let name = |it: Option<crate::ast::IdentifierNode>| it.map(|it| it.value());
+        let relations = vec![("A -> end : x\n", "A", "end"), ("node -> A\n", "node", "A")];
+        for (text, left, right) in relations {
+            let parse = crate::parse(text);
+            assert!(parse.errors().is_empty(), "{}: {:?}", text, parse.errors());
+            let relation = parse.root().relations().next().unwrap();
+            assert_eq!(name(relation.left()).as_deref(), Some(left));
+            assert_eq!(name(relation.right()).as_deref(), Some(right));
+        }
+        let parse = crate::parse("class node\n");
+        assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+        let class = parse.root().class_decls().next().unwrap();
+        assert_eq!(name(class.name()).as_deref(), Some("node"));
+        let parse = crate::parse("state end\n");
+        assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+        let state = parse.root().state_decls().next().unwrap();
+        assert_eq!(name(state.name()).as_deref(), Some("end"));
+    }
+}
@@ -0,0 +1,197 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::parsing::class;
+use crate::syntax::SyntaxKind::*;
+
+/// An element name such as `Api`, `"Web Server"`, the component `[Web Server]`,
+/// the use case `(Log In)` or the actor `:Customer:`
+pub(crate) fn element_name(parser: &mut Parser) {
+    parser.skip_ws();
+    let (close, message) = match parser.current() {
+        L_BRACK => (R_BRACK, "expected ']'"),
+        L_PAREN => (R_PAREN, "expected ')'"),
+        COLON => (COLON, "expected ':'"),
+        _ => {
+            class::name(parser);
+            return;
+        }
+    };
+    parser.start_node(IDENTIFIER);
+    parser.consume_token();
+    while !parser.at_eol() && !parser.at(close) {
+        parser.consume_token();
+    }
+    if !parser.eat(close) {
+        parser.error(message);
+    }
+    parser.finish_node();
+}
+
+pub(crate) fn at_element_name(parser: &Parser) -> bool {
+    class::at_name(parser) || matches!(parser.current(), L_BRACK | L_PAREN | COLON)
+}
+
+/// Whatever follows the name of an element: `as Alias <<stereo>> #color { ... }`
+pub(crate) fn element_tail(parser: &mut Parser) {
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            AS_KW => {
+                parser.consume(AS_KW);
+                parser.skip_ws();
+                if at_element_name(parser) {
+                    element_name(parser);
+                }
+            }
+            STEREO_OPEN => class::stereotype(parser),
+            L_BRACE => {
+                parser.block();
+                break;
+            }
+            EOL | EOF => break,
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eol();
+}
+
+/// `component Name`, `[Name]` or the lollipop interface `() Name`
+pub(crate) fn component_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting component decl node");
+    parser.start_node(COMPONENT_DECL);
+    match parser.current() {
+        L_PAREN => {
+            parser.consume(L_PAREN);
+            parser.skip_ws();
+            if !parser.eat(R_PAREN) {
+                parser.error("expected ')'");
+            }
+        }
+        L_BRACK => {}
+        _ => parser.consume(COMPONENT_KW),
+    }
+    parser.skip_ws();
+    if at_element_name(parser) {
+        element_name(parser);
+    } else {
+        parser.error("expected a component name");
+    }
+    element_tail(parser);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `node Name { ... }`, and likewise `folder`, `frame`, `cloud`, `database`
+/// and `rectangle`
+pub(crate) fn container_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting container decl node");
+    parser.start_node(CONTAINER_DECL);
+    parser.consume_token(); // the keyword
+    parser.skip_ws();
+    // the name is optional, as in 'cloud { ... }'
+    if at_element_name(parser) {
+        element_name(parser);
+    } else if !parser.at(L_BRACE) {
+        parser.error("expected a name");
+    }
+    element_tail(parser);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `port Name`, `portin Name` or `portout Name`
+pub(crate) fn port_decl(parser: &mut Parser) -> Statement {
+    parser.start_node(PORT_DECL);
+    parser.consume_token(); // the keyword
+    parser.skip_ws();
+    if at_element_name(parser) {
+        element_name(parser);
+    } else {
+        parser.error("expected a port name");
+    }
+    element_tail(parser);
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_component_decl() {
+        let _ = env_logger::try_init();
+        let text = "package \"Some Group\" {\n  HTTP - [First Component]\n  [Another component]\n}\nnode \"Other Groups\" {\n  FTP - [Second Component]\n  [First Component] --> FTP\n}\ncloud {\n  [Example 1]\n}\ndatabase \"MySql\" as db {\n  folder \"This is my folder\" {\n    [Folder 3]\n  }\n  frame \"Foo\" {\n    [Frame 4]\n  }\n}\ncomponent Api <<service>> {\n  portin p1\n  portout p2\n}\n() \"Data Access\" as DA\n[Last] as L #yellow\nDA )--( L\n[Api] -[#red,dashed]-> db\nL -(0- Api\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let components = root
+            .component_decls()
+            .map(|it| {
+                (
+                    it.name().unwrap().value(),
+                    it.alias().map(|a| a.value()),
+                    it.element_kind(),
+                )
+            })
+            .collect::<Vec<_>>();
+        use crate::ast::ElementKind::*;
+        assert_eq!(
+            components,
+            vec![
+                ("Another component".into(), None, Component),
+                ("Example 1".into(), None, Component),
+                ("Folder 3".into(), None, Component),
+                ("Frame 4".into(), None, Component),
+                ("Api".into(), None, Component),
+                ("Data Access".into(), Some("DA".into()), Interface),
+                ("Last".into(), Some("L".into()), Component),
+            ]
+        );
+
+        let database = root.container_decls().nth(2).unwrap();
+        assert_eq!(database.alias().unwrap().value(), "db");
+        let nested = database
+            .containers()
+            .map(|it| it.name().unwrap().value())
+            .collect::<Vec<_>>();
+        assert_eq!(nested, vec!["This is my folder", "Foo"]);
+
+        let api = root.component_decls().nth(4).unwrap();
+        let ports = api
+            .ports()
+            .map(|it| (it.name().unwrap().value(), it.port_kind()))
+            .collect::<Vec<_>>();
+        use crate::ast::PortKind;
+        assert_eq!(
+            ports,
+            vec![("p1".into(), PortKind::In), ("p2".into(), PortKind::Out)]
+        );
+
+        let links = root
+            .relations()
+            .map(|it| {
+                (
+                    it.left().unwrap().value(),
+                    it.arrow().unwrap().text().to_string(),
+                    it.right().unwrap().value(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            vec![
+                ("HTTP".into(), "-".into(), "First Component".into()),
+                ("FTP".into(), "-".into(), "Second Component".into()),
+                ("First Component".into(), "-->".into(), "FTP".into()),
+                ("DA".into(), ")--(".into(), "L".into()),
+                ("Api".into(), "-[#red,dashed]->".into(), "db".into()),
+                ("L".into(), "-(0-".into(), "Api".into()),
+            ]
+        );
+    }
+}
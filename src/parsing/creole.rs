@@ -0,0 +1,323 @@
+//! The Creole markup of labels: `**bold**`, `//italic//`, `""monospaced""`,
+//! `--stricken--`, `__underlined__`, tags such as `<color:red>`, OpenIconic
+//! icons such as `<&heart>`, images such as `<img:logo.png>` and escapes such
+//! as `\n`.
+//!
+//! The markup is found in the text of the label, which doesn't split into
+//! tokens the way the rest of a diagram does, around the atoms the parser
+//! keeps as they are: variables, calls, comments and line ends.
+use std::ops::Range;
+
+use crate::syntax::SyntaxKind::{self, *};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Event {
+    Start(SyntaxKind),
+    /// A token of the kind, as long as the next that many bytes of text
+    Token(SyntaxKind, usize),
+    Finish,
+    /// The next of the atoms
+    Atom,
+}
+
+/// The delimiters of emphasized text, and the nodes they make
+const EMPHASIS: &[(&str, SyntaxKind)] = &[
+    ("**", CREOLE_BOLD),
+    ("//", CREOLE_ITALIC),
+    ("\"\"", CREOLE_MONOSPACED),
+    ("--", CREOLE_STRIKE),
+    ("__", CREOLE_UNDERLINE),
+];
+
+/// The tags that apply to the text up to their closing tag, or to the end of
+/// the line
+pub(crate) const TAGS: &[&str] = &[
+    "b", "i", "u", "s", "w", "strike", "plain", "sub", "sup", "color", "back", "size", "font",
+];
+
+/// How deeply emphasis and tags may nest before any more of them are taken
+/// as plain text
+const MAX_DEPTH: usize = 100;
+
+struct Markup<'a> {
+    text: &'a str,
+    atoms: &'a [Range<usize>],
+    events: Vec<Event>,
+    /// how many emphases and tags the markup is inside of
+    depth: usize,
+}
+
+impl Markup<'_> {
+    fn atom_at(&self, offset: usize) -> Option<&Range<usize>> {
+        self.atoms.iter().find(|it| it.contains(&offset))
+    }
+
+    /// Where the line `offset` is on ends, as markup doesn't carry over to
+    /// the next line
+    fn line_end(&self, offset: usize, end: usize) -> usize {
+        self.atoms
+            .iter()
+            .filter(|it| it.start >= offset && it.start < end)
+            .find(|it| self.text[it.start..].starts_with(['\r', '\n']))
+            .map(|it| it.start)
+            .unwrap_or(end)
+    }
+
+    /// The length of the escape at the start of `rest`, if there is one
+    fn escape(rest: &str) -> Option<usize> {
+        let mut chars = rest.chars();
+        match (chars.next()?, chars.next()?) {
+            ('~', '\r' | '\n') => None,
+            ('~', c) => Some(1 + c.len_utf8()),
+            ('\\', 'n' | 't' | 'l' | 'r' | '\\') => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Whether the `len` bytes at `offset` are clear of the atoms, so that
+    /// markup there doesn't run into one
+    fn clear(&self, offset: usize, len: usize) -> bool {
+        !self
+            .atoms
+            .iter()
+            .any(|it| it.start < offset + len && offset < it.end)
+    }
+
+    /// The length of the escape at `offset`, unless it would escape the
+    /// start of an atom
+    fn escape_at(&self, offset: usize, end: usize) -> Option<usize> {
+        Self::escape(&self.text[offset..end]).filter(|len| self.clear(offset, *len))
+    }
+
+    /// Where `pattern` next is between `offset` and `end`, outside the atoms
+    /// and escapes
+    fn find(&self, pattern: &str, mut offset: usize, end: usize) -> Option<usize> {
+        while offset + pattern.len() <= end {
+            if let Some(atom) = self.atom_at(offset) {
+                offset = atom.end;
+                continue;
+            }
+            let rest = &self.text[offset..end];
+            if rest.starts_with(pattern) && self.clear(offset, pattern.len()) {
+                return Some(offset);
+            }
+            offset += self
+                .escape_at(offset, end)
+                .unwrap_or_else(|| rest.chars().next().unwrap().len_utf8());
+        }
+        None
+    }
+
+    fn token(&mut self, kind: SyntaxKind, len: usize) {
+        if len > 0 {
+            self.events.push(Event::Token(kind, len));
+        }
+    }
+
+    /// The length of the `<...>` starting at `offset`, if it's closed on
+    /// the same line and around no atoms
+    fn angled(&self, offset: usize, end: usize) -> Option<usize> {
+        let close = self.find(">", offset + 1, end)?;
+        let around_atom = self
+            .atoms
+            .iter()
+            .any(|it| offset < it.start && it.start < close);
+        if around_atom || self.text[offset + 1..close].contains('<') {
+            return None;
+        }
+        Some(close + 1 - offset)
+    }
+
+    /// The markup from `offset` up to `end`
+    fn inline(&mut self, mut offset: usize, end: usize) {
+        self.depth += 1;
+        // where the plain text since the last of the markup started
+        let mut text = offset;
+        while offset < end {
+            if let Some(atom) = self.atom_at(offset).cloned() {
+                self.token(CREOLE_TEXT, offset - text);
+                self.events.push(Event::Atom);
+                offset = atom.end;
+                text = offset;
+                continue;
+            }
+            let rest = &self.text[offset..end];
+            if let Some(len) = self.escape_at(offset, end) {
+                self.token(CREOLE_TEXT, offset - text);
+                self.token(CREOLE_ESCAPE, len);
+                offset += len;
+                text = offset;
+                continue;
+            }
+            if self.depth > MAX_DEPTH {
+                offset += rest.chars().next().unwrap().len_utf8();
+                continue;
+            }
+            if let Some(len) = self.emphasis(offset, end, text) {
+                offset += len;
+                text = offset;
+                continue;
+            }
+            if rest.starts_with('<') {
+                if let Some(len) = self.tag(offset, end, text) {
+                    offset += len;
+                    text = offset;
+                    continue;
+                }
+            }
+            offset += rest.chars().next().unwrap().len_utf8();
+        }
+        self.token(CREOLE_TEXT, offset - text);
+        self.depth -= 1;
+    }
+
+    /// Emphasized text starting at `offset`, returning how long it is
+    fn emphasis(&mut self, offset: usize, end: usize, text: usize) -> Option<usize> {
+        let rest = &self.text[offset..end];
+        let (delimiter, kind) = EMPHASIS.iter().find(|(it, _)| rest.starts_with(it))?;
+        // the `//` of a URL such as `http://example.com` isn't italic
+        if *delimiter == "//" && self.text[..offset].ends_with(':') {
+            return None;
+        }
+        if !self.clear(offset, delimiter.len()) {
+            return None;
+        }
+        let inner = offset + delimiter.len();
+        let close = self.find(delimiter, inner, self.line_end(inner, end))?;
+        if close == inner {
+            return None;
+        }
+        self.token(CREOLE_TEXT, offset - text);
+        self.events.push(Event::Start(*kind));
+        self.token(CREOLE_DELIMITER, delimiter.len());
+        self.inline(inner, close);
+        self.token(CREOLE_DELIMITER, delimiter.len());
+        self.events.push(Event::Finish);
+        Some(close + delimiter.len() - offset)
+    }
+
+    /// An icon, image or tag starting at `offset`, returning how long it is,
+    /// along with the text the tag applies to
+    fn tag(&mut self, offset: usize, end: usize, text: usize) -> Option<usize> {
+        let len = self.angled(offset, end)?;
+        let inner = &self.text[offset + 1..offset + len - 1];
+        let lowercase = inner.to_ascii_lowercase();
+        if inner.starts_with('&') && inner[1..].starts_with(|it: char| it.is_ascii_alphanumeric()) {
+            self.token(CREOLE_TEXT, offset - text);
+            self.token(CREOLE_ICON, len);
+            return Some(len);
+        }
+        if lowercase.starts_with("img:") || lowercase.starts_with("img ") {
+            self.token(CREOLE_TEXT, offset - text);
+            self.token(CREOLE_IMAGE, len);
+            return Some(len);
+        }
+        let end_of_name = inner
+            .find(|it: char| !it.is_ascii_alphabetic())
+            .unwrap_or(inner.len());
+        let name = &inner[..end_of_name];
+        if !TAGS.contains(&name.to_ascii_lowercase().as_str()) {
+            return None;
+        }
+        let closing = format!("</{}>", name);
+        self.token(CREOLE_TEXT, offset - text);
+        self.events.push(Event::Start(CREOLE_TAG));
+        self.token(CREOLE_TAG_START, len);
+        let inner = offset + len;
+        let line_end = self.line_end(inner, end);
+        let close = self.find(&closing, inner, line_end);
+        self.inline(inner, close.unwrap_or(line_end));
+        if close.is_some() {
+            self.token(CREOLE_TAG_END, closing.len());
+        }
+        self.events.push(Event::Finish);
+        Some(close.map(|it| it + closing.len()).unwrap_or(line_end) - offset)
+    }
+}
+
+/// How to build the markup of `text`: the nodes and tokens it's made of, and
+/// where the `atoms` go among them. The atoms are the ranges of `text` to be
+/// left out of the markup, in order.
+pub(crate) fn markup(text: &str, atoms: &[Range<usize>]) -> Vec<Event> {
+    let mut markup = Markup {
+        text,
+        atoms,
+        events: Vec::new(),
+        depth: 0,
+    };
+    markup.inline(0, text.len());
+    markup.events
+}
+
+mod tests {
+    #[test]
+    fn test_markup() {
+        use super::Event::{self, *};
+        // write the events out as text, with the atoms as `$`
+        let show = |text: &str, atoms: &[std::ops::Range<usize>]| {
+            let mut offset = 0;
+            let mut remaining = atoms.iter();
+            super::markup(text, atoms)
+                .into_iter()
+                .map(|event: Event| match event {
+                    Start(kind) => format!("{:?}(", kind),
+                    Finish => ")".to_string(),
+                    Token(kind, len) => {
+                        offset += len;
+                        format!("{:?}'{}'", kind, &text[offset - len..offset])
+                    }
+                    Atom => {
+                        offset = remaining.next().unwrap().end;
+                        "$".to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(
+            show("a **bold //and italic//** b", &[]),
+            "CREOLE_TEXT'a ' CREOLE_BOLD( CREOLE_DELIMITER'**' CREOLE_TEXT'bold ' CREOLE_ITALIC( CREOLE_DELIMITER'//' CREOLE_TEXT'and italic' CREOLE_DELIMITER'//' ) CREOLE_DELIMITER'**' ) CREOLE_TEXT' b'"
+        );
+        assert_eq!(
+            show("<color:red>red</color> <&heart> <img:logo.png>\\nList<T>", &[]),
+            "CREOLE_TAG( CREOLE_TAG_START'<color:red>' CREOLE_TEXT'red' CREOLE_TAG_END'</color>' ) CREOLE_TEXT' ' CREOLE_ICON'<&heart>' CREOLE_TEXT' ' CREOLE_IMAGE'<img:logo.png>' CREOLE_ESCAPE'\\n' CREOLE_TEXT'List<T>'"
+        );
+        assert_eq!(
+            show("see http://a.b/c ~**not bold** <b>open", &[]),
+            "CREOLE_TEXT'see http://a.b/c ' CREOLE_ESCAPE'~*' CREOLE_TEXT'*not bold** ' CREOLE_TAG( CREOLE_TAG_START'<b>' CREOLE_TEXT'open' )"
+        );
+        // the markup goes around the atoms, and stops at the end of the line
+        assert_eq!(
+            show("**$x** **a\nb**", &[2..4, 10..11]),
+            "CREOLE_BOLD( CREOLE_DELIMITER'**' $ CREOLE_DELIMITER'**' ) CREOLE_TEXT' **a' $ CREOLE_TEXT'b**'"
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\nclass \"Order \\\"Service\\\"\" as Größe\nGröße -> _user : **$name(1)** /' c '/ \\n<&heart>\nstart\n:first //line// too\nsecond <b>line;\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+        let labels = root
+            .labels()
+            .map(|it| it.plain_text())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec!["$name(1) /' c '/ \n", "first line too\nsecond line"]
+        );
+        let label = root.labels().next().unwrap();
+        let bold = label
+            .syntax()
+            .children()
+            .find(|it| it.kind() == crate::syntax::SyntaxKind::CREOLE_BOLD)
+            .unwrap();
+        assert!(bold
+            .children()
+            .any(|it| it.kind() == crate::syntax::SyntaxKind::CALL));
+        assert_eq!(label.icons().count(), 1);
+    }
+}
@@ -0,0 +1,91 @@
+use log::*;
+
+use crate::grammar::{Diagram, Parser, Statement};
+use crate::syntax::SyntaxKind::*;
+
+/// A statement in a `@startjson` or `@startyaml` diagram: either a
+/// `#highlight` line or the data itself
+pub(crate) fn statement(parser: &mut Parser) -> Statement {
+    if at_highlight(parser) {
+        return highlight(parser);
+    }
+    trace!("Starting data node");
+    parser.start_node(if parser.diagram() == Diagram::Yaml {
+        YAML_DATA
+    } else {
+        JSON_DATA
+    });
+    // the data runs up to the '@endjson', its syntax is checked separately
+    while !parser.at(DIAGRAM_END) && !parser.at(EOF) {
+        parser.consume_token();
+    }
+    parser.finish_node();
+    Statement::Ok
+}
+
+fn at_highlight(parser: &Parser) -> bool {
+    matches!(
+        parser.rest_of_line().as_slice(),
+        [(HASH, _), (IDENTIFIER, "highlight"), ..]
+    )
+}
+
+/// `#highlight "address" / "city"`
+fn highlight(parser: &mut Parser) -> Statement {
+    parser.start_node(DATA_HIGHLIGHT);
+    parser.consume(HASH);
+    parser.consume(IDENTIFIER);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOL | EOF => break,
+            STRING => parser.consume(STRING),
+            SLASH => parser.consume(SLASH),
+            _ => {
+                parser.error("expected a \"key\"");
+                break;
+            }
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_data() {
+        let _ = env_logger::try_init();
+        let text = "@startjson\n#highlight \"address\" / \"city\"\n{\n  \"address\": { \"city\": \"Paris\" }\n}\n@endjson\n@startyaml\n'quoted': yes\nlist:\n  - a\n@endyaml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let data = root
+            .data_nodes()
+            .map(|it| (it.is_yaml(), it.syntax().text().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            data,
+            vec![
+                (
+                    false,
+                    "{\n  \"address\": { \"city\": \"Paris\" }\n}\n".into()
+                ),
+                (true, "'quoted': yes\nlist:\n  - a\n".into()),
+            ]
+        );
+        let highlight = root.data_highlights().next().unwrap();
+        let path = highlight
+            .path()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(path, vec!["address", "city"]);
+        assert!(highlight.data().is_some());
+    }
+}
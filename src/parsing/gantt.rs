@@ -0,0 +1,247 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::syntax::SyntaxKind::*;
+
+pub(crate) const WEEKDAYS: &[&str] = &[
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// A statement in a `@startgantt` diagram
+pub(crate) fn statement(parser: &mut Parser) -> Statement {
+    if parser.at(L_BRACK) || parser.at(PSEUDO_STATE) {
+        if at_dependency(parser) {
+            return dependency(parser);
+        }
+        return task_statement(parser);
+    }
+    if parser.at(THEN_KW) {
+        return task_statement(parser);
+    }
+    if parser.at_word("project") {
+        return project_start(parser);
+    }
+    if parser.at(DATE) || WEEKDAYS.iter().any(|day| parser.at_word(day)) {
+        return closed_days(parser);
+    }
+    // settings such as 'printscale weekly' and separators such as '-- Phase --'
+    parser.recover("unexpected statement");
+    parser.eat(EOL);
+    Statement::Ok
+}
+
+/// `[Task name]`, with a single letter name such as `[H]` lexed as a
+/// PSEUDO_STATE
+fn task_name(parser: &mut Parser) {
+    parser.skip_ws();
+    match parser.current() {
+        L_BRACK => crate::parsing::component::element_name(parser),
+        PSEUDO_STATE => {
+            parser.start_node(IDENTIFIER);
+            parser.consume(PSEUDO_STATE);
+            parser.finish_node();
+        }
+        _ => parser.error("expected a task name"),
+    }
+}
+
+fn at_task_name(parser: &Parser) -> bool {
+    parser.at(L_BRACK) || parser.at(PSEUDO_STATE)
+}
+
+/// Whether the line is `[A] -> [B]`
+fn at_dependency(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    let first = match line.first() {
+        Some((PSEUDO_STATE, _)) => 1,
+        _ => match line.iter().position(|(kind, _)| *kind == R_BRACK) {
+            Some(close) => close + 1,
+            None => return false,
+        },
+    };
+    matches!(line.get(first), Some((ARROW, _)))
+}
+
+/// `[A] -> [B] -> [C]`
+fn dependency(parser: &mut Parser) -> Statement {
+    parser.start_node(DEPENDENCY);
+    task_name(parser);
+    loop {
+        parser.skip_ws();
+        if !parser.eat(ARROW) {
+            break;
+        }
+        task_name(parser);
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `[Task] as [T] on {Alice:50%} lasts 10 days and starts at [Other]'s end`,
+/// or the same following `then`, which starts where the previous task ended
+fn task_statement(parser: &mut Parser) -> Statement {
+    trace!("Starting task statement node");
+    parser.start_node(TASK_STATEMENT);
+    parser.eat(THEN_KW);
+    task_name(parser);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOL | EOF => break,
+            AS_KW => {
+                parser.consume(AS_KW);
+                task_name(parser);
+            }
+            L_BRACE => resource(parser),
+            IDENTIFIER if parser.at_word("lasts") || parser.at_word("requires") => duration(parser),
+            IDENTIFIER
+                if parser.at_word("starts")
+                    || parser.at_word("ends")
+                    || parser.at_word("happens") =>
+            {
+                constraint(parser)
+            }
+            // 'is colored in Red', 'is 40% completed' and the like
+            IS_KW => {
+                while !parser.at_eol() && !parser.at_word("and") {
+                    parser.consume_token();
+                }
+            }
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eat(EOL);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `lasts 10 days`, `lasts 2 weeks` or `requires 5 days`
+fn duration(parser: &mut Parser) {
+    parser.start_node(DURATION);
+    parser.consume_token();
+    parser.skip_ws();
+    if !parser.eat(NUMBER) {
+        parser.error("expected a number of days");
+    }
+    parser.skip_ws();
+    if parser.at_word("day")
+        || parser.at_word("days")
+        || parser.at_word("week")
+        || parser.at_word("weeks")
+    {
+        parser.consume_token();
+    } else {
+        parser.error("expected 'days' or 'weeks'");
+    }
+    parser.finish_node();
+}
+
+/// `starts 2020-07-01`, `starts at [A]'s end`, `ends 2 days after [A]'s start`
+/// or `happens at [A]'s end`
+fn constraint(parser: &mut Parser) {
+    parser.start_node(CONSTRAINT);
+    parser.consume_token();
+    while !parser.at_eol() && !parser.at_word("and") {
+        if at_task_name(parser) {
+            task_name(parser);
+        } else {
+            parser.consume_token();
+        }
+    }
+    parser.finish_node();
+}
+
+/// `{Alice}` or `{Bob:50%}`, usually following `on`
+fn resource(parser: &mut Parser) {
+    parser.start_node(RESOURCE);
+    parser.consume(L_BRACE);
+    parser.skip_ws();
+    parser.start_node(IDENTIFIER);
+    while !parser.at_eol() && !parser.at(COLON) && !parser.at(R_BRACE) {
+        parser.consume_token();
+    }
+    parser.finish_node();
+    while !parser.at_eol() && !parser.at(R_BRACE) {
+        parser.consume_token();
+    }
+    if !parser.eat(R_BRACE) {
+        parser.error("expected '}'");
+    }
+    parser.finish_node();
+}
+
+/// `Project starts 2020-07-01`
+fn project_start(parser: &mut Parser) -> Statement {
+    parser.start_node(PROJECT_START);
+    parser.skip_to_eol();
+    parser.eat(EOL);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `saturday are closed`, `2020-07-13 is closed` or
+/// `2020-07-13 to 2020-07-15 is open`
+fn closed_days(parser: &mut Parser) -> Statement {
+    parser.start_node(CLOSED_DAYS);
+    parser.skip_to_eol();
+    parser.eat(EOL);
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_gantt() {
+        let _ = env_logger::try_init();
+        let text = "@startgantt\nProject starts 2020-07-01\nsaturday are closed\n2020-07-14 is closed\n[Prototype design] as [D] on {Alice:50%} {Bob} lasts 2 weeks\n[Test prototype] lasts 10 days and starts at [D]'s end\nthen [Ship] lasts 1 day\n[Review] starts 2020-08-01 and ends 2020-08-05\n[Release] happens at [Ship]'s end\n[D] -> [Review]\n[Ship] is colored in Lavender/LightBlue\n@endgantt\n[Not a task]\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let tasks = root
+            .tasks()
+            .map(|it| (it.name().unwrap().value(), it.duration_days(5)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tasks,
+            vec![
+                ("Prototype design".into(), Some(10)),
+                ("Test prototype".into(), Some(10)),
+                ("Ship".into(), Some(1)),
+                ("Review".into(), None),
+                ("Release".into(), None),
+                ("Ship".into(), None),
+            ]
+        );
+        let design = root.tasks().next().unwrap();
+        assert_eq!(design.alias().unwrap().value(), "D");
+        let resources = design
+            .resources()
+            .map(|it| (it.name().unwrap().value(), it.percent()))
+            .collect::<Vec<_>>();
+        assert_eq!(resources, vec![("Alice".into(), 50), ("Bob".into(), 100)]);
+
+        let test = root.tasks().nth(1).unwrap();
+        let constraint = test.constraints().next().unwrap();
+        assert_eq!(constraint.task().unwrap().value(), "D");
+        assert!(root.tasks().nth(2).unwrap().follows_previous());
+        assert!(root.tasks().nth(4).unwrap().is_milestone());
+        assert_eq!(
+            root.project_start().and_then(|it| it.date()),
+            Some("2020-07-01".into())
+        );
+        assert_eq!(root.component_decls().count(), 1);
+    }
+}
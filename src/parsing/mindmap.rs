@@ -0,0 +1,173 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::syntax::SyntaxKind::*;
+
+/// A statement in a `@startmindmap` or `@startwbs` diagram
+pub(crate) fn statement(parser: &mut Parser) -> Statement {
+    if depth(parser).is_some() {
+        node(parser);
+        return Statement::Ok;
+    }
+    if at_side(parser) {
+        side(parser);
+        return Statement::Ok;
+    }
+    // 'title', 'caption' and the like
+    parser.recover("unexpected statement");
+    parser.eat(EOL);
+    Statement::Ok
+}
+
+/// The depth of the node starting on the current line, going by how many
+/// `*`, `+` or `-` it starts with. A run of dashes is lexed as an ARROW.
+fn depth(parser: &Parser) -> Option<usize> {
+    let mut depth = 0;
+    for (kind, text) in parser.rest_of_line() {
+        match kind {
+            STAR | PLUS | MINUS => depth += 1,
+            ARROW if text.chars().all(|it| it == '-') => depth += text.len(),
+            _ => break,
+        }
+    }
+    Some(depth).filter(|it| *it > 0)
+}
+
+fn at_marker(parser: &Parser) -> bool {
+    match parser.current() {
+        STAR | PLUS | MINUS => true,
+        ARROW => parser.current_text().chars().all(|it| it == '-'),
+        _ => false,
+    }
+}
+
+/// Whether the line is `left side` or `right side`
+fn at_side(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    match line.as_slice() {
+        [(IDENTIFIER, side), (IDENTIFIER, "side")] => {
+            side.eq_ignore_ascii_case("left") || side.eq_ignore_ascii_case("right")
+        }
+        _ => false,
+    }
+}
+
+fn side(parser: &mut Parser) {
+    parser.start_node(SIDE);
+    parser.skip_to_eol();
+    parser.eat(EOL);
+    parser.finish_node();
+}
+
+/// `** Topic`, `**_ Boxless`, `*[#Orange] Colored`, `**< Left` in a WBS, or
+/// `**:Multi\nline;`, followed by the nodes that are deeper than it
+fn node(parser: &mut Parser) {
+    let level = depth(parser).unwrap_or(0);
+    trace!("Starting mindmap node at depth {}", level);
+    parser.start_node(MINDMAP_NODE);
+    while at_marker(parser) {
+        parser.consume_token();
+    }
+    // the color, as in '*[#Orange]'
+    if parser.at(L_BRACK) {
+        while !parser.at_eol() && !parser.eat(R_BRACK) {
+            parser.consume_token();
+        }
+    }
+    // boxless, as in '**_', and the side of a WBS node, as in '**<'
+    while parser.current_text() == "_" || parser.at(L_ANGLE) || parser.at(R_ANGLE) {
+        parser.consume_token();
+    }
+    parser.skip_ws();
+    if parser.at(COLON) {
+        multiline_label(parser);
+    } else {
+        parser.label();
+    }
+    parser.eat(EOL);
+    loop {
+        parser.skip_ws();
+        // the side applies to the children of the root
+        if level == 1 && at_side(parser) {
+            side(parser);
+        } else if parser.at(EOL) {
+            parser.consume(EOL);
+        } else if depth(parser).filter(|it| *it > level).is_some() {
+            node(parser);
+        } else {
+            break;
+        }
+    }
+    parser.finish_node();
+}
+
+/// `:first line\nsecond line;`, which ends at the `;` closing a line
+fn multiline_label(parser: &mut Parser) {
+    parser.start_node(LABEL);
+    parser.consume(COLON);
+    parser.markup(|parser| parser.at(SEMI) && matches!(parser.nth(1), EOL | EOF));
+    if !parser.eat(SEMI) {
+        parser.error("expected ';'");
+    }
+    parser.finish_node();
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_mindmap() {
+        let _ = env_logger::try_init();
+        let text = "@startmindmap\n* Root\n** First\n***_ boxless\n*** deeper\n**:multi\nline;\nleft side\n** Left\n-- Also left\n+++ right of it\n@endmindmap\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let nodes = root
+            .mindmap_nodes()
+            .map(|it| (it.text(), it.depth(), it.is_left(), it.is_boxless()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            nodes,
+            vec![
+                ("Root".into(), 1, false, false),
+                ("First".into(), 2, false, false),
+                ("boxless".into(), 3, false, true),
+                ("deeper".into(), 3, false, false),
+                ("multi\nline".into(), 2, false, false),
+                ("Left".into(), 2, true, false),
+                ("Also left".into(), 2, true, false),
+                ("right of it".into(), 3, false, false),
+            ]
+        );
+        let top = root.mindmap_nodes().next().unwrap();
+        let children = top.children().map(|it| it.text()).collect::<Vec<_>>();
+        assert_eq!(children, vec!["First", "multi\nline", "Left", "Also left"]);
+    }
+
+    #[test]
+    fn test_wbs() {
+        let _ = env_logger::try_init();
+        let text =
+            "@startwbs\n* Business Process\n**< Launch\n*** Complete\n**> Stakeholders\n@endwbs\n";
+        let parsed = crate::parse(text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+        let nodes = root
+            .mindmap_nodes()
+            .map(|it| (it.text(), it.depth(), it.is_left()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            nodes,
+            vec![
+                ("Business Process".into(), 1, false),
+                ("Launch".into(), 2, true),
+                ("Complete".into(), 3, true),
+                ("Stakeholders".into(), 2, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::parsing::class;
+use crate::parsing::component;
+use crate::syntax::SyntaxKind::*;
+
+/// `object Name as Alias <<stereo>> { field = value }` or
+/// `map Name { key => value }`
+pub(crate) fn object_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting object decl node");
+    parser.start_node(if parser.at(MAP_KW) {
+        MAP_DECL
+    } else {
+        OBJECT_DECL
+    });
+    parser.consume_token(); // 'object' or 'map'
+    parser.skip_ws();
+    if component::at_element_name(parser) {
+        component::element_name(parser);
+    } else {
+        parser.error("expected a name");
+    }
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            AS_KW => {
+                parser.consume(AS_KW);
+                parser.skip_ws();
+                if component::at_element_name(parser) {
+                    component::element_name(parser);
+                }
+            }
+            STEREO_OPEN => class::stereotype(parser),
+            L_BRACE => {
+                object_body(parser);
+                break;
+            }
+            EOL | EOF => break,
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+fn object_body(parser: &mut Parser) {
+    parser.start_node(OBJECT_BODY);
+    parser.consume(L_BRACE);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOF | DIAGRAM_END => {
+                parser.error("expected '}'");
+                break;
+            }
+            R_BRACE => {
+                parser.consume(R_BRACE);
+                break;
+            }
+            EOL => parser.consume(EOL),
+            _ => entry(parser),
+        }
+    }
+    parser.finish_node();
+}
+
+/// Whether the parser is at whatever separates an entry's key from its value
+fn at_separator(parser: &Parser) -> bool {
+    matches!(parser.current(), EQ | FAT_ARROW | ARROW)
+}
+
+/// `field = value`, `key => value` or a link to another object, `key *-> Other`
+fn entry(parser: &mut Parser) {
+    parser.start_node(ENTRY);
+    // keys may contain spaces, e.g. 'United Kingdom => London'
+    parser.start_node(IDENTIFIER);
+    while !parser.at_eol() && !at_separator(parser) {
+        if parser.at(WHITESPACE) && matches!(parser.nth(0), EQ | FAT_ARROW | ARROW) {
+            break;
+        }
+        parser.consume_token();
+    }
+    parser.finish_node();
+    parser.skip_ws();
+    match parser.current() {
+        EQ | FAT_ARROW => {
+            parser.consume_token();
+            parser.label();
+        }
+        ARROW => {
+            parser.consume(ARROW);
+            parser.skip_ws();
+            if component::at_element_name(parser) {
+                component::element_name(parser);
+            } else {
+                parser.error("expected a name");
+            }
+        }
+        _ => {}
+    }
+    parser.eol();
+    parser.finish_node();
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_object_decl() {
+        let _ = env_logger::try_init();
+        let text = "object user {\n  name = \"Dummy\"\n  id = 123\n}\nobject \"Group Admin\" as admin\nmap CapitalCity {\n  UK *-> London\n  United States => Washington\n  Germany => Berlin\n}\nobject London\nuser --> admin\nCapitalCity::UK --> London\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let objects = root
+            .object_decls()
+            .map(|it| (it.name().unwrap().value(), it.is_map()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            objects,
+            vec![
+                ("user".into(), false),
+                ("Group Admin".into(), false),
+                ("CapitalCity".into(), true),
+                ("London".into(), false),
+            ]
+        );
+        let entries = |index| {
+            root.object_decls()
+                .nth(index)
+                .unwrap()
+                .entries()
+                .map(|it| {
+                    (
+                        it.key().unwrap().value(),
+                        it.value().map(|v| v.text().to_string()),
+                        it.target().map(|t| t.value()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            entries(0),
+            vec![
+                ("name".into(), Some("\"Dummy\"".into()), None),
+                ("id".into(), Some("123".into()), None),
+            ]
+        );
+        assert_eq!(
+            entries(2),
+            vec![
+                ("UK".into(), None, Some("London".into())),
+                ("United States".into(), Some("Washington".into()), None),
+                ("Germany".into(), Some("Berlin".into()), None),
+            ]
+        );
+        let link = root.relations().nth(1).unwrap();
+        assert_eq!(link.left().unwrap().value(), "CapitalCity::UK");
+    }
+}
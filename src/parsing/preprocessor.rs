@@ -0,0 +1,667 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::syntax::SyntaxKind::*;
+
+/// The directives that pull in another file
+pub(crate) const INCLUDES: &[&str] = &[
+    "!include",
+    "!include_many",
+    "!include_once",
+    "!includesub",
+    "!includeurl",
+    "!import",
+];
+
+/// The directives that continue or close an `!if`
+const BRANCH_ENDS: &[&str] = &["!elseif", "!else", "!endif"];
+
+/// The directives that close a `!procedure` or `!function`
+const PROCEDURE_ENDS: &[&str] = &["!endprocedure", "!endfunction"];
+
+/// A line starting with a preprocessor directive, such as `!include`
+pub(crate) fn directive(parser: &mut Parser) -> Statement {
+    match parser.current_text() {
+        it if INCLUDES.contains(&it) => include(parser),
+        "!" | "!local" | "!global" | "!assign" if parser.nth(1) == VARIABLE => variable_def(parser),
+        "!define" => define(parser),
+        "!definelong" => define_long(parser),
+        "!undef" => undef(parser),
+        "!if" | "!ifdef" | "!ifndef" => conditional(parser),
+        "!procedure" | "!function" => procedure(parser),
+        "!unquoted" if at_unquoted_procedure(parser) => procedure(parser),
+        "!return" => return_statement(parser),
+        "!theme" => theme(parser),
+        it if BRANCH_ENDS.contains(&it) || PROCEDURE_ENDS.contains(&it) => {
+            // an '!else' or '!endif' without an '!if', or an '!endprocedure'
+            // without a '!procedure'
+            parser.recover("unexpected directive");
+            parser.eat(EOL);
+        }
+        _ => {
+            trace!("Starting preprocessor node");
+            parser.start_node(PREPROCESSOR);
+            parser.skip_to_eol();
+            parser.eat(EOL);
+            parser.finish_node();
+        }
+    }
+    Statement::Ok
+}
+
+/// The rest of the line, less any trailing whitespace, as an EXPRESSION
+fn expression(parser: &mut Parser) {
+    parser.skip_ws();
+    if parser.at_eol() {
+        parser.error("expected an expression");
+        return;
+    }
+    parser.start_node(EXPRESSION);
+    while !parser.at_eol() && !at_trailing_whitespace(parser) {
+        if at_call(parser) {
+            call(parser);
+        } else {
+            parser.consume_token();
+        }
+    }
+    parser.finish_node();
+}
+
+/// `!$name = "value"`, `!$count ?= 1` or `!local $x = $y + 1`
+fn variable_def(parser: &mut Parser) {
+    trace!("Starting variable def node");
+    parser.start_node(VARIABLE_DEF);
+    parser.consume(DIRECTIVE);
+    parser.skip_ws();
+    parser.consume(VARIABLE);
+    parser.skip_ws();
+    // '?=' only assigns a variable that doesn't have a value yet
+    if parser.current_text() == "?" {
+        parser.consume_token();
+    }
+    if parser.eat(EQ) {
+        expression(parser);
+    } else {
+        parser.error("expected '='");
+    }
+    parser.eol();
+    parser.finish_node();
+}
+
+/// The name a `!define` or `!undef` is about, which may be a keyword
+fn macro_name(parser: &mut Parser) -> bool {
+    parser.skip_ws();
+    if parser.at(IDENTIFIER) || parser.current().is_keyword() {
+        parser.consume_as(IDENTIFIER);
+        true
+    } else {
+        parser.error("expected a name");
+        false
+    }
+}
+
+/// The `(a, b)` of a macro, right after its name
+fn macro_params(parser: &mut Parser) {
+    if parser.at(L_PAREN) {
+        while !parser.at_eol() && !parser.eat(R_PAREN) {
+            parser.consume_token();
+        }
+    }
+}
+
+/// `!define NAME`, `!define NAME value` or `!define NAME(a, b) a -> b`
+fn define(parser: &mut Parser) {
+    trace!("Starting define node");
+    parser.start_node(DEFINE);
+    parser.consume(DIRECTIVE);
+    if macro_name(parser) {
+        macro_params(parser);
+        parser.label();
+    }
+    parser.eol();
+    parser.finish_node();
+}
+
+/// `!definelong NAME(a)` up to `!enddefinelong`, whose lines are kept as they
+/// are until the macro is used
+fn define_long(parser: &mut Parser) {
+    trace!("Starting definelong node");
+    parser.start_node(DEFINE);
+    parser.consume(DIRECTIVE);
+    if macro_name(parser) {
+        macro_params(parser);
+    }
+    parser.eol();
+    loop {
+        if parser.at(EOF) {
+            parser.error("expected '!enddefinelong'");
+            break;
+        }
+        if parser.at(DIRECTIVE) && parser.current_text() == "!enddefinelong" {
+            parser.consume(DIRECTIVE);
+            parser.eol();
+            break;
+        }
+        parser.consume_token();
+    }
+    parser.finish_node();
+}
+
+/// `!undef NAME`
+fn undef(parser: &mut Parser) {
+    parser.start_node(UNDEF);
+    parser.consume(DIRECTIVE);
+    macro_name(parser);
+    parser.eol();
+    parser.finish_node();
+}
+
+fn at_branch_end(parser: &Parser) -> bool {
+    parser.at(DIRECTIVE) && BRANCH_ENDS.contains(&parser.current_text())
+}
+
+/// `!if $a == 1`, `!ifdef NAME` or `!ifndef NAME`, each of its `!elseif` and
+/// `!else` branches holding the statements up to the next, and `!endif`
+fn conditional(parser: &mut Parser) {
+    trace!("Starting preprocessor if node");
+    parser.start_node(PP_IF);
+    loop {
+        parser.start_node(PP_BRANCH);
+        let is_else = parser.current_text() == "!else";
+        parser.consume(DIRECTIVE);
+        if !is_else {
+            expression(parser);
+        }
+        parser.eol();
+        loop {
+            parser.skip_ws();
+            if parser.at(EOF) || at_branch_end(parser) {
+                break;
+            }
+            parser.statement();
+        }
+        parser.finish_node();
+        match parser.current_text() {
+            "!endif" => {
+                parser.consume(DIRECTIVE);
+                parser.eol();
+                break;
+            }
+            "!elseif" | "!else" => {}
+            _ => {
+                parser.error("expected '!endif'");
+                break;
+            }
+        }
+    }
+    parser.finish_node();
+}
+
+/// Whether the line is `!unquoted procedure` or `!unquoted function`
+fn at_unquoted_procedure(parser: &Parser) -> bool {
+    matches!(
+        parser.rest_of_line().as_slice(),
+        [(DIRECTIVE, _), (IDENTIFIER, "procedure"), ..]
+            | [(DIRECTIVE, _), (IDENTIFIER, "function"), ..]
+    )
+}
+
+/// `!procedure $name($a, $b="x")` and the lines up to `!endprocedure`, or
+/// `!function $name($a)` up to `!endfunction`, which may also be written on a
+/// single line as `!function $double($a) !return $a * 2`
+fn procedure(parser: &mut Parser) {
+    trace!("Starting procedure node");
+    parser.start_node(PROCEDURE);
+    if parser.current_text() == "!unquoted" {
+        parser.consume(DIRECTIVE);
+        parser.skip_ws();
+        parser.consume(IDENTIFIER);
+    } else {
+        parser.consume(DIRECTIVE);
+    }
+    parser.skip_ws();
+    match parser.current() {
+        VARIABLE => parser.consume(VARIABLE),
+        // C4 and friends name their procedures without a '$'
+        IDENTIFIER => parser.consume(IDENTIFIER),
+        kind if kind.is_keyword() => parser.consume_as(IDENTIFIER),
+        _ => parser.error("expected a name"),
+    }
+    if parser.at(L_PAREN) {
+        params(parser);
+    } else {
+        parser.error("expected '('");
+    }
+    parser.skip_ws();
+    if parser.at(DIRECTIVE) && parser.current_text() == "!return" {
+        return_statement(parser);
+        parser.finish_node();
+        return;
+    }
+    parser.eol();
+    loop {
+        parser.skip_ws();
+        if parser.at(EOF) {
+            parser.error("expected '!endprocedure'");
+            break;
+        }
+        if parser.at(DIRECTIVE) && PROCEDURE_ENDS.contains(&parser.current_text()) {
+            parser.consume(DIRECTIVE);
+            parser.eol();
+            break;
+        }
+        parser.statement();
+    }
+    parser.finish_node();
+}
+
+/// Whether the parser is at a comma or parenthesis that isn't nested
+fn at_separator(parser: &Parser, depth: usize) -> bool {
+    depth == 0 && matches!(parser.current(), COMMA | R_PAREN)
+}
+
+/// The `($a, $b="x")` of a procedure
+fn params(parser: &mut Parser) {
+    parser.start_node(PARAM_LIST);
+    parser.consume(L_PAREN);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            R_PAREN => {
+                parser.consume(R_PAREN);
+                break;
+            }
+            COMMA => parser.consume(COMMA),
+            VARIABLE => {
+                parser.start_node(PARAM);
+                parser.consume(VARIABLE);
+                parser.skip_ws();
+                if parser.eat(EQ) {
+                    // the default value, up to the next parameter
+                    parser.skip_ws();
+                    let mut depth = 0;
+                    while !parser.at_eol() && !at_separator(parser, depth) {
+                        match parser.current() {
+                            L_PAREN => depth += 1,
+                            R_PAREN => depth -= 1,
+                            _ => {}
+                        }
+                        parser.consume_token();
+                    }
+                }
+                parser.finish_node();
+            }
+            _ => {
+                parser.error("expected ')'");
+                break;
+            }
+        }
+    }
+    parser.finish_node();
+}
+
+/// `!return $a * 2`
+fn return_statement(parser: &mut Parser) {
+    parser.start_node(RETURN);
+    parser.consume(DIRECTIVE);
+    expression(parser);
+    parser.eol();
+    parser.finish_node();
+}
+
+/// Whether the parser is at a call such as `$greet("Bob")` or
+/// `Person(user, "User")`, with nothing between the name and the `(`
+pub(crate) fn at_call(parser: &Parser) -> bool {
+    parser.at_seq(&[VARIABLE, L_PAREN]) || parser.at_seq(&[IDENTIFIER, L_PAREN])
+}
+
+/// A call on a line of its own, which may be followed by the `{ }` of a
+/// boundary, as in `System_Boundary(b, "Shop") {`
+pub(crate) fn call_statement(parser: &mut Parser) -> Statement {
+    trace!("Starting call node");
+    parser.start_node(CALL);
+    call_contents(parser);
+    parser.skip_ws();
+    if parser.at(L_BRACE) {
+        parser.block();
+    }
+    parser.finish_node();
+    parser.eol();
+    Statement::Ok
+}
+
+/// `name(a, "b", $c=1)`, where an argument may itself contain calls
+pub(crate) fn call(parser: &mut Parser) {
+    trace!("Starting call node");
+    parser.start_node(CALL);
+    call_contents(parser);
+    parser.finish_node();
+}
+
+/// The name of what's called and its arguments
+fn call_contents(parser: &mut Parser) {
+    parser.consume_token();
+    parser.start_node(ARG_LIST);
+    parser.consume(L_PAREN);
+    if parser.too_deep() {
+        parser.skip_nested(L_PAREN, R_PAREN, true);
+        parser.eat(R_PAREN);
+        parser.finish_node();
+        return;
+    }
+    parser.nest();
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            R_PAREN => {
+                parser.consume(R_PAREN);
+                break;
+            }
+            COMMA => parser.consume(COMMA),
+            _ if parser.at_eol() => {
+                parser.error("expected ')'");
+                break;
+            }
+            _ => argument(parser),
+        }
+    }
+    parser.unnest();
+    parser.finish_node();
+}
+
+fn argument(parser: &mut Parser) {
+    parser.start_node(ARGUMENT);
+    let mut depth = 0;
+    while !parser.at_eol() && !at_separator(parser, depth) {
+        if parser.at(WHITESPACE) && at_separator_after_whitespace(parser, depth) {
+            break;
+        }
+        match parser.current() {
+            _ if at_call(parser) => {
+                call(parser);
+                continue;
+            }
+            L_PAREN => depth += 1,
+            R_PAREN => depth -= 1,
+            _ => {}
+        }
+        parser.consume_token();
+    }
+    parser.finish_node();
+}
+
+/// Whether only whitespace is left before the end of the argument
+fn at_separator_after_whitespace(parser: &Parser, depth: usize) -> bool {
+    depth == 0 && matches!(parser.nth(0), COMMA | R_PAREN | EOL | EOF)
+}
+
+/// `!include common.puml`, `!includesub lib.puml!PART` or `!include <C4/C4>`
+fn include(parser: &mut Parser) {
+    trace!("Starting include node");
+    parser.start_node(INCLUDE);
+    parser.consume(DIRECTIVE);
+    parser.skip_ws();
+    if parser.at_eol() {
+        parser.error("expected a path");
+    } else {
+        parser.start_node(INCLUDE_PATH);
+        // the path runs to the end of the line, less any trailing whitespace
+        while !parser.at_eol() && !at_trailing_whitespace(parser) {
+            parser.consume_token();
+        }
+        parser.finish_node();
+    }
+    parser.eol();
+    parser.finish_node();
+}
+
+/// `!theme cerulean`, or `!theme mine from ./themes` for one that isn't
+/// built in
+fn theme(parser: &mut Parser) {
+    trace!("Starting theme node");
+    parser.start_node(THEME);
+    parser.consume(DIRECTIVE);
+    parser.skip_ws();
+    if parser.at_eol() {
+        parser.error("expected a theme name");
+    } else {
+        // names such as 'cerulean-outline' are lexed as several tokens
+        parser.start_node(THEME_NAME);
+        while !parser.at_eol() && !parser.at(WHITESPACE) {
+            parser.consume_token();
+        }
+        parser.finish_node();
+        parser.skip_ws();
+        if parser.at_word("from") {
+            parser.consume_token();
+            parser.skip_ws();
+            if parser.at_eol() {
+                parser.error("expected a path");
+            } else {
+                parser.start_node(THEME_SOURCE);
+                while !parser.at_eol() && !at_trailing_whitespace(parser) {
+                    parser.consume_token();
+                }
+                parser.finish_node();
+            }
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+}
+
+fn at_trailing_whitespace(parser: &Parser) -> bool {
+    parser.at(WHITESPACE) && matches!(parser.nth(0), EOL | EOF)
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_conditionals() {
+        let _ = env_logger::try_init();
+        let text = "!$show = 1\n!$name ?= \"x\"\n!define DEBUG\n!define SHOUT(x) x!\n!undef DEBUG\n!definelong LOGIN(a)\na -> b\n!enddefinelong\n!if $show == 1\n  class A\n  !ifdef DEBUG\n    class B\n  !endif\n!elseif %strlen($name) > 2\n  class C\n!else\n  class D\n!endif\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let variables = root
+            .variable_defs()
+            .map(|it| {
+                (
+                    it.name(),
+                    it.is_default(),
+                    it.value().map(|v| v.syntax().text().to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variables,
+            vec![
+                (Some("$show".into()), false, Some("1".into())),
+                (Some("$name".into()), true, Some("\"x\"".into())),
+            ]
+        );
+        let defines = root
+            .defines()
+            .map(|it| (it.name(), it.value()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            defines,
+            vec![
+                (Some("DEBUG".into()), "".into()),
+                (Some("SHOUT".into()), "x!".into()),
+                (Some("LOGIN".into()), "a -> b\n".into()),
+            ]
+        );
+        let conditional = root.conditionals().next().unwrap();
+        let branches = conditional
+            .branches()
+            .map(|it| {
+                (
+                    it.directive(),
+                    it.condition().map(|c| c.syntax().text().to_string()),
+                    it.statements().count(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            branches,
+            vec![
+                ("!if".into(), Some("$show == 1".into()), 2),
+                ("!elseif".into(), Some("%strlen($name) > 2".into()), 1),
+                ("!else".into(), None, 1),
+            ]
+        );
+        assert!(conditional.is_closed());
+        assert_eq!(root.class_decls().count(), 4);
+    }
+
+    #[test]
+    fn test_procedures() {
+        let _ = env_logger::try_init();
+        let text = "!procedure $greet($name, $greeting=\"Hello, you\")\n  Alice -> Bob : $greeting $name\n!endprocedure\n!function $double($x) !return $x * 2\n!unquoted function Twice($x)\n  !return $double($x)\n!endfunction\n$greet(\"Bob\", $greeting=\"Hi\")\nAlice -> Bob : $double(21) times\nPerson(user , \"A (user)\", $f(1, 2))\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let procedures = root
+            .procedures()
+            .map(|it| {
+                (
+                    it.name(),
+                    it.is_function(),
+                    it.params()
+                        .map(|param| (param.name(), param.default()))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            procedures,
+            vec![
+                (
+                    Some("$greet".into()),
+                    false,
+                    vec![
+                        (Some("$name".into()), None),
+                        (Some("$greeting".into()), Some("\"Hello, you\"".into()))
+                    ]
+                ),
+                (
+                    Some("$double".into()),
+                    true,
+                    vec![(Some("$x".into()), None)]
+                ),
+                (Some("Twice".into()), true, vec![(Some("$x".into()), None)]),
+            ]
+        );
+        let calls = root
+            .calls()
+            .map(|it| {
+                (
+                    it.name(),
+                    it.arguments().map(|arg| arg.text()).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            vec![
+                (Some("$double".into()), vec!["$x".to_string()]),
+                (
+                    Some("$greet".into()),
+                    vec!["\"Bob\"".to_string(), "$greeting=\"Hi\"".to_string()]
+                ),
+                (Some("$double".into()), vec!["21".to_string()]),
+                (
+                    Some("Person".into()),
+                    vec![
+                        "user".to_string(),
+                        "\"A (user)\"".to_string(),
+                        "$f(1, 2)".to_string()
+                    ]
+                ),
+                (Some("$f".into()), vec!["1".to_string(), "2".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_includes() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\n!include common.puml\n!include_many ../lib/shared-parts.iuml \n!includesub parts.puml!BASIC\n!include <C4/C4_Container>\n!import lib.zip\n!startsub NAME\n!endsub\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        use crate::ast::IncludeKind::*;
+        let includes = root
+            .includes()
+            .map(|it| {
+                (
+                    it.include_kind(),
+                    it.target().map(|(path, _)| path),
+                    it.sub(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            includes,
+            vec![
+                (Include, Some("common.puml".into()), None),
+                (IncludeMany, Some("../lib/shared-parts.iuml".into()), None),
+                (IncludeSub, Some("parts.puml".into()), Some("BASIC".into())),
+                (Include, Some("<C4/C4_Container>".into()), None),
+                (Import, Some("lib.zip".into()), None),
+            ]
+        );
+        let subs = root
+            .preprocessor_lines()
+            .map(|it| (it.directive(), it.argument()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            subs,
+            vec![
+                ("!startsub".to_string(), "NAME".to_string()),
+                ("!endsub".to_string(), "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_themes() {
+        let _ = env_logger::try_init();
+        let text = "!theme cerulean-outline\n!theme mine from ./themes \n!theme\n!theme x y\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        let errors = parsed
+            .errors()
+            .iter()
+            .map(|it| (&text[it.range], it.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![("", "expected a theme name"), ("y", "expected end of line")]
+        );
+        let themes = parsed
+            .root()
+            .themes()
+            .map(|it| (it.name().map(|(name, _)| name), it.source()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            themes,
+            vec![
+                (Some("cerulean-outline".to_string()), None),
+                (Some("mine".into()), Some("./themes".to_string())),
+                (None, None),
+                (Some("x".into()), None),
+            ]
+        );
+    }
+}
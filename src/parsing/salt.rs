@@ -0,0 +1,231 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::syntax::SyntaxKind::*;
+
+/// A statement in a `@startsalt` diagram, which is only its outermost `{ }`
+pub(crate) fn statement(parser: &mut Parser) -> Statement {
+    match parser.current() {
+        L_BRACE => {
+            group(parser);
+            parser.eol();
+        }
+        // a '}' closing nothing
+        R_BRACE => {
+            parser.start_node(ERROR);
+            parser.error("unexpected '}'");
+            parser.consume(R_BRACE);
+            parser.finish_node();
+        }
+        _ => {
+            parser.recover("unexpected statement");
+            parser.eat(EOL);
+        }
+    }
+    Statement::Ok
+}
+
+/// Whether the line is the `salt` that starts a wireframe in `@startuml`
+pub(crate) fn at_salt(parser: &Parser) -> bool {
+    parser.at_word("salt") && matches!(parser.nth(1), EOL | EOF | L_BRACE)
+}
+
+/// `salt` followed by the `{ }` of the wireframe, on the same line or the next
+pub(crate) fn salt(parser: &mut Parser) -> Statement {
+    trace!("Starting salt diagram node");
+    parser.start_node(SALT_DIAGRAM);
+    parser.consume(IDENTIFIER);
+    loop {
+        parser.skip_ws();
+        if !parser.eat(EOL) {
+            break;
+        }
+    }
+    if parser.at(L_BRACE) {
+        group(parser);
+    } else {
+        parser.error("expected '{'");
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// The character after the `{` that styles the group: `+`, `#`, `!` and `-`
+/// for its lines, `/` for tabs, `*` for a menu, `T` for a tree, `S` for a
+/// scroll area and `^` for a group box
+fn style(parser: &mut Parser) {
+    match parser.current() {
+        PLUS | HASH | MINUS | SLASH | STAR => parser.consume_token(),
+        IDENTIFIER if matches!(parser.current_text(), "T" | "S" | "SI") => parser.consume_token(),
+        DIRECTIVE if parser.current_text() == "!" => parser.consume_token(),
+        // '{^"Title"'
+        ERROR if parser.current_text() == "^" => {
+            parser.consume_token();
+            parser.skip_ws();
+            parser.eat(STRING);
+        }
+        _ => {}
+    }
+}
+
+/// `{ ... }`, with cells separated by `|` and rows by line breaks
+fn group(parser: &mut Parser) {
+    trace!("Starting salt group node");
+    parser.start_node(SALT_GROUP);
+    parser.consume(L_BRACE);
+    if parser.too_deep() {
+        parser.skip_nested(L_BRACE, R_BRACE, false);
+        parser.eat(R_BRACE);
+        parser.finish_node();
+        return;
+    }
+    parser.nest();
+    style(parser);
+    loop {
+        match parser.current() {
+            EOF | DIAGRAM_END => {
+                parser.error("expected '}'");
+                break;
+            }
+            R_BRACE => {
+                parser.consume(R_BRACE);
+                break;
+            }
+            L_BRACE => group(parser),
+            _ if at_widget(parser) => widget(parser),
+            // text, separators such as '..' and '==', and the '+' of tree items
+            _ => parser.consume_token(),
+        }
+    }
+    parser.unnest();
+    parser.finish_node();
+}
+
+/// Whether the line goes on with a checkbox `[X]` or a radio button `( )`
+fn at_choice(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    match line.as_slice() {
+        [(L_BRACK, _), (R_BRACK, _), ..] | [(L_PAREN, _), (R_PAREN, _), ..] => true,
+        [(L_BRACK, _), (IDENTIFIER, x), (R_BRACK, _), ..]
+        | [(L_PAREN, _), (IDENTIFIER, x), (R_PAREN, _), ..] => x.eq_ignore_ascii_case("x"),
+        _ => false,
+    }
+}
+
+fn at_widget(parser: &Parser) -> bool {
+    match parser.current() {
+        L_BRACK | STRING => true,
+        L_PAREN => at_choice(parser),
+        ERROR => parser.current_text().starts_with('^'),
+        _ => false,
+    }
+}
+
+/// `[Ok]`, `[X] checked`, `(X) chosen`, `"text field"` or `^droplist^`
+fn widget(parser: &mut Parser) {
+    parser.start_node(SALT_WIDGET);
+    let choice = at_choice(parser);
+    match parser.current() {
+        STRING => parser.consume(STRING),
+        L_BRACK => closed_by(parser, R_BRACK, "expected ']'"),
+        L_PAREN => closed_by(parser, R_PAREN, "expected ')'"),
+        _ => {
+            // '^^' is a single token, as is the '^' closing '^Choice^'
+            let empty = parser.current_text().len() > 1 && parser.current_text().ends_with('^');
+            parser.consume_token();
+            if !empty {
+                loop {
+                    if parser.at_eol() || parser.at(PIPE) {
+                        parser.error("expected '^'");
+                        break;
+                    }
+                    let closing = parser.at(ERROR) && parser.current_text().contains('^');
+                    parser.consume_token();
+                    if closing {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    // a checkbox or radio button is labelled by the rest of its cell
+    if choice {
+        parser.skip_ws();
+        if !at_cell_end(parser) {
+            parser.start_node(LABEL);
+            parser.markup(at_cell_end);
+            parser.finish_node();
+        }
+    }
+    parser.finish_node();
+}
+
+fn closed_by(parser: &mut Parser, closer: crate::syntax::SyntaxKind, message: &str) {
+    parser.consume_token();
+    while !parser.at_eol() && !parser.at(PIPE) && !parser.at(closer) {
+        parser.consume_token();
+    }
+    if !parser.eat(closer) {
+        parser.error(message);
+    }
+}
+
+fn at_cell_end(parser: &Parser) -> bool {
+    parser.at_eol()
+        || matches!(parser.current(), PIPE | L_BRACE | R_BRACE | DIAGRAM_END)
+        || at_widget(parser)
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_salt() {
+        let _ = env_logger::try_init();
+        let text = "@startsalt\n{+\n  Login    | \"MyName   \"\n  [X] Remember | (X) Yes | ( ) No\n  ^Choice^ | ^^\n  {/ <b>General | Fullscreen }\n  {T\n   + World\n   ++ America\n  }\n  [Cancel] | [  OK   ]\n}\n@endsalt\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let group = root.salt_groups().next().unwrap();
+        use crate::ast::SaltStyle::*;
+        assert_eq!(group.style(), Border);
+        let styles = group.groups().map(|it| it.style()).collect::<Vec<_>>();
+        assert_eq!(styles, vec![Tabs, Tree]);
+        use crate::ast::SaltWidget::*;
+        let widgets = group
+            .widgets()
+            .map(|it| (it.widget_kind(), it.text()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            widgets,
+            vec![
+                (TextField, "MyName".into()),
+                (Checkbox(true), "Remember".into()),
+                (Radio(true), "Yes".into()),
+                (Radio(false), "No".into()),
+                (Droplist, "Choice".into()),
+                (Droplist, "".into()),
+                (Button, "Cancel".into()),
+                (Button, "OK".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_salt_in_uml() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\nsalt\n{\n  [Ok] | {\n}\n}\n}\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        let root = parsed.root();
+        let groups = root.salt_groups().collect::<Vec<_>>();
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0].is_closed());
+        assert_eq!(groups[0].groups().count(), 1);
+    }
+}
@@ -5,40 +5,28 @@ use crate::grammar::Statement;
 use crate::syntax::SyntaxKind::*;
 
 fn participant_name(parser: &mut Parser) {
-    assert_eq!(parser.current(), IDENTIFIER);
     parser.start_node(IDENTIFIER);
     parser.consume(IDENTIFIER);
     parser.finish_node();
 }
 
 pub(crate) fn participant_decl(parser: &mut Parser) -> Statement {
-    assert_eq!(parser.current(), PARTICIPANT_KW);
     trace!("Starting decl node");
     parser.start_node(PARTICIPANT_DECL);
-    parser.consume_token(); // 'participant'
+    parser.consume(PARTICIPANT_KW);
 
     parser.skip_ws();
     if parser.at(IDENTIFIER) {
         participant_name(parser);
     }
 
-    // expect an identifier
-    loop {
-        match parser.current() {
-            EOF => {
-                parser.finish_node();
-                return Statement::Ok;
-            }
-            EOL => {
-                parser.consume_token();
-                parser.finish_node();
-                return Statement::Ok;
-            }
-            _ => {
-                parser.consume_token();
-            }
-        }
+    // the rest of the line, such as 'as B' or '#red'
+    while !parser.at_sync() {
+        parser.consume_token();
     }
+    parser.eat(EOL);
+    parser.finish_node();
+    Statement::Ok
 }
 
 mod tests {
@@ -47,12 +35,12 @@ mod tests {
 
     #[test]
     fn test_participant_decl() {
-        env_logger::init();
+        let _ = env_logger::try_init();
         let text = "\tparticipant kelly\nparticipant bob\n";
         let root = crate::parse(text).root();
         let res = root
             .participant_decls()
-            .filter_map(|it| it.participant_name())
+            .filter_map(|it| it.name())
             .map(|it| it.identifier())
             .collect::<Vec<_>>();
         trace!("Participant Names - {:?}", res);
@@ -65,7 +53,7 @@ mod tests {
 
         let res = root
             .participant_decls()
-            .filter_map(|it| it.participant_name())
+            .filter_map(|it| it.name())
             .map(|it| it.position())
             .collect::<Vec<_>>();
         trace!("Participant Positions - {:?}", res);
@@ -0,0 +1,160 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::parsing::class;
+use crate::syntax::SyntaxKind::*;
+
+/// `state Name as Alias <<choice>> #color : description { ... }`
+pub(crate) fn state_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting state decl node");
+    parser.start_node(STATE_DECL);
+    parser.consume(STATE_KW);
+    parser.skip_ws();
+    if class::at_name(parser) {
+        class::name(parser);
+    } else {
+        parser.error("expected a state name");
+    }
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            AS_KW => {
+                parser.consume(AS_KW);
+                parser.skip_ws();
+                if class::at_name(parser) {
+                    class::name(parser);
+                }
+            }
+            STEREO_OPEN => class::stereotype(parser),
+            COLON => {
+                parser.consume(COLON);
+                parser.label();
+            }
+            L_BRACE => {
+                state_body(parser);
+                break;
+            }
+            EOL | EOF => break,
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// A line holding nothing but `--` or `||`, which splits a composite state
+/// into concurrent regions
+fn at_region_separator(parser: &Parser) -> bool {
+    match parser.rest_of_line().as_slice() {
+        [(ARROW, text)] => text.chars().all(|c| c == '-'),
+        [(PIPE, _), (PIPE, _)] => true,
+        _ => false,
+    }
+}
+
+fn state_body(parser: &mut Parser) {
+    parser.start_node(STATE_BODY);
+    parser.consume(L_BRACE);
+    parser.start_node(REGION);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOF | DIAGRAM_END => {
+                parser.error("expected '}'");
+                parser.finish_node();
+                break;
+            }
+            R_BRACE => {
+                parser.finish_node();
+                parser.consume(R_BRACE);
+                break;
+            }
+            _ if at_region_separator(parser) => {
+                parser.finish_node();
+                parser.skip_to_eol();
+                parser.eat(EOL);
+                parser.start_node(REGION);
+            }
+            _ => {
+                parser.statement();
+            }
+        }
+    }
+    parser.finish_node();
+}
+
+/// Whether the line looks like `Name : description`
+pub(crate) fn at_description(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    let mut index = 1;
+    while matches!(line.get(index), Some((DOT, _))) {
+        index += 2;
+    }
+    matches!(line.first(), Some((IDENTIFIER, _)) | Some((STRING, _)))
+        && matches!(line.get(index), Some((COLON, _)))
+}
+
+/// `Name : description`
+pub(crate) fn description(parser: &mut Parser) -> Statement {
+    parser.start_node(DESCRIPTION);
+    class::name(parser);
+    parser.skip_ws();
+    parser.consume(COLON);
+    parser.label();
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_state_decl() {
+        let _ = env_logger::try_init();
+        let text = "[*] --> Active\nstate Active {\n  [*] -> Idle\n  Idle --> Busy : request\n  --\n  state Busy <<choice>>\n  ||\n  state \"Waiting for input\" as Waiting\n}\nActive : the main state\nActive.Busy --> [*]\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let active = root.state_decls().next().unwrap();
+        assert_eq!(active.name().unwrap().identifier().to_string(), "Active");
+        let regions = active.regions().collect::<Vec<_>>();
+        assert_eq!(regions.len(), 3);
+        let busy = regions[1]
+            .statements()
+            .find_map(|it| crate::ast::StateDeclNode::cast(it.syntax().clone()))
+            .unwrap();
+        assert_eq!(busy.stereotype().unwrap().name(), "choice");
+        let waiting = root.state_decls().nth(2).unwrap();
+        assert_eq!(waiting.alias().unwrap().identifier().to_string(), "Waiting");
+
+        let transitions = root
+            .relations()
+            .map(|it| {
+                (
+                    it.left().map(|n| n.identifier().to_string()),
+                    it.right().map(|n| n.identifier().to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transitions,
+            vec![
+                (None, Some("Active".into())),
+                (None, Some("Idle".into())),
+                (Some("Idle".into()), Some("Busy".into())),
+                (Some("Active.Busy".into()), None),
+            ]
+        );
+        let description = root.descriptions().next().unwrap();
+        assert_eq!(
+            description.label().unwrap().text().to_string(),
+            "the main state"
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::syntax::SyntaxKind::*;
+
+/// Whether the line sets skinparams
+pub(crate) fn at_skinparam(parser: &Parser) -> bool {
+    parser.at_word("skinparam") || parser.at_word("skinparamlocked")
+}
+
+/// Whether the current token is a name, which may be a keyword, as the
+/// `class` of `skinparam class {` is
+fn at_name(parser: &Parser) -> bool {
+    parser.at(IDENTIFIER) || parser.current().is_keyword()
+}
+
+/// `skinparam ArrowColor red`, or a block of them sharing a prefix:
+/// `skinparam class {` with `BackgroundColor red` on the lines that follow
+pub(crate) fn skinparam(parser: &mut Parser) -> Statement {
+    trace!("Starting skinparam node");
+    parser.start_node(SKINPARAM);
+    parser.consume_token();
+    parser.skip_ws();
+    let is_block = parser.rest_of_line().last().map(|(kind, _)| *kind) == Some(L_BRACE);
+    if !at_name(parser) {
+        parser.error("expected a skinparam");
+    } else if is_block {
+        parser.consume_as(IDENTIFIER);
+        parser.skip_ws();
+        if parser.at(STEREO_OPEN) {
+            crate::parsing::class::stereotype(parser);
+            parser.skip_ws();
+        }
+        if parser.at(L_BRACE) {
+            parser.consume(L_BRACE);
+            parser.eol();
+            block_entries(parser);
+        }
+    } else {
+        entry(parser);
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// The lines of a `skinparam x { }` block, and the `}` closing it
+fn block_entries(parser: &mut Parser) {
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOF | DIAGRAM_END => {
+                parser.error("expected '}'");
+                break;
+            }
+            R_BRACE => {
+                parser.consume(R_BRACE);
+                break;
+            }
+            EOL => parser.consume(EOL),
+            _ if at_name(parser) => {
+                entry(parser);
+                parser.eol();
+            }
+            _ => {
+                parser.recover("expected a skinparam");
+            }
+        }
+    }
+}
+
+/// `ArrowColor red` or `BackgroundColor<<Entity>> #EEE`
+fn entry(parser: &mut Parser) {
+    parser.start_node(SKINPARAM_ENTRY);
+    parser.consume_as(IDENTIFIER);
+    if parser.at(STEREO_OPEN) {
+        crate::parsing::class::stereotype(parser);
+    }
+    value(parser, &[]);
+    parser.finish_node();
+}
+
+/// The rest of the line, up to any of `enders`, as a PARAM_VALUE, without the
+/// whitespace around it
+fn value(parser: &mut Parser, enders: &[crate::syntax::SyntaxKind]) {
+    parser.skip_ws();
+    let at_end = |parser: &Parser| parser.at_eol() || enders.contains(&parser.current());
+    if at_end(parser) {
+        parser.error("expected a value");
+        return;
+    }
+    parser.start_node(PARAM_VALUE);
+    while !at_end(parser) {
+        let trailing = parser.at(WHITESPACE)
+            && (matches!(parser.nth(0), EOL | EOF) || enders.contains(&parser.nth(0)));
+        if trailing {
+            break;
+        }
+        parser.consume_token();
+    }
+    parser.finish_node();
+}
+
+/// Whether the line is the `<style>` that starts a style sheet
+pub(crate) fn at_style(parser: &Parser) -> bool {
+    matches!(
+        parser.rest_of_line().as_slice(),
+        [(L_ANGLE, _), (IDENTIFIER, "style"), (R_ANGLE, _), ..]
+    )
+}
+
+fn at_style_end(parser: &Parser) -> bool {
+    matches!(
+        parser.rest_of_line().as_slice(),
+        [
+            (L_ANGLE, _),
+            (SLASH, _),
+            (IDENTIFIER, "style"),
+            (R_ANGLE, _),
+            ..
+        ]
+    )
+}
+
+/// `<style>`, rules such as `classDiagram { class { BackgroundColor red } }`
+/// and `</style>`
+pub(crate) fn style(parser: &mut Parser) -> Statement {
+    trace!("Starting style node");
+    parser.start_node(STYLE);
+    parser.consume(L_ANGLE);
+    parser.consume(IDENTIFIER);
+    parser.consume(R_ANGLE);
+    parser.eol();
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOF | DIAGRAM_END => {
+                parser.error("expected '</style>'");
+                break;
+            }
+            EOL => parser.consume(EOL),
+            L_ANGLE if at_style_end(parser) => {
+                for _ in 0..4 {
+                    parser.consume_token();
+                }
+                break;
+            }
+            R_BRACE => {
+                parser.start_node(ERROR);
+                parser.error("unexpected '}'");
+                parser.consume(R_BRACE);
+                parser.finish_node();
+            }
+            _ => style_item(parser),
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// A rule, if there's a `{` on the line, or else a property
+fn style_item(parser: &mut Parser) {
+    if parser
+        .rest_of_line()
+        .iter()
+        .any(|(kind, _)| *kind == L_BRACE)
+    {
+        style_rule(parser);
+    } else if at_name(parser) {
+        style_property(parser);
+    } else {
+        parser.recover("expected a style property");
+    }
+}
+
+/// `classDiagram, .entity { ... }`
+fn style_rule(parser: &mut Parser) {
+    parser.start_node(STYLE_RULE);
+    while !parser.at(L_BRACE) {
+        if at_name(parser) {
+            parser.consume_as(IDENTIFIER);
+        } else {
+            parser.consume_token();
+        }
+    }
+    parser.consume(L_BRACE);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOF | DIAGRAM_END => {
+                parser.error("expected '}'");
+                break;
+            }
+            _ if at_style_end(parser) => {
+                parser.error("expected '}'");
+                break;
+            }
+            R_BRACE => {
+                parser.consume(R_BRACE);
+                break;
+            }
+            EOL | SEMI => parser.consume_token(),
+            _ => style_item(parser),
+        }
+    }
+    parser.finish_node();
+}
+
+/// `BackgroundColor #EEE`, which may end with a `;`
+fn style_property(parser: &mut Parser) {
+    parser.start_node(STYLE_PROPERTY);
+    parser.consume_as(IDENTIFIER);
+    parser.skip_ws();
+    parser.eat(COLON);
+    value(parser, &[SEMI, R_BRACE]);
+    parser.finish_node();
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_skinparams() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\nskinparam ArrowColor #Red \nskinparam class {\n  BackgroundColor<<Entity>> LightBlue\n  FontName \"Courier New\"\n}\nskinparam\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        let errors = parsed
+            .errors()
+            .iter()
+            .map(|it| (&text[it.range], it.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(errors, vec![("", "expected a skinparam")]);
+        let root = parsed.root();
+        let params = root
+            .skinparams()
+            .flat_map(|it| it.entries().collect::<Vec<_>>())
+            .map(|it| (it.key().unwrap(), it.value().unwrap().0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            params,
+            vec![
+                ("ArrowColor".to_string(), "#Red".to_string()),
+                ("classBackgroundColor".into(), "LightBlue".into()),
+                ("classFontName".into(), "\"Courier New\"".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_style() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\n<style>\nclassDiagram {\n  class, .entity {\n    BackgroundColor #EEE;\n    LineThickness 2\n  }\n  FontSize 12\n}\n</style>\nclass A\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+        assert_eq!(root.class_decls().count(), 1);
+        let style = root.styles().next().unwrap();
+        assert!(style.is_closed());
+        let rule = style.rules().next().unwrap();
+        assert_eq!(rule.selectors(), vec!["classDiagram"]);
+        let inner = rule.rules().next().unwrap();
+        assert_eq!(inner.selectors(), vec!["class", ".entity"]);
+        let properties = style
+            .syntax()
+            .descendants()
+            .filter_map(crate::ast::StylePropertyNode::cast)
+            .map(|it| (it.name().unwrap(), it.value().unwrap().0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            properties,
+            vec![
+                ("BackgroundColor".to_string(), "#EEE".to_string()),
+                ("LineThickness".into(), "2".into()),
+                ("FontSize".into(), "12".into()),
+            ]
+        );
+    }
+}
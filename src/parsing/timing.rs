@@ -0,0 +1,336 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::parsing::class;
+use crate::parsing::component;
+use crate::syntax::SyntaxKind::*;
+
+const SIGNAL_KINDS: &[&str] = &["robust", "concise", "clock", "binary"];
+
+/// Whether the line declares a signal, e.g. `robust "Web Browser" as WB`
+pub(crate) fn at_signal_decl(parser: &Parser) -> bool {
+    SIGNAL_KINDS.iter().any(|kind| parser.at_word(kind))
+        && matches!(parser.nth(1), IDENTIFIER | STRING)
+}
+
+/// `robust "Web Browser" as WB`, `concise WU` or `clock clk with period 50`
+pub(crate) fn signal_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting signal decl node");
+    parser.start_node(SIGNAL_DECL);
+    parser.consume_token();
+    class::name(parser);
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOL | EOF => break,
+            AS_KW => {
+                parser.consume(AS_KW);
+                parser.skip_ws();
+                if component::at_element_name(parser) {
+                    component::element_name(parser);
+                }
+            }
+            STEREO_OPEN => class::stereotype(parser),
+            // 'with period 50 pulse 15 offset 10'
+            _ => parser.consume_token(),
+        }
+    }
+    parser.eat(EOL);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// Whether the line declares the states of a signal, e.g. `WB has Idle,Busy`
+pub(crate) fn at_signal_states(parser: &Parser) -> bool {
+    matches!(
+        parser.rest_of_line().as_slice(),
+        [(IDENTIFIER, _), (IDENTIFIER, "has"), ..]
+    )
+}
+
+/// `WB has Idle,Processing,Waiting` or `WB has "Waiting for input" as wait`
+pub(crate) fn signal_states(parser: &mut Parser) -> Statement {
+    parser.start_node(SIGNAL_STATES);
+    class::name(parser);
+    parser.skip_ws();
+    parser.consume_token(); // 'has'
+    loop {
+        parser.skip_ws();
+        match parser.current() {
+            EOL | EOF => break,
+            IDENTIFIER | STRING => class::name(parser),
+            AS_KW => {
+                parser.consume(AS_KW);
+                parser.skip_ws();
+                if parser.at(IDENTIFIER) || parser.at(STRING) {
+                    class::name(parser);
+                } else {
+                    parser.error("expected a state");
+                }
+            }
+            COMMA => parser.consume(COMMA),
+            _ => {
+                parser.error("expected a state");
+                break;
+            }
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// A time label such as `start` in `@:start`, which may well be a keyword
+fn time_label(parser: &mut Parser) -> bool {
+    if parser.at(IDENTIFIER) || parser.current().is_keyword() {
+        parser.consume_as(IDENTIFIER);
+        true
+    } else {
+        false
+    }
+}
+
+fn at_time(parser: &Parser) -> bool {
+    matches!(parser.current(), NUMBER | PLUS | MINUS | COLON)
+}
+
+/// `100`, `+50`, `:label` or `:label+50`
+fn time(parser: &mut Parser) {
+    parser.start_node(TIME);
+    if parser.eat(COLON) && !time_label(parser) {
+        parser.error("expected a time label");
+    }
+    if parser.at(PLUS) || parser.at(MINUS) {
+        parser.consume_token();
+    }
+    if parser.at(NUMBER) {
+        parser.consume(NUMBER);
+    } else if parser.at(PLUS) || parser.at(MINUS) {
+        parser.error("expected a time");
+    }
+    parser.finish_node();
+}
+
+/// `@100`, `@+50`, `@:label`, `@100 as :label`, or `@WB` to start giving the
+/// changes of a single signal
+pub(crate) fn time_anchor(parser: &mut Parser) -> Statement {
+    trace!("Starting time anchor node");
+    parser.start_node(TIME_ANCHOR);
+    parser.consume(AT);
+    if at_time(parser) {
+        time(parser);
+    } else if component::at_element_name(parser) {
+        component::element_name(parser);
+    } else {
+        parser.error("expected a time or a signal");
+    }
+    parser.skip_ws();
+    if parser.eat(AS_KW) {
+        parser.skip_ws();
+        if !(parser.eat(COLON) && time_label(parser)) {
+            parser.error("expected a ':label'");
+        }
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// Whether the line is `WB is Idle`, or `+50 is Idle` inside an `@WB` block
+pub(crate) fn at_state_change(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    let is = match line.iter().position(|(kind, _)| *kind == IS_KW) {
+        Some(is) => is,
+        None => return false,
+    };
+    matches!(
+        &line[..is],
+        [(IDENTIFIER, _)] | [(STRING, _)] | [(NUMBER, _)] | [(PLUS, _), (NUMBER, _)]
+    )
+}
+
+/// `WU is Idle`, `WB is {-}`, `0 is Waiting : comment`
+pub(crate) fn state_change(parser: &mut Parser) -> Statement {
+    parser.start_node(STATE_CHANGE);
+    if at_time(parser) {
+        time(parser);
+    } else {
+        class::name(parser);
+    }
+    parser.skip_ws();
+    parser.consume(IS_KW);
+    parser.skip_ws();
+    match parser.current() {
+        IDENTIFIER | STRING => class::name(parser),
+        // '{-}' and '{hidden}' for no state at all, '{A,B}' for either
+        L_BRACE => {
+            while !parser.at_eol() && !parser.eat(R_BRACE) {
+                parser.consume_token();
+            }
+        }
+        _ => parser.error("expected a state"),
+    }
+    parser.skip_ws();
+    if parser.eat(COLON) {
+        parser.label();
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// Whether the line is `highlight 200 to 450 #Gold : caption`
+pub(crate) fn at_highlight(parser: &Parser) -> bool {
+    parser.at_word("highlight") && matches!(parser.nth(1), NUMBER | PLUS | AT | COLON)
+}
+
+pub(crate) fn highlight(parser: &mut Parser) -> Statement {
+    parser.start_node(HIGHLIGHT);
+    parser.consume_token();
+    parser.skip_ws();
+    parser.eat(AT);
+    time(parser);
+    parser.skip_ws();
+    if parser.at_word("to") {
+        parser.consume_token();
+        parser.skip_ws();
+        parser.eat(AT);
+        time(parser);
+    } else {
+        parser.error("expected 'to'");
+    }
+    while !parser.at_eol() && !parser.at(COLON) {
+        parser.consume_token();
+    }
+    if parser.eat(COLON) {
+        parser.label();
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// Whether the line links two signals at given times, e.g. `WB@0 <-> @50`
+/// or `WU -> WB@+50 : message`
+pub(crate) fn at_timing_link(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    let arrow = match line.iter().position(|(kind, _)| *kind == ARROW) {
+        Some(arrow) => arrow,
+        None => return false,
+    };
+    let end = line
+        .iter()
+        .skip(arrow)
+        .position(|(kind, _)| *kind == COLON)
+        .map(|it| it + arrow)
+        .unwrap_or_else(|| line.len());
+    matches!(line.first(), Some((IDENTIFIER, _)) | Some((AT, _)))
+        && line[..end].iter().any(|(kind, _)| *kind == AT)
+}
+
+/// One end of a link: `WB@0`, `@+50` or just `WB`
+fn link_end(parser: &mut Parser) {
+    parser.skip_ws();
+    if parser.at(IDENTIFIER) || parser.at(STRING) {
+        class::name(parser);
+    }
+    if parser.eat(AT) {
+        time(parser);
+    }
+}
+
+/// `WB@0 <-> @50 : {50 ms lag}` constrains the time between two points,
+/// `WU -> WB@+50 : URL` sends a message
+pub(crate) fn timing_link(parser: &mut Parser) -> Statement {
+    let checkpoint = parser.checkpoint();
+    link_end(parser);
+    parser.skip_ws();
+    let kind = if parser.current_text() == "<->" {
+        TIMING_CONSTRAINT
+    } else {
+        TIMING_MESSAGE
+    };
+    parser.start_node_at(checkpoint, kind);
+    parser.consume(ARROW);
+    link_end(parser);
+    parser.skip_ws();
+    if parser.eat(COLON) {
+        parser.label();
+    }
+    parser.eol();
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_timing() {
+        let _ = env_logger::try_init();
+        let text = "robust \"Web Browser\" as WB\nconcise \"Web User\" as WU\nclock clk with period 50\nbinary \"Enable\" as EN\nWB has Idle,Processing,\"Waiting for input\" as wait\n\n@0\nWU is Idle\nWB is Idle\n@100 as :start\nWU -> WB : URL\nWB is Processing\n@:start+200\nWB is wait\n@WB\n0 is Idle\n+200 is Processing\nWB@0 <-> @50 : {50 ms lag}\nhighlight 200 to 450 #Gold;line:DimGrey : Caption\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let signals = root
+            .signal_decls()
+            .map(|it| {
+                (
+                    it.signal_kind(),
+                    it.name().unwrap().value(),
+                    it.alias().map(|a| a.value()),
+                )
+            })
+            .collect::<Vec<_>>();
+        use crate::ast::SignalKind::*;
+        assert_eq!(
+            signals,
+            vec![
+                (Robust, "Web Browser".into(), Some("WB".into())),
+                (Concise, "Web User".into(), Some("WU".into())),
+                (Clock, "clk".into(), None),
+                (Binary, "Enable".into(), Some("EN".into())),
+            ]
+        );
+        let changes = root
+            .state_changes()
+            .map(|it| {
+                (
+                    it.signal().map(|s| s.value()),
+                    it.time().map(|t| t.syntax().text().to_string()),
+                    it.state().map(|s| s.value()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (Some("WU".into()), None, Some("Idle".into())),
+                (Some("WB".into()), None, Some("Idle".into())),
+                (Some("WB".into()), None, Some("Processing".into())),
+                (Some("WB".into()), None, Some("wait".into())),
+                (None, Some("0".into()), Some("Idle".into())),
+                (None, Some("+200".into()), Some("Processing".into())),
+            ]
+        );
+        let states = root
+            .syntax()
+            .descendants()
+            .find_map(crate::ast::SignalStatesNode::cast)
+            .unwrap();
+        assert_eq!(
+            states.states().map(|it| it.value()).collect::<Vec<_>>(),
+            vec!["Idle", "Processing", "Waiting for input", "wait"]
+        );
+        let constraint = root
+            .syntax()
+            .descendants()
+            .find(|it| it.kind() == crate::syntax::SyntaxKind::TIMING_CONSTRAINT);
+        assert!(constraint.is_some());
+        assert_eq!(root.relations().count(), 1);
+    }
+}
@@ -0,0 +1,145 @@
+use log::*;
+
+use crate::grammar::Parser;
+use crate::grammar::Statement;
+use crate::parsing::component;
+use crate::syntax::SyntaxKind::*;
+
+/// Whether the line declares an actor with the `:Name:` shorthand. Unlike an
+/// activity's `:action;` the closing colon is followed by the end of the line
+/// or whatever may follow a name.
+pub(crate) fn at_actor_shorthand(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    let close = match line.iter().skip(1).position(|(kind, _)| *kind == COLON) {
+        Some(close) => close + 1,
+        None => return false,
+    };
+    close > 1
+        && matches!(
+            line.get(close + 1),
+            None | Some((AS_KW, _)) | Some((STEREO_OPEN, _)) | Some((HASH, _))
+        )
+}
+
+/// `actor Name`, `actor :Long Name: as A` or just `:Long Name:`
+pub(crate) fn actor_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting actor decl node");
+    parser.start_node(ACTOR_DECL);
+    if parser.eat(ACTOR_KW) {
+        parser.skip_ws();
+    }
+    if component::at_element_name(parser) {
+        component::element_name(parser);
+    } else {
+        parser.error("expected an actor name");
+    }
+    component::element_tail(parser);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// `usecase Name`, `usecase (Long Name) as U` or just `(Long Name)`
+pub(crate) fn usecase_decl(parser: &mut Parser) -> Statement {
+    trace!("Starting usecase decl node");
+    parser.start_node(USECASE_DECL);
+    if parser.eat(USECASE_KW) {
+        parser.skip_ws();
+    }
+    if component::at_element_name(parser) {
+        component::element_name(parser);
+    } else {
+        parser.error("expected a use case name");
+    }
+    component::element_tail(parser);
+    parser.finish_node();
+    Statement::Ok
+}
+
+/// Whether the line is `left to right direction` or `top to bottom direction`
+pub(crate) fn at_direction(parser: &Parser) -> bool {
+    let line = parser.rest_of_line();
+    matches!(
+        line.as_slice(),
+        [
+            (IDENTIFIER, _),
+            (IDENTIFIER, "to"),
+            (IDENTIFIER, _),
+            (IDENTIFIER, "direction")
+        ]
+    )
+}
+
+pub(crate) fn direction(parser: &mut Parser) -> Statement {
+    parser.start_node(DIRECTION);
+    parser.skip_to_eol();
+    parser.eat(EOL);
+    parser.finish_node();
+    Statement::Ok
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use log::*;
+
+    #[test]
+    fn test_usecase_decl() {
+        let _ = env_logger::try_init();
+        let text = "left to right direction\nactor Guest as g\n:Main Admin: as Admin\nactor :Chef:\nusecase (Use the application) as UC1\n(Eat Food) as Eat\nrectangle Restaurant {\n  usecase Review\n  (Pay)\n}\ng --> Eat\nAdmin -- (Pay) : manages\n:Chef: --> (Cook)\n(Pay) .> (Eat Food) : <<include>>\nReview ..> Eat : <<extend>> later\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let root = parsed.root();
+
+        let actors = root
+            .actor_decls()
+            .map(|it| (it.name().unwrap().value(), it.alias().map(|a| a.value())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actors,
+            vec![
+                ("Guest".into(), Some("g".into())),
+                ("Main Admin".into(), Some("Admin".into())),
+                ("Chef".into(), None),
+            ]
+        );
+        let usecases = root
+            .usecase_decls()
+            .map(|it| (it.name().unwrap().value(), it.alias().map(|a| a.value())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            usecases,
+            vec![
+                ("Use the application".into(), Some("UC1".into())),
+                ("Eat Food".into(), Some("Eat".into())),
+                ("Review".into(), None),
+                ("Pay".into(), None),
+            ]
+        );
+        let restaurant = root.container_decls().next().unwrap();
+        assert_eq!(
+            restaurant.container_kind(),
+            crate::ast::ContainerKind::Rectangle
+        );
+
+        let relations = root
+            .relations()
+            .map(|it| {
+                (
+                    it.left().unwrap().value(),
+                    it.right().unwrap().value(),
+                    it.stereotype().map(|s| s.name()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            relations,
+            vec![
+                ("g".into(), "Eat".into(), None),
+                ("Admin".into(), "Pay".into(), None),
+                ("Chef".into(), "Cook".into(), None),
+                ("Pay".into(), "Eat Food".into(), Some("include".into())),
+                ("Review".into(), "Eat".into(), Some("extend".into())),
+            ]
+        );
+    }
+}
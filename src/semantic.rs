@@ -0,0 +1,282 @@
+//! Name resolution: which declaration each name in a diagram refers to
+use crate::ast::{
+    ActorDeclNode, ClassDeclNode, ClassKind, ComponentDeclNode, ContainerDeclNode, ContainerKind,
+    ElementKind, EntryNode, IdentifierNode, ObjectDeclNode, PackageDeclNode, ParticipantDeclNode,
+    PortDeclNode, PortKind, RootNode, SignalDeclNode, SignalKind, StateDeclNode, StateKind,
+    SyntaxNode, UsecaseDeclNode,
+};
+use rowan::TextRange;
+use crate::syntax::SyntaxKind::*;
+
+pub(crate) mod c4;
+pub(crate) mod color;
+pub(crate) mod creole;
+pub(crate) mod data;
+pub(crate) mod expansion;
+pub(crate) mod gantt;
+pub(crate) mod preprocessor;
+pub(crate) mod skinparam;
+pub(crate) mod stdlib;
+pub(crate) mod theme;
+pub(crate) mod timing;
+
+/// What a definition declares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DefinitionKind {
+    Participant,
+    Package,
+    Class(ClassKind),
+    State(StateKind),
+    Element(ElementKind),
+    Container(ContainerKind),
+    Port(PortKind),
+    Actor,
+    Usecase,
+    Object,
+    Map,
+    Entry,
+    Signal(SignalKind),
+}
+
+impl DefinitionKind {
+    /// Whether a name written the way `name` is can refer to a definition of
+    /// the kind: `(Name)` is a use case, `[Name]` a component and `:Name:` an
+    /// actor, while other names can refer to anything
+    pub(crate) fn named_by(self, name: &IdentifierNode) -> bool {
+        match bracket(name) {
+            Some('(') => self == DefinitionKind::Usecase,
+            Some('[') => matches!(self, DefinitionKind::Element(_)),
+            Some(':') => self == DefinitionKind::Actor,
+            _ => true,
+        }
+    }
+}
+
+/// The bracket `name` is written in, of `(Name)`, `[Name]` and `:Name:`,
+/// which says what kind of entity it is
+pub(crate) fn bracket(name: &IdentifierNode) -> Option<char> {
+    name.identifier()
+        .char_at(0.into())
+        .filter(|it| matches!(it, '(' | '[' | ':'))
+}
+
+/// A name introduced by a declaration such as `class A` or `state B { }`
+#[derive(Debug, Clone)]
+pub(crate) struct Definition {
+    /// The names of the enclosing packages and composite states, followed by
+    /// the name itself, e.g. `["Outer", "Inner"]`
+    pub(crate) path: Vec<String>,
+    pub(crate) name: IdentifierNode,
+    pub(crate) kind: DefinitionKind,
+}
+
+impl Definition {
+    /// The path of the scope the definition is declared in
+    fn scope(&self) -> &[String] {
+        &self.path[..self.path.len() - 1]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
+
+/// Something wrong with what a diagram says, rather than with its syntax
+#[derive(Debug, Clone)]
+pub(crate) struct Problem {
+    pub(crate) range: TextRange,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+}
+
+/// Every definition in a document
+pub(crate) struct Model {
+    definitions: Vec<Definition>,
+}
+
+impl Model {
+    pub(crate) fn new(root: &RootNode) -> Self {
+        let mut definitions = Vec::new();
+        for node in root.syntax().descendants() {
+            let (names, kind) = match node.kind() {
+                PARTICIPANT_DECL => {
+                    let decl = ParticipantDeclNode::cast(node.clone()).unwrap();
+                    (vec![decl.name()], DefinitionKind::Participant)
+                }
+                PACKAGE_DECL => {
+                    let decl = PackageDeclNode::cast(node.clone()).unwrap();
+                    (vec![decl.name()], DefinitionKind::Package)
+                }
+                CLASS_DECL => {
+                    let decl = ClassDeclNode::cast(node.clone()).unwrap();
+                    (
+                        vec![decl.name(), decl.alias()],
+                        DefinitionKind::Class(decl.class_kind()),
+                    )
+                }
+                STATE_DECL => {
+                    let decl = StateDeclNode::cast(node.clone()).unwrap();
+                    (
+                        vec![decl.name(), decl.alias()],
+                        DefinitionKind::State(decl.state_kind()),
+                    )
+                }
+                COMPONENT_DECL => {
+                    let decl = ComponentDeclNode::cast(node.clone()).unwrap();
+                    (
+                        vec![decl.name(), decl.alias()],
+                        DefinitionKind::Element(decl.element_kind()),
+                    )
+                }
+                CONTAINER_DECL => {
+                    let decl = ContainerDeclNode::cast(node.clone()).unwrap();
+                    (
+                        vec![decl.name(), decl.alias()],
+                        DefinitionKind::Container(decl.container_kind()),
+                    )
+                }
+                PORT_DECL => {
+                    let decl = PortDeclNode::cast(node.clone()).unwrap();
+                    (vec![decl.name()], DefinitionKind::Port(decl.port_kind()))
+                }
+                ACTOR_DECL => {
+                    let decl = ActorDeclNode::cast(node.clone()).unwrap();
+                    (vec![decl.name(), decl.alias()], DefinitionKind::Actor)
+                }
+                USECASE_DECL => {
+                    let decl = UsecaseDeclNode::cast(node.clone()).unwrap();
+                    (vec![decl.name(), decl.alias()], DefinitionKind::Usecase)
+                }
+                OBJECT_DECL | MAP_DECL => {
+                    let decl = ObjectDeclNode::cast(node.clone()).unwrap();
+                    let kind = if decl.is_map() {
+                        DefinitionKind::Map
+                    } else {
+                        DefinitionKind::Object
+                    };
+                    (vec![decl.name(), decl.alias()], kind)
+                }
+                ENTRY => {
+                    let entry = EntryNode::cast(node.clone()).unwrap();
+                    (vec![entry.key()], DefinitionKind::Entry)
+                }
+                SIGNAL_DECL => {
+                    let decl = SignalDeclNode::cast(node.clone()).unwrap();
+                    (
+                        vec![decl.name(), decl.alias()],
+                        DefinitionKind::Signal(decl.signal_kind()),
+                    )
+                }
+                _ => continue,
+            };
+            let scope = scope(&node);
+            for name in names.into_iter().flatten() {
+                let mut path = scope.clone();
+                path.extend(segments(&name));
+                definitions.push(Definition { path, name, kind });
+            }
+        }
+        Model { definitions }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn definitions(&self) -> &[Definition] {
+        &self.definitions
+    }
+
+    /// The definition `reference` refers to. Qualified names such as
+    /// `Outer.Inner` match the end of a definition's path, and a definition
+    /// in the reference's own or an enclosing scope wins over one elsewhere.
+    /// A bracketed name only refers to the kind of definition its brackets
+    /// make, so `(Name)` and `[Name]` are different entities.
+    pub(crate) fn resolve(&self, reference: &IdentifierNode) -> Option<&Definition> {
+        if let Some(definition) = self.definitions.iter().find(|it| &it.name == reference) {
+            return Some(definition);
+        }
+        let segments = segments(reference);
+        let scope = scope(reference.syntax());
+        let candidates = self
+            .definitions
+            .iter()
+            .filter(|it| it.path.ends_with(&segments) && it.kind.named_by(reference));
+        let mut best: Option<(&Definition, Option<usize>)> = None;
+        for candidate in candidates {
+            // how deep the candidate's scope encloses the reference, if at all
+            let depth =
+                Some(candidate.scope().len()).filter(|_| scope.starts_with(candidate.scope()));
+            match best {
+                Some((_, best_depth)) if best_depth >= depth => {}
+                _ => best = Some((candidate, depth)),
+            }
+        }
+        best.map(|(definition, _)| definition)
+    }
+}
+
+/// The parts of a qualified name such as `net.foo.Bar` or `Map::key`.
+/// Delimited names such as `"Long Name"` or `(Use Case)` are a single part.
+fn segments(name: &IdentifierNode) -> Vec<String> {
+    let text = name.identifier().to_string();
+    if text.starts_with(['"', '[', '(', ':']) {
+        vec![name.value()]
+    } else {
+        text.replace("::", ".")
+            .split('.')
+            .map(|it| it.trim().to_string())
+            .collect()
+    }
+}
+
+/// The path of the packages, composite states, objects and maps enclosing
+/// `node`
+fn scope(node: &SyntaxNode) -> Vec<String> {
+    let mut path = Vec::new();
+    let enclosing = node.ancestors().skip(1).filter(|it| {
+        matches!(
+            it.kind(),
+            PACKAGE_DECL | STATE_DECL | OBJECT_DECL | MAP_DECL
+        )
+    });
+    for ancestor in enclosing {
+        let name = match ancestor.kind() {
+            PACKAGE_DECL => PackageDeclNode::cast(ancestor).and_then(|it| it.name()),
+            STATE_DECL => {
+                StateDeclNode::cast(ancestor).and_then(|it| it.alias().or_else(|| it.name()))
+            }
+            _ => ObjectDeclNode::cast(ancestor).and_then(|it| it.alias().or_else(|| it.name())),
+        };
+        if let Some(name) = name {
+            path.splice(0..0, segments(&name));
+        }
+    }
+    path
+}
+
+mod tests {
+    #[test]
+    fn test_resolve_states() {
+        let text = "state Outer {\n  state Inner\n  Inner --> Other\n}\nstate Inner\nstate Other\nOuter.Inner --> Inner\n";
+        let root = crate::parse(text).root();
+        let model = crate::semantic::Model::new(&root);
+        let resolved = root
+            .relations()
+            .flat_map(|it| vec![it.left(), it.right()])
+            .flatten()
+            .map(|it| {
+                let definition = model.resolve(&it).unwrap();
+                (it.identifier().to_string(), definition.path.join("."))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            vec![
+                ("Inner".to_string(), "Outer.Inner".to_string()),
+                ("Other".to_string(), "Other".to_string()),
+                ("Outer.Inner".to_string(), "Outer.Inner".to_string()),
+                ("Inner".to_string(), "Inner".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! The C4 model that the procedures of `!include <C4/...>` describe: the
+//! people, systems, containers and components they declare by alias, and the
+//! relationships between them
+use crate::ast::{ArgumentNode, CallNode, RootNode};
+use crate::semantic::{Problem, Severity};
+use crate::syntax::SyntaxKind::*;
+use rowan::{TextRange, TextSize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum C4Kind {
+    Person,
+    System,
+    Container,
+    Component,
+    /// A `Deployment_Node` or `Node`
+    Node,
+    /// A `System_Boundary`, `Container_Boundary`, `Enterprise_Boundary` or
+    /// `Boundary`
+    Boundary,
+}
+
+impl C4Kind {
+    /// What calling the procedure `name` declares, e.g. a system for
+    /// `SystemDb_Ext`
+    pub(crate) fn of(name: &str) -> Option<C4Kind> {
+        if name == "Boundary" || name.ends_with("_Boundary") {
+            return Some(C4Kind::Boundary);
+        }
+        let kinds = [
+            ("Person", C4Kind::Person),
+            ("System", C4Kind::System),
+            ("Container", C4Kind::Container),
+            ("Component", C4Kind::Component),
+            ("Deployment_Node", C4Kind::Node),
+            ("Node", C4Kind::Node),
+        ];
+        let (base, kind) = kinds.iter().find(|(base, _)| name.starts_with(base))?;
+        let variants = ["", "_Ext", "Db", "Db_Ext", "Queue", "Queue_Ext", "_L", "_R"];
+        if variants.contains(&&name[base.len()..]) {
+            Some(*kind)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether calling the procedure `name` relates two elements, as `Rel`,
+/// `BiRel`, `Rel_U` and the `Lay_*` layout hints do
+pub(crate) fn is_relation(name: &str) -> bool {
+    name == "Rel"
+        || name == "BiRel"
+        || name.starts_with("Rel_")
+        || name.starts_with("BiRel_")
+        || name.starts_with("Lay_")
+}
+
+/// An element a C4 procedure declares, such as `Container(api, "API")`
+#[derive(Debug, Clone)]
+pub(crate) struct C4Element {
+    pub(crate) kind: C4Kind,
+    pub(crate) alias: String,
+    /// Where the alias is written
+    pub(crate) range: TextRange,
+    pub(crate) label: Option<String>,
+}
+
+/// A relationship between two elements, by their aliases
+#[derive(Debug, Clone)]
+pub(crate) struct C4Relation {
+    pub(crate) from: (String, TextRange),
+    pub(crate) to: (String, TextRange),
+}
+
+/// The C4 elements and relationships of a document
+pub(crate) struct C4Model {
+    elements: Vec<C4Element>,
+    relations: Vec<C4Relation>,
+}
+
+/// The value of the argument, unquoted, and where it's written
+fn value(argument: &ArgumentNode) -> (String, TextRange) {
+    let mut tokens = argument
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .collect::<Vec<_>>();
+    // '$alias=api'
+    if argument.keyword().is_some() {
+        let eq = tokens.iter().position(|it| it.kind() == EQ).unwrap_or(0);
+        tokens.drain(..=eq);
+    }
+    let tokens = tokens
+        .into_iter()
+        .skip_while(|it| it.kind() == WHITESPACE)
+        .collect::<Vec<_>>();
+    let range = match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => first.text_range().cover(last.text_range()),
+        _ => return (String::new(), argument.syntax().text_range()),
+    };
+    let text = tokens
+        .iter()
+        .map(|it| it.text().as_str())
+        .collect::<String>();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        let range = TextRange::new(
+            range.start() + TextSize::from(1),
+            range.end() - TextSize::from(1),
+        );
+        return (text[1..text.len() - 1].to_string(), range);
+    }
+    (text, range)
+}
+
+/// The argument for the parameter `keyword`, or else the positional argument
+/// at `index`
+fn argument(call: &CallNode, index: usize, keyword: &str) -> Option<(String, TextRange)> {
+    let arguments = call.arguments().collect::<Vec<_>>();
+    let argument = arguments
+        .iter()
+        .find(|it| it.keyword().as_deref() == Some(keyword))
+        .or_else(|| {
+            arguments
+                .iter()
+                .filter(|it| it.keyword().is_none())
+                .nth(index)
+        })?;
+    Some(value(argument)).filter(|(text, _)| !text.is_empty())
+}
+
+impl C4Element {
+    /// The element the call declares, if it's of a C4 element procedure
+    pub(crate) fn from_call(call: &CallNode) -> Option<C4Element> {
+        let kind = C4Kind::of(&call.name()?)?;
+        let (alias, range) = argument(call, 0, "$alias")?;
+        Some(C4Element {
+            kind,
+            alias,
+            range,
+            label: argument(call, 1, "$label").map(|(it, _)| it),
+        })
+    }
+}
+
+impl C4Model {
+    pub(crate) fn new(root: &RootNode) -> Self {
+        let mut elements = Vec::new();
+        let mut relations = Vec::new();
+        for call in root.calls() {
+            let name = match call.name() {
+                Some(name) => name,
+                None => continue,
+            };
+            if let Some(element) = C4Element::from_call(&call) {
+                elements.push(element);
+            } else if is_relation(&name) {
+                let from = argument(&call, 0, "$from");
+                let to = argument(&call, 1, "$to");
+                if let (Some(from), Some(to)) = (from, to) {
+                    relations.push(C4Relation { from, to });
+                }
+            }
+        }
+        C4Model {
+            elements,
+            relations,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.elements.is_empty() && self.relations.is_empty()
+    }
+
+    /// The element declared with the alias
+    pub(crate) fn element(&self, alias: &str) -> Option<&C4Element> {
+        self.elements.iter().find(|it| it.alias == alias)
+    }
+
+    /// Every mention of an alias, with where it's written
+    fn mentions(&self) -> impl Iterator<Item = (&String, TextRange)> + '_ {
+        let declarations = self.elements.iter().map(|it| (&it.alias, it.range));
+        let ends = self
+            .relations
+            .iter()
+            .flat_map(|it| vec![(&it.from.0, it.from.1), (&it.to.0, it.to.1)]);
+        declarations.chain(ends)
+    }
+
+    /// The alias at `offset`, where an element is declared or related
+    pub(crate) fn alias_at(&self, offset: TextSize) -> Option<String> {
+        self.mentions()
+            .find(|(_, range)| range.contains_inclusive(offset))
+            .map(|(alias, _)| alias.clone())
+    }
+
+    /// Where the alias is declared and related, in the order it's written
+    pub(crate) fn references(&self, alias: &str) -> Vec<TextRange> {
+        let mut ranges = self
+            .mentions()
+            .filter(|(it, _)| it.as_str() == alias)
+            .map(|(_, range)| range)
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|it| it.start());
+        ranges
+    }
+
+    /// Relationships with an end that's declared neither in the document nor,
+    /// as far as `is_known` says, anywhere else
+    pub(crate) fn problems(&self, is_known: impl Fn(&str) -> bool) -> Vec<Problem> {
+        self.relations
+            .iter()
+            .flat_map(|it| vec![&it.from, &it.to])
+            .filter(|(alias, _)| self.element(alias).is_none() && !is_known(alias))
+            .map(|(alias, range)| Problem {
+                range: *range,
+                severity: Severity::Warning,
+                message: format!("'{}' is not a declared element", alias),
+            })
+            .collect()
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_c4_model() {
+        let _ = env_logger::try_init();
+        let text = "@startuml\n!include <C4/C4_Container>\nPerson(user, \"Customer\")\nSystem_Boundary(shop, \"Shop\") {\n  Container(web, \"Web\", \"Rust\")\n  ContainerDb($alias=\"db\", $label=\"Database\")\n}\nRel(user, web, \"Uses\")\nRel_R(web, db, \"Reads\")\nBiRel(web, cache, \"Caches\")\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert_eq!(parsed.syntax().text().to_string(), text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let model = super::C4Model::new(&parsed.root());
+
+        use super::C4Kind::*;
+        let elements = model
+            .elements
+            .iter()
+            .map(|it| (it.kind, it.alias.as_str(), it.label.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            elements,
+            vec![
+                (Person, "user", Some("Customer")),
+                (Boundary, "shop", Some("Shop")),
+                (Container, "web", Some("Web")),
+                (Container, "db", Some("Database")),
+            ]
+        );
+        let db = model.element("db").unwrap();
+        assert_eq!(&text[db.range], "db");
+
+        let web = text.find("web").unwrap() as u32;
+        assert_eq!(model.alias_at(web.into()), Some("web".to_string()));
+        let references = model
+            .references("web")
+            .into_iter()
+            .map(|it| &text[it])
+            .collect::<Vec<_>>();
+        assert_eq!(references, vec!["web"; 4]);
+
+        let problems = model
+            .problems(|_| false)
+            .into_iter()
+            .map(|it| it.message)
+            .collect::<Vec<_>>();
+        assert_eq!(problems, vec!["'cache' is not a declared element"]);
+        assert!(model.problems(|it| it == "cache").is_empty());
+
+        assert_eq!(super::C4Kind::of("SystemQueue_Ext"), Some(System));
+        assert_eq!(super::C4Kind::of("Deployment_Node_L"), Some(Node));
+        assert_eq!(super::C4Kind::of("Personal"), None);
+    }
+}
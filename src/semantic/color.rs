@@ -0,0 +1,322 @@
+//! Colors as PlantUML writes them: `#RGB`, `#RRGGBB`, `#RRGGBBAA`, the names
+//! of the CSS colors, with or without a `#`, and gradients such as
+//! `#red/blue`
+
+/// An RGBA color, each channel between 0 and 255
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Color {
+    pub(crate) red: u8,
+    pub(crate) green: u8,
+    pub(crate) blue: u8,
+    pub(crate) alpha: u8,
+}
+
+/// The colors PlantUML knows by name, and the archimate colors
+pub(crate) const NAMED: &[(&str, u32)] = &[
+    ("AliceBlue", 0xF0F8FF),
+    ("AntiqueWhite", 0xFAEBD7),
+    ("Aqua", 0x00FFFF),
+    ("Aquamarine", 0x7FFFD4),
+    ("Azure", 0xF0FFFF),
+    ("Beige", 0xF5F5DC),
+    ("Bisque", 0xFFE4C4),
+    ("Black", 0x000000),
+    ("BlanchedAlmond", 0xFFEBCD),
+    ("Blue", 0x0000FF),
+    ("BlueViolet", 0x8A2BE2),
+    ("Brown", 0xA52A2A),
+    ("BurlyWood", 0xDEB887),
+    ("CadetBlue", 0x5F9EA0),
+    ("Chartreuse", 0x7FFF00),
+    ("Chocolate", 0xD2691E),
+    ("Coral", 0xFF7F50),
+    ("CornflowerBlue", 0x6495ED),
+    ("Cornsilk", 0xFFF8DC),
+    ("Crimson", 0xDC143C),
+    ("Cyan", 0x00FFFF),
+    ("DarkBlue", 0x00008B),
+    ("DarkCyan", 0x008B8B),
+    ("DarkGoldenRod", 0xB8860B),
+    ("DarkGray", 0xA9A9A9),
+    ("DarkGreen", 0x006400),
+    ("DarkGrey", 0xA9A9A9),
+    ("DarkKhaki", 0xBDB76B),
+    ("DarkMagenta", 0x8B008B),
+    ("DarkOliveGreen", 0x556B2F),
+    ("DarkOrchid", 0x9932CC),
+    ("DarkRed", 0x8B0000),
+    ("DarkSalmon", 0xE9967A),
+    ("DarkSeaGreen", 0x8FBC8F),
+    ("DarkSlateBlue", 0x483D8B),
+    ("DarkSlateGray", 0x2F4F4F),
+    ("DarkSlateGrey", 0x2F4F4F),
+    ("DarkTurquoise", 0x00CED1),
+    ("DarkViolet", 0x9400D3),
+    ("Darkorange", 0xFF8C00),
+    ("DeepPink", 0xFF1493),
+    ("DeepSkyBlue", 0x00BFFF),
+    ("DimGray", 0x696969),
+    ("DimGrey", 0x696969),
+    ("DodgerBlue", 0x1E90FF),
+    ("FireBrick", 0xB22222),
+    ("FloralWhite", 0xFFFAF0),
+    ("ForestGreen", 0x228B22),
+    ("Fuchsia", 0xFF00FF),
+    ("Gainsboro", 0xDCDCDC),
+    ("GhostWhite", 0xF8F8FF),
+    ("Gold", 0xFFD700),
+    ("GoldenRod", 0xDAA520),
+    ("Gray", 0x808080),
+    ("Green", 0x008000),
+    ("GreenYellow", 0xADFF2F),
+    ("Grey", 0x808080),
+    ("HoneyDew", 0xF0FFF0),
+    ("HotPink", 0xFF69B4),
+    ("IndianRed", 0xCD5C5C),
+    ("Indigo", 0x4B0082),
+    ("Ivory", 0xFFFFF0),
+    ("Khaki", 0xF0E68C),
+    ("Lavender", 0xE6E6FA),
+    ("LavenderBlush", 0xFFF0F5),
+    ("LawnGreen", 0x7CFC00),
+    ("LemonChiffon", 0xFFFACD),
+    ("LightBlue", 0xADD8E6),
+    ("LightCoral", 0xF08080),
+    ("LightCyan", 0xE0FFFF),
+    ("LightGoldenRodYellow", 0xFAFAD2),
+    ("LightGray", 0xD3D3D3),
+    ("LightGreen", 0x90EE90),
+    ("LightGrey", 0xD3D3D3),
+    ("LightPink", 0xFFB6C1),
+    ("LightSalmon", 0xFFA07A),
+    ("LightSeaGreen", 0x20B2AA),
+    ("LightSkyBlue", 0x87CEFA),
+    ("LightSlateGray", 0x778899),
+    ("LightSlateGrey", 0x778899),
+    ("LightSteelBlue", 0xB0C4DE),
+    ("LightYellow", 0xFFFFE0),
+    ("Lime", 0x00FF00),
+    ("LimeGreen", 0x32CD32),
+    ("Linen", 0xFAF0E6),
+    ("Magenta", 0xFF00FF),
+    ("Maroon", 0x800000),
+    ("MediumAquaMarine", 0x66CDAA),
+    ("MediumBlue", 0x0000CD),
+    ("MediumOrchid", 0xBA55D3),
+    ("MediumPurple", 0x9370D8),
+    ("MediumSeaGreen", 0x3CB371),
+    ("MediumSlateBlue", 0x7B68EE),
+    ("MediumSpringGreen", 0x00FA9A),
+    ("MediumTurquoise", 0x48D1CC),
+    ("MediumVioletRed", 0xC71585),
+    ("MidnightBlue", 0x191970),
+    ("MintCream", 0xF5FFFA),
+    ("MistyRose", 0xFFE4E1),
+    ("Moccasin", 0xFFE4B5),
+    ("NavajoWhite", 0xFFDEAD),
+    ("Navy", 0x000080),
+    ("OldLace", 0xFDF5E6),
+    ("Olive", 0x808000),
+    ("OliveDrab", 0x6B8E23),
+    ("Orange", 0xFFA500),
+    ("OrangeRed", 0xFF4500),
+    ("Orchid", 0xDA70D6),
+    ("PaleGoldenRod", 0xEEE8AA),
+    ("PaleGreen", 0x98FB98),
+    ("PaleTurquoise", 0xAFEEEE),
+    ("PaleVioletRed", 0xD87093),
+    ("PapayaWhip", 0xFFEFD5),
+    ("PeachPuff", 0xFFDAB9),
+    ("Peru", 0xCD853F),
+    ("Pink", 0xFFC0CB),
+    ("Plum", 0xDDA0DD),
+    ("PowderBlue", 0xB0E0E6),
+    ("Purple", 0x800080),
+    ("Red", 0xFF0000),
+    ("RosyBrown", 0xBC8F8F),
+    ("RoyalBlue", 0x4169E1),
+    ("SaddleBrown", 0x8B4513),
+    ("Salmon", 0xFA8072),
+    ("SandyBrown", 0xF4A460),
+    ("SeaGreen", 0x2E8B57),
+    ("SeaShell", 0xFFF5EE),
+    ("Sienna", 0xA0522D),
+    ("Silver", 0xC0C0C0),
+    ("SkyBlue", 0x87CEEB),
+    ("SlateBlue", 0x6A5ACD),
+    ("SlateGray", 0x708090),
+    ("SlateGrey", 0x708090),
+    ("Snow", 0xFFFAFA),
+    ("SpringGreen", 0x00FF7F),
+    ("SteelBlue", 0x4682B4),
+    ("Tan", 0xD2B48C),
+    ("Teal", 0x008080),
+    ("Thistle", 0xD8BFD8),
+    ("Tomato", 0xFF6347),
+    ("Turquoise", 0x40E0D0),
+    ("Violet", 0xEE82EE),
+    ("Wheat", 0xF5DEB3),
+    ("White", 0xFFFFFF),
+    ("WhiteSmoke", 0xF5F5F5),
+    ("Yellow", 0xFFFF00),
+    ("YellowGreen", 0x9ACD32),
+    ("Application", 0xC2F0FF),
+    ("Business", 0xFFFFCC),
+    ("Implementation", 0xE5FFE5),
+    ("Motivation", 0xCCCCFF),
+    ("Physical", 0x97FF97),
+    ("Strategy", 0xF8E7C0),
+    ("Technology", 0xC9E7B7),
+];
+
+fn hex(text: &str) -> Option<Color> {
+    if !text.chars().all(|it| it.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |index: usize, width: usize| {
+        let value = u8::from_str_radix(&text[index * width..(index + 1) * width], 16).ok()?;
+        Some(if width == 1 { value * 17 } else { value })
+    };
+    let (width, alpha) = match text.len() {
+        3 => (1, None),
+        6 => (2, None),
+        8 => (2, Some(channel(3, 2)?)),
+        _ => return None,
+    };
+    Some(Color {
+        red: channel(0, width)?,
+        green: channel(1, width)?,
+        blue: channel(2, width)?,
+        alpha: alpha.unwrap_or(255),
+    })
+}
+
+impl Color {
+    /// A single color, such as `#F80`, `#FF8800`, `FF880080` or `#Orange`
+    pub(crate) fn parse(text: &str) -> Option<Color> {
+        let text = text.strip_prefix('#').unwrap_or(text);
+        if text.eq_ignore_ascii_case("transparent") {
+            return Some(Color::from_rgb(0xFFFFFF, 0));
+        }
+        if let Some((_, rgb)) = NAMED
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(text))
+        {
+            return Some(Color::from_rgb(*rgb, 255));
+        }
+        hex(text)
+    }
+
+    /// `#RRGGBB`, or `#RRGGBBAA` if it's not opaque
+    pub(crate) fn hex(&self) -> String {
+        let rgb = format!("#{:02X}{:02X}{:02X}", self.red, self.green, self.blue);
+        if self.alpha == 255 {
+            rgb
+        } else {
+            format!("{}{:02X}", rgb, self.alpha)
+        }
+    }
+
+    /// The name of the color, if it has one
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        if self.alpha != 255 {
+            return None;
+        }
+        let rgb = (self.red as u32) << 16 | (self.green as u32) << 8 | self.blue as u32;
+        NAMED
+            .iter()
+            .find(|(_, it)| *it == rgb)
+            .map(|(name, _)| *name)
+    }
+
+    fn from_rgb(rgb: u32, alpha: u8) -> Color {
+        Color {
+            red: (rgb >> 16) as u8,
+            green: (rgb >> 8) as u8,
+            blue: rgb as u8,
+            alpha,
+        }
+    }
+}
+
+/// The colors of a color or gradient written at the start of `text`, such as
+/// `#lightblue/white`, with where each is written in `text`
+pub(crate) fn colors_at(text: &str) -> Vec<(Color, std::ops::Range<usize>)> {
+    let mut colors = Vec::new();
+    let mut start = 0;
+    loop {
+        let name = text[start..].strip_prefix('#').unwrap_or(&text[start..]);
+        let hash = text.len() - start - name.len();
+        let length = name
+            .find(|it: char| !it.is_ascii_alphanumeric())
+            .unwrap_or(name.len());
+        let end = start + hash + length;
+        match Color::parse(&name[..length]) {
+            Some(color) if length > 0 => colors.push((color, start..end)),
+            _ => break,
+        }
+        // the next color of a gradient
+        match text[end..].chars().next() {
+            Some('/') | Some('|') | Some('\\') | Some('-') => start = end + 1,
+            _ => break,
+        }
+    }
+    colors
+}
+
+/// Whether the text is a color or a gradient of two colors, as in
+/// `#red/blue`, `#red|blue`, `#red\blue` and `#red-blue`
+pub(crate) fn is_color(text: &str) -> bool {
+    let text = text.strip_prefix('#').unwrap_or(text);
+    Color::parse(text).is_some()
+        || text
+            .split(['/', '|', '\\', '-'])
+            .all(|it| !it.is_empty() && Color::parse(it).is_some())
+}
+
+mod tests {
+    #[test]
+    fn test_colors() {
+        use super::Color;
+        let orange = Color {
+            red: 0xFF,
+            green: 0x88,
+            blue: 0x00,
+            alpha: 255,
+        };
+        assert_eq!(Color::parse("#F80"), Some(orange));
+        assert_eq!(Color::parse("#FF8800"), Some(orange));
+        assert_eq!(Color::parse("FF8800FF"), Some(orange));
+        assert_eq!(Color::parse("#lightblue").map(|it| it.blue), Some(0xE6));
+        assert_eq!(Color::parse("Transparent").map(|it| it.alpha), Some(0));
+        assert_eq!(Color::parse("#FF88"), None);
+        assert_eq!(Color::parse("#Orangey"), None);
+        assert!(super::is_color("#red/blue"));
+        assert!(super::is_color("#FFFFFF-#000"));
+        assert!(!super::is_color("#red/"));
+        assert!(!super::is_color("bold"));
+
+        assert_eq!(orange.hex(), "#FF8800");
+        assert_eq!(
+            Color {
+                alpha: 0x80,
+                ..orange
+            }
+            .hex(),
+            "#FF880080"
+        );
+        assert_eq!(Color::parse("#FFA500").unwrap().name(), Some("Orange"));
+        assert_eq!(orange.name(), None);
+        let colors = super::colors_at("#lightblue/White]->")
+            .into_iter()
+            .map(|(it, range)| (it.hex(), range))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            colors,
+            vec![
+                ("#ADD8E6".to_string(), 0..10),
+                ("#FFFFFF".to_string(), 11..16)
+            ]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! The Creole markup of labels: the OpenIconic icons there are, and the
+//! values the tags take
+use crate::ast::RootNode;
+use crate::semantic::skinparam::ValueType;
+use crate::semantic::{Problem, Severity};
+use rowan::{TextRange, TextSize};
+
+/// The icons of the OpenIconic set, for `<&name>`
+pub(crate) const ICONS: &[&str] = &[
+    "account-login",
+    "account-logout",
+    "action-redo",
+    "action-undo",
+    "align-center",
+    "align-left",
+    "align-right",
+    "aperture",
+    "arrow-bottom",
+    "arrow-circle-bottom",
+    "arrow-circle-left",
+    "arrow-circle-right",
+    "arrow-circle-top",
+    "arrow-left",
+    "arrow-right",
+    "arrow-thick-bottom",
+    "arrow-thick-left",
+    "arrow-thick-right",
+    "arrow-thick-top",
+    "arrow-top",
+    "audio",
+    "audio-spectrum",
+    "badge",
+    "ban",
+    "bar-chart",
+    "basket",
+    "battery-empty",
+    "battery-full",
+    "beaker",
+    "bell",
+    "bluetooth",
+    "bold",
+    "bolt",
+    "book",
+    "bookmark",
+    "box",
+    "briefcase",
+    "british-pound",
+    "browser",
+    "brush",
+    "bug",
+    "bullhorn",
+    "calculator",
+    "calendar",
+    "camera-slr",
+    "caret-bottom",
+    "caret-left",
+    "caret-right",
+    "caret-top",
+    "cart",
+    "chat",
+    "check",
+    "chevron-bottom",
+    "chevron-left",
+    "chevron-right",
+    "chevron-top",
+    "circle-check",
+    "circle-x",
+    "clipboard",
+    "clock",
+    "cloud",
+    "cloud-download",
+    "cloud-upload",
+    "cloudy",
+    "code",
+    "cog",
+    "collapse-down",
+    "collapse-left",
+    "collapse-right",
+    "collapse-up",
+    "command",
+    "comment-square",
+    "compass",
+    "contrast",
+    "copywriting",
+    "credit-card",
+    "crop",
+    "dashboard",
+    "data-transfer-download",
+    "data-transfer-upload",
+    "delete",
+    "dial",
+    "document",
+    "dollar",
+    "double-quote-sans-left",
+    "double-quote-sans-right",
+    "double-quote-serif-left",
+    "double-quote-serif-right",
+    "droplet",
+    "eject",
+    "elevator",
+    "ellipses",
+    "envelope-closed",
+    "envelope-open",
+    "euro",
+    "excerpt",
+    "expand-down",
+    "expand-left",
+    "expand-right",
+    "expand-up",
+    "external-link",
+    "eye",
+    "eyedropper",
+    "file",
+    "fire",
+    "flag",
+    "flash",
+    "folder",
+    "fork",
+    "fullscreen-enter",
+    "fullscreen-exit",
+    "globe",
+    "graph",
+    "grid-four-up",
+    "grid-three-up",
+    "grid-two-up",
+    "hard-drive",
+    "header",
+    "headphones",
+    "heart",
+    "home",
+    "image",
+    "inbox",
+    "infinity",
+    "info",
+    "italic",
+    "justify-center",
+    "justify-left",
+    "justify-right",
+    "key",
+    "laptop",
+    "layers",
+    "lightbulb",
+    "link-broken",
+    "link-intact",
+    "list",
+    "list-rich",
+    "location",
+    "lock-locked",
+    "lock-unlocked",
+    "loop",
+    "loop-circular",
+    "loop-square",
+    "magnifying-glass",
+    "map",
+    "map-marker",
+    "media-pause",
+    "media-play",
+    "media-record",
+    "media-skip-backward",
+    "media-skip-forward",
+    "media-step-backward",
+    "media-step-forward",
+    "media-stop",
+    "medical-cross",
+    "menu",
+    "microphone",
+    "minus",
+    "monitor",
+    "moon",
+    "move",
+    "musical-note",
+    "paperclip",
+    "pencil",
+    "people",
+    "person",
+    "phone",
+    "pie-chart",
+    "pin",
+    "play-circle",
+    "plus",
+    "power-standby",
+    "print",
+    "project",
+    "pulse",
+    "puzzle-piece",
+    "question-mark",
+    "rain",
+    "random",
+    "reload",
+    "resize-both",
+    "resize-height",
+    "resize-width",
+    "rss",
+    "rss-alt",
+    "script",
+    "share",
+    "share-boxed",
+    "shield",
+    "signal",
+    "signpost",
+    "sort-ascending",
+    "sort-descending",
+    "spreadsheet",
+    "star",
+    "sun",
+    "tablet",
+    "tag",
+    "tags",
+    "target",
+    "task",
+    "terminal",
+    "text",
+    "thumb-down",
+    "thumb-up",
+    "timer",
+    "transfer",
+    "trash",
+    "underline",
+    "vertical-align-bottom",
+    "vertical-align-center",
+    "vertical-align-top",
+    "video",
+    "volume-high",
+    "volume-low",
+    "volume-off",
+    "warning",
+    "wifi",
+    "wrench",
+    "x",
+    "yen",
+    "zoom-in",
+    "zoom-out",
+];
+
+/// The type of the value each tag that takes one takes, as in `<size:12>`
+fn tag_value(name: &str) -> Option<ValueType> {
+    match name {
+        "color" | "back" => Some(ValueType::Color),
+        "size" => Some(ValueType::Number),
+        _ => None,
+    }
+}
+
+fn warning(range: TextRange, message: String) -> Problem {
+    Problem {
+        range,
+        severity: Severity::Warning,
+        message,
+    }
+}
+
+/// Icons that aren't in the OpenIconic set, and tags with values of the
+/// wrong type, such as `<color:bleu>`
+pub(crate) fn problems(root: &RootNode) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for label in root.labels() {
+        for icon in label.icons() {
+            // the name may be followed by a scale, as in `<&heart*2>`
+            let text = &icon.text()[2..];
+            let end = text
+                .find(|it: char| !(it.is_ascii_alphanumeric() || it == '-'))
+                .unwrap_or(text.len());
+            let name = &text[..end];
+            if !ICONS.contains(&name) {
+                let start = icon.text_range().start() + TextSize::from(2);
+                let range = TextRange::at(start, TextSize::from(name.len() as u32));
+                problems.push(warning(range, format!("unknown icon '{}'", name)));
+            }
+        }
+        for tag in label.tags() {
+            let (value, range) = match (tag_value(&tag.name()), tag.value()) {
+                (Some(kind), Some((value, range))) => (kind.check(&value), range),
+                _ => continue,
+            };
+            problems.extend(value.map(|message| warning(range, message)));
+        }
+    }
+    problems
+}
+
+mod tests {
+    #[test]
+    fn test_problems() {
+        let text = "@startuml\nA -> B : <&heart> <&hart> <color:red>ok</color> <color:bleu>no\n:<size:big>**bold**;\n@enduml\n";
+        let parsed = crate::parse(text);
+        let problems = super::problems(&parsed.root())
+            .into_iter()
+            .map(|it| (&text[it.range], it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            vec![
+                ("hart", "unknown icon 'hart'".to_string()),
+                ("bleu", "expected a color, not 'bleu'".into()),
+                ("big", "expected a number, not 'big'".into()),
+            ]
+        );
+    }
+}
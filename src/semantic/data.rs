@@ -0,0 +1,220 @@
+//! The data of `@startjson` and `@startyaml` diagrams, checked with real JSON
+//! and YAML parsers
+use crate::ast::{DataHighlightNode, DataNode, RootNode};
+use crate::semantic::{Problem, Severity};
+use rowan::{TextRange, TextSize};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// The parts of the data a `#highlight` path can walk through
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Object(Vec<(String, Value)>),
+    Array(Vec<Value>),
+    Scalar,
+}
+
+impl Value {
+    /// The value at the end of `path`, or the index of the first key that
+    /// doesn't exist
+    pub(crate) fn lookup<'a>(&'a self, path: &[String]) -> Result<&'a Value, usize> {
+        let mut value = self;
+        for (index, key) in path.iter().enumerate() {
+            value = match value {
+                Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+                Value::Array(items) => key.parse::<usize>().ok().and_then(|it| items.get(it)),
+                Value::Scalar => None,
+            }
+            .ok_or(index)?;
+        }
+        Ok(value)
+    }
+
+    /// The keys, or indices, a path can continue with
+    pub(crate) fn keys(&self) -> Vec<String> {
+        match self {
+            Value::Object(entries) => entries.iter().map(|(key, _)| key.clone()).collect(),
+            Value::Array(items) => (0..items.len()).map(|it| it.to_string()).collect(),
+            Value::Scalar => Vec::new(),
+        }
+    }
+
+    fn from_json(json: serde_json::Value) -> Value {
+        match json {
+            serde_json::Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, Value::from_json(value)))
+                    .collect(),
+            ),
+            serde_json::Value::Array(items) => {
+                Value::Array(items.into_iter().map(Value::from_json).collect())
+            }
+            _ => Value::Scalar,
+        }
+    }
+
+    fn from_yaml(yaml: Yaml) -> Value {
+        match yaml {
+            Yaml::Hash(hash) => Value::Object(
+                hash.into_iter()
+                    .map(|(key, value)| (yaml_key(key), Value::from_yaml(value)))
+                    .collect(),
+            ),
+            Yaml::Array(items) => Value::Array(items.into_iter().map(Value::from_yaml).collect()),
+            _ => Value::Scalar,
+        }
+    }
+}
+
+fn yaml_key(key: Yaml) -> String {
+    match key {
+        Yaml::String(it) | Yaml::Real(it) => it,
+        Yaml::Integer(it) => it.to_string(),
+        Yaml::Boolean(it) => it.to_string(),
+        _ => "~".into(),
+    }
+}
+
+/// The offset in `text` of the 1-based `line` and 0-based `column`, counted
+/// in characters
+fn offset(text: &str, line: usize, column: usize) -> TextSize {
+    let start: usize = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(|it| it.len())
+        .sum();
+    let within: usize = text[start..]
+        .chars()
+        .take_while(|it| *it != '\n')
+        .take(column)
+        .map(|it| it.len_utf8())
+        .sum();
+    TextSize::from((start + within) as u32)
+}
+
+/// The parser's message, without the position it already appends
+fn message(error: &str) -> String {
+    error.split(" at line ").next().unwrap_or(error).to_string()
+}
+
+/// Parse the data, or say where and why it isn't valid
+pub(crate) fn parse(data: &DataNode) -> Result<Value, Problem> {
+    let text = data.syntax().text().to_string();
+    let start = data.syntax().text_range().start();
+    let (result, position) = if data.is_yaml() {
+        match YamlLoader::load_from_str(&text) {
+            Ok(documents) => (
+                Ok(documents
+                    .into_iter()
+                    .next()
+                    .map(Value::from_yaml)
+                    .unwrap_or(Value::Scalar)),
+                TextSize::from(0),
+            ),
+            Err(error) => {
+                let marker = error.marker();
+                (
+                    Err(message(&error.to_string())),
+                    offset(&text, marker.line(), marker.col()),
+                )
+            }
+        }
+    } else {
+        match serde_json::from_str::<serde_json::Value>(&text) {
+            Ok(json) => (Ok(Value::from_json(json)), TextSize::from(0)),
+            Err(error) => (
+                Err(message(&error.to_string())),
+                offset(&text, error.line(), error.column().saturating_sub(1)),
+            ),
+        }
+    };
+    result.map_err(|message| {
+        let at = start + position;
+        // point at the character the parser stopped on, if there is one
+        let end = text[usize::from(position)..]
+            .chars()
+            .next()
+            .filter(|it| *it != '\n')
+            .map(|it| at + TextSize::of(it))
+            .unwrap_or(at);
+        Problem {
+            range: TextRange::new(at, end),
+            severity: Severity::Error,
+            message,
+        }
+    })
+}
+
+/// Where `#highlight` paths lead to nothing
+fn check_highlight(highlight: &DataHighlightNode, value: &Value) -> Option<Problem> {
+    let path = highlight.path();
+    let keys = path.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+    let missing = value.lookup(&keys).err()?;
+    let message = match missing {
+        0 => format!("\"{}\" is not in the data", keys[0]),
+        _ => format!(
+            "\"{}\" is not in \"{}\"",
+            keys[missing],
+            keys[..missing].join("\" / \"")
+        ),
+    };
+    Some(Problem {
+        range: path[missing].1,
+        severity: Severity::Warning,
+        message,
+    })
+}
+
+/// Invalid data and `#highlight` paths that don't match it
+pub(crate) fn problems(root: &RootNode) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for data in root.data_nodes() {
+        if let Err(problem) = parse(&data) {
+            problems.push(problem);
+        }
+    }
+    for highlight in root.data_highlights() {
+        let value = match highlight.data().map(|it| parse(&it)) {
+            Some(Ok(value)) => value,
+            _ => continue,
+        };
+        problems.extend(check_highlight(&highlight, &value));
+    }
+    problems
+}
+
+mod tests {
+    #[test]
+    fn test_invalid_data() {
+        let text = "@startjson\n{\n  \"a\": 1,\n  \"b\" 2\n}\n@endjson\n@startyaml\nkey: [1, 2\n@endyaml\n";
+        let doc = crate::workspace::Document::new(
+            lsp_types::Url::parse("file:///test.puml").unwrap(),
+            text.into(),
+        );
+        let problems = super::problems(&doc.parsed.root())
+            .into_iter()
+            .map(|it| (doc.range(it.range).start, it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert_eq!(problems[0].0, lsp_types::Position::new(3, 6));
+        assert_eq!(problems[0].1, "expected `:`");
+        assert_eq!(problems[1].0.line, 8);
+    }
+
+    #[test]
+    fn test_highlight_paths() {
+        let text = "@startyaml\n#highlight \"address\" / \"city\"\n#highlight \"address\" / \"town\"\n#highlight \"phones\" / \"1\"\n#highlight \"nope\"\naddress:\n  city: Paris\nphones:\n  - 555\n@endyaml\n";
+        let root = crate::parse(text).root();
+        let problems = super::problems(&root)
+            .into_iter()
+            .map(|it| it.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            vec![
+                "\"town\" is not in \"address\"",
+                "\"1\" is not in \"phones\"",
+                "\"nope\" is not in the data",
+            ]
+        );
+    }
+}
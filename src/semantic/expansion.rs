@@ -0,0 +1,819 @@
+//! The text PlantUML ends up seeing once the preprocessor has run: includes
+//! pulled in, conditionals decided, and variables, macros, procedures and
+//! builtins such as `%date()` expanded, along with where each of its lines
+//! comes from
+use crate::parsing::preprocessor::INCLUDES;
+use crate::semantic::gantt::Date;
+use crate::semantic::preprocessor::{expression, Scope, Value};
+use crate::workspace::{Document, Workspace};
+use lsp_types::{Position, Range, Url};
+use std::collections::{HashMap, HashSet};
+
+/// How deeply calls and includes may nest before they're left unexpanded
+const MAX_DEPTH: usize = 64;
+
+/// Where a line of the expanded text comes from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SourceLine {
+    pub(crate) uri: Url,
+    /// The line of `uri` it was written on
+    pub(crate) line: u64,
+    /// The line of the expanded document that's responsible for it: the line
+    /// itself, or the `!include` or call that produced it
+    pub(crate) anchor: u64,
+    /// Whether a procedure or macro wrote the line, rather than it being
+    /// copied from a document
+    pub(crate) generated: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct Expansion {
+    pub(crate) text: String,
+    /// Where each line of `text` comes from
+    pub(crate) lines: Vec<SourceLine>,
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    uri: Url,
+    line: u64,
+    text: String,
+}
+
+fn lines(document: &Document) -> Vec<Line> {
+    let mut text = document.text.as_str();
+    if text.ends_with('\n') {
+        text = &text[..text.len() - 1];
+    }
+    text.split('\n')
+        .enumerate()
+        .map(|(index, text)| Line {
+            uri: document.uri.clone(),
+            line: index as u64,
+            text: text.trim_end_matches('\r').to_string(),
+        })
+        .collect()
+}
+
+/// `$a, $b="x"` as names and default values
+type Params = Vec<(String, Option<String>)>;
+
+#[derive(Debug, Clone)]
+struct Procedure {
+    params: Params,
+    body: Vec<Line>,
+    is_function: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Macro {
+    params: Option<Params>,
+    body: String,
+}
+
+/// The state of an `!if` that's being expanded
+struct Frame {
+    /// Whether the current branch is taken
+    active: bool,
+    /// Whether one of the branches so far was taken
+    taken: bool,
+    /// Whether the `!if` itself is in an active branch
+    outer: bool,
+}
+
+/// A procedure or function that's running
+#[derive(Default)]
+struct Call {
+    variables: HashMap<String, Value>,
+    is_function: bool,
+    returned: Option<Value>,
+}
+
+impl Expansion {
+    /// Where a range of the expanded text is in the document that was
+    /// expanded: the same range for a line copied from it, or the whole of the
+    /// line that produced any other
+    pub(crate) fn original(&self, document: &Document, range: Range) -> Option<Range> {
+        let source = self.lines.get(range.start.line as usize)?;
+        if !source.generated && source.uri == document.uri {
+            let end = if range.end.line == range.start.line {
+                range.end.character
+            } else {
+                u64::MAX
+            };
+            let start = Position::new(source.line, range.start.character);
+            let end = document.offset(Position::new(source.line, end))?;
+            return Some(Range::new(start, document.position(end)));
+        }
+        let end = document.offset(Position::new(source.anchor, u64::MAX))?;
+        Some(Range::new(
+            Position::new(source.anchor, 0),
+            document.position(end),
+        ))
+    }
+}
+
+/// How the lines being expanded got there
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    /// The line of the expanded document that brought them in
+    anchor: Option<u64>,
+    generated: bool,
+    included: bool,
+}
+
+struct Expander<'a> {
+    workspace: &'a Workspace,
+    globals: HashMap<String, Value>,
+    calls: Vec<Call>,
+    macros: HashMap<String, Macro>,
+    procedures: HashMap<String, Procedure>,
+    included: HashSet<Url>,
+    /// The documents being expanded, the innermost last
+    files: Vec<Url>,
+    output: Vec<(String, SourceLine)>,
+}
+
+/// Run the preprocessor over the document
+pub(crate) fn expand(workspace: &Workspace, document: &Document) -> Expansion {
+    let mut expander = Expander {
+        workspace,
+        globals: HashMap::new(),
+        calls: Vec::new(),
+        macros: HashMap::new(),
+        procedures: HashMap::new(),
+        included: HashSet::new(),
+        files: vec![document.uri.clone()],
+        output: Vec::new(),
+    };
+    expander.run(&lines(document), Context::default());
+    let (texts, lines): (Vec<_>, Vec<_>) = expander.output.into_iter().unzip();
+    let mut text = texts.join("\n");
+    if !texts.is_empty() {
+        text.push('\n');
+    }
+    Expansion { text, lines }
+}
+
+/// The directive a line starts with, if any, and the rest of the line
+fn split_directive(line: &str) -> (Option<&str>, &str) {
+    let line = line.trim();
+    if !line.starts_with('!') {
+        return (None, line);
+    }
+    let len = 1 + line[1..]
+        .find(|it: char| !(it.is_ascii_lowercase() || it == '_'))
+        .unwrap_or(line.len() - 1);
+    (Some(&line[..len]), line[len..].trim())
+}
+
+fn word_len(text: &str) -> usize {
+    text.find(|it: char| !(it.is_alphanumeric() || it == '_'))
+        .unwrap_or(text.len())
+}
+
+/// The name at the start of `text`, which may start with a `$`
+fn name(text: &str) -> &str {
+    let start = if text.starts_with('$') { 1 } else { 0 };
+    &text[..start + word_len(&text[start..])]
+}
+
+/// Where the `)` closing the `(` that `text` follows is, skipping over
+/// strings and nested parentheses
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth == 0 => return Some(index),
+            (None, ')') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The arguments of a call, split on the commas that aren't nested
+fn split_arguments(text: &str) -> Vec<String> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                arguments.push(text[start..index].trim().to_string());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(text[start..].trim().to_string());
+    arguments
+}
+
+/// The `$a, $b="x"` between the parentheses of a definition
+fn parse_params(text: &str) -> Params {
+    split_arguments(text)
+        .into_iter()
+        .map(|param| match param.split_once('=') {
+            Some((name, default)) => (name.trim().into(), Some(default.trim().into())),
+            None => (param, None),
+        })
+        .collect()
+}
+
+/// `text` with every occurrence of the word `from` replaced by `to`
+fn replace_word(text: &str, from: &str, to: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_alphanumeric() || c == '_' || c == '$' {
+            let len = name(rest).len().max(c.len_utf8());
+            let word = &rest[..len];
+            result.push_str(if word == from { to } else { word });
+            rest = &rest[len..];
+        } else {
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    result
+}
+
+impl Expander<'_> {
+    fn run(&mut self, lines: &[Line], context: Context) {
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut index = 0;
+        while index < lines.len() {
+            if self.calls.last().map(|it| it.returned.is_some()) == Some(true) {
+                break;
+            }
+            let line = &lines[index];
+            index += 1;
+            let (directive, rest) = split_directive(&line.text);
+            let active = frames.iter().all(|it| it.active);
+            match directive {
+                Some("!if") | Some("!ifdef") | Some("!ifndef") => {
+                    let holds = active && self.condition(directive.unwrap(), rest);
+                    frames.push(Frame {
+                        active: holds,
+                        taken: holds,
+                        outer: active,
+                    });
+                    continue;
+                }
+                Some("!elseif") => {
+                    if let Some(frame) = frames.last() {
+                        let holds = frame.outer && !frame.taken && self.condition("!if", rest);
+                        let frame = frames.last_mut().unwrap();
+                        frame.active = holds;
+                        frame.taken |= holds;
+                    }
+                    continue;
+                }
+                Some("!else") => {
+                    if let Some(frame) = frames.last_mut() {
+                        frame.active = frame.outer && !frame.taken;
+                        frame.taken = true;
+                    }
+                    continue;
+                }
+                Some("!endif") => {
+                    frames.pop();
+                    continue;
+                }
+                _ if !active => continue,
+                _ => {}
+            }
+            match directive {
+                Some("!import") => {}
+                Some(it) if INCLUDES.contains(&it) => self.include(it, rest, line, context),
+                Some("!") | Some("!local") | Some("!global") | Some("!assign")
+                    if rest.starts_with('$') =>
+                {
+                    self.assign(directive.unwrap(), rest)
+                }
+                Some("!define") => self.define(rest, None),
+                Some("!definelong") => {
+                    let body = lines[index..]
+                        .iter()
+                        .take_while(|it| split_directive(&it.text).0 != Some("!enddefinelong"))
+                        .map(|it| it.text.as_str())
+                        .collect::<Vec<_>>();
+                    index += body.len() + 1;
+                    self.define(rest, Some(body.join("\n")));
+                }
+                Some("!undef") => {
+                    self.macros.remove(rest);
+                }
+                Some("!procedure") | Some("!function") | Some("!unquoted") => {
+                    index += self.procedure(directive.unwrap(), rest, line, &lines[index..]);
+                }
+                Some("!return") => {
+                    let value = self.evaluate(rest);
+                    if let Some(call) = self.calls.last_mut() {
+                        call.returned = Some(value);
+                    }
+                }
+                Some("!startsub") | Some("!endsub") | Some("!log") | Some("!dump_memory")
+                | Some("!assert") => {}
+                // a function only returns a value, it doesn't write lines
+                _ if self.calls.last().map(|it| it.is_function) == Some(true) => {}
+                // an included file's '@startuml' and '@enduml' are left out
+                _ if context.included && rest.starts_with('@') => {}
+                _ => {
+                    if !self.call_procedure(line, context) {
+                        let text = self.substitute(&line.text);
+                        self.emit(&text, line, context);
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self, text: &str, line: &Line, context: Context) {
+        let parts = text.split('\n').collect::<Vec<_>>();
+        for part in &parts {
+            self.output.push((
+                part.to_string(),
+                SourceLine {
+                    uri: line.uri.clone(),
+                    line: line.line,
+                    anchor: context.anchor.unwrap_or(line.line),
+                    generated: context.generated || parts.len() > 1,
+                },
+            ));
+        }
+    }
+
+    fn condition(&mut self, directive: &str, rest: &str) -> bool {
+        match directive {
+            "!ifdef" => self.is_defined(rest) == Some(true),
+            "!ifndef" => self.is_defined(rest) != Some(true),
+            // PlantUML would complain about what can't be evaluated
+            _ => expression(rest, self).map(|it| it.is_true()) == Some(true),
+        }
+    }
+
+    /// The value of an expression, or the text itself with what can be
+    /// substituted in it when it isn't one
+    fn evaluate(&mut self, text: &str) -> Value {
+        match expression(text, self) {
+            Some(value) => value,
+            None => Value::Str(self.substitute(text.trim())),
+        }
+    }
+
+    fn include(&mut self, directive: &str, rest: &str, line: &Line, context: Context) {
+        let name = rest.rfind('/').map(|it| it + 1).unwrap_or(0);
+        let (path, sub) = match rest[name..].find('!') {
+            Some(bang) => (&rest[..name + bang], Some(&rest[name + bang + 1..])),
+            None => (rest, None),
+        };
+        let from = self.files.last().unwrap().clone();
+        let document = self
+            .workspace
+            .resolve(&from, path)
+            .and_then(|uri| self.workspace.get(&uri));
+        let document = match document {
+            Some(document) if !path.starts_with('<') => document,
+            // the standard library, and files that can't be found, are left as
+            // they are
+            _ => return self.emit(&line.text, line, context),
+        };
+        let uri = document.uri.clone();
+        let once = directive != "!include_many" && sub.is_none();
+        if self.files.contains(&uri) || self.files.len() > MAX_DEPTH {
+            return;
+        }
+        if !self.included.insert(uri.clone()) && once {
+            return;
+        }
+        let mut lines = lines(document);
+        if let Some(sub) = sub {
+            lines = lines
+                .into_iter()
+                .skip_while(|it| split_directive(&it.text) != (Some("!startsub"), sub))
+                .skip(1)
+                .take_while(|it| split_directive(&it.text).0 != Some("!endsub"))
+                .collect();
+        }
+        self.files.push(uri);
+        let context = Context {
+            anchor: Some(context.anchor.unwrap_or(line.line)),
+            included: true,
+            ..context
+        };
+        self.run(&lines, context);
+        self.files.pop();
+    }
+
+    /// `!$name = value`, `!$name ?= value` or `!local $name = value`
+    fn assign(&mut self, directive: &str, rest: &str) {
+        let variable = name(rest).to_string();
+        let rest = rest[variable.len()..].trim();
+        let (is_default, value) = if let Some(value) = rest.strip_prefix("?=") {
+            (true, value)
+        } else if let Some(value) = rest.strip_prefix('=') {
+            (false, value)
+        } else {
+            return;
+        };
+        if is_default && self.variable(&variable).is_some() {
+            return;
+        }
+        let value = self.evaluate(value);
+        let local = match self.calls.last() {
+            Some(call) => {
+                directive == "!local"
+                    || directive != "!global" && call.variables.contains_key(&variable)
+            }
+            None => false,
+        };
+        if local {
+            self.calls
+                .last_mut()
+                .unwrap()
+                .variables
+                .insert(variable, value);
+        } else {
+            self.globals.insert(variable, value);
+        }
+    }
+
+    /// `!define NAME value`, `!define NAME(a, b) value`, or a `!definelong`
+    /// with its body
+    fn define(&mut self, rest: &str, body: Option<String>) {
+        let macro_name = name(rest).to_string();
+        let rest = &rest[macro_name.len()..];
+        let (params, value) = match rest.strip_prefix('(') {
+            Some(inner) => match closing_paren(inner) {
+                Some(end) => (Some(parse_params(&inner[..end])), &inner[end + 1..]),
+                None => return,
+            },
+            None => (None, rest),
+        };
+        let body = body.unwrap_or_else(|| value.trim().to_string());
+        self.macros.insert(macro_name, Macro { params, body });
+    }
+
+    /// A `!procedure` or `!function`, returning how many of the lines after
+    /// it are its body
+    fn procedure(&mut self, directive: &str, rest: &str, line: &Line, after: &[Line]) -> usize {
+        let (keyword, rest) = match directive {
+            "!unquoted" => {
+                let keyword = name(rest);
+                (keyword, rest[keyword.len()..].trim())
+            }
+            it => (&it[1..], rest),
+        };
+        let procedure_name = name(rest).to_string();
+        let inner = match rest[procedure_name.len()..].strip_prefix('(') {
+            Some(inner) => inner,
+            None => return 0,
+        };
+        let end = closing_paren(inner).unwrap_or(inner.len());
+        let params = parse_params(&inner[..end]);
+        let tail = inner.get(end + 1..).unwrap_or("").trim();
+        let (body, used) = if tail.starts_with("!return") {
+            // '!function $double($x) !return $x * 2'
+            let body = Line {
+                text: tail.to_string(),
+                ..line.clone()
+            };
+            (vec![body], 0)
+        } else {
+            let body = after
+                .iter()
+                .take_while(|it| {
+                    !matches!(
+                        split_directive(&it.text).0,
+                        Some("!endprocedure") | Some("!endfunction")
+                    )
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            let used = body.len() + 1;
+            (body, used)
+        };
+        let procedure = Procedure {
+            params,
+            body,
+            is_function: keyword == "function",
+        };
+        self.procedures.insert(procedure_name, procedure);
+        used
+    }
+
+    /// Bind the arguments of a call to the parameters of the procedure, given
+    /// either in order or as `$name=value`
+    fn bind(
+        &mut self,
+        params: &[(String, Option<String>)],
+        arguments: Vec<(Option<String>, Value)>,
+    ) -> Call {
+        let mut variables = HashMap::new();
+        let mut position = 0;
+        for (keyword, value) in arguments {
+            let param = match keyword {
+                Some(keyword) => keyword,
+                None => match params.get(position) {
+                    Some((param, _)) => {
+                        position += 1;
+                        param.clone()
+                    }
+                    None => continue,
+                },
+            };
+            variables.insert(param, value);
+        }
+        for (param, default) in params {
+            if !variables.contains_key(param) {
+                let value = match default {
+                    Some(default) => self.evaluate(default),
+                    None => Value::Str(String::new()),
+                };
+                variables.insert(param.clone(), value);
+            }
+        }
+        Call {
+            variables,
+            ..Call::default()
+        }
+    }
+
+    /// A line that calls a procedure, such as `$box("a")` or
+    /// `Container(api, "API") {`, replaced by the lines the procedure writes
+    fn call_procedure(&mut self, line: &Line, context: Context) -> bool {
+        let text = line.text.trim_start();
+        let called = name(text);
+        let procedure = match self.procedures.get(called) {
+            Some(it) if !it.is_function && self.calls.len() < MAX_DEPTH => it.clone(),
+            _ => return false,
+        };
+        let inner = match text[called.len()..].strip_prefix('(') {
+            Some(inner) => inner,
+            None => return false,
+        };
+        let end = match closing_paren(inner) {
+            Some(end) => end,
+            None => return false,
+        };
+        let arguments = split_arguments(&inner[..end])
+            .into_iter()
+            .map(|argument| {
+                let keyword = name(&argument);
+                match argument[keyword.len()..].trim_start().strip_prefix('=') {
+                    Some(value) if keyword.starts_with('$') && !value.starts_with('=') => {
+                        (Some(keyword.to_string()), self.evaluate(value))
+                    }
+                    _ => (None, self.evaluate(&argument)),
+                }
+            })
+            .collect();
+        let call = self.bind(&procedure.params, arguments);
+        let written = self.output.len();
+        self.calls.push(call);
+        let inner_context = Context {
+            anchor: Some(context.anchor.unwrap_or(line.line)),
+            generated: true,
+            ..context
+        };
+        self.run(&procedure.body, inner_context);
+        self.calls.pop();
+        // whatever follows the call, such as the '{' of a boundary, goes on
+        // the last line it wrote
+        let tail = self.substitute(&inner[end + 1..]);
+        if !tail.trim().is_empty() {
+            match self.output.get_mut(written..).and_then(|it| it.last_mut()) {
+                Some((last, _)) => last.push_str(&tail),
+                None => self.emit(tail.trim(), line, inner_context),
+            }
+        }
+        true
+    }
+
+    /// Run a function, or a builtin that needs more than its arguments
+    fn run_function(&mut self, name: &str, arguments: Vec<Value>) -> Option<Value> {
+        let function = match self.procedures.get(name) {
+            Some(it) if it.is_function && self.calls.len() < MAX_DEPTH => it.clone(),
+            _ => return None,
+        };
+        let arguments = arguments.into_iter().map(|it| (None, it)).collect();
+        let mut call = self.bind(&function.params, arguments);
+        call.is_function = true;
+        self.calls.push(call);
+        self.run(&function.body, Context::default());
+        self.calls.pop()?.returned
+    }
+
+    /// The line with its variables, macros and calls replaced by their values
+    fn substitute(&mut self, text: &str) -> String {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if !(c.is_alphabetic() || c == '_' || c == '$' || c == '%') {
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let word = if c == '%' {
+                &rest[..1 + word_len(&rest[1..])]
+            } else {
+                name(rest)
+            };
+            let word = if word.is_empty() { &rest[..1] } else { word };
+            let after = &rest[word.len()..];
+            if let Some(end) = after.strip_prefix('(').and_then(closing_paren) {
+                let call = &rest[..word.len() + end + 2];
+                if let Some(value) = self.expand_call(word, &after[1..end + 1], call) {
+                    result.push_str(&value);
+                    rest = &rest[call.len()..];
+                    continue;
+                }
+            }
+            let value = if c == '$' {
+                self.variable(word).map(|it| it.to_string())
+            } else {
+                match self.macros.get(word) {
+                    Some(it) if it.params.is_none() && self.calls.len() < MAX_DEPTH => {
+                        Some(it.body.clone())
+                    }
+                    _ => None,
+                }
+            };
+            result.push_str(value.as_deref().unwrap_or(word));
+            rest = after;
+        }
+        result
+    }
+
+    /// The value of `call`, a call of `name` with `arguments`
+    fn expand_call(&mut self, name: &str, arguments: &str, call: &str) -> Option<String> {
+        if let Some(Macro {
+            params: Some(params),
+            body,
+        }) = self.macros.get(name).cloned()
+        {
+            // a macro that uses itself is left as it is once it's deep enough
+            if self.calls.len() >= MAX_DEPTH {
+                return Some(call.to_string());
+            }
+            let mut body = body;
+            let values = split_arguments(arguments);
+            for (index, (param, default)) in params.iter().enumerate() {
+                let value = values.get(index).or(default.as_ref()).cloned();
+                body = replace_word(&body, param, &value.unwrap_or_default());
+            }
+            // the macro's body may use other macros, and the variables of the
+            // procedure it's called in
+            let variables = self
+                .calls
+                .last()
+                .map(|it| it.variables.clone())
+                .unwrap_or_default();
+            self.calls.push(Call {
+                variables,
+                ..Call::default()
+            });
+            let body = self.substitute(&body);
+            self.calls.pop();
+            return Some(body);
+        }
+        expression(call, self).map(|it| it.to_string())
+    }
+}
+
+impl Scope for Expander<'_> {
+    fn variable(&self, name: &str) -> Option<Value> {
+        self.calls
+            .last()
+            .and_then(|it| it.variables.get(name))
+            .or_else(|| self.globals.get(name))
+            .cloned()
+    }
+
+    fn is_defined(&self, name: &str) -> Option<bool> {
+        Some(
+            self.macros.contains_key(name)
+                || self.procedures.contains_key(name)
+                || self.variable(name).is_some(),
+        )
+    }
+
+    fn define(&self, name: &str) -> Option<String> {
+        let define = self.macros.get(name)?;
+        match define.params {
+            None => Some(define.body.clone()),
+            Some(_) => None,
+        }
+    }
+
+    fn call(&mut self, name: &str, arguments: Vec<Value>) -> Option<Value> {
+        match (name, arguments.as_slice()) {
+            ("%date", []) => Some(Value::Str(Date::today().to_string())),
+            // only the year, month and day of the Java date formats
+            ("%date", [format]) => {
+                let today = Date::today().to_string();
+                let format = format
+                    .to_string()
+                    .replace("yyyy", &today[0..4])
+                    .replace("MM", &today[5..7])
+                    .replace("dd", &today[8..10]);
+                Some(Value::Str(format))
+            }
+            ("%filename", []) => {
+                let file = self.files.last()?;
+                let name = file.path_segments()?.next_back()?;
+                Some(Value::Str(name.to_string()))
+            }
+            _ => self.run_function(name, arguments),
+        }
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_expansion() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///lib.puml"),
+            "@startuml\n!procedure $box($name, $color=\"red\")\n  rectangle \"$name\" #$color\n!endprocedure\n!function $double($x) !return $x * 2\n@enduml\n".into(),
+        );
+        workspace.update(
+            url("file:///main.puml"),
+            "@startuml\n!include lib.puml\n!$n = 3\n!define TITLE Hello\n!if $double($n) > 5\n$box(\"a\")\n!else\n$box(\"b\")\n!endif\n$box($name=\"c\", $color=\"blue\") as C\ntitle TITLE %strlen(\"abc\") $double($n)\n@enduml\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let expansion = super::expand(&workspace, doc);
+        assert_eq!(
+            expansion.text,
+            "@startuml\n  rectangle \"a\" #red\n  rectangle \"c\" #blue as C\ntitle Hello 3 6\n@enduml\n"
+        );
+        let lines = expansion
+            .lines
+            .iter()
+            .map(|it| (it.uri.path(), it.line, it.anchor, it.generated))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                ("/main.puml", 0, 0, false),
+                ("/lib.puml", 2, 5, true),
+                ("/lib.puml", 2, 9, true),
+                ("/main.puml", 10, 10, false),
+                ("/main.puml", 11, 11, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_builtins_and_macros() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///main.puml"),
+            "!define SHOUT(x) x!\n!definelong TWO(a)\nclass a\nclass a2\n!enddefinelong\nnote : SHOUT(hi) %upper(\"b\")\nTWO(X)\nfooter %date(\"dd/MM/yyyy\") %filename()\n!include <C4/C4>\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let expansion = super::expand(&workspace, doc);
+        let lines = expansion.text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "note : hi! B");
+        assert_eq!(lines[1], "class X");
+        // only whole words are parameters
+        assert_eq!(lines[2], "class a2");
+        assert!(expansion.lines[1].generated);
+        assert!(lines[3].starts_with("footer "), "{}", lines[3]);
+        assert!(lines[3].ends_with(" main.puml"), "{}", lines[3]);
+        assert_eq!(lines[3].len(), "footer dd/MM/yyyy main.puml".len());
+        assert_eq!(lines[4], "!include <C4/C4>");
+    }
+
+    #[test]
+    fn test_expand_recursive_macro() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///main.puml"),
+            "!define LOOP(x) LOOP(x)\n!define GREET(x) x $who\n!procedure $hello($who)\nA -> B : GREET(hi)\n!endprocedure\nA -> B : LOOP(1)\n$hello(\"Bob\")\n".into(),
+        );
+        let doc = workspace.get(&url("file:///main.puml")).unwrap();
+        let expansion = super::expand(&workspace, doc);
+        let lines = expansion.text.lines().collect::<Vec<_>>();
+        assert_eq!(lines, vec!["A -> B : LOOP(1)", "A -> B : hi Bob"]);
+    }
+}
@@ -0,0 +1,619 @@
+//! Scheduling of the tasks of a `@startgantt` diagram: when each task starts
+//! and ends, given the project start, the closed days and the constraints
+//! between tasks
+use crate::ast::{ConstraintKind, DependencyNode, IdentifierNode, RootNode, TaskNode};
+use crate::semantic::{Problem, Severity};
+use crate::syntax::SyntaxKind::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A day of the calendar, counted from 1970-01-01
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Date(i64);
+
+impl Date {
+    /// Parse a `2020-07-01` date
+    pub(crate) fn parse(text: &str) -> Option<Date> {
+        let mut parts = text.trim().splitn(3, '-').map(|it| it.parse::<i64>());
+        let year = parts.next()?.ok()?;
+        let month = parts.next()?.ok()?;
+        let day = parts.next()?.ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        // see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some(Date(era * 146_097 + day_of_era - 719_468))
+    }
+
+    /// The current day, in UTC
+    pub(crate) fn today() -> Date {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|it| it.as_secs())
+            .unwrap_or(0);
+        Date(seconds as i64 / 86_400)
+    }
+
+    /// The day of the week, with monday as 0
+    pub(crate) fn weekday(self) -> usize {
+        // 1970-01-01 was a thursday
+        (self.0 + 3).rem_euclid(7) as usize
+    }
+
+    fn add(self, days: i64) -> Date {
+        Date(self.0 + days)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = self.0 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// How far to look for an open day before giving up, should every day of the
+/// week be closed. Durations and offsets are capped at this many days too, so
+/// that a typo such as `lasts 4000000000 days` can't hold up the server.
+const HORIZON: i64 = 3660;
+
+/// Which days work can be done on. Days are counted from the project start.
+struct Calendar {
+    start: Option<Date>,
+    closed_weekdays: [bool; 7],
+    /// Closed or reopened ranges of days, the last one that matches wins
+    exceptions: Vec<(i64, i64, bool)>,
+}
+
+impl Calendar {
+    fn new(root: &RootNode, start: Option<Date>) -> Self {
+        let mut closed_weekdays = [false; 7];
+        let mut exceptions = Vec::new();
+        for days in root.closed_days() {
+            let open = days.is_open();
+            if let Some(weekday) = days.weekday() {
+                closed_weekdays[weekday] = !open;
+                continue;
+            }
+            let (start, dates) = match start {
+                Some(start) => (start, days.dates()),
+                None => continue,
+            };
+            let mut range = dates.iter().filter_map(|it| Date::parse(it));
+            if let Some(first) = range.next() {
+                let last = range.next().unwrap_or(first);
+                exceptions.push((first.0 - start.0, last.0 - start.0, open));
+            }
+        }
+        Calendar {
+            start,
+            closed_weekdays,
+            exceptions,
+        }
+    }
+
+    fn is_open(&self, day: i64) -> bool {
+        let start = match self.start {
+            Some(start) => start,
+            // without dates there are no weekdays either
+            None => return true,
+        };
+        let exception = self
+            .exceptions
+            .iter()
+            .rev()
+            .find(|(first, last, _)| (*first..=*last).contains(&day));
+        match exception {
+            Some((_, _, open)) => *open,
+            None => !self.closed_weekdays[start.add(day).weekday()],
+        }
+    }
+
+    /// The first open day from `day` on, in the direction of `step`
+    fn next_open(&self, day: i64, step: i64) -> i64 {
+        (0..HORIZON)
+            .map(|it| day + it * step)
+            .find(|it| self.is_open(*it))
+            .unwrap_or(day)
+    }
+
+    /// The day `count` open days from `day`, which is itself the first of
+    /// them, in the direction of `step`
+    fn count_open(&self, day: i64, count: i64, step: i64) -> i64 {
+        let mut day = self.next_open(day, step);
+        let mut left = count.min(HORIZON) - 1;
+        // closed days in a row, to give up once there are no open days left
+        let mut closed = 0;
+        while left > 0 && closed < HORIZON {
+            day += step;
+            if self.is_open(day) {
+                left -= 1;
+                closed = 0;
+            } else {
+                closed += 1;
+            }
+        }
+        day
+    }
+}
+
+/// When a task starts or ends
+#[derive(Debug, Clone)]
+enum Anchor {
+    Day(i64),
+    Task {
+        name: String,
+        reference: IdentifierNode,
+        at_end: bool,
+        /// Open days after the other task's start or end, or before it if
+        /// negative
+        offset: i64,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Task {
+    name: String,
+    definition: IdentifierNode,
+    duration: Option<u32>,
+    milestone: bool,
+    start: Option<Anchor>,
+    end: Option<Anchor>,
+}
+
+/// The days a task takes place on, counted from the project start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) start: i64,
+    pub(crate) end: i64,
+}
+
+/// The tasks of a gantt diagram and the days they take place on
+pub(crate) struct Schedule {
+    start: Option<Date>,
+    tasks: Vec<Task>,
+    aliases: HashMap<String, String>,
+    spans: HashMap<String, Option<Span>>,
+    problems: Vec<Problem>,
+}
+
+impl Schedule {
+    pub(crate) fn new(root: &RootNode) -> Self {
+        let start = root
+            .project_start()
+            .and_then(|it| it.date())
+            .and_then(|it| Date::parse(&it));
+        let calendar = Calendar::new(root, start);
+        let mut schedule = Schedule {
+            start,
+            tasks: Vec::new(),
+            aliases: HashMap::new(),
+            spans: HashMap::new(),
+            problems: Vec::new(),
+        };
+        schedule.collect(root, &calendar);
+        let mut visiting = Vec::new();
+        for index in 0..schedule.tasks.len() {
+            let name = schedule.tasks[index].name.clone();
+            schedule.span(&name, &calendar, &mut visiting);
+        }
+        schedule
+    }
+
+    /// Gather the tasks and their constraints from the statements, in the
+    /// order of the document
+    fn collect(&mut self, root: &RootNode, calendar: &Calendar) {
+        let mut previous: Option<IdentifierNode> = None;
+        for node in root.syntax().descendants() {
+            match node.kind() {
+                TASK_STATEMENT => {
+                    let statement = TaskNode::cast(node).unwrap();
+                    let name = match statement.name() {
+                        Some(name) => name,
+                        None => continue,
+                    };
+                    self.check_references(&statement);
+                    let index = self.define(&name);
+                    if let Some(alias) = statement.alias() {
+                        let name = self.tasks[index].name.clone();
+                        self.aliases.insert(alias.value(), name);
+                    }
+                    if statement.follows_previous() {
+                        if let Some(previous) = previous.clone() {
+                            self.tasks[index].start = Some(Anchor::Task {
+                                name: self.name(&previous),
+                                reference: previous,
+                                at_end: true,
+                                offset: 0,
+                            });
+                        }
+                    }
+                    self.constrain(index, &statement, calendar);
+                    previous = Some(name);
+                }
+                DEPENDENCY => {
+                    let dependency = DependencyNode::cast(node).unwrap();
+                    let tasks = dependency.tasks().collect::<Vec<_>>();
+                    for task in &tasks {
+                        self.define(task);
+                    }
+                    for pair in tasks.windows(2) {
+                        let index = self.define(&pair[1]);
+                        self.tasks[index].start = Some(Anchor::Task {
+                            name: self.name(&pair[0]),
+                            reference: pair[0].clone(),
+                            at_end: true,
+                            offset: 0,
+                        });
+                    }
+                    previous = tasks.last().cloned();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The name of the task `identifier` refers to, by its name or alias
+    fn name(&self, identifier: &IdentifierNode) -> String {
+        let value = identifier.value();
+        self.aliases.get(&value).cloned().unwrap_or(value)
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.tasks.iter().position(|it| it.name == name)
+    }
+
+    /// The index of the task `identifier` names, defining it if this is its
+    /// first mention
+    fn define(&mut self, identifier: &IdentifierNode) -> usize {
+        let name = self.name(identifier);
+        if let Some(index) = self.find(&name) {
+            return index;
+        }
+        self.tasks.push(Task {
+            name,
+            definition: identifier.clone(),
+            duration: None,
+            milestone: false,
+            start: None,
+            end: None,
+        });
+        self.tasks.len() - 1
+    }
+
+    /// Tasks must be defined before another task starts or ends relative to
+    /// them
+    fn check_references(&mut self, statement: &TaskNode) {
+        let subject = statement.name().map(|it| self.name(&it));
+        for constraint in statement.constraints() {
+            let reference = match constraint.task() {
+                Some(reference) => reference,
+                None => continue,
+            };
+            let name = self.name(&reference);
+            if self.find(&name).is_some() || Some(&name) == subject.as_ref() {
+                continue;
+            }
+            let defined_later = statement
+                .syntax()
+                .parent()
+                .into_iter()
+                .flat_map(|it| it.descendants())
+                .filter_map(TaskNode::cast)
+                .filter(|it| {
+                    it.syntax().text_range().start() > statement.syntax().text_range().start()
+                })
+                .any(|it| {
+                    it.name().map(|n| n.value()) == Some(name.clone())
+                        || it.alias().map(|a| a.value()) == Some(name.clone())
+                });
+            let message = if defined_later {
+                format!("task '{}' is used before it is defined", name)
+            } else {
+                format!("task '{}' is never defined", name)
+            };
+            self.problems.push(Problem {
+                range: reference.position(),
+                severity: Severity::Warning,
+                message,
+            });
+        }
+    }
+
+    fn constrain(&mut self, index: usize, statement: &TaskNode, calendar: &Calendar) {
+        let task = &mut self.tasks[index];
+        if let Some(duration) = statement.duration_days(calendar.open_days_per_week()) {
+            task.duration = Some(duration);
+        }
+        task.milestone |= statement.is_milestone();
+        for constraint in statement.constraints() {
+            let anchor = if let Some(date) = constraint.date().and_then(|it| Date::parse(&it)) {
+                match self.start {
+                    Some(start) => Anchor::Day(date.0 - start.0),
+                    None => continue,
+                }
+            } else if let Some(reference) = constraint.task() {
+                let name = self
+                    .aliases
+                    .get(&reference.value())
+                    .cloned()
+                    .unwrap_or_else(|| reference.value());
+                Anchor::Task {
+                    name,
+                    reference,
+                    at_end: constraint.at_task_end(),
+                    offset: constraint.offset_days(),
+                }
+            } else {
+                continue;
+            };
+            let task = &mut self.tasks[index];
+            match constraint.constraint_kind() {
+                ConstraintKind::Starts => task.start = Some(anchor),
+                ConstraintKind::Ends => task.end = Some(anchor),
+                ConstraintKind::Happens => {
+                    task.start = Some(anchor);
+                    task.milestone = true;
+                }
+            }
+        }
+    }
+
+    /// The day `anchor` refers to, `after` being whether a task anchored at
+    /// the end of another begins on the next open day
+    fn anchor_day(
+        &mut self,
+        anchor: &Anchor,
+        after: bool,
+        calendar: &Calendar,
+        visiting: &mut Vec<String>,
+    ) -> Option<i64> {
+        match anchor {
+            Anchor::Day(day) => Some(*day),
+            Anchor::Task {
+                name,
+                reference,
+                at_end,
+                offset,
+            } => {
+                if let Some(index) = visiting.iter().position(|it| it == name) {
+                    let message = if index == visiting.len() - 1 {
+                        format!("task '{}' depends on itself", name)
+                    } else {
+                        let cycle = visiting[index..]
+                            .iter()
+                            .chain(std::iter::once(name))
+                            .map(|it| it.as_str())
+                            .collect::<Vec<_>>();
+                        format!("tasks depend on each other: {}", cycle.join(" → "))
+                    };
+                    let problem = Problem {
+                        range: reference.position(),
+                        severity: Severity::Error,
+                        message,
+                    };
+                    if !self.problems.iter().any(|it| it.range == problem.range) {
+                        self.problems.push(problem);
+                    }
+                    return None;
+                }
+                let span = self.span(name, calendar, visiting)?;
+                let day = if *at_end {
+                    if after {
+                        calendar.next_open(span.end + 1, 1)
+                    } else {
+                        span.end
+                    }
+                } else {
+                    span.start
+                };
+                match offset.signum() {
+                    0 => Some(day),
+                    step => Some(calendar.count_open(day + step, offset.abs(), step)),
+                }
+            }
+        }
+    }
+
+    fn span(
+        &mut self,
+        name: &str,
+        calendar: &Calendar,
+        visiting: &mut Vec<String>,
+    ) -> Option<Span> {
+        if let Some(span) = self.spans.get(name) {
+            return *span;
+        }
+        let task = self.tasks[self.find(name)?].clone();
+        visiting.push(name.to_string());
+        let start = match &task.start {
+            Some(anchor) => self.anchor_day(anchor, !task.milestone, calendar, visiting),
+            None => None,
+        };
+        let end = match &task.end {
+            Some(anchor) => self.anchor_day(anchor, false, calendar, visiting),
+            None => None,
+        };
+        visiting.pop();
+        let duration = task.duration.unwrap_or(1);
+        let span = match (start, end) {
+            (Some(start), Some(end)) => Some(Span { start, end }),
+            (Some(start), None) if task.milestone => Some(Span { start, end: start }),
+            (Some(start), None) => Some(Span {
+                start,
+                end: calendar.count_open(start, duration.into(), 1),
+            }),
+            (None, Some(end)) => Some(Span {
+                start: calendar.count_open(end, duration.into(), -1),
+                end,
+            }),
+            (None, None) if task.start.is_some() || task.end.is_some() => None,
+            (None, None) => {
+                let start = calendar.next_open(0, 1);
+                let end = if task.milestone {
+                    start
+                } else {
+                    calendar.count_open(start, duration.into(), 1)
+                };
+                Some(Span { start, end })
+            }
+        };
+        self.spans.insert(name.to_string(), span);
+        span
+    }
+
+    /// The days the task named, or aliased, `name` takes place on, if they
+    /// can be worked out
+    pub(crate) fn task(&self, name: &str) -> Option<Span> {
+        let name = self.aliases.get(name).map(|it| it.as_str()).unwrap_or(name);
+        self.spans.get(name).cloned().flatten()
+    }
+
+    /// Whether the task named, or aliased, `name` is a milestone
+    pub(crate) fn is_milestone(&self, name: &str) -> bool {
+        let name = self.aliases.get(name).map(|it| it.as_str()).unwrap_or(name);
+        self.find(name)
+            .map(|it| self.tasks[it].milestone)
+            .unwrap_or(false)
+    }
+
+    /// Where each task is first mentioned
+    #[allow(dead_code)]
+    pub(crate) fn definition(&self, name: &str) -> Option<&IdentifierNode> {
+        let name = self.aliases.get(name).map(|it| it.as_str()).unwrap_or(name);
+        self.find(name).map(|it| &self.tasks[it].definition)
+    }
+
+    pub(crate) fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    /// The date of `day`, or its number when the project has no start date
+    pub(crate) fn format_day(&self, day: i64) -> String {
+        match self.start {
+            Some(start) => start.add(day).to_string(),
+            None => format!("day {}", day + 1),
+        }
+    }
+}
+
+impl Calendar {
+    /// How many days a week lasts, for durations given in weeks
+    fn open_days_per_week(&self) -> u32 {
+        self.closed_weekdays.iter().filter(|it| !**it).count() as u32
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_dates() {
+        use super::Date;
+        let date = Date::parse("2020-07-01").unwrap();
+        assert_eq!(date.to_string(), "2020-07-01");
+        // a wednesday
+        assert_eq!(date.weekday(), 2);
+        assert_eq!(Date::parse("1970-01-01"), Some(Date(0)));
+        assert_eq!(
+            Date::parse("2020-02-29").unwrap().add(1).to_string(),
+            "2020-03-01"
+        );
+        assert_eq!(Date::parse("2020-13-01"), None);
+    }
+
+    #[test]
+    fn test_schedule() {
+        let text = "@startgantt\nProject starts 2020-07-01\nsaturday are closed\nsunday are closed\n2020-07-14 is closed\n[Design] as [D] lasts 2 weeks\n[Test] lasts 3 days and starts at [D]'s end\nthen [Ship] lasts 1 day\n[Release] happens at [Ship]'s end\n[Review] starts 2020-08-03 and ends 2020-08-05\n[Ship] -> [Party]\n@endgantt\n";
+        let root = crate::parse(text).root();
+        let schedule = super::Schedule::new(&root);
+        let days = |name: &str| {
+            let span = schedule.task(name).unwrap();
+            (
+                schedule.format_day(span.start),
+                schedule.format_day(span.end),
+            )
+        };
+        // ten open days, skipping two weekends and the 14th
+        assert_eq!(days("D"), ("2020-07-01".into(), "2020-07-15".into()));
+        assert_eq!(days("Test"), ("2020-07-16".into(), "2020-07-20".into()));
+        assert_eq!(days("Ship"), ("2020-07-21".into(), "2020-07-21".into()));
+        assert_eq!(days("Release"), ("2020-07-21".into(), "2020-07-21".into()));
+        assert!(schedule.is_milestone("Release"));
+        assert_eq!(days("Review"), ("2020-08-03".into(), "2020-08-05".into()));
+        assert_eq!(days("Party"), ("2020-07-22".into(), "2020-07-22".into()));
+        assert!(schedule.problems().is_empty(), "{:?}", schedule.problems());
+    }
+
+    #[test]
+    fn test_schedule_problems() {
+        let text = "@startgantt\n[A] lasts 2 days and starts at [B]'s end\n[B] lasts 1 day and starts at [A]'s end\n[C] starts at [Later]'s end\n[D] starts at [Nowhere]'s start\n[Later] lasts 1 day\n@endgantt\n";
+        let root = crate::parse(text).root();
+        let schedule = super::Schedule::new(&root);
+        let problems = schedule
+            .problems()
+            .iter()
+            .map(|it| (it.severity, it.message.clone()))
+            .collect::<Vec<_>>();
+        use crate::semantic::Severity::*;
+        assert_eq!(
+            problems,
+            vec![
+                (Warning, "task 'B' is used before it is defined".to_string()),
+                (
+                    Warning,
+                    "task 'Later' is used before it is defined".to_string()
+                ),
+                (Warning, "task 'Nowhere' is never defined".to_string()),
+                (Error, "tasks depend on each other: A → B → A".to_string()),
+            ]
+        );
+        assert_eq!(schedule.task("A"), None);
+        assert_eq!(schedule.task("B"), None);
+        assert_eq!(
+            schedule.format_day(schedule.task("Later").unwrap().start),
+            "day 1"
+        );
+    }
+
+    #[test]
+    fn test_huge_durations() {
+        let text = "@startgantt\n[A] lasts 4000000000 days\n[B] lasts 4000000000 weeks and starts 4000000000 days after [A]'s end\n[C] starts at [C]'s end\n@endgantt\n";
+        let root = crate::parse(text).root();
+        let schedule = super::Schedule::new(&root);
+        let horizon = super::HORIZON;
+        assert_eq!(
+            schedule.task("A"),
+            Some(super::Span {
+                start: 0,
+                end: horizon - 1
+            })
+        );
+        assert_eq!(
+            schedule.task("B"),
+            Some(super::Span {
+                start: 2 * horizon,
+                end: 3 * horizon - 1
+            })
+        );
+        let problems = schedule
+            .problems()
+            .iter()
+            .map(|it| it.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(problems, vec!["task 'C' depends on itself"]);
+    }
+}
@@ -0,0 +1,600 @@
+//! What the preprocessor makes of a document, worked out without running it:
+//! the values of `!$variables` and `!define`s, and which branches of each
+//! `!if` are taken
+use crate::ast::{CallNode, DefineNode, PpIfNode, ProcedureNode, RootNode, SyntaxNode};
+use crate::syntax::SyntaxKind::*;
+use rowan::TextRange;
+use std::collections::HashMap;
+
+/// The value of a variable or of an expression
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Int(i64),
+    Str(String),
+}
+
+impl Value {
+    pub(crate) fn is_true(&self) -> bool {
+        match self {
+            Value::Int(it) => *it != 0,
+            Value::Str(it) => !it.is_empty() && it != "false",
+        }
+    }
+
+    fn int(&self) -> Option<i64> {
+        match self {
+            Value::Int(it) => Some(*it),
+            Value::Str(it) => it.trim().parse().ok(),
+        }
+    }
+
+    fn bool(it: bool) -> Value {
+        Value::Int(it as i64)
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(it) => write!(f, "{}", it),
+            Value::Str(it) => write!(f, "{}", it),
+        }
+    }
+}
+
+/// The variables and macros defined so far. A value that can't be worked out,
+/// such as one that depends on an included file, is `None`.
+#[derive(Debug, Default)]
+pub(crate) struct Environment {
+    variables: HashMap<String, Option<Value>>,
+    defines: HashMap<String, String>,
+    /// Whether names this document doesn't define may come from elsewhere
+    open: bool,
+}
+
+/// What an expression can refer to
+pub(crate) trait Scope {
+    /// `None` if the variable isn't known, or has a value that isn't
+    fn variable(&self, name: &str) -> Option<Value>;
+    /// Whether `name`, a macro or a `$variable`, is defined, or `None` if
+    /// that can't be told
+    fn is_defined(&self, name: &str) -> Option<bool>;
+    /// The value of a macro without parameters
+    fn define(&self, name: &str) -> Option<String>;
+    /// Call a function, or a builtin whose result depends on more than its
+    /// arguments, such as `%date()`
+    fn call(&mut self, _name: &str, _arguments: Vec<Value>) -> Option<Value> {
+        None
+    }
+}
+
+impl Scope for Environment {
+    fn variable(&self, name: &str) -> Option<Value> {
+        self.variables.get(name)?.clone()
+    }
+
+    /// `None` if it could be defined by an included file
+    fn is_defined(&self, name: &str) -> Option<bool> {
+        let defined = self.defines.contains_key(name) || self.variables.contains_key(name);
+        if defined || !self.open {
+            Some(defined)
+        } else {
+            None
+        }
+    }
+
+    fn define(&self, name: &str) -> Option<String> {
+        self.defines.get(name).cloned()
+    }
+}
+
+/// The outcome of evaluating a document
+#[derive(Debug, Default)]
+pub(crate) struct Evaluation {
+    pub(crate) environment: Environment,
+    /// The bodies of the branches that aren't taken
+    pub(crate) inactive: Vec<TextRange>,
+}
+
+/// Go through the preprocessor statements of the document in order. When an
+/// `!if` can't be decided all of its branches are taken to be active.
+pub(crate) fn evaluate(root: &RootNode) -> Evaluation {
+    let mut evaluation = Evaluation::default();
+    evaluation.environment.open = root.includes().next().is_some();
+    walk(root.syntax(), &mut evaluation);
+    evaluation
+}
+
+fn walk(node: &SyntaxNode, evaluation: &mut Evaluation) {
+    for child in node.children() {
+        match child.kind() {
+            VARIABLE_DEF => assign(&child, &mut evaluation.environment),
+            DEFINE => {
+                let define = crate::ast::DefineNode::cast(child).unwrap();
+                if let Some(name) = define.name() {
+                    evaluation.environment.defines.insert(name, define.value());
+                }
+            }
+            UNDEF => {
+                let undef = crate::ast::UndefNode::cast(child).unwrap();
+                if let Some(name) = undef.name() {
+                    evaluation.environment.defines.remove(&name);
+                }
+            }
+            PP_IF => conditional(&PpIfNode::cast(child).unwrap(), evaluation),
+            // the body of a procedure only runs when it's called
+            PROCEDURE => {}
+            _ => walk(&child, evaluation),
+        }
+    }
+}
+
+fn assign(node: &SyntaxNode, environment: &mut Environment) {
+    let def = crate::ast::VariableDefNode::cast(node.clone()).unwrap();
+    let name = match def.name() {
+        Some(name) => name,
+        None => return,
+    };
+    if def.is_default() && environment.variables.contains_key(&name) {
+        return;
+    }
+    let value = def
+        .value()
+        .and_then(|it| expression(&it.syntax().text().to_string(), environment));
+    environment.variables.insert(name, value);
+}
+
+fn conditional(node: &PpIfNode, evaluation: &mut Evaluation) {
+    let branches = node.branches().collect::<Vec<_>>();
+    let mut taken = None;
+    let mut undecided = false;
+    for (index, branch) in branches.iter().enumerate() {
+        let condition = branch.condition().map(|it| it.syntax().text().to_string());
+        let environment = &mut evaluation.environment;
+        let holds = match (branch.directive().as_str(), condition) {
+            ("!else", _) => Some(true),
+            ("!ifdef", Some(name)) => environment.is_defined(name.trim()),
+            ("!ifndef", Some(name)) => environment.is_defined(name.trim()).map(|it| !it),
+            (_, Some(condition)) => expression(&condition, environment).map(|it| it.is_true()),
+            (_, None) => None,
+        };
+        match holds {
+            Some(true) => {
+                taken = Some(index);
+                break;
+            }
+            Some(false) => {}
+            None => {
+                undecided = true;
+                break;
+            }
+        }
+    }
+    for (index, branch) in branches.iter().enumerate() {
+        if undecided || taken == Some(index) {
+            walk(branch.syntax(), evaluation);
+        } else {
+            evaluation.inactive.push(branch.body_range());
+        }
+    }
+}
+
+/// What a call can name: a `!procedure`, a `!function`, or a `!define` that
+/// takes parameters
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Signature {
+    pub(crate) name: String,
+    /// The name of each parameter, with its default value if it has one
+    pub(crate) params: Vec<(String, Option<String>)>,
+    pub(crate) is_function: bool,
+    /// Where the definition names it
+    pub(crate) range: TextRange,
+}
+
+impl Signature {
+    fn from_procedure(procedure: &ProcedureNode) -> Option<Signature> {
+        let name = procedure.name_token()?;
+        Some(Signature {
+            name: name.text().to_string(),
+            params: procedure
+                .params()
+                .filter_map(|it| Some((it.name()?, it.default())))
+                .collect(),
+            is_function: procedure.is_function(),
+            range: name.text_range(),
+        })
+    }
+
+    fn from_define(define: &DefineNode) -> Option<Signature> {
+        let name = define.name_token()?;
+        Some(Signature {
+            name: name.text().to_string(),
+            params: define.params()?,
+            is_function: false,
+            range: name.text_range(),
+        })
+    }
+
+    /// How it's written, e.g. `$greet($name, $greeting="Hello")`
+    pub(crate) fn label(&self) -> String {
+        format!("{}({})", self.name, self.param_labels().join(", "))
+    }
+
+    pub(crate) fn param_labels(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .map(|(name, default)| match default {
+                Some(default) => format!("{}={}", name, default),
+                None => name.clone(),
+            })
+            .collect()
+    }
+
+    /// What's wrong with calling it with `count` arguments, if anything
+    pub(crate) fn arity_problem(&self, count: usize) -> Option<String> {
+        let required = self.params.iter().filter(|(_, it)| it.is_none()).count();
+        let total = self.params.len();
+        if (required..=total).contains(&count) {
+            return None;
+        }
+        let expected = match (required, total) {
+            (1, 1) => "1 argument".to_string(),
+            (required, total) if required == total => format!("{} arguments", total),
+            (required, total) => format!("{} to {} arguments", required, total),
+        };
+        Some(format!(
+            "'{}' takes {}, but {} {} given",
+            self.name,
+            expected,
+            count,
+            if count == 1 { "was" } else { "were" }
+        ))
+    }
+}
+
+/// Every procedure, function and macro with parameters the document defines
+pub(crate) fn signatures(root: &RootNode) -> Vec<Signature> {
+    let procedures = root
+        .procedures()
+        .filter_map(|it| Signature::from_procedure(&it));
+    let defines = root.defines().filter_map(|it| Signature::from_define(&it));
+    procedures.chain(defines).collect()
+}
+
+/// The call whose name is at `offset`
+pub(crate) fn call_named_at(root: &SyntaxNode, offset: rowan::TextSize) -> Option<CallNode> {
+    root.token_at_offset(offset).find_map(|token| {
+        let call = CallNode::cast(token.parent())?;
+        if call.name_token()? == token {
+            Some(call)
+        } else {
+            None
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Value(Value),
+    Variable(String),
+    Name(String),
+    Builtin(String),
+    Op(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", ",",
+];
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let word = |rest: &str| {
+            rest.find(|it: char| !(it.is_alphanumeric() || it == '_'))
+                .unwrap_or(rest.len())
+        };
+        let len = if c.is_whitespace() {
+            c.len_utf8()
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|it: char| !it.is_ascii_digit())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Value(Value::Int(rest[..len].parse().ok()?)));
+            len
+        } else if c == '"' || c == '\'' {
+            let len = rest[1..].find(c)? + 2;
+            tokens.push(Token::Value(Value::Str(rest[1..len - 1].to_string())));
+            len
+        } else if c == '$' {
+            let len = 1 + word(&rest[1..]);
+            tokens.push(Token::Variable(rest[..len].to_string()));
+            len
+        } else if c == '%' && rest[1..].starts_with(|it: char| it.is_alphabetic()) {
+            let len = 1 + word(&rest[1..]);
+            tokens.push(Token::Builtin(rest[..len].to_string()));
+            len
+        } else if c.is_alphabetic() || c == '_' {
+            let len = word(rest);
+            tokens.push(Token::Name(rest[..len].to_string()));
+            len
+        } else {
+            let op = OPERATORS.iter().find(|it| rest.starts_with(*it))?;
+            tokens.push(Token::Op(op));
+            op.len()
+        };
+        rest = &rest[len..];
+    }
+    Some(tokens)
+}
+
+/// Evaluate an expression such as `$a == 1 && %strlen($name) > 2`, or `None`
+/// if it uses something that can't be worked out
+pub(crate) fn expression(text: &str, scope: &mut dyn Scope) -> Option<Value> {
+    let tokens = tokenize(text)?;
+    let mut evaluator = Evaluator {
+        tokens: &tokens,
+        position: 0,
+        scope,
+    };
+    let value = evaluator.binary(0)?;
+    if evaluator.position == tokens.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+struct Evaluator<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    scope: &'a mut dyn Scope,
+}
+
+/// How tightly each binary operator binds
+fn precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "||" => 1,
+        "&&" => 2,
+        "==" | "!=" => 3,
+        "<" | "<=" | ">" | ">=" => 4,
+        "+" | "-" => 5,
+        "*" | "/" | "%" => 6,
+        _ => return None,
+    })
+}
+
+impl Evaluator<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(it)) if *it == op) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn binary(&mut self, min: u8) -> Option<Value> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            let level = match precedence(op) {
+                Some(level) if level > min => level,
+                _ => break,
+            };
+            self.position += 1;
+            let right = self.binary(level)?;
+            left = apply(op, left, right)?;
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<Value> {
+        if self.eat("!") {
+            return Some(Value::bool(!self.unary()?.is_true()));
+        }
+        if self.eat("-") {
+            return Some(Value::Int(self.unary()?.int()?.checked_neg()?));
+        }
+        if self.eat("(") {
+            let value = self.binary(0)?;
+            return if self.eat(")") { Some(value) } else { None };
+        }
+        let token = self.peek()?.clone();
+        self.position += 1;
+        let is_call = matches!(self.peek(), Some(Token::Op("(")));
+        match token {
+            Token::Value(value) => Some(value),
+            Token::Variable(name) | Token::Name(name) if is_call => {
+                let arguments = self.arguments()?;
+                self.scope.call(&name, arguments)
+            }
+            Token::Variable(name) => self.scope.variable(&name),
+            // a macro used as a value
+            Token::Name(name) => self.scope.define(&name).map(Value::Str),
+            Token::Builtin(name) => {
+                let arguments = self.arguments()?;
+                match builtin(&name, &arguments, &*self.scope) {
+                    Some(value) => Some(value),
+                    None => self.scope.call(&name, arguments),
+                }
+            }
+            Token::Op(_) => None,
+        }
+    }
+
+    /// The `(a, b)` of a call
+    fn arguments(&mut self) -> Option<Vec<Value>> {
+        let mut arguments = Vec::new();
+        if !self.eat("(") {
+            return None;
+        }
+        if self.eat(")") {
+            return Some(arguments);
+        }
+        loop {
+            arguments.push(self.binary(0)?);
+            if self.eat(")") {
+                return Some(arguments);
+            }
+            if !self.eat(",") {
+                return None;
+            }
+        }
+    }
+}
+
+fn apply(op: &str, left: Value, right: Value) -> Option<Value> {
+    let ints = (left.int(), right.int());
+    Some(match (op, ints) {
+        ("||", _) => Value::bool(left.is_true() || right.is_true()),
+        ("&&", _) => Value::bool(left.is_true() && right.is_true()),
+        // strings are compared as numbers when they both are
+        ("==", (Some(a), Some(b))) => Value::bool(a == b),
+        ("!=", (Some(a), Some(b))) => Value::bool(a != b),
+        ("==", _) => Value::bool(left.to_string() == right.to_string()),
+        ("!=", _) => Value::bool(left.to_string() != right.to_string()),
+        ("<", (Some(a), Some(b))) => Value::bool(a < b),
+        ("<=", (Some(a), Some(b))) => Value::bool(a <= b),
+        (">", (Some(a), Some(b))) => Value::bool(a > b),
+        (">=", (Some(a), Some(b))) => Value::bool(a >= b),
+        ("+", _) if matches!(left, Value::Str(_)) || matches!(right, Value::Str(_)) => {
+            Value::Str(format!("{}{}", left, right))
+        }
+        ("+", (Some(a), Some(b))) => Value::Int(a.checked_add(b)?),
+        ("-", (Some(a), Some(b))) => Value::Int(a.checked_sub(b)?),
+        ("*", (Some(a), Some(b))) => Value::Int(a.checked_mul(b)?),
+        ("/", (Some(a), Some(b))) => Value::Int(a.checked_div(b)?),
+        ("%", (Some(a), Some(b))) => Value::Int(a.checked_rem(b)?),
+        _ => return None,
+    })
+}
+
+/// The builtin functions whose result doesn't depend on the outside world
+pub(crate) fn builtin(name: &str, arguments: &[Value], scope: &dyn Scope) -> Option<Value> {
+    let text = |index: usize| arguments.get(index).map(|it| it.to_string());
+    Some(match (name, arguments.len()) {
+        ("%true", 0) => Value::Int(1),
+        ("%false", 0) => Value::Int(0),
+        ("%not", 1) => Value::bool(!arguments[0].is_true()),
+        ("%boolval", 1) => Value::bool(arguments[0].is_true()),
+        ("%strlen", 1) => Value::Int(text(0)?.chars().count() as i64),
+        ("%upper", 1) => Value::Str(text(0)?.to_uppercase()),
+        ("%lower", 1) => Value::Str(text(0)?.to_lowercase()),
+        ("%string", 1) => Value::Str(text(0)?),
+        ("%intval", 1) => Value::Int(arguments[0].int()?),
+        ("%strpos", 2) => {
+            let haystack = text(0)?;
+            let position = haystack
+                .find(&text(1)?)
+                .map(|it| haystack[..it].chars().count() as i64);
+            Value::Int(position.unwrap_or(-1))
+        }
+        ("%substr", 2) | ("%substr", 3) => {
+            let start = arguments[1].int()?.max(0) as usize;
+            let length = match arguments.get(2) {
+                Some(it) => it.int()?.max(0) as usize,
+                None => usize::MAX,
+            };
+            Value::Str(text(0)?.chars().skip(start).take(length).collect())
+        }
+        ("%variable_exists", 1) => {
+            let name = text(0)?;
+            let name = if name.starts_with('$') {
+                name
+            } else {
+                format!("${}", name)
+            };
+            Value::bool(scope.is_defined(&name)?)
+        }
+        ("%function_exists", 1) => Value::bool(scope.is_defined(&text(0)?)?),
+        _ => return None,
+    })
+}
+
+mod tests {
+    #[test]
+    fn test_expressions() {
+        let root = crate::parse(
+            "!$a = 2\n!$name = \"Bob\"\n!$a ?= 5\n!define DEBUG\n!$min = 0 - 9223372036854775807 - 1\n",
+        )
+        .root();
+        let mut environment = super::evaluate(&root).environment;
+        use super::Value::*;
+        let cases = vec![
+            ("$a * 3 + 1", Some(Int(7))),
+            ("$a == 2 && %strlen($name) > 2", Some(Int(1))),
+            ("%upper($name) + \"!\"", Some(Str("BOB!".into()))),
+            ("!($a < 1) || $a > 9", Some(Int(1))),
+            ("DEBUG == \"\"", Some(Int(1))),
+            ("%substr($name, 1)", Some(Str("ob".into()))),
+            ("%variable_exists(\"a\")", Some(Int(1))),
+            ("$missing == 1", None),
+            ("%date()", None),
+            ("($a", None),
+            ("-$a", Some(Int(-2))),
+            ("$min < 0", Some(Int(1))),
+            ("-$min", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                super::expression(text, &mut environment),
+                expected,
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_signatures() {
+        let text = "!procedure $greet($name, $greeting=\"Hello\")\n!endprocedure\n!function $pi() !return 3\n!define SHOUT(x, y=\"!\") x y\n!define DEBUG\n";
+        let root = crate::parse(text).root();
+        let signatures = super::signatures(&root);
+        let labels = signatures
+            .iter()
+            .map(|it| (it.label(), it.is_function))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![
+                ("$greet($name, $greeting=\"Hello\")".to_string(), false),
+                ("$pi()".to_string(), true),
+                ("SHOUT(x, y=\"!\")".to_string(), false),
+            ]
+        );
+        let arity = |index: usize, count| signatures[index].arity_problem(count);
+        assert_eq!(arity(0, 1), None);
+        assert_eq!(arity(0, 2), None);
+        assert_eq!(
+            arity(0, 3),
+            Some("'$greet' takes 1 to 2 arguments, but 3 were given".into())
+        );
+        assert_eq!(
+            arity(1, 1),
+            Some("'$pi' takes 0 arguments, but 1 was given".into())
+        );
+    }
+
+    #[test]
+    fn test_inactive_branches() {
+        let text = "!$mode = \"dark\"\n!if $mode == \"light\"\nclass A\n!elseif $mode == \"dark\"\nclass B\n!ifndef DEBUG\nclass C\n!else\nclass D\n!endif\n!else\nclass E\n!endif\n!if $unknown\nclass F\n!else\nclass G\n!endif\n";
+        let root = crate::parse(text).root();
+        let evaluation = super::evaluate(&root);
+        let inactive = evaluation
+            .inactive
+            .iter()
+            .map(|it| text[*it].to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(inactive, vec!["class A\n", "class D\n", "class E\n",]);
+        use super::Scope;
+        assert_eq!(
+            evaluation.environment.variable("$mode"),
+            Some(super::Value::Str("dark".into()))
+        );
+    }
+}
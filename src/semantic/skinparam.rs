@@ -0,0 +1,734 @@
+//! What `skinparam` and `<style>` accept: every skinparam and style property
+//! with the type of its value, and the checks of the values a document gives
+use crate::ast::{RootNode, StyleRuleNode};
+use crate::semantic::color::{is_color, NAMED};
+use crate::semantic::{Problem, Severity};
+use rowan::TextRange;
+
+/// The values a skinparam or style property takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValueType {
+    Color,
+    Number,
+    Boolean,
+    FontName,
+    /// Any of `plain`, `bold`, `italic` and `underline`, such as
+    /// `bold italic`
+    FontStyle,
+    Enum(&'static [&'static str]),
+    Text,
+}
+
+const FONT_STYLES: &[&str] = &["plain", "bold", "italic", "underline"];
+const FONT_NAMES: &[&str] = &[
+    "Arial",
+    "Courier",
+    "Courier New",
+    "Helvetica",
+    "Monospaced",
+    "SansSerif",
+    "Serif",
+    "Times New Roman",
+    "Verdana",
+];
+
+impl ValueType {
+    /// What a value of the type is, e.g. `a color`
+    pub(crate) fn describe(self) -> String {
+        match self {
+            ValueType::Color => "a color".to_string(),
+            ValueType::Number => "a number".to_string(),
+            ValueType::Boolean => "`true` or `false`".to_string(),
+            ValueType::FontName => "a font name".to_string(),
+            ValueType::FontStyle => "a font style, any of `plain`, `bold`, `italic` and \
+                                     `underline`"
+                .to_string(),
+            ValueType::Enum(values) => {
+                let values = values
+                    .iter()
+                    .map(|it| format!("`{}`", it))
+                    .collect::<Vec<_>>();
+                format!("one of {}", values.join(", "))
+            }
+            ValueType::Text => "text".to_string(),
+        }
+    }
+
+    /// What's wrong with the value, if it's not of the type
+    pub(crate) fn check(self, value: &str) -> Option<String> {
+        let unquoted = value.trim_matches('"');
+        let ok = match self {
+            ValueType::Color => is_color(unquoted),
+            ValueType::Number => unquoted.parse::<f64>().is_ok(),
+            ValueType::Boolean => matches!(unquoted.to_lowercase().as_str(), "true" | "false"),
+            ValueType::FontStyle => unquoted.split_whitespace().all(|it| {
+                FONT_STYLES
+                    .iter()
+                    .any(|style| style.eq_ignore_ascii_case(it))
+            }),
+            ValueType::Enum(values) => values.iter().any(|it| it.eq_ignore_ascii_case(unquoted)),
+            ValueType::FontName | ValueType::Text => true,
+        };
+        if ok {
+            None
+        } else {
+            Some(format!("expected {}, not '{}'", self.describe(), value))
+        }
+    }
+
+    /// The values to offer when completing a value of the type
+    pub(crate) fn suggestions(self) -> Vec<String> {
+        match self {
+            ValueType::Color => NAMED.iter().map(|(name, _)| name.to_string()).collect(),
+            ValueType::Boolean => vec!["true".to_string(), "false".to_string()],
+            ValueType::FontName => FONT_NAMES.iter().map(|it| it.to_string()).collect(),
+            ValueType::FontStyle => FONT_STYLES.iter().map(|it| it.to_string()).collect(),
+            ValueType::Enum(values) => values.iter().map(|it| it.to_string()).collect(),
+            ValueType::Number | ValueType::Text => Vec::new(),
+        }
+    }
+}
+
+/// A skinparam or style property
+#[derive(Debug, Clone)]
+pub(crate) struct Param {
+    pub(crate) name: String,
+    pub(crate) value: ValueType,
+    pub(crate) doc: String,
+}
+
+use ValueType::*;
+
+const ALIGNMENTS: &[&str] = &["left", "center", "right"];
+
+/// The skinparams that aren't about a kind of element
+const GLOBAL: &[(&str, ValueType, &str)] = &[
+    (
+        "BackgroundColor",
+        Color,
+        "The background color of the whole diagram",
+    ),
+    (
+        "Handwritten",
+        Boolean,
+        "Whether lines are drawn as if by hand",
+    ),
+    (
+        "Monochrome",
+        Enum(&["true", "false", "reverse"]),
+        "Whether the diagram is drawn in shades of gray",
+    ),
+    ("Shadowing", Boolean, "Whether elements cast shadows"),
+    (
+        "Linetype",
+        Enum(&["ortho", "polyline", "splines"]),
+        "How links are routed",
+    ),
+    (
+        "Nodesep",
+        Number,
+        "The horizontal space between elements, in pixels",
+    ),
+    (
+        "Ranksep",
+        Number,
+        "The vertical space between ranks of elements, in pixels",
+    ),
+    ("Padding", Number, "The padding around elements, in pixels"),
+    (
+        "BoxPadding",
+        Number,
+        "The padding of the boxes of a sequence diagram, in pixels",
+    ),
+    (
+        "ParticipantPadding",
+        Number,
+        "The space between participants, in pixels",
+    ),
+    (
+        "RoundCorner",
+        Number,
+        "The radius of the corners of elements",
+    ),
+    (
+        "Dpi",
+        Number,
+        "The resolution of the image, in dots per inch",
+    ),
+    ("DefaultFontName", FontName, "The font of all text"),
+    ("DefaultFontSize", Number, "The size of all text"),
+    ("DefaultFontColor", Color, "The color of all text"),
+    ("DefaultFontStyle", FontStyle, "The style of all text"),
+    (
+        "DefaultMonospacedFontName",
+        FontName,
+        "The font of monospaced text",
+    ),
+    (
+        "DefaultTextAlignment",
+        Enum(ALIGNMENTS),
+        "The alignment of all text",
+    ),
+    ("ArrowColor", Color, "The color of arrows"),
+    ("ArrowThickness", Number, "The thickness of arrows"),
+    ("ArrowFontColor", Color, "The color of the labels of arrows"),
+    (
+        "ArrowFontName",
+        FontName,
+        "The font of the labels of arrows",
+    ),
+    ("ArrowFontSize", Number, "The size of the labels of arrows"),
+    (
+        "ArrowFontStyle",
+        FontStyle,
+        "The style of the labels of arrows",
+    ),
+    (
+        "ArrowLollipopColor",
+        Color,
+        "The color of the lollipops of arrows",
+    ),
+    (
+        "ArrowMessageAlignment",
+        Enum(&["left", "center", "right", "direction"]),
+        "The alignment of the messages of arrows",
+    ),
+    (
+        "SequenceMessageAlign",
+        Enum(&["left", "center", "right", "direction", "reverseDirection"]),
+        "The alignment of the messages of a sequence diagram",
+    ),
+    (
+        "SequenceArrowThickness",
+        Number,
+        "The thickness of the arrows of a sequence diagram",
+    ),
+    (
+        "SequenceLifeLineBorderColor",
+        Color,
+        "The color of lifelines",
+    ),
+    (
+        "SequenceLifeLineBackgroundColor",
+        Color,
+        "The background color of activated lifelines",
+    ),
+    (
+        "SequenceParticipant",
+        Enum(&["underline"]),
+        "How participants are drawn",
+    ),
+    (
+        "ResponseMessageBelowArrow",
+        Boolean,
+        "Whether the messages of replies are drawn below their arrows",
+    ),
+    (
+        "LifelineStrategy",
+        Enum(&["nosolid", "solid"]),
+        "How lifelines are drawn",
+    ),
+    ("Style", Enum(&["strictuml"]), "The drawing style"),
+    ("MaxMessageSize", Number, "The width messages wrap at"),
+    ("WrapWidth", Number, "The width text wraps at"),
+    ("MinClassWidth", Number, "The minimum width of classes"),
+    (
+        "SameClassWidth",
+        Boolean,
+        "Whether classes are all drawn as wide as the widest",
+    ),
+    (
+        "GenericDisplay",
+        Enum(&["old"]),
+        "How generic parameters are drawn",
+    ),
+    (
+        "ConditionStyle",
+        Enum(&["inside", "diamond", "InsideDiamond", "foo1"]),
+        "How the conditions of activity diagrams are drawn",
+    ),
+    (
+        "ConditionEndStyle",
+        Enum(&["diamond", "hline"]),
+        "How the ends of conditions are drawn",
+    ),
+    (
+        "ComponentStyle",
+        Enum(&["uml1", "uml2", "rectangle"]),
+        "How components are drawn",
+    ),
+    (
+        "PackageStyle",
+        Enum(&["node", "rectangle", "folder", "frame", "cloud", "database"]),
+        "How packages are drawn",
+    ),
+    (
+        "ActorStyle",
+        Enum(&["stickman", "awesome", "hollow"]),
+        "How actors are drawn",
+    ),
+    ("HyperlinkColor", Color, "The color of hyperlinks"),
+    (
+        "HyperlinkUnderline",
+        Boolean,
+        "Whether hyperlinks are underlined",
+    ),
+    (
+        "NoteTextAlignment",
+        Enum(ALIGNMENTS),
+        "The alignment of the text of notes",
+    ),
+    (
+        "SvgLinkTarget",
+        Text,
+        "The target of the links of an SVG image",
+    ),
+    (
+        "TitleBorderRoundCorner",
+        Number,
+        "The radius of the corners of the title's border",
+    ),
+    (
+        "TitleBorderThickness",
+        Number,
+        "The thickness of the title's border",
+    ),
+    (
+        "ClassAttributeIconSize",
+        Number,
+        "The size of the visibility icons of attributes, 0 to draw `+`, `-`, `#` and `~` instead",
+    ),
+    (
+        "ClassAttributeFontColor",
+        Color,
+        "The color of the attributes of classes",
+    ),
+    (
+        "ClassAttributeFontName",
+        FontName,
+        "The font of the attributes of classes",
+    ),
+    (
+        "ClassAttributeFontSize",
+        Number,
+        "The size of the attributes of classes",
+    ),
+    (
+        "ClassAttributeFontStyle",
+        FontStyle,
+        "The style of the attributes of classes",
+    ),
+    (
+        "CircledCharacterFontName",
+        FontName,
+        "The font of the letters in the circles of classes",
+    ),
+    (
+        "CircledCharacterFontSize",
+        Number,
+        "The size of the letters in the circles of classes",
+    ),
+    (
+        "CircledCharacterRadius",
+        Number,
+        "The radius of the circles of classes",
+    ),
+    ("TabSize", Number, "The width of a tab, in spaces"),
+    (
+        "GuillemetSpacing",
+        Boolean,
+        "Whether guillemets are drawn with spaces",
+    ),
+];
+
+/// The kinds of element the ATTRIBUTES apply to, and what they're called in
+/// the docs
+const ELEMENTS: &[(&str, &str)] = &[
+    ("Activity", "activities"),
+    ("ActivityDiamond", "the diamonds of activity diagrams"),
+    ("Actor", "actors"),
+    ("Agent", "agents"),
+    ("Archimate", "archimate elements"),
+    ("Arrow", "arrows"),
+    ("Artifact", "artifacts"),
+    ("Boundary", "boundaries"),
+    ("Card", "cards"),
+    ("Class", "classes"),
+    ("Cloud", "clouds"),
+    ("Collections", "collections"),
+    ("Component", "components"),
+    ("Control", "controls"),
+    ("Database", "databases"),
+    ("Entity", "entities"),
+    ("File", "files"),
+    ("Folder", "folders"),
+    ("Footer", "the footer"),
+    ("Frame", "frames"),
+    ("Header", "the header"),
+    ("Interface", "interfaces"),
+    ("Legend", "the legend"),
+    ("Node", "nodes"),
+    ("Note", "notes"),
+    ("Object", "objects"),
+    ("Package", "packages"),
+    ("Participant", "participants"),
+    ("Partition", "partitions"),
+    ("Queue", "queues"),
+    ("Rectangle", "rectangles"),
+    ("SequenceBox", "the boxes of sequence diagrams"),
+    ("SequenceDivider", "the dividers of sequence diagrams"),
+    ("SequenceGroup", "the groups of sequence diagrams"),
+    ("SequenceGroupHeader", "the headers of groups"),
+    ("SequenceReference", "the references of sequence diagrams"),
+    ("Stack", "stacks"),
+    ("State", "states"),
+    ("Storage", "storages"),
+    ("Swimlane", "swimlanes"),
+    ("Timing", "timing diagrams"),
+    ("Title", "the title"),
+    ("Usecase", "use cases"),
+];
+
+/// What each of the ELEMENTS can set, with a doc where `{}` stands for the
+/// element
+const ATTRIBUTES: &[(&str, ValueType, &str)] = &[
+    ("BackgroundColor", Color, "The background color of {}"),
+    ("BorderColor", Color, "The border color of {}"),
+    (
+        "BorderThickness",
+        Number,
+        "The thickness of the border of {}",
+    ),
+    ("FontColor", Color, "The color of the text of {}"),
+    ("FontName", FontName, "The font of the text of {}"),
+    ("FontSize", Number, "The size of the text of {}"),
+    ("FontStyle", FontStyle, "The style of the text of {}"),
+    (
+        "StereotypeFontColor",
+        Color,
+        "The color of the stereotypes of {}",
+    ),
+    (
+        "StereotypeFontName",
+        FontName,
+        "The font of the stereotypes of {}",
+    ),
+    (
+        "StereotypeFontSize",
+        Number,
+        "The size of the stereotypes of {}",
+    ),
+    (
+        "StereotypeFontStyle",
+        FontStyle,
+        "The style of the stereotypes of {}",
+    ),
+    ("Shadowing", Boolean, "Whether {} cast shadows"),
+    ("RoundCorner", Number, "The radius of the corners of {}"),
+];
+
+/// The letters in the circles of classes, and what they're drawn for
+const CIRCLED: &[(&str, &str)] = &[
+    ("A", "abstract classes"),
+    ("C", "classes"),
+    ("E", "enums"),
+    ("I", "interfaces"),
+    ("N", "annotations"),
+];
+
+/// What the circle of each of the CIRCLED letters can set, as
+/// `Stereotype<letter><attribute>`
+const CIRCLE_ATTRIBUTES: &[(&str, &str)] = &[
+    (
+        "BackgroundColor",
+        "The background color of the circles of {}",
+    ),
+    ("BorderColor", "The border color of the circles of {}"),
+];
+
+/// Every skinparam
+pub(crate) fn skinparams() -> Vec<Param> {
+    let global = GLOBAL.iter().map(|(name, value, doc)| Param {
+        name: name.to_string(),
+        value: *value,
+        doc: doc.to_string(),
+    });
+    let elements = ELEMENTS.iter().flat_map(|(element, described)| {
+        ATTRIBUTES.iter().map(move |(attribute, value, doc)| Param {
+            name: format!("{}{}", element, attribute),
+            value: *value,
+            doc: doc.replace("{}", described),
+        })
+    });
+    let circles = CIRCLED.iter().flat_map(|(letter, described)| {
+        CIRCLE_ATTRIBUTES.iter().map(move |(attribute, doc)| Param {
+            name: format!("Stereotype{}{}", letter, attribute),
+            value: Color,
+            doc: doc.replace("{}", described),
+        })
+    });
+    global.chain(elements).chain(circles).collect()
+}
+
+/// The diagrams whose name PlantUML drops from the front of a skinparam that
+/// doesn't exist with it, so that `sequenceArrowColor`, or `ArrowColor` in a
+/// `skinparam sequence` block, is `ArrowColor`
+const DIAGRAM_PREFIXES: &[&str] = &[
+    "sequence",
+    "activity",
+    "class",
+    "component",
+    "object",
+    "state",
+    "usecase",
+];
+
+/// The skinparam, whatever its case, as PlantUML ignores it
+pub(crate) fn skinparam(name: &str) -> Option<Param> {
+    let skinparams = skinparams();
+    let find = |name: &str| {
+        skinparams
+            .iter()
+            .find(|it| it.name.eq_ignore_ascii_case(name))
+            .cloned()
+    };
+    find(name).or_else(|| {
+        DIAGRAM_PREFIXES.iter().find_map(|prefix| {
+            let rest = name.get(prefix.len()..)?;
+            if name[..prefix.len()].eq_ignore_ascii_case(prefix) && !rest.is_empty() {
+                find(rest)
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// The properties a rule of a `<style>` can set
+pub(crate) const STYLE_PROPERTIES: &[(&str, ValueType, &str)] = &[
+    ("BackgroundColor", Color, "The background color"),
+    ("LineColor", Color, "The color of lines and borders"),
+    (
+        "LineThickness",
+        Number,
+        "The thickness of lines and borders",
+    ),
+    ("LineStyle", Text, "The dashes of lines, e.g. `5` or `5-2`"),
+    ("FontColor", Color, "The color of text"),
+    ("FontName", FontName, "The font of text"),
+    ("FontSize", Number, "The size of text"),
+    ("FontStyle", FontStyle, "The style of text"),
+    ("RoundCorner", Number, "The radius of the corners"),
+    ("DiagonalCorner", Number, "The size of cut off corners"),
+    (
+        "Padding",
+        Text,
+        "The space inside the border, e.g. `5` or `5 10`",
+    ),
+    (
+        "Margin",
+        Text,
+        "The space outside the border, e.g. `5` or `5 10`",
+    ),
+    (
+        "HorizontalAlignment",
+        Enum(ALIGNMENTS),
+        "The alignment of text",
+    ),
+    ("MaximumWidth", Number, "The width text wraps at"),
+    ("MinimumWidth", Number, "The minimum width"),
+    ("Shadowing", Number, "The size of the shadow, 0 for none"),
+    ("HyperLinkColor", Color, "The color of hyperlinks"),
+    ("ExportedName", Text, "The name to export the element with"),
+    ("Image", Text, "An image to draw"),
+];
+
+/// The style property, whatever its case
+pub(crate) fn style_property(name: &str) -> Option<Param> {
+    STYLE_PROPERTIES
+        .iter()
+        .find(|(it, _, _)| it.eq_ignore_ascii_case(name))
+        .map(|(name, value, doc)| Param {
+            name: name.to_string(),
+            value: *value,
+            doc: doc.to_string(),
+        })
+}
+
+/// The selectors a rule of a `<style>` can start with, besides the
+/// `.stereotype` ones
+pub(crate) const STYLE_SELECTORS: &[&str] = &[
+    "root",
+    "element",
+    "document",
+    "activityDiagram",
+    "classDiagram",
+    "componentDiagram",
+    "deploymentDiagram",
+    "ganttDiagram",
+    "jsonDiagram",
+    "mindmapDiagram",
+    "objectDiagram",
+    "sequenceDiagram",
+    "stateDiagram",
+    "timingDiagram",
+    "usecaseDiagram",
+    "wbsDiagram",
+    "yamlDiagram",
+    "actor",
+    "activity",
+    "arrow",
+    "box",
+    "class",
+    "component",
+    "database",
+    "diamond",
+    "footer",
+    "group",
+    "groupHeader",
+    "header",
+    "legend",
+    "lifeLine",
+    "node",
+    "note",
+    "package",
+    "participant",
+    "partition",
+    "rectangle",
+    "reference",
+    "separator",
+    "state",
+    "swimlane",
+    "task",
+    "title",
+    "usecase",
+];
+
+/// A value that's only known once the preprocessor has run
+fn is_dynamic(value: &str) -> bool {
+    value.contains('$') || value.contains('%')
+}
+
+fn unknown(range: TextRange, message: String) -> Problem {
+    Problem {
+        range,
+        severity: Severity::Warning,
+        message,
+    }
+}
+
+fn check(param: &Param, value: &Option<(String, TextRange)>) -> Option<Problem> {
+    let (value, range) = value.as_ref()?;
+    if is_dynamic(value) {
+        return None;
+    }
+    let message = param.value.check(value)?;
+    Some(unknown(*range, message))
+}
+
+fn rule_problems(rule: &StyleRuleNode, problems: &mut Vec<Problem>) {
+    for property in rule.properties() {
+        let token = match property.name_token() {
+            Some(token) => token,
+            None => continue,
+        };
+        match style_property(token.text()) {
+            Some(param) => problems.extend(check(&param, &property.value())),
+            None => problems.push(unknown(
+                token.text_range(),
+                format!("unknown style property '{}'", token.text()),
+            )),
+        }
+    }
+    for rule in rule.rules() {
+        rule_problems(&rule, problems);
+    }
+}
+
+/// Skinparams and style properties that don't exist, and values of the
+/// wrong type
+pub(crate) fn problems(root: &RootNode) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for entry in root.skinparams().flat_map(|it| it.entries()) {
+        let (key, token) = match (entry.key(), entry.key_token()) {
+            (Some(key), Some(token)) => (key, token),
+            _ => continue,
+        };
+        match skinparam(&key) {
+            Some(param) => problems.extend(check(&param, &entry.value())),
+            None => problems.push(unknown(
+                token.text_range(),
+                format!("unknown skinparam '{}'", key),
+            )),
+        }
+    }
+    for style in root.styles() {
+        for rule in style.rules() {
+            rule_problems(&rule, &mut problems);
+        }
+    }
+    problems
+}
+
+mod tests {
+    #[test]
+    fn test_schema() {
+        use super::ValueType;
+        let param = super::skinparam("classbackgroundcolor").unwrap();
+        assert_eq!(param.name, "ClassBackgroundColor");
+        assert_eq!(param.value, ValueType::Color);
+        assert_eq!(param.doc, "The background color of classes");
+        assert!(super::skinparam("ClassColour").is_none());
+        let param = super::skinparam("stereotypeCBackgroundColor").unwrap();
+        assert_eq!(param.name, "StereotypeCBackgroundColor");
+        assert_eq!(param.doc, "The background color of the circles of classes");
+        for letter in &["A", "C", "E", "I", "N"] {
+            assert!(super::skinparam(&format!("Stereotype{}BorderColor", letter)).is_some());
+        }
+        assert!(super::skinparam("StereotypeXBorderColor").is_none());
+        assert_eq!(ValueType::FontStyle.check("bold Italic"), None);
+        assert_eq!(
+            ValueType::Boolean.check("yes"),
+            Some("expected `true` or `false`, not 'yes'".to_string())
+        );
+        assert_eq!(
+            super::style_property("linecolor").unwrap().name,
+            "LineColor"
+        );
+    }
+
+    #[test]
+    fn test_problems() {
+        let text = "@startuml\nskinparam ArrowColour red\nskinparam Shadowing maybe\nskinparam class {\n  BackgroundColor #Orangey\n  FontSize 12\n  BorderColor $color\n}\n<style>\nclassDiagram {\n  LineThickness thick\n  Colour red\n  class {\n    FontColor #F80\n  }\n}\n</style>\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let problems = super::problems(&parsed.root())
+            .into_iter()
+            .map(|it| (&text[it.range], it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            vec![
+                ("ArrowColour", "unknown skinparam 'ArrowColour'".to_string()),
+                ("maybe", "expected `true` or `false`, not 'maybe'".into()),
+                ("#Orangey", "expected a color, not '#Orangey'".into()),
+                ("thick", "expected a number, not 'thick'".into()),
+                ("Colour", "unknown style property 'Colour'".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sequence_skinparams() {
+        let text = "@startuml\nskinparam SequenceLifeLineBorderColor red\nskinparam sequenceArrowThickness 2\nskinparam sequence {\n  ArrowColor red\n  LifeLineBorderColor blue\n  ParticipantBorderColor #F80\n  MessageAlign center\n  GroupBackgroundColor red\n}\n@enduml\n";
+        let parsed = crate::parse(text);
+        assert!(parsed.errors().is_empty(), "{:?}", parsed.errors());
+        let problems = super::problems(&parsed.root());
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(
+            super::skinparam("sequenceArrowColor").unwrap().name,
+            "ArrowColor"
+        );
+        assert!(super::skinparam("sequenceArrowColour").is_none());
+    }
+}
@@ -0,0 +1,520 @@
+//! An offline index of the standard library PlantUML ships with, as in
+//! `!include <C4/C4_Container>`: its files, and the procedures and macros they
+//! define
+use crate::semantic::preprocessor::Signature;
+use rowan::TextRange;
+
+type Params = &'static [(&'static str, Option<&'static str>)];
+
+/// A procedure, or a macro with parameters, from the standard library
+#[derive(Debug)]
+pub(crate) struct Procedure {
+    pub(crate) name: &'static str,
+    /// The name of each parameter, with its default value if it has one
+    pub(crate) params: Params,
+    pub(crate) doc: &'static str,
+}
+
+/// A file of the standard library
+#[derive(Debug)]
+pub(crate) struct Library {
+    /// The path `!include <...>` names it by, e.g. `C4/C4_Container`
+    pub(crate) path: &'static str,
+    pub(crate) doc: &'static str,
+    /// The files of the library it includes itself
+    includes: &'static [&'static str],
+    procedures: &'static [Procedure],
+}
+
+const EMPTY: Option<&str> = Some("\"\"");
+
+/// People and systems
+const ELEMENT: Params = &[
+    ("$alias", None),
+    ("$label", None),
+    ("$descr", EMPTY),
+    ("$sprite", EMPTY),
+    ("$tags", EMPTY),
+    ("$link", EMPTY),
+    ("$type", EMPTY),
+];
+/// Containers and components, which also have a technology
+const CONTAINER: Params = &[
+    ("$alias", None),
+    ("$label", None),
+    ("$techn", EMPTY),
+    ("$descr", EMPTY),
+    ("$sprite", EMPTY),
+    ("$tags", EMPTY),
+    ("$link", EMPTY),
+];
+const NODE: Params = &[
+    ("$alias", None),
+    ("$label", None),
+    ("$type", EMPTY),
+    ("$descr", EMPTY),
+    ("$sprite", EMPTY),
+    ("$tags", EMPTY),
+    ("$link", EMPTY),
+];
+const BOUNDARY: Params = &[
+    ("$alias", None),
+    ("$label", None),
+    ("$tags", EMPTY),
+    ("$link", EMPTY),
+];
+const REL: Params = &[
+    ("$from", None),
+    ("$to", None),
+    ("$label", None),
+    ("$techn", EMPTY),
+    ("$descr", EMPTY),
+    ("$sprite", EMPTY),
+    ("$tags", EMPTY),
+    ("$link", EMPTY),
+];
+const LAY: Params = &[("$from", None), ("$to", None)];
+const NONE: Params = &[];
+const AWS: Params = &[
+    ("e_alias", None),
+    ("e_label", None),
+    ("e_techn", None),
+    ("e_descr", EMPTY),
+];
+const AWS_LEGACY: Params = &[
+    ("alias", None),
+    ("label", EMPTY),
+    ("e_type", EMPTY),
+    ("e_color", EMPTY),
+];
+
+const fn procedure(name: &'static str, params: Params, doc: &'static str) -> Procedure {
+    Procedure { name, params, doc }
+}
+
+/// Every file of the standard library the server knows about
+pub(crate) static LIBRARIES: &[Library] = &[
+    Library {
+        path: "C4/C4",
+        doc: "The common definitions of the C4 model: relationships, boundaries, tags and layout",
+        includes: &[],
+        procedures: &[
+            procedure("Rel", REL, "A relationship between two elements"),
+            procedure("BiRel", REL, "A relationship in both directions"),
+            procedure(
+                "Rel_Back",
+                REL,
+                "A relationship drawn from `$to` to `$from`",
+            ),
+            procedure(
+                "Rel_Neighbor",
+                REL,
+                "A relationship between elements drawn side by side",
+            ),
+            procedure("Rel_U", REL, "A relationship drawn upwards"),
+            procedure("Rel_Up", REL, "A relationship drawn upwards"),
+            procedure("Rel_D", REL, "A relationship drawn downwards"),
+            procedure("Rel_Down", REL, "A relationship drawn downwards"),
+            procedure("Rel_L", REL, "A relationship drawn to the left"),
+            procedure("Rel_Left", REL, "A relationship drawn to the left"),
+            procedure("Rel_R", REL, "A relationship drawn to the right"),
+            procedure("Rel_Right", REL, "A relationship drawn to the right"),
+            procedure(
+                "Lay_U",
+                LAY,
+                "Place `$to` above `$from`, without a relationship",
+            ),
+            procedure(
+                "Lay_D",
+                LAY,
+                "Place `$to` below `$from`, without a relationship",
+            ),
+            procedure(
+                "Lay_L",
+                LAY,
+                "Place `$to` left of `$from`, without a relationship",
+            ),
+            procedure(
+                "Lay_R",
+                LAY,
+                "Place `$to` right of `$from`, without a relationship",
+            ),
+            procedure(
+                "Boundary",
+                &[
+                    ("$alias", None),
+                    ("$label", None),
+                    ("$type", EMPTY),
+                    ("$tags", EMPTY),
+                    ("$link", EMPTY),
+                ],
+                "A boundary around the elements in its `{ }`",
+            ),
+            procedure(
+                "AddElementTag",
+                &[
+                    ("$tagStereo", None),
+                    ("$bgColor", EMPTY),
+                    ("$fontColor", EMPTY),
+                    ("$borderColor", EMPTY),
+                    ("$shadowing", EMPTY),
+                    ("$shape", EMPTY),
+                    ("$sprite", EMPTY),
+                    ("$techn", EMPTY),
+                    ("$legendText", EMPTY),
+                ],
+                "A tag that styles the elements given it with `$tags`",
+            ),
+            procedure(
+                "AddRelTag",
+                &[
+                    ("$tagStereo", None),
+                    ("$textColor", EMPTY),
+                    ("$lineColor", EMPTY),
+                    ("$lineStyle", EMPTY),
+                    ("$sprite", EMPTY),
+                    ("$techn", EMPTY),
+                    ("$legendText", EMPTY),
+                ],
+                "A tag that styles the relationships given it with `$tags`",
+            ),
+            procedure(
+                "LAYOUT_TOP_DOWN",
+                NONE,
+                "Lay the diagram out from top to bottom",
+            ),
+            procedure(
+                "LAYOUT_LEFT_RIGHT",
+                NONE,
+                "Lay the diagram out from left to right",
+            ),
+            procedure(
+                "LAYOUT_LANDSCAPE",
+                NONE,
+                "Lay the diagram out from left to right",
+            ),
+            procedure(
+                "LAYOUT_WITH_LEGEND",
+                NONE,
+                "Show a legend of the element kinds",
+            ),
+            procedure(
+                "LAYOUT_AS_SKETCH",
+                NONE,
+                "Draw the diagram as a hand-drawn sketch",
+            ),
+            procedure(
+                "SHOW_LEGEND",
+                &[("$hideStereotype", Some("true"))],
+                "Show a legend of the element kinds and tags used",
+            ),
+            procedure("HIDE_STEREOTYPE", NONE, "Hide the stereotypes of elements"),
+        ],
+    },
+    Library {
+        path: "C4/C4_Context",
+        doc: "C4 system context diagrams: people and software systems",
+        includes: &["C4/C4"],
+        procedures: &[
+            procedure("Person", ELEMENT, "A person using the software systems"),
+            procedure("Person_Ext", ELEMENT, "A person outside of the enterprise"),
+            procedure("System", ELEMENT, "A software system"),
+            procedure(
+                "System_Ext",
+                ELEMENT,
+                "A software system outside of the enterprise",
+            ),
+            procedure("SystemDb", ELEMENT, "A software system drawn as a database"),
+            procedure(
+                "SystemDb_Ext",
+                ELEMENT,
+                "An external software system drawn as a database",
+            ),
+            procedure("SystemQueue", ELEMENT, "A software system drawn as a queue"),
+            procedure(
+                "SystemQueue_Ext",
+                ELEMENT,
+                "An external software system drawn as a queue",
+            ),
+            procedure(
+                "Enterprise_Boundary",
+                BOUNDARY,
+                "The boundary of an enterprise",
+            ),
+            procedure(
+                "System_Boundary",
+                BOUNDARY,
+                "The boundary of a software system",
+            ),
+        ],
+    },
+    Library {
+        path: "C4/C4_Container",
+        doc: "C4 container diagrams: the applications and data stores of a system",
+        includes: &["C4/C4_Context"],
+        procedures: &[
+            procedure(
+                "Container",
+                CONTAINER,
+                "An application or data store of a system",
+            ),
+            procedure(
+                "Container_Ext",
+                CONTAINER,
+                "A container outside of the system",
+            ),
+            procedure("ContainerDb", CONTAINER, "A container drawn as a database"),
+            procedure(
+                "ContainerDb_Ext",
+                CONTAINER,
+                "An external container drawn as a database",
+            ),
+            procedure("ContainerQueue", CONTAINER, "A container drawn as a queue"),
+            procedure(
+                "ContainerQueue_Ext",
+                CONTAINER,
+                "An external container drawn as a queue",
+            ),
+            procedure(
+                "Container_Boundary",
+                BOUNDARY,
+                "The boundary of a container",
+            ),
+        ],
+    },
+    Library {
+        path: "C4/C4_Component",
+        doc: "C4 component diagrams: the components of a container",
+        includes: &["C4/C4_Container"],
+        procedures: &[
+            procedure("Component", CONTAINER, "A component of a container"),
+            procedure(
+                "Component_Ext",
+                CONTAINER,
+                "A component outside of the container",
+            ),
+            procedure("ComponentDb", CONTAINER, "A component drawn as a database"),
+            procedure(
+                "ComponentDb_Ext",
+                CONTAINER,
+                "An external component drawn as a database",
+            ),
+            procedure("ComponentQueue", CONTAINER, "A component drawn as a queue"),
+            procedure(
+                "ComponentQueue_Ext",
+                CONTAINER,
+                "An external component drawn as a queue",
+            ),
+        ],
+    },
+    Library {
+        path: "C4/C4_Deployment",
+        doc: "C4 deployment diagrams: where containers run",
+        includes: &["C4/C4_Container"],
+        procedures: &[
+            procedure(
+                "Deployment_Node",
+                NODE,
+                "Infrastructure that containers are deployed to",
+            ),
+            procedure(
+                "Deployment_Node_L",
+                NODE,
+                "A deployment node laid out to the left",
+            ),
+            procedure(
+                "Deployment_Node_R",
+                NODE,
+                "A deployment node laid out to the right",
+            ),
+            procedure(
+                "Node",
+                NODE,
+                "Infrastructure that containers are deployed to",
+            ),
+            procedure("Node_L", NODE, "A node laid out to the left"),
+            procedure("Node_R", NODE, "A node laid out to the right"),
+        ],
+    },
+    Library {
+        path: "C4/C4_Dynamic",
+        doc: "C4 dynamic diagrams: numbered interactions between elements",
+        includes: &["C4/C4_Component"],
+        procedures: &[
+            procedure("Index", NONE, "The number of the next interaction"),
+            procedure("LastIndex", NONE, "The number of the last interaction"),
+            procedure(
+                "SetIndex",
+                &[("$new_index", None)],
+                "Number the interactions from `$new_index` on",
+            ),
+        ],
+    },
+    Library {
+        path: "awslib/AWSCommon",
+        doc: "The common definitions of the AWS icons",
+        includes: &[],
+        procedures: &[procedure(
+            "AWSEntity",
+            &[
+                ("e_alias", None),
+                ("e_label", None),
+                ("e_techn", None),
+                ("e_color", None),
+                ("e_sprite", None),
+                ("e_stereo", None),
+            ],
+            "An element drawn with an AWS icon",
+        )],
+    },
+    Library {
+        path: "awslib/Compute/EC2",
+        doc: "The Amazon EC2 icon",
+        includes: &["awslib/AWSCommon"],
+        procedures: &[procedure("EC2", AWS, "An Amazon EC2 element")],
+    },
+    Library {
+        path: "awslib/Compute/Lambda",
+        doc: "The AWS Lambda icon",
+        includes: &["awslib/AWSCommon"],
+        procedures: &[procedure("Lambda", AWS, "An AWS Lambda element")],
+    },
+    Library {
+        path: "awslib/Database/DynamoDB",
+        doc: "The Amazon DynamoDB icon",
+        includes: &["awslib/AWSCommon"],
+        procedures: &[procedure("DynamoDB", AWS, "An Amazon DynamoDB element")],
+    },
+    Library {
+        path: "awslib/Database/RDS",
+        doc: "The Amazon RDS icon",
+        includes: &["awslib/AWSCommon"],
+        procedures: &[procedure("RDS", AWS, "An Amazon RDS element")],
+    },
+    Library {
+        path: "awslib/Storage/SimpleStorageService",
+        doc: "The Amazon S3 icon",
+        includes: &["awslib/AWSCommon"],
+        procedures: &[procedure(
+            "SimpleStorageService",
+            AWS,
+            "An Amazon S3 element",
+        )],
+    },
+    Library {
+        path: "aws/common",
+        doc: "The common definitions of the legacy AWS icons",
+        includes: &[],
+        procedures: &[],
+    },
+    Library {
+        path: "aws/Compute/AmazonEC2/AmazonEC2",
+        doc: "The legacy Amazon EC2 icon",
+        includes: &["aws/common"],
+        procedures: &[procedure("AMAZONEC2", AWS_LEGACY, "An Amazon EC2 element")],
+    },
+    Library {
+        path: "aws/Compute/AWSLambda/AWSLambda",
+        doc: "The legacy AWS Lambda icon",
+        includes: &["aws/common"],
+        procedures: &[procedure("AWSLAMBDA", AWS_LEGACY, "An AWS Lambda element")],
+    },
+    Library {
+        path: "aws/Storage/AmazonS3/AmazonS3",
+        doc: "The legacy Amazon S3 icon",
+        includes: &["aws/common"],
+        procedures: &[procedure("AMAZONS3", AWS_LEGACY, "An Amazon S3 element")],
+    },
+    Library {
+        path: "kubernetes/k8s-sprites-labeled-25pct",
+        doc: "Sprites of the Kubernetes resources, labelled, at 25% of their size",
+        includes: &[],
+        procedures: &[],
+    },
+    Library {
+        path: "kubernetes/k8s-sprites-unlabeled-25pct",
+        doc: "Sprites of the Kubernetes resources, at 25% of their size",
+        includes: &[],
+        procedures: &[],
+    },
+    Library {
+        path: "kubernetes/k8s-sprites-labeled-full",
+        doc: "Sprites of the Kubernetes resources, labelled, at full size",
+        includes: &[],
+        procedures: &[],
+    },
+];
+
+/// The file `<path>` names, with or without its angle brackets
+pub(crate) fn library(path: &str) -> Option<&'static Library> {
+    let path = path.trim_start_matches('<').trim_end_matches('>');
+    LIBRARIES.iter().find(|it| it.path == path)
+}
+
+/// The procedures that including the files makes available, with the file
+/// each comes from
+pub(crate) fn procedures<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+) -> Vec<(&'static Library, &'static Procedure)> {
+    let mut libraries: Vec<&'static Library> = Vec::new();
+    let mut pending = paths.into_iter().filter_map(library).collect::<Vec<_>>();
+    while let Some(library) = pending.pop() {
+        if libraries.iter().any(|it| it.path == library.path) {
+            continue;
+        }
+        libraries.push(library);
+        pending.extend(library.includes.iter().filter_map(|it| self::library(it)));
+    }
+    libraries
+        .into_iter()
+        .flat_map(|library| library.procedures.iter().map(move |it| (library, it)))
+        .collect()
+}
+
+impl Procedure {
+    pub(crate) fn signature(&self) -> Signature {
+        Signature {
+            name: self.name.to_string(),
+            params: self
+                .params
+                .iter()
+                .map(|(name, default)| (name.to_string(), default.map(|it| it.to_string())))
+                .collect(),
+            is_function: false,
+            // it isn't defined in any of the documents
+            range: TextRange::default(),
+        }
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_stdlib_procedures() {
+        let names = |paths: &[&str]| {
+            let mut names = super::procedures(paths.iter().copied())
+                .into_iter()
+                .map(|(_, it)| it.name)
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            names
+        };
+        let container = names(&["<C4/C4_Container>"]);
+        assert!(container.contains(&"Container"));
+        assert!(container.contains(&"Person"));
+        assert!(container.contains(&"Rel"));
+        assert!(!container.contains(&"Component"));
+        assert!(names(&["<C4/C4_Context>"]).len() < container.len());
+        assert_eq!(names(&["<nowhere>"]), Vec::<&str>::new());
+        assert_eq!(names(&["<awslib/Compute/EC2>"]), vec!["AWSEntity", "EC2"]);
+
+        let (library, container) = super::procedures(vec!["C4/C4_Container"])
+            .into_iter()
+            .find(|(_, it)| it.name == "Container")
+            .unwrap();
+        assert_eq!(library.path, "C4/C4_Container");
+        assert_eq!(
+            container.signature().label(),
+            "Container($alias, $label, $techn=\"\", $descr=\"\", $sprite=\"\", $tags=\"\", $link=\"\")"
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! The themes that come with PlantUML, for `!theme`, and the skinparams they
+//! set
+use crate::ast::RootNode;
+use crate::semantic::{Problem, Severity};
+
+/// A built-in theme
+#[derive(Debug)]
+pub(crate) struct Theme {
+    pub(crate) name: &'static str,
+    pub(crate) doc: &'static str,
+    /// The skinparams the theme sets, and their values
+    pub(crate) skinparams: &'static [(&'static str, &'static str)],
+}
+
+impl Theme {
+    /// The value the theme gives the skinparam, whatever its case
+    pub(crate) fn skinparam(&self, name: &str) -> Option<&'static str> {
+        self.skinparams
+            .iter()
+            .find(|(it, _)| it.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+}
+
+const OUTLINE: &[(&str, &str)] = &[("BackgroundColor", "white"), ("Shadowing", "false")];
+
+pub(crate) const THEMES: &[Theme] = &[
+    Theme {
+        name: "_none_",
+        doc: "No theme at all, the plain defaults of PlantUML",
+        skinparams: &[],
+    },
+    Theme {
+        name: "amiga",
+        doc: "The blue, white and orange of the Amiga Workbench",
+        skinparams: &[
+            ("BackgroundColor", "#0055AA"),
+            ("DefaultFontColor", "white"),
+            ("ArrowColor", "#FF8800"),
+            ("DefaultFontName", "Topaz"),
+        ],
+    },
+    Theme {
+        name: "aws-orange",
+        doc: "The orange and dark blue of Amazon Web Services",
+        skinparams: &[
+            ("BackgroundColor", "white"),
+            ("ArrowColor", "#232F3E"),
+            ("ClassBackgroundColor", "#FF9900"),
+            ("DefaultFontName", "Helvetica"),
+        ],
+    },
+    Theme {
+        name: "black-knight",
+        doc: "Light text and lines on a black background",
+        skinparams: &[
+            ("BackgroundColor", "#222222"),
+            ("DefaultFontColor", "#FFFFFF"),
+            ("ArrowColor", "#BBBBBB"),
+        ],
+    },
+    Theme {
+        name: "bluegray",
+        doc: "Shades of blue gray",
+        skinparams: &[
+            ("BackgroundColor", "white"),
+            ("ArrowColor", "#4A6076"),
+            ("ClassBackgroundColor", "#B0C4DE"),
+        ],
+    },
+    Theme {
+        name: "blueprint",
+        doc: "White lines on the blue of an architect's blueprint",
+        skinparams: &[
+            ("BackgroundColor", "#1C4E80"),
+            ("DefaultFontColor", "white"),
+            ("ArrowColor", "white"),
+            ("DefaultFontName", "Courier"),
+        ],
+    },
+    Theme {
+        name: "carbon-gray",
+        doc: "The grays of IBM's Carbon design system",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#393939")],
+    },
+    Theme {
+        name: "cerulean",
+        doc: "The blues of the Bootswatch Cerulean theme",
+        skinparams: &[
+            ("BackgroundColor", "white"),
+            ("ArrowColor", "#2FA4E7"),
+            ("ClassBackgroundColor", "#2FA4E7"),
+            ("DefaultFontColor", "#FFFFFF"),
+        ],
+    },
+    Theme {
+        name: "cerulean-outline",
+        doc: "Cerulean, with outlined rather than filled elements",
+        skinparams: OUTLINE,
+    },
+    Theme {
+        name: "crt-amber",
+        doc: "Amber text on black, like an old terminal",
+        skinparams: &[
+            ("BackgroundColor", "#000000"),
+            ("DefaultFontColor", "#FFB000"),
+            ("ArrowColor", "#FFB000"),
+            ("DefaultFontName", "Monospaced"),
+        ],
+    },
+    Theme {
+        name: "crt-green",
+        doc: "Green text on black, like an old terminal",
+        skinparams: &[
+            ("BackgroundColor", "#000000"),
+            ("DefaultFontColor", "#33FF00"),
+            ("ArrowColor", "#33FF00"),
+            ("DefaultFontName", "Monospaced"),
+        ],
+    },
+    Theme {
+        name: "cyborg",
+        doc: "The dark Bootswatch Cyborg theme",
+        skinparams: &[
+            ("BackgroundColor", "#060606"),
+            ("DefaultFontColor", "#ADAFAE"),
+            ("ArrowColor", "#2A9FD6"),
+        ],
+    },
+    Theme {
+        name: "cyborg-outline",
+        doc: "Cyborg, with outlined rather than filled elements",
+        skinparams: &[("BackgroundColor", "#060606"), ("Shadowing", "false")],
+    },
+    Theme {
+        name: "hacker",
+        doc: "Green on black, for hackers",
+        skinparams: &[
+            ("BackgroundColor", "black"),
+            ("DefaultFontColor", "#00FF00"),
+            ("ArrowColor", "#00FF00"),
+        ],
+    },
+    Theme {
+        name: "lightgray",
+        doc: "Light grays",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#A6A6A6")],
+    },
+    Theme {
+        name: "mars",
+        doc: "The reds and oranges of Mars",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#E34D2F")],
+    },
+    Theme {
+        name: "materia",
+        doc: "The Bootswatch Materia theme, after Material Design",
+        skinparams: &[
+            ("BackgroundColor", "white"),
+            ("ArrowColor", "#2196F3"),
+            ("DefaultFontName", "Roboto"),
+        ],
+    },
+    Theme {
+        name: "materia-outline",
+        doc: "Materia, with outlined rather than filled elements",
+        skinparams: OUTLINE,
+    },
+    Theme {
+        name: "metal",
+        doc: "Metallic grays",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#6F6F6F")],
+    },
+    Theme {
+        name: "mimeograph",
+        doc: "The purple ink of a mimeographed page",
+        skinparams: &[
+            ("BackgroundColor", "#FDFDFD"),
+            ("DefaultFontColor", "#5D1A9A"),
+            ("ArrowColor", "#5D1A9A"),
+            ("Handwritten", "true"),
+        ],
+    },
+    Theme {
+        name: "minty",
+        doc: "The mint greens of the Bootswatch Minty theme",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#78C2AD")],
+    },
+    Theme {
+        name: "mono",
+        doc: "Black and white",
+        skinparams: &[
+            ("BackgroundColor", "white"),
+            ("ArrowColor", "black"),
+            ("Monochrome", "true"),
+        ],
+    },
+    Theme {
+        name: "plain",
+        doc: "Plain black lines on white, without shadows",
+        skinparams: &[
+            ("BackgroundColor", "white"),
+            ("ArrowColor", "black"),
+            ("Shadowing", "false"),
+        ],
+    },
+    Theme {
+        name: "reddress-darkblue",
+        doc: "Dark blue, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "#1B2631"), ("ArrowColor", "#5DADE2")],
+    },
+    Theme {
+        name: "reddress-darkgreen",
+        doc: "Dark green, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "#0B3D2E"), ("ArrowColor", "#58D68D")],
+    },
+    Theme {
+        name: "reddress-darkorange",
+        doc: "Dark orange, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "#3D2B1F"), ("ArrowColor", "#F5B041")],
+    },
+    Theme {
+        name: "reddress-darkred",
+        doc: "Dark red, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "#3B1F1F"), ("ArrowColor", "#EC7063")],
+    },
+    Theme {
+        name: "reddress-lightblue",
+        doc: "Light blue, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#2E86C1")],
+    },
+    Theme {
+        name: "reddress-lightgreen",
+        doc: "Light green, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#239B56")],
+    },
+    Theme {
+        name: "reddress-lightorange",
+        doc: "Light orange, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#CA6F1E")],
+    },
+    Theme {
+        name: "reddress-lightred",
+        doc: "Light red, from the reddress family of themes",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#CB4335")],
+    },
+    Theme {
+        name: "sandstone",
+        doc: "The warm tones of the Bootswatch Sandstone theme",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#325D88")],
+    },
+    Theme {
+        name: "silver",
+        doc: "Silver grays",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#C0C0C0")],
+    },
+    Theme {
+        name: "sketchy",
+        doc: "Hand-drawn lines, as if sketched on paper",
+        skinparams: &[("Handwritten", "true"), ("BackgroundColor", "white")],
+    },
+    Theme {
+        name: "sketchy-outline",
+        doc: "Sketchy, with outlined rather than filled elements",
+        skinparams: &[
+            ("Handwritten", "true"),
+            ("BackgroundColor", "white"),
+            ("Shadowing", "false"),
+        ],
+    },
+    Theme {
+        name: "spacelab",
+        doc: "The Bootswatch Spacelab theme",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#446E9B")],
+    },
+    Theme {
+        name: "spacelab-white",
+        doc: "Spacelab on white",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#446E9B")],
+    },
+    Theme {
+        name: "sunlust",
+        doc: "The warm colors of a sunset",
+        skinparams: &[("BackgroundColor", "#FFF5E1"), ("ArrowColor", "#E85D04")],
+    },
+    Theme {
+        name: "superhero",
+        doc: "The dark blue Bootswatch Superhero theme",
+        skinparams: &[
+            ("BackgroundColor", "#2B3E50"),
+            ("DefaultFontColor", "#EBEBEB"),
+            ("ArrowColor", "#DF691A"),
+        ],
+    },
+    Theme {
+        name: "superhero-outline",
+        doc: "Superhero, with outlined rather than filled elements",
+        skinparams: &[("BackgroundColor", "#2B3E50"), ("Shadowing", "false")],
+    },
+    Theme {
+        name: "toy",
+        doc: "The bright colors of toy bricks",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#E3000B")],
+    },
+    Theme {
+        name: "united",
+        doc: "The orange and purple of the Bootswatch United theme",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#E95420")],
+    },
+    Theme {
+        name: "vibrant",
+        doc: "Vibrant colors",
+        skinparams: &[("BackgroundColor", "white"), ("ArrowColor", "#F44336")],
+    },
+];
+
+/// The built-in theme
+pub(crate) fn theme(name: &str) -> Option<&'static Theme> {
+    THEMES.iter().find(|it| it.name == name)
+}
+
+/// The built-in theme the document uses, the last one if it names several
+pub(crate) fn active(root: &RootNode) -> Option<&'static Theme> {
+    root.themes()
+        .filter(|it| it.source().is_none())
+        .filter_map(|it| theme(&it.name()?.0))
+        .last()
+}
+
+/// `!theme`s that are neither built in nor come from somewhere else
+pub(crate) fn problems(root: &RootNode) -> Vec<Problem> {
+    root.themes()
+        .filter(|it| it.source().is_none())
+        .filter_map(|it| it.name())
+        .filter(|(name, _)| theme(name).is_none())
+        .map(|(name, range)| Problem {
+            range,
+            severity: Severity::Warning,
+            message: format!("unknown theme '{}'", name),
+        })
+        .collect()
+}
+
+mod tests {
+    #[test]
+    fn test_themes() {
+        let text =
+            "@startuml\n!theme cerulean\n!theme ceruleum\n!theme mine from ./themes\n@enduml\n";
+        let parsed = crate::parse(text);
+        let root = parsed.root();
+        let problems = super::problems(&root)
+            .into_iter()
+            .map(|it| (&text[it.range], it.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            vec![("ceruleum", "unknown theme 'ceruleum'".to_string())]
+        );
+        let theme = super::active(&root).unwrap();
+        assert_eq!(theme.name, "cerulean");
+        assert_eq!(theme.skinparam("arrowcolor"), Some("#2FA4E7"));
+        assert_eq!(theme.skinparam("Handwritten"), None);
+    }
+}
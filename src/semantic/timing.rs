@@ -0,0 +1,197 @@
+//! The times of a timing diagram: what each `@100`, `@+50` or `@:label`
+//! resolves to, and which states each signal is given
+use crate::ast::{
+    IdentifierNode, RootNode, SignalDeclNode, SignalKind, SignalStatesNode, StateChangeNode,
+    TimeAnchorNode, TimeNode, TimeReference,
+};
+use crate::semantic::{Problem, Severity};
+use crate::syntax::SyntaxKind::*;
+use rowan::TextRange;
+use std::collections::{HashMap, HashSet};
+
+/// The absolute time of every TIME in a timing diagram
+pub(crate) struct Timeline {
+    times: Vec<(TextRange, f64)>,
+    problems: Vec<Problem>,
+}
+
+impl Timeline {
+    pub(crate) fn new(root: &RootNode) -> Self {
+        let mut timeline = Timeline {
+            times: Vec::new(),
+            problems: Vec::new(),
+        };
+        let mut now = 0.0;
+        let mut labels = HashMap::new();
+        // the signal of an '@WB' block, whose changes only give their time
+        let mut signal: Option<IdentifierNode> = None;
+        let mut changes = Vec::new();
+        for node in root.syntax().descendants() {
+            match node.kind() {
+                TIME_ANCHOR => {
+                    let anchor = TimeAnchorNode::cast(node).unwrap();
+                    if let Some(name) = anchor.signal() {
+                        signal = Some(name);
+                        continue;
+                    }
+                    if let Some(time) = anchor
+                        .time()
+                        .and_then(|it| timeline.resolve(&it, now, &labels))
+                    {
+                        now = time;
+                    }
+                    if let Some(label) = anchor.label() {
+                        labels.insert(label, now);
+                    }
+                }
+                STATE_CHANGE => {
+                    let change = StateChangeNode::cast(node).unwrap();
+                    if let Some(time) = change
+                        .time()
+                        .and_then(|it| timeline.resolve(&it, now, &labels))
+                    {
+                        now = time;
+                    }
+                    let on = change.signal().or_else(|| signal.clone());
+                    if let (Some(on), Some(state)) = (on, change.state()) {
+                        changes.push((on, state));
+                    }
+                }
+                // the times of highlights and links don't move the time on
+                HIGHLIGHT | TIMING_CONSTRAINT | TIMING_MESSAGE => {
+                    for time in node.children().filter_map(TimeNode::cast) {
+                        timeline.resolve(&time, now, &labels);
+                    }
+                }
+                _ => {}
+            }
+        }
+        timeline.check_states(root, &changes);
+        timeline
+    }
+
+    /// Work out, and remember, the absolute time of `time`, given the current
+    /// time and the labelled ones
+    fn resolve(&mut self, time: &TimeNode, now: f64, labels: &HashMap<String, f64>) -> Option<f64> {
+        let value = match time.reference()? {
+            TimeReference::Absolute(value) => value,
+            TimeReference::Relative(offset) => now + offset,
+            TimeReference::Label(label, offset) => match labels.get(&label) {
+                Some(value) => value + offset,
+                None => {
+                    self.problems.push(Problem {
+                        range: time.position(),
+                        severity: Severity::Error,
+                        message: format!("time ':{}' is never defined", label),
+                    });
+                    return None;
+                }
+            },
+        };
+        self.times.push((time.position(), value));
+        Some(value)
+    }
+
+    /// A robust signal that declares its states, as in `WB has Idle,Busy`,
+    /// should only be given those
+    fn check_states(&mut self, root: &RootNode, changes: &[(IdentifierNode, IdentifierNode)]) {
+        let robust = root
+            .signal_decls()
+            .filter(|it| it.signal_kind() == SignalKind::Robust)
+            .collect::<Vec<_>>();
+        let signal_of = |name: &IdentifierNode| -> Option<&SignalDeclNode> {
+            let name = name.value();
+            robust.iter().find(|it| {
+                it.name().map(|n| n.value()) == Some(name.clone())
+                    || it.alias().map(|a| a.value()) == Some(name.clone())
+            })
+        };
+        let mut declared: HashMap<TextRange, HashSet<String>> = HashMap::new();
+        for states in root
+            .syntax()
+            .descendants()
+            .filter_map(SignalStatesNode::cast)
+        {
+            let decl = match states.signal().as_ref().and_then(&signal_of) {
+                Some(decl) => decl,
+                None => continue,
+            };
+            declared
+                .entry(decl.syntax().text_range())
+                .or_default()
+                .extend(states.states().map(|it| it.value()));
+        }
+        for (on, state) in changes {
+            let decl = match signal_of(on) {
+                Some(decl) => decl,
+                None => continue,
+            };
+            let states = match declared.get(&decl.syntax().text_range()) {
+                Some(states) => states,
+                None => continue,
+            };
+            if !states.contains(&state.value()) {
+                self.problems.push(Problem {
+                    range: state.position(),
+                    severity: Severity::Warning,
+                    message: format!(
+                        "state '{}' is not declared for '{}'",
+                        state.value(),
+                        on.value()
+                    ),
+                });
+            }
+        }
+    }
+
+    /// The absolute time of the TIME at `range`
+    pub(crate) fn time(&self, range: TextRange) -> Option<f64> {
+        self.times
+            .iter()
+            .find(|(it, _)| *it == range)
+            .map(|(_, time)| *time)
+    }
+
+    pub(crate) fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_timeline() {
+        let text = "robust \"Web Browser\" as WB\nWB has Idle,Processing\n@0\nWB is Idle\n@100 as :start\nWB is Busy\n@:start+50\n@+25\n@WB\n+10 is Idle\nhighlight @:start to @:end\n";
+        let root = crate::parse(text).root();
+        let timeline = super::Timeline::new(&root);
+        let times = root
+            .syntax()
+            .descendants()
+            .filter_map(crate::ast::TimeNode::cast)
+            .map(|it| (it.syntax().text().to_string(), timeline.time(it.position())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            times,
+            vec![
+                ("0".to_string(), Some(0.0)),
+                ("100".to_string(), Some(100.0)),
+                (":start+50".to_string(), Some(150.0)),
+                ("+25".to_string(), Some(175.0)),
+                ("+10".to_string(), Some(185.0)),
+                (":start".to_string(), Some(100.0)),
+                (":end".to_string(), None),
+            ]
+        );
+        let problems = timeline
+            .problems()
+            .iter()
+            .map(|it| it.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            vec![
+                "time ':end' is never defined",
+                "state 'Busy' is not declared for 'WB'",
+            ]
+        );
+    }
+}
@@ -1,10 +1,9 @@
-use crate::grammar::Parsed;
-use jsonrpc_tcp_server::jsonrpc_core::{IoHandler, Params, RpcMethodSimple, RpcNotificationSimple};
+use crate::workspace::{Document, Workspace};
+use jsonrpc_tcp_server::jsonrpc_core::{IoHandler, Params};
 use log::*;
 use std::default::Default;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, RwLock};
 use tokio::net::TcpListener;
-use tokio::sync::watch;
 
 #[allow(unused_imports)]
 use std::net::SocketAddr;
@@ -29,23 +28,22 @@ impl Serializable for serde_json::Value {
     }
 }
 
+#[allow(dead_code)]
 mod advice {
     fn disconnected_participants() {
         // find any participants that have no connectsion
     }
 }
 
-type ReadWriteGuarded<T> = Arc<RwLock<Option<T>>>;
-fn empty<T>() -> ReadWriteGuarded<T> {
-    Arc::new(RwLock::new(None))
-}
+type ReadWriteGuarded<T> = Arc<RwLock<T>>;
 
+#[allow(dead_code)]
 mod events {
     #[derive(Default)]
     pub(crate) struct Events {}
 
     impl Events {
-        fn fireDocumentUpdated(event: DocumentUpdated) {}
+        fn fire_document_updated(_event: DocumentUpdated) {}
     }
 
     pub(crate) struct DocumentUpdated {
@@ -54,12 +52,94 @@ mod events {
     }
 
     #[derive(Debug, Clone)]
-    pub enum Event {}
+    pub enum Event {
+        /// Diagnostics to push to the client for a document
+        Diagnostics(lsp_types::PublishDiagnosticsParams),
+    }
+
+    impl Event {
+        /// The JSON-RPC notification that tells the client about the event
+        pub(crate) fn notification(&self) -> String {
+            let (method, params) = match self {
+                Event::Diagnostics(params) => (
+                    "textDocument/publishDiagnostics",
+                    serde_json::to_value(params),
+                ),
+            };
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params.unwrap_or_default(),
+            })
+            .to_string()
+        }
+    }
 }
 
+type Bus = tokio::sync::broadcast::Sender<events::Event>;
+
+fn publish_diagnostics(bus: &Bus, workspace: &Workspace, document: &Document) {
+    let mut diagnostics = crate::ide::diagnostics::diagnostics(document);
+    diagnostics.extend(crate::ide::diagnostics::include_diagnostics(
+        workspace, document,
+    ));
+    diagnostics.extend(crate::ide::diagnostics::procedure_diagnostics(
+        workspace, document,
+    ));
+    diagnostics.extend(crate::ide::diagnostics::expansion_diagnostics(
+        workspace, document,
+    ));
+    diagnostics.extend(crate::ide::diagnostics::c4_diagnostics(workspace, document));
+    let params = lsp_types::PublishDiagnosticsParams {
+        uri: document.uri.clone(),
+        diagnostics,
+        version: None,
+    };
+    if bus.send(events::Event::Diagnostics(params)).is_err() {
+        trace!("No connections to publish diagnostics to");
+    }
+}
+
+/// Publish the diagnostics of the document that changed, and of the open
+/// documents that include it, as what they include changed along with it
+fn publish_changed(bus: &Bus, workspace: &Workspace, uri: &lsp_types::Url) {
+    if let Some(document) = workspace.get(uri) {
+        trace!("Parse errors - {:?}", document.parsed.errors());
+        publish_diagnostics(bus, workspace, document);
+    }
+    for document in workspace.includers(uri) {
+        publish_diagnostics(bus, workspace, document);
+    }
+}
+
+/// The directories given as `includePaths` in the initialization options, with
+/// relative ones taken relative to the root of the workspace
+fn include_roots(params: &lsp_types::InitializeParams) -> Vec<std::path::PathBuf> {
+    let root = params
+        .root_uri
+        .as_ref()
+        .and_then(|it| it.to_file_path().ok());
+    let paths = params
+        .initialization_options
+        .as_ref()
+        .and_then(|it| it.get("includePaths"))
+        .and_then(|it| it.as_array())
+        .cloned()
+        .unwrap_or_default();
+    paths
+        .iter()
+        .filter_map(|it| it.as_str())
+        .map(|path| match &root {
+            Some(root) => root.join(path),
+            None => path.into(),
+        })
+        .collect()
+}
+
+#[allow(dead_code)]
 pub struct PlantUmlLanguageServer {
     address: String,
-    parsed: Arc<RwLock<Option<Parsed>>>,
+    workspace: ReadWriteGuarded<Workspace>,
     handler: Arc<IoHandler<()>>,
     bus: (
         tokio::sync::broadcast::Sender<events::Event>,
@@ -67,66 +147,76 @@ pub struct PlantUmlLanguageServer {
     ),
 }
 
-fn onHello(_params: Params) -> SerializedResponse {
+fn on_hello(_params: Params) -> SerializedResponse {
     serde_json::Value::String("hello".to_string()).serialize()
 }
 
 impl PlantUmlLanguageServer {
     pub fn new(address: &str) -> Self {
-        let parsed: ReadWriteGuarded<Parsed> = empty();
+        let workspace: ReadWriteGuarded<Workspace> = Default::default();
         let mut handler = IoHandler::<()>::default();
-        let events = events::Events {};
+        let bus = tokio::sync::broadcast::channel::<events::Event>(10);
 
-        let plock = parsed.clone();
         info!("Registering rpc methods");
+        let wlock = workspace.clone();
+        let sender = bus.0.clone();
         handler.add_notification("textDocument/didChange", move |params: Params| {
             let p = params.parse::<lsp_types::DidChangeTextDocumentParams>();
             if let Ok(x) = p {
-                let text: String = x.content_changes.iter().next().unwrap().text.to_string();
-                let p = crate::parse(&text);
-                let res = p
-                    .root()
-                    .participant_decls()
-                    .filter_map(|it| it.participant_name())
-                    .map(|it| it.identifier())
-                    .collect::<Vec<_>>();
-                trace!("Participant Names - {:?}", res);
-
-                let mut x = plock.write().unwrap();
-                x.replace(p);
+                // we only advertise full document sync, so the last change is the whole text
+                if let Some(change) = x.content_changes.into_iter().last() {
+                    let mut workspace = wlock.write().unwrap();
+                    let uri = x.text_document.uri;
+                    workspace.update(uri.clone(), change.text);
+                    publish_changed(&sender, &workspace, &uri);
+                }
             }
         });
 
-        handler.add_method("say_hello", onHello);
+        handler.add_method("say_hello", on_hello);
 
-        handler.add_notification("textDocument/didOpen", |params: Params| {
+        let wlock = workspace.clone();
+        let sender = bus.0.clone();
+        handler.add_notification("textDocument/didOpen", move |params: Params| {
             let p = params.parse::<lsp_types::DidOpenTextDocumentParams>();
             debug!("textDocument/didOpen {:?}", p);
+            if let Ok(x) = p {
+                let mut workspace = wlock.write().unwrap();
+                let uri = x.text_document.uri;
+                workspace.update(uri.clone(), x.text_document.text);
+                publish_changed(&sender, &workspace, &uri);
+            }
+        });
+
+        let wlock = workspace.clone();
+        let sender = bus.0.clone();
+        handler.add_notification("textDocument/didClose", move |params: Params| {
+            let p = params.parse::<lsp_types::DidCloseTextDocumentParams>();
+            if let Ok(x) = p {
+                let mut workspace = wlock.write().unwrap();
+                let uri = x.text_document.uri;
+                workspace.remove(&uri);
+                // what includes it now sees what's on disk
+                for document in workspace.includers(&uri) {
+                    publish_diagnostics(&sender, &workspace, document);
+                }
+            }
         });
 
-        let plock = parsed.clone();
+        let wlock = workspace.clone();
         handler.add_method("textDocument/completion", move |params: Params| {
-            debug!("Initialize called");
-            let p = params.parse::<lsp_types::CompletionParams>();
-            debug!("initialize {:?}", p);
+            let p = params.parse::<lsp_types::CompletionParams>()?;
+            debug!("completion {:?}", p);
 
-            let items = match plock.try_read() {
-                Ok(locked) => locked
-                    .as_ref()
-                    .unwrap()
-                    .root()
-                    .participant_decls()
-                    .filter_map(|it| it.participant_name())
-                    .map(|it| lsp_types::CompletionItem {
-                        kind: Some(lsp_types::CompletionItemKind::Struct),
-                        label: it.identifier().to_string(),
-                        //detail: Some("This is detailed sup".into()),
-                        //insert_text: Some(it.identifier
-                        ..Default::default()
-                    })
-                    .collect(),
-                Err(_) => vec![],
-            };
+            let workspace = wlock.read().unwrap();
+            let items = workspace
+                .get(&p.text_document_position.text_document.uri)
+                .map(|document| {
+                    let includes = workspace.includes(document);
+                    let position = p.text_document_position.position;
+                    crate::ide::completion::completions(document, &includes, position)
+                })
+                .unwrap_or_default();
             let response = lsp_types::CompletionList {
                 is_incomplete: false,
                 items,
@@ -135,10 +225,160 @@ impl PlantUmlLanguageServer {
             serialized_response(response)
         });
 
-        handler.add_method("initialize", |params: Params| {
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/documentSymbol", move |params: Params| {
+            let p = params.parse::<lsp_types::DocumentSymbolParams>()?;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&p.text_document.uri)
+                .map(crate::ide::symbols::document_symbols)
+                .map(lsp_types::DocumentSymbolResponse::Nested);
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/rename", move |params: Params| {
+            let p = params.parse::<lsp_types::RenameParams>()?;
+            let position = &p.text_document_position;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&position.text_document.uri)
+                .and_then(|document| {
+                    crate::ide::rename::rename(document, position.position, &p.new_name)
+                });
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/definition", move |params: Params| {
+            let p = params.parse::<lsp_types::GotoDefinitionParams>()?;
+            let position = &p.text_document_position_params;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&position.text_document.uri)
+                .and_then(|document| {
+                    let includes = workspace.includes(document);
+                    crate::ide::goto_definition::goto_definition(
+                        document,
+                        &includes,
+                        position.position,
+                    )
+                })
+                .map(lsp_types::GotoDefinitionResponse::Scalar);
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/signatureHelp", move |params: Params| {
+            let p = params.parse::<lsp_types::SignatureHelpParams>()?;
+            let position = &p.text_document_position_params;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&position.text_document.uri)
+                .and_then(|document| {
+                    let includes = workspace.includes(document);
+                    crate::ide::signature_help::signature_help(
+                        document,
+                        &includes,
+                        position.position,
+                    )
+                });
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("puml/expandedSource", move |params: Params| {
+            let p = params.parse::<crate::ide::expansion::ExpandedSourceParams>()?;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&p.text_document.uri)
+                .map(|document| crate::ide::expansion::expanded_source(&workspace, document));
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/hover", move |params: Params| {
+            let p = params.parse::<lsp_types::HoverParams>()?;
+            let position = &p.text_document_position_params;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&position.text_document.uri)
+                .and_then(|document| {
+                    let includes = workspace.includes(document);
+                    crate::ide::hover::hover(document, &includes, position.position)
+                });
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/references", move |params: Params| {
+            let p = params.parse::<lsp_types::ReferenceParams>()?;
+            let position = &p.text_document_position;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&position.text_document.uri)
+                .and_then(|document| {
+                    crate::ide::references::find_references(document, position.position)
+                });
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/foldingRange", move |params: Params| {
+            let p = params.parse::<lsp_types::FoldingRangeParams>()?;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&p.text_document.uri)
+                .map(crate::ide::folding::folding_ranges);
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/documentLink", move |params: Params| {
+            let p = params.parse::<lsp_types::DocumentLinkParams>()?;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&p.text_document.uri)
+                .map(|document| crate::ide::links::document_links(&workspace, document));
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/codeAction", move |params: Params| {
+            let p = params.parse::<lsp_types::CodeActionParams>()?;
+            let workspace = wlock.read().unwrap();
+            let response: lsp_types::CodeActionResponse = workspace
+                .get(&p.text_document.uri)
+                .map(|document| crate::ide::code_actions::code_actions(document, p.range))
+                .unwrap_or_default();
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("textDocument/documentColor", move |params: Params| {
+            let p = params.parse::<lsp_types::DocumentColorParams>()?;
+            let workspace = wlock.read().unwrap();
+            let response = workspace
+                .get(&p.text_document.uri)
+                .map(crate::ide::colors::document_colors)
+                .unwrap_or_default();
+            serialized_response(response)
+        });
+
+        handler.add_method("textDocument/colorPresentation", move |params: Params| {
+            let p = params.parse::<lsp_types::ColorPresentationParams>()?;
+            let response = crate::ide::colors::color_presentations(p.color, p.range);
+            serialized_response(response)
+        });
+
+        let wlock = workspace.clone();
+        handler.add_method("initialize", move |params: Params| {
             debug!("Initialize called");
             let p = params.parse::<lsp_types::InitializeParams>();
             debug!("initialize {:?}", p);
+            if let Ok(p) = &p {
+                wlock.write().unwrap().set_include_roots(include_roots(p));
+            }
             let response = lsp_types::InitializeResult {
                 server_info: Some(lsp_types::ServerInfo {
                     name: "pummls".into(),
@@ -153,6 +393,27 @@ impl PlantUmlLanguageServer {
                         resolve_provider: Some(true),
                         ..Default::default()
                     }),
+                    document_symbol_provider: Some(true),
+                    definition_provider: Some(true),
+                    signature_help_provider: Some(lsp_types::SignatureHelpOptions {
+                        trigger_characters: Some(vec!["(".into(), ",".into()]),
+                        retrigger_characters: None,
+                        work_done_progress_options: Default::default(),
+                    }),
+                    hover_provider: Some(true),
+                    references_provider: Some(true),
+                    rename_provider: Some(lsp_types::RenameProviderCapability::Simple(true)),
+                    document_link_provider: Some(lsp_types::DocumentLinkOptions {
+                        resolve_provider: Some(false),
+                        work_done_progress_options: Default::default(),
+                    }),
+                    folding_range_provider: Some(
+                        lsp_types::FoldingRangeProviderCapability::Simple(true),
+                    ),
+                    color_provider: Some(lsp_types::ColorProviderCapability::Simple(true)),
+                    code_action_provider: Some(lsp_types::CodeActionProviderCapability::Simple(
+                        true,
+                    )),
                     ..Default::default()
                 },
             };
@@ -167,9 +428,9 @@ impl PlantUmlLanguageServer {
 
         PlantUmlLanguageServer {
             address: address.to_owned(),
-            parsed,
+            workspace,
             handler: Arc::new(handler),
-            bus: tokio::sync::broadcast::channel::<events::Event>(10),
+            bus,
         }
     }
 
@@ -179,9 +440,10 @@ impl PlantUmlLanguageServer {
         loop {
             let (socket, _) = listener.accept().await?;
             let h = self.handler.clone();
+            let events = self.bus.0.subscribe();
             tokio::spawn(async move {
                 debug!("Spawning lsp connection");
-                let l = conn::LspConnection::new(h);
+                let mut l = conn::LspConnection::new(h, events);
                 l.run(socket).await;
                 debug!("LSP Connection done");
             });
@@ -196,18 +458,24 @@ mod conn {
     use jsonrpc_tcp_server::jsonrpc_core::futures::Future;
     use jsonrpc_tcp_server::jsonrpc_core::IoHandler;
     use log::*;
+    use tokio::sync::broadcast::Receiver;
     use tokio_util::codec::Framed;
 
     pub struct LspConnection {
         handler: std::sync::Arc<IoHandler>,
+        /// events raised while handling messages, to be sent on to the client
+        events: Receiver<super::events::Event>,
     }
 
     impl LspConnection {
-        pub fn new(handler: std::sync::Arc<IoHandler>) -> Self {
-            LspConnection { handler }
+        pub fn new(
+            handler: std::sync::Arc<IoHandler>,
+            events: Receiver<super::events::Event>,
+        ) -> Self {
+            LspConnection { handler, events }
         }
 
-        pub async fn run(&self, socket: tokio::net::TcpStream) {
+        pub async fn run(&mut self, socket: tokio::net::TcpStream) {
             let codec = LspCodec::new();
             let (mut _sink, mut input) = Framed::new(socket, codec).split();
             while let Some(Ok(event)) = input.next().await {
@@ -217,7 +485,7 @@ mod conn {
                         let f = futures03::compat::Compat01As03::new(
                             self.handler
                                 .handle_request(&value)
-                                .map(|x| Ok::<Option<String>, ()>(x)),
+                                .map(Ok::<Option<String>, ()>),
                         )
                         .await;
 
@@ -242,6 +510,13 @@ mod conn {
                         }
                     }
                 };
+                while let Ok(event) = self.events.try_recv() {
+                    let notification = event.notification();
+                    trace!(target: "tcp", "Sending notification: {}", &notification);
+                    if let Err(e) = _sink.send(LspEvent::Message(notification)).await {
+                        warn!(target: "tcp", "Failed to send {}", e);
+                    }
+                }
             }
             debug!("Exiting LSP Connection Loop");
         }
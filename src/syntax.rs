@@ -1,39 +1,13 @@
-use num;
-use log::*;
 use num::FromPrimitive;
-use num_derive::{FromPrimitive,ToPrimitive};
 
-#[derive(FromPrimitive, ToPrimitive, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[allow(non_camel_case_types)]
-#[repr(u16)]
-pub enum SyntaxKind {
-    EOF = 0,
+mod generated;
 
-    WHITESPACE,
-    IDENTIFIER,
-    EOL,
-    ERROR,
+pub use self::generated::SyntaxKind;
 
-    // synthetic nodes
-    ROOT,
-
-    // keywords
-    PARTICIPANT_KW,
-
-    // composite nodes
-    STATEMENT,
-    PARTICIPANT_DECL,
-}
-impl From<SyntaxKind> for &str {
-    fn from(k: SyntaxKind) -> Self {
-        //trace!("from for syntax kind, kind = {:?}", k);
-        match k {
-            SyntaxKind::PARTICIPANT_KW => r"participant",
-            SyntaxKind::IDENTIFIER => r"[a-zA-Z][a-zA-Z0-9]*",
-            SyntaxKind::EOL => "\n",
-            SyntaxKind::WHITESPACE => r"[^\S\r\n]+",
-            _ => unreachable!("You're using a syntax token kind that's not lexable"),
-        }
+impl SyntaxKind {
+    /// Whitespace and comments, which the parser looks past
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::WHITESPACE | SyntaxKind::COMMENT)
     }
 }
 
@@ -0,0 +1,455 @@
+//! Generated from `src/puml.ungram` by the `codegen` test, don't edit by hand
+// num_derive expands to impls inside an anonymous const
+#![allow(non_local_definitions)]
+
+use num_derive::{FromPrimitive, ToPrimitive};
+
+use self::SyntaxKind::*;
+
+#[derive(FromPrimitive, ToPrimitive, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[repr(u16)]
+pub enum SyntaxKind {
+    EOF = 0,
+    WHITESPACE,
+    /// `' to the end of the line`, at the start of a line, or `/' ... '/`
+    COMMENT,
+    IDENTIFIER,
+    EOL,
+    ERROR,
+    NUMBER,
+    STRING,
+    ARROW,
+    L_BRACE,
+    R_BRACE,
+    L_PAREN,
+    R_PAREN,
+    L_BRACK,
+    R_BRACK,
+    L_ANGLE,
+    R_ANGLE,
+    STEREO_OPEN,
+    STEREO_CLOSE,
+    COLON,
+    COMMA,
+    DOT,
+    PLUS,
+    MINUS,
+    HASH,
+    TILDE,
+    SEMI,
+    PIPE,
+    SLASH,
+    BACKSLASH,
+    DIAGRAM_START,
+    DIAGRAM_END,
+    PSEUDO_STATE,
+    EQ,
+    FAT_ARROW,
+    DATE,
+    PERCENT,
+    STAR,
+    AT,
+    DIRECTIVE,
+    VARIABLE,
+    BUILTIN,
+    PARTICIPANT_KW,
+    CLASS_KW,
+    ABSTRACT_KW,
+    INTERFACE_KW,
+    ENUM_KW,
+    ANNOTATION_KW,
+    PACKAGE_KW,
+    NAMESPACE_KW,
+    AS_KW,
+    EXTENDS_KW,
+    IMPLEMENTS_KW,
+    START_KW,
+    STOP_KW,
+    END_KW,
+    IF_KW,
+    THEN_KW,
+    ELSEIF_KW,
+    ELSE_KW,
+    ENDIF_KW,
+    WHILE_KW,
+    IS_KW,
+    ENDWHILE_KW,
+    REPEAT_KW,
+    FORK_KW,
+    SPLIT_KW,
+    AGAIN_KW,
+    PARTITION_KW,
+    DETACH_KW,
+    KILL_KW,
+    STATE_KW,
+    COMPONENT_KW,
+    NODE_KW,
+    FOLDER_KW,
+    FRAME_KW,
+    CLOUD_KW,
+    DATABASE_KW,
+    PORT_KW,
+    PORTIN_KW,
+    PORTOUT_KW,
+    ACTOR_KW,
+    USECASE_KW,
+    RECTANGLE_KW,
+    OBJECT_KW,
+    MAP_KW,
+    CREOLE_TEXT,
+    /// `\n`, `\t`, or a `~` and the character it escapes
+    CREOLE_ESCAPE,
+    /// the `**`, `//`, `""`, `--` or `__` around emphasized text
+    CREOLE_DELIMITER,
+    /// `<color:red>` or `<b>`
+    CREOLE_TAG_START,
+    /// `</color>` or `</b>`
+    CREOLE_TAG_END,
+    /// an OpenIconic icon such as `<&heart>`
+    CREOLE_ICON,
+    /// `<img:logo.png>`
+    CREOLE_IMAGE,
+
+    // nodes
+    ROOT,
+    BLOCK,
+    /// The text of an action, a link or a note, with its Creole markup
+    LABEL,
+    STEREOTYPE,
+    PACKAGE_DECL,
+    CLASS_DECL,
+    GENERIC_PARAMS,
+    EXTENDS_CLAUSE,
+    IMPLEMENTS_CLAUSE,
+    CLASS_BODY,
+    FIELD,
+    METHOD,
+    /// `+`, `-`, `#` or `~`
+    VISIBILITY,
+    /// `{static}`, `{classifier}` or `{abstract}`
+    MODIFIER,
+    RELATION,
+    CARDINALITY,
+    PARTICIPANT_DECL,
+    START,
+    STOP,
+    ACTION,
+    ACTIVITY_ARROW,
+    IF_BLOCK,
+    WHILE_BLOCK,
+    REPEAT_BLOCK,
+    FORK_BLOCK,
+    SPLIT_BLOCK,
+    /// The `(a)` of `if (a) then (yes)`
+    CONDITION,
+    BRANCH,
+    SWIMLANE,
+    PARTITION,
+    STATE_DECL,
+    STATE_BODY,
+    /// One of the concurrent parts of a composite state, separated by `--` or
+    /// `||`
+    REGION,
+    DESCRIPTION,
+    COMPONENT_DECL,
+    CONTAINER_DECL,
+    PORT_DECL,
+    ACTOR_DECL,
+    USECASE_DECL,
+    /// `left to right direction` or `top to bottom direction`
+    DIRECTION,
+    OBJECT_DECL,
+    MAP_DECL,
+    OBJECT_BODY,
+    /// `field = value`, `key => value` or `key *-> Other`
+    ENTRY,
+    TASK_STATEMENT,
+    /// `lasts 10 days` or `requires 2 weeks`
+    DURATION,
+    /// `starts 2020-07-01` or `ends 2 days after [A]'s end`
+    CONSTRAINT,
+    /// `{Alice}` or `{Bob:50%}`
+    RESOURCE,
+    DEPENDENCY,
+    PROJECT_START,
+    CLOSED_DAYS,
+    /// A node of a mind map, followed by the nodes that are deeper than it
+    MINDMAP_NODE,
+    /// `left side` or `right side`
+    SIDE,
+    SIGNAL_DECL,
+    /// `WB has Idle,Processing,Waiting`
+    SIGNAL_STATES,
+    /// `100`, `+50`, `:label` or `:label+50`
+    TIME,
+    TIME_ANCHOR,
+    STATE_CHANGE,
+    /// `highlight 200 to 450 #Gold : caption`
+    HIGHLIGHT,
+    TIMING_CONSTRAINT,
+    TIMING_MESSAGE,
+    JSON_DATA,
+    YAML_DATA,
+    /// `#highlight "address" / "city"`
+    DATA_HIGHLIGHT,
+    SALT_DIAGRAM,
+    /// `{ ... }`, with cells separated by `|` and rows by line breaks
+    SALT_GROUP,
+    /// `[Ok]`, `[X] checked`, `(X) chosen`, `"text field"` or `^droplist^`
+    SALT_WIDGET,
+    INCLUDE,
+    /// The file `!include` includes, and the part of it after any `!`
+    INCLUDE_PATH,
+    /// A directive other than the ones with a node of their own, such as
+    /// `!startsub`
+    PREPROCESSOR,
+    VARIABLE_DEF,
+    EXPRESSION,
+    /// `!define` or `!definelong`
+    DEFINE,
+    UNDEF,
+    /// An `!if`, `!ifdef` or `!ifndef`, with its `!elseif` and `!else` branches
+    PP_IF,
+    PP_BRANCH,
+    /// `!procedure` or `!function`, with its body
+    PROCEDURE,
+    PARAM_LIST,
+    PARAM,
+    RETURN,
+    /// A call of a procedure, a function or a macro
+    CALL,
+    ARG_LIST,
+    ARGUMENT,
+    SKINPARAM,
+    SKINPARAM_ENTRY,
+    PARAM_VALUE,
+    /// `<style>` ... `</style>`
+    STYLE,
+    /// `classDiagram, .entity { ... }`
+    STYLE_RULE,
+    STYLE_PROPERTY,
+    /// `!theme cerulean`, or `!theme mine from ./themes`
+    THEME,
+    THEME_NAME,
+    THEME_SOURCE,
+    CREOLE_BOLD,
+    CREOLE_ITALIC,
+    CREOLE_MONOSPACED,
+    CREOLE_STRIKE,
+    CREOLE_UNDERLINE,
+    /// A CREOLE_TAG_START, the text it applies to, and its CREOLE_TAG_END
+    CREOLE_TAG,
+}
+
+impl SyntaxKind {
+    /// The keyword `text` is, as keywords are only keywords when they're the
+    /// whole of a name
+    pub fn from_keyword(text: &str) -> Option<SyntaxKind> {
+        let kind = match text {
+            "participant" => PARTICIPANT_KW,
+            "class" => CLASS_KW,
+            "abstract" => ABSTRACT_KW,
+            "interface" => INTERFACE_KW,
+            "enum" => ENUM_KW,
+            "annotation" => ANNOTATION_KW,
+            "package" => PACKAGE_KW,
+            "namespace" => NAMESPACE_KW,
+            "as" => AS_KW,
+            "extends" => EXTENDS_KW,
+            "implements" => IMPLEMENTS_KW,
+            "start" => START_KW,
+            "stop" => STOP_KW,
+            "end" => END_KW,
+            "if" => IF_KW,
+            "then" => THEN_KW,
+            "elseif" => ELSEIF_KW,
+            "else" => ELSE_KW,
+            "endif" => ENDIF_KW,
+            "while" => WHILE_KW,
+            "is" => IS_KW,
+            "endwhile" => ENDWHILE_KW,
+            "repeat" => REPEAT_KW,
+            "fork" => FORK_KW,
+            "split" => SPLIT_KW,
+            "again" => AGAIN_KW,
+            "partition" => PARTITION_KW,
+            "detach" => DETACH_KW,
+            "kill" => KILL_KW,
+            "state" => STATE_KW,
+            "component" => COMPONENT_KW,
+            "node" => NODE_KW,
+            "folder" => FOLDER_KW,
+            "frame" => FRAME_KW,
+            "cloud" => CLOUD_KW,
+            "database" => DATABASE_KW,
+            "port" => PORT_KW,
+            "portin" => PORTIN_KW,
+            "portout" => PORTOUT_KW,
+            "actor" => ACTOR_KW,
+            "usecase" => USECASE_KW,
+            "rectangle" => RECTANGLE_KW,
+            "object" => OBJECT_KW,
+            "map" => MAP_KW,
+            _ => return None,
+        };
+        Some(kind)
+    }
+    /// The token that's a single character `c`
+    pub fn from_char(c: char) -> Option<SyntaxKind> {
+        let kind = match c {
+            '{' => L_BRACE,
+            '}' => R_BRACE,
+            '(' => L_PAREN,
+            ')' => R_PAREN,
+            '[' => L_BRACK,
+            ']' => R_BRACK,
+            '<' => L_ANGLE,
+            '>' => R_ANGLE,
+            ':' => COLON,
+            ',' => COMMA,
+            '.' => DOT,
+            '+' => PLUS,
+            '-' => MINUS,
+            '#' => HASH,
+            '~' => TILDE,
+            ';' => SEMI,
+            '|' => PIPE,
+            '/' => SLASH,
+            '\\' => BACKSLASH,
+            '=' => EQ,
+            '%' => PERCENT,
+            '*' => STAR,
+            '@' => AT,
+            _ => return None,
+        };
+        Some(kind)
+    }
+    /// The text of a keyword or of punctuation, which is always the same
+    pub fn text(self) -> Option<&'static str> {
+        let text = match self {
+            L_BRACE => "{",
+            R_BRACE => "}",
+            L_PAREN => "(",
+            R_PAREN => ")",
+            L_BRACK => "[",
+            R_BRACK => "]",
+            L_ANGLE => "<",
+            R_ANGLE => ">",
+            STEREO_OPEN => "<<",
+            STEREO_CLOSE => ">>",
+            COLON => ":",
+            COMMA => ",",
+            DOT => ".",
+            PLUS => "+",
+            MINUS => "-",
+            HASH => "#",
+            TILDE => "~",
+            SEMI => ";",
+            PIPE => "|",
+            SLASH => "/",
+            BACKSLASH => "\\",
+            EQ => "=",
+            FAT_ARROW => "=>",
+            PERCENT => "%",
+            STAR => "*",
+            AT => "@",
+            PARTICIPANT_KW => "participant",
+            CLASS_KW => "class",
+            ABSTRACT_KW => "abstract",
+            INTERFACE_KW => "interface",
+            ENUM_KW => "enum",
+            ANNOTATION_KW => "annotation",
+            PACKAGE_KW => "package",
+            NAMESPACE_KW => "namespace",
+            AS_KW => "as",
+            EXTENDS_KW => "extends",
+            IMPLEMENTS_KW => "implements",
+            START_KW => "start",
+            STOP_KW => "stop",
+            END_KW => "end",
+            IF_KW => "if",
+            THEN_KW => "then",
+            ELSEIF_KW => "elseif",
+            ELSE_KW => "else",
+            ENDIF_KW => "endif",
+            WHILE_KW => "while",
+            IS_KW => "is",
+            ENDWHILE_KW => "endwhile",
+            REPEAT_KW => "repeat",
+            FORK_KW => "fork",
+            SPLIT_KW => "split",
+            AGAIN_KW => "again",
+            PARTITION_KW => "partition",
+            DETACH_KW => "detach",
+            KILL_KW => "kill",
+            STATE_KW => "state",
+            COMPONENT_KW => "component",
+            NODE_KW => "node",
+            FOLDER_KW => "folder",
+            FRAME_KW => "frame",
+            CLOUD_KW => "cloud",
+            DATABASE_KW => "database",
+            PORT_KW => "port",
+            PORTIN_KW => "portin",
+            PORTOUT_KW => "portout",
+            ACTOR_KW => "actor",
+            USECASE_KW => "usecase",
+            RECTANGLE_KW => "rectangle",
+            OBJECT_KW => "object",
+            MAP_KW => "map",
+            _ => return None,
+        };
+        Some(text)
+    }
+    pub fn is_keyword(self) -> bool {
+        matches!(
+            self,
+            PARTICIPANT_KW
+                | CLASS_KW
+                | ABSTRACT_KW
+                | INTERFACE_KW
+                | ENUM_KW
+                | ANNOTATION_KW
+                | PACKAGE_KW
+                | NAMESPACE_KW
+                | AS_KW
+                | EXTENDS_KW
+                | IMPLEMENTS_KW
+                | START_KW
+                | STOP_KW
+                | END_KW
+                | IF_KW
+                | THEN_KW
+                | ELSEIF_KW
+                | ELSE_KW
+                | ENDIF_KW
+                | WHILE_KW
+                | IS_KW
+                | ENDWHILE_KW
+                | REPEAT_KW
+                | FORK_KW
+                | SPLIT_KW
+                | AGAIN_KW
+                | PARTITION_KW
+                | DETACH_KW
+                | KILL_KW
+                | STATE_KW
+                | COMPONENT_KW
+                | NODE_KW
+                | FOLDER_KW
+                | FRAME_KW
+                | CLOUD_KW
+                | DATABASE_KW
+                | PORT_KW
+                | PORTIN_KW
+                | PORTOUT_KW
+                | ACTOR_KW
+                | USECASE_KW
+                | RECTANGLE_KW
+                | OBJECT_KW
+                | MAP_KW
+        )
+    }
+}
@@ -0,0 +1,511 @@
+use crate::ast::{IncludeKind, IncludeNode};
+use crate::grammar::Parsed;
+use log::*;
+use lsp_types::{Position, Range, Url};
+use rowan::{TextRange, TextSize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// A document the client has told us about, along with its syntax tree
+pub(crate) struct Document {
+    pub(crate) uri: Url,
+    pub(crate) text: String,
+    /// The syntax tree of the branches of `!if` that are taken, the others
+    /// are blanked out so that offsets still match `text`
+    pub(crate) parsed: Parsed,
+    /// The bodies of the branches of `!if` that aren't taken
+    pub(crate) inactive: Vec<TextRange>,
+    /// byte offset of the start of every line
+    line_starts: Vec<usize>,
+}
+
+/// The text with everything in `ranges` but line breaks replaced by spaces
+fn blank(text: &str, ranges: &[TextRange]) -> String {
+    let mut text = text.to_string();
+    for range in ranges {
+        let blanked = text[*range]
+            .chars()
+            .map(|it| {
+                if it == '\n' {
+                    "\n".to_string()
+                } else {
+                    " ".repeat(it.len_utf8())
+                }
+            })
+            .collect::<String>();
+        text.replace_range(std::ops::Range::<usize>::from(*range), &blanked);
+    }
+    text
+}
+
+impl Document {
+    pub(crate) fn new(uri: Url, text: String) -> Self {
+        let mut parsed = crate::parse(&text);
+        let inactive = crate::semantic::preprocessor::evaluate(&parsed.root()).inactive;
+        if !inactive.is_empty() {
+            parsed = crate::parse(&blank(&text, &inactive));
+        }
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Document {
+            uri,
+            text,
+            parsed,
+            inactive,
+            line_starts,
+        }
+    }
+
+    /// Convert a byte offset into an LSP position, whose character is
+    /// counted in UTF-16 code units.
+    pub(crate) fn position(&self, offset: TextSize) -> Position {
+        let offset = usize::from(offset).min(self.text.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+        Position::new(line as u64, character as u64)
+    }
+
+    /// Convert an LSP position into a byte offset, clamping to the end of the
+    /// line.
+    pub(crate) fn offset(&self, position: Position) -> Option<TextSize> {
+        let start = *self.line_starts.get(position.line as usize)?;
+        let mut units = 0;
+        let mut offset = start;
+        for c in self.text[start..].chars() {
+            if c == '\n' || units >= position.character as usize {
+                break;
+            }
+            units += c.len_utf16();
+            offset += c.len_utf8();
+        }
+        Some(TextSize::from(offset as u32))
+    }
+
+    pub(crate) fn range(&self, range: TextRange) -> Range {
+        Range::new(self.position(range.start()), self.position(range.end()))
+    }
+
+    /// The last segment of the document's uri, e.g. `common.puml`
+    pub(crate) fn file_name(&self) -> String {
+        self.uri
+            .path_segments()
+            .and_then(|mut it| it.next_back())
+            .unwrap_or("")
+            .to_string()
+    }
+
+    /// What's between `!startsub name` and the next `!endsub`
+    fn sub_range(&self, name: &str) -> Option<TextRange> {
+        let root = self.parsed.root();
+        let mut lines = root.preprocessor_lines();
+        let start = lines
+            .find(|it| it.directive() == "!startsub" && it.argument() == name)?
+            .position()
+            .end();
+        let end = lines
+            .find(|it| it.directive() == "!endsub")
+            .map(|it| it.position().start())
+            .unwrap_or_else(|| root.syntax().text_range().end());
+        Some(TextRange::new(start, end))
+    }
+}
+
+/// A document pulled in by an `!include`
+pub(crate) struct Included<'a> {
+    pub(crate) document: &'a Document,
+    /// All of the document, or the part of it an `!includesub` asks for
+    pub(crate) range: TextRange,
+}
+
+/// All the documents known to the server, by uri, along with the files they
+/// include
+#[derive(Default)]
+pub(crate) struct Workspace {
+    documents: HashMap<Url, Document>,
+    /// The documents the client has open, whose text comes from the client
+    /// rather than from disk
+    open: HashSet<Url>,
+    /// When each of the other documents was last modified on disk, and how
+    /// long it was then, to tell when to read it again
+    on_disk: HashMap<Url, (SystemTime, u64)>,
+    /// Where to look for included files that aren't where the including
+    /// document says they are
+    include_roots: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Parse the text and remember it as the latest version of the document,
+    /// loading the files it includes that aren't known yet
+    pub(crate) fn update(&mut self, uri: Url, text: String) -> &Document {
+        trace!("Updating document {}", uri);
+        self.open.insert(uri.clone());
+        self.on_disk.remove(&uri);
+        self.documents
+            .insert(uri.clone(), Document::new(uri.clone(), text));
+        self.load_includes(&uri);
+        &self.documents[&uri]
+    }
+
+    pub(crate) fn set_include_roots(&mut self, roots: Vec<PathBuf>) {
+        debug!("Include roots {:?}", roots);
+        self.include_roots = roots;
+    }
+
+    /// Where the file an `!include` names is: relative to the including
+    /// document, or else to one of the include roots. Files from the standard
+    /// library, as in `<C4/C4>`, aren't resolved here.
+    pub(crate) fn resolve(&self, from: &Url, path: &str) -> Option<Url> {
+        if path.starts_with('<') {
+            return None;
+        }
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return Url::parse(path).ok();
+        }
+        let roots = self
+            .include_roots
+            .iter()
+            .map(|root| Url::from_directory_path(root).ok());
+        std::iter::once(Some(from.clone()))
+            .chain(roots)
+            .flatten()
+            .filter_map(|base| base.join(path).ok())
+            .find(|url| self.exists(url))
+    }
+
+    fn exists(&self, url: &Url) -> bool {
+        self.documents.contains_key(url)
+            || url
+                .to_file_path()
+                .map(|path| path.is_file())
+                .unwrap_or(false)
+    }
+
+    /// Where the files `document` includes are, for those that can be found
+    fn targets(&self, document: &Document) -> Vec<(IncludeNode, Url)> {
+        document
+            .parsed
+            .root()
+            .includes()
+            .filter(|it| it.include_kind() != IncludeKind::Import)
+            .filter_map(|it| {
+                let target = self.resolve(&document.uri, &it.target()?.0)?;
+                Some((it, target))
+            })
+            .collect()
+    }
+
+    /// When the file was last modified on disk, and how long it is
+    fn metadata(url: &Url) -> Option<(SystemTime, u64)> {
+        let metadata = std::fs::metadata(url.to_file_path().ok()?).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// Read the file from disk, replacing the document if there is one
+    fn load(&mut self, url: &Url) -> bool {
+        let metadata = Workspace::metadata(url);
+        let text = url
+            .to_file_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+        match (text, metadata) {
+            (Some(text), Some(metadata)) => {
+                debug!("Loading included file {}", url);
+                self.documents
+                    .insert(url.clone(), Document::new(url.clone(), text));
+                self.on_disk.insert(url.clone(), metadata);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Read the files the document includes, and the files those include, from
+    /// disk, unless they are open or haven't changed since they were last read
+    fn load_includes(&mut self, uri: &Url) {
+        let mut pending = vec![uri.clone()];
+        let mut seen = HashSet::new();
+        while let Some(uri) = pending.pop() {
+            if !seen.insert(uri.clone()) {
+                continue;
+            }
+            let targets = match self.documents.get(&uri) {
+                Some(document) => self.targets(document),
+                None => continue,
+            };
+            for (_, target) in targets {
+                let stale = !self.open.contains(&target)
+                    && (!self.documents.contains_key(&target)
+                        || self.on_disk.get(&target) != Workspace::metadata(&target).as_ref());
+                if stale {
+                    self.load(&target);
+                }
+                pending.push(target);
+            }
+        }
+    }
+
+    /// The documents `document` includes, directly or through the documents
+    /// it includes, each of them once
+    pub(crate) fn includes(&self, document: &Document) -> Vec<Included<'_>> {
+        let mut included = Vec::new();
+        let mut seen = HashSet::new();
+        seen.insert(document.uri.clone());
+        self.collect_includes(document, &mut seen, &mut included);
+        included
+    }
+
+    fn collect_includes<'a>(
+        &'a self,
+        document: &Document,
+        seen: &mut HashSet<Url>,
+        included: &mut Vec<Included<'a>>,
+    ) {
+        for (include, target) in self.targets(document) {
+            let target = match self.documents.get(&target) {
+                Some(target) if seen.insert(target.uri.clone()) => target,
+                _ => continue,
+            };
+            let range = include
+                .sub()
+                .and_then(|sub| target.sub_range(&sub))
+                .unwrap_or_else(|| target.parsed.syntax().text_range());
+            included.push(Included {
+                document: target,
+                range,
+            });
+            self.collect_includes(target, seen, included);
+        }
+    }
+
+    /// The chain of files through which `include` ends up including the
+    /// document it's in again, starting and ending with that document
+    pub(crate) fn include_cycle(
+        &self,
+        document: &Document,
+        include: &IncludeNode,
+    ) -> Option<Vec<Url>> {
+        let target = self.resolve(&document.uri, &include.target()?.0)?;
+        let mut chain = vec![document.uri.clone()];
+        let mut seen = HashSet::new();
+        if self.reaches(&target, &document.uri, &mut seen, &mut chain) {
+            Some(chain)
+        } else {
+            None
+        }
+    }
+
+    fn reaches(&self, from: &Url, to: &Url, seen: &mut HashSet<Url>, chain: &mut Vec<Url>) -> bool {
+        chain.push(from.clone());
+        if from == to {
+            return true;
+        }
+        if seen.insert(from.clone()) {
+            if let Some(document) = self.documents.get(from) {
+                for (_, target) in self.targets(document) {
+                    if self.reaches(&target, to, seen, chain) {
+                        return true;
+                    }
+                }
+            }
+        }
+        chain.pop();
+        false
+    }
+
+    pub(crate) fn get(&self, uri: &Url) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+
+    /// The open documents that include the document, directly or through the
+    /// documents they include
+    pub(crate) fn includers(&self, uri: &Url) -> Vec<&Document> {
+        self.open
+            .iter()
+            .filter(|it| *it != uri)
+            .filter_map(|it| self.documents.get(it))
+            .filter(|it| self.includes(it).iter().any(|it| &it.document.uri == uri))
+            .collect()
+    }
+
+    /// Forget the document once the client closes it, unless another open
+    /// document still includes it, in which case it goes back to what's on
+    /// disk, along with the files read from disk that nothing open includes
+    /// any more
+    pub(crate) fn remove(&mut self, uri: &Url) {
+        self.open.remove(uri);
+        if self.includers(uri).is_empty() || !self.load(uri) {
+            self.documents.remove(uri);
+            self.on_disk.remove(uri);
+        }
+        let included = self
+            .open
+            .iter()
+            .filter_map(|it| self.documents.get(it))
+            .flat_map(|it| self.includes(it))
+            .map(|it| it.document.uri.clone())
+            .collect::<HashSet<_>>();
+        let open = &self.open;
+        self.documents
+            .retain(|uri, _| open.contains(uri) || included.contains(uri));
+        self.on_disk.retain(|uri, _| included.contains(uri));
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_inactive_branches() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let text = "!define LIGHT\n!ifdef LIGHT\nclass Light\n!else\nclass Dark\n!endif\n";
+        let doc = super::Document::new(uri, text.into());
+        assert_eq!(doc.inactive.len(), 1);
+        assert_eq!(&text[doc.inactive[0]], "class Dark\n");
+        let root = doc.parsed.root();
+        let names = root
+            .class_decls()
+            .filter_map(|it| it.name())
+            .map(|it| it.value())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Light"]);
+        assert_eq!(doc.parsed.syntax().text().len(), rowan::TextSize::of(text));
+    }
+
+    #[test]
+    fn test_positions() {
+        let uri = lsp_types::Url::parse("file:///test.puml").unwrap();
+        let doc = crate::workspace::Document::new(uri, "class A\nclass \u{e9}B\n".into());
+        let offset = rowan::TextSize::from(16);
+        let position = doc.position(offset);
+        assert_eq!(position, lsp_types::Position::new(1, 7));
+        assert_eq!(doc.offset(position), Some(offset));
+        assert_eq!(doc.offset(lsp_types::Position::new(0, 99)), Some(7.into()));
+    }
+
+    #[test]
+    fn test_includes() {
+        let url = |it: &str| lsp_types::Url::parse(it).unwrap();
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(
+            url("file:///project/main.puml"),
+            "!include lib/a.puml\n!includesub parts.puml!TWO\n!include missing.puml\n".into(),
+        );
+        workspace.update(
+            url("file:///project/lib/a.puml"),
+            "!include ../main.puml\nparticipant A\n".into(),
+        );
+        workspace.update(
+            url("file:///project/parts.puml"),
+            "!startsub ONE\nactor One\n!endsub\n!startsub TWO\nactor Two\n!endsub\n".into(),
+        );
+        let main = workspace.get(&url("file:///project/main.puml")).unwrap();
+        let includes = workspace
+            .includes(main)
+            .into_iter()
+            .map(|it| {
+                (
+                    it.document.file_name(),
+                    it.document.text[it.range].trim().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            includes,
+            vec![
+                (
+                    "a.puml".to_string(),
+                    "!include ../main.puml\nparticipant A".to_string()
+                ),
+                ("parts.puml".to_string(), "actor Two".to_string()),
+            ]
+        );
+        let cycles = main
+            .parsed
+            .root()
+            .includes()
+            .map(|it| workspace.include_cycle(main, &it).map(|chain| chain.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(cycles, vec![Some(3), None, None]);
+        assert_eq!(workspace.resolve(&main.uri, "missing.puml"), None);
+    }
+
+    #[test]
+    fn test_load_includes() {
+        let dir = std::env::temp_dir().join(format!("puml-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        std::fs::write(dir.join("lib/a.puml"), "!include common.puml\nclass A\n").unwrap();
+        std::fs::write(dir.join("shared/common.puml"), "class Common\n").unwrap();
+
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.set_include_roots(vec![dir.join("shared")]);
+        let uri = lsp_types::Url::from_file_path(dir.join("main.puml")).unwrap();
+        workspace.update(uri.clone(), "!include lib/a.puml\n".into());
+        let main = workspace.get(&uri).unwrap();
+        let names = workspace
+            .includes(main)
+            .into_iter()
+            .map(|it| it.document.file_name())
+            .collect::<Vec<_>>();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, vec!["a.puml", "common.puml"]);
+    }
+
+    #[test]
+    fn test_close_included() {
+        let dir = std::env::temp_dir().join(format!("puml-close-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("lib.puml"), "participant Saved\n").unwrap();
+        let url = |it: &str| lsp_types::Url::from_file_path(dir.join(it)).unwrap();
+        let participants = |workspace: &crate::workspace::Workspace| {
+            let main = workspace.get(&url("main.puml")).unwrap();
+            workspace
+                .includes(main)
+                .into_iter()
+                .flat_map(|it| {
+                    it.document
+                        .parsed
+                        .root()
+                        .participant_decls()
+                        .collect::<Vec<_>>()
+                })
+                .filter_map(|it| it.name())
+                .map(|it| it.value())
+                .collect::<Vec<_>>()
+        };
+
+        let mut workspace = crate::workspace::Workspace::default();
+        workspace.update(url("main.puml"), "!include lib.puml\n".into());
+        workspace.update(url("lib.puml"), "participant Unsaved\n".into());
+        assert_eq!(participants(&workspace), vec!["Unsaved"]);
+        // closing the included file without saving goes back to the disk
+        workspace.remove(&url("lib.puml"));
+        assert_eq!(participants(&workspace), vec!["Saved"]);
+        // and changes on disk are picked up with the next edit
+        std::fs::write(dir.join("lib.puml"), "participant SavedAgain\n").unwrap();
+        workspace.update(url("main.puml"), "!include lib.puml\n".into());
+        assert_eq!(participants(&workspace), vec!["SavedAgain"]);
+        // once nothing includes it, it's forgotten
+        workspace.remove(&url("main.puml"));
+        workspace.remove(&url("lib.puml"));
+        assert!(workspace.get(&url("lib.puml")).is_none());
+        // as is a file read from disk once the document including it closes
+        workspace.update(url("main.puml"), "!include lib.puml\n".into());
+        assert_eq!(participants(&workspace), vec!["SavedAgain"]);
+        assert_eq!(
+            workspace
+                .includers(&url("lib.puml"))
+                .into_iter()
+                .map(|it| it.file_name())
+                .collect::<Vec<_>>(),
+            vec!["main.puml"]
+        );
+        workspace.remove(&url("main.puml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(workspace.get(&url("lib.puml")).is_none());
+    }
+}